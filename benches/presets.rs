@@ -0,0 +1,19 @@
+//! Renders each built-in preset small, one ray per pixel, to keep an eye on tracer performance
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ray_tracer::scenes::Preset;
+
+fn presets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("presets");
+    group.sample_size(10);
+    for preset in Preset::ALL {
+        let world = preset.world();
+        let mut camera = preset.camera(80, 45);
+        camera.set_samples(1);
+        group.bench_function(preset.name(), |b| b.iter(|| camera.render(&world)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, presets);
+criterion_main!(benches);
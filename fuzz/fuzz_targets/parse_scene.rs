@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ray_tracer::scene::parse_scene;
+
+// Scene files must never panic the renderer, whatever bytes they contain. What builds is
+// rendered at a tiny size, so rendering and writing the image are covered too.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(description) = parse_scene(data) {
+        if let Ok(scene) = description.build() {
+            let canvas = scene.camera.with_resolution(2, 2).render(&scene.world);
+            let _ = canvas.get_ppm_pixel_data();
+        }
+    }
+});
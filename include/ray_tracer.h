@@ -0,0 +1,145 @@
+#ifndef RAY_TRACER_H
+#define RAY_TRACER_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum RtStatus {
+  RT_STATUS_OK = 0,
+  RT_STATUS_NULL_POINTER = 1,
+  RT_STATUS_DEGENERATE_TRANSFORM = 2,
+  RT_STATUS_BUFFER_TOO_SMALL = 3,
+} RtStatus;
+
+/**
+ * Opaque handle to a `Camera`
+ */
+typedef struct RtCamera RtCamera;
+
+/**
+ * Opaque handle to a `World`
+ */
+typedef struct RtWorld RtWorld;
+
+typedef struct RtMaterial {
+  double color[3];
+  double ambient;
+  double diffuse;
+  double specular;
+  double shininess;
+} RtMaterial;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The material spheres and planes get when none is given
+ */
+struct RtMaterial rt_material_default(void);
+
+/**
+ * Creates an empty world, release it with `rt_world_free`
+ */
+struct RtWorld *rt_world_new(void);
+
+/**
+ * # Safety
+ *
+ * `world` must come from `rt_world_new` and not have been freed already, or be null.
+ */
+void rt_world_free(struct RtWorld *world);
+
+/**
+ * Adds a point light at (x, y, z) with the given intensity
+ *
+ * # Safety
+ *
+ * `world` must be a live pointer from `rt_world_new`, or null.
+ */
+enum RtStatus rt_world_add_light(struct RtWorld *world,
+                                 double x,
+                                 double y,
+                                 double z,
+                                 double red,
+                                 double green,
+                                 double blue);
+
+/**
+ * Adds a sphere with the given transform (16 values, row major) and material.
+ * Either can be null for the identity transform and the default material.
+ *
+ * # Safety
+ *
+ * `world` must be a live pointer from `rt_world_new`, or null. `transform` must point to
+ * 16 doubles and `material` to an `RtMaterial`, or be null.
+ */
+enum RtStatus rt_world_add_sphere(struct RtWorld *world,
+                                  const double *transform,
+                                  const struct RtMaterial *material);
+
+/**
+ * Adds the plane y = 0, facing +y, placed by the given transform (16 values, row major) and
+ * with the given material. Either can be null like for `rt_world_add_sphere`.
+ * The plane is a square reaching 10000 units from its center, not endless.
+ *
+ * # Safety
+ *
+ * `world` must be a live pointer from `rt_world_new`, or null. `transform` must point to
+ * 16 doubles and `material` to an `RtMaterial`, or be null.
+ */
+enum RtStatus rt_world_add_plane(struct RtWorld *world,
+                                 const double *transform,
+                                 const struct RtMaterial *material);
+
+/**
+ * Creates a camera rendering `width` x `height` pixels, looking down -z from the origin
+ * until moved with `rt_camera_look_at`. Release it with `rt_camera_free`.
+ * Returns null when either side is 0 or larger than 16384.
+ */
+struct RtCamera *rt_camera_new(size_t width, size_t height, double field_of_view);
+
+/**
+ * # Safety
+ *
+ * `camera` must come from `rt_camera_new` and not have been freed already, or be null.
+ */
+void rt_camera_free(struct RtCamera *camera);
+
+/**
+ * Points the camera from one point to another, see `view_transform`
+ *
+ * # Safety
+ *
+ * `camera` must be a live pointer from `rt_camera_new`, or null.
+ * `from`, `to` and `up` must each point to 3 doubles, or be null.
+ */
+enum RtStatus rt_camera_look_at(struct RtCamera *camera,
+                                const double *from,
+                                const double *to,
+                                const double *up);
+
+/**
+ * Renders the world into `buffer` as 8 bit RGBA, row by row from the top left.
+ * The buffer has to hold at least width * height * 4 bytes.
+ *
+ * # Safety
+ *
+ * `camera` and `world` must be live pointers from their `_new` functions, or null.
+ * `buffer` must be valid for writes of `len` bytes, or be null.
+ */
+enum RtStatus rt_render(const struct RtCamera *camera,
+                        const struct RtWorld *world,
+                        uint8_t *buffer,
+                        size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RAY_TRACER_H */
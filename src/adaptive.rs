@@ -0,0 +1,174 @@
+//! Adaptive sampling: rays are spent where the image is noisy instead of evenly everywhere.
+//!
+//! Each pixel starts with `min_samples` rays and keeps getting more, up to the camera's
+//! `samples`, until the standard error of its mean brightness drops below `threshold`. Flat
+//! background settles after the first few, edges, soft shadows and glossy highlights take the
+//! rest. `SampleMap` records where the effort went, as auxiliary images to tune the two
+//! settings by.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+
+/// See `Camera::set_adaptive`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    /// Rays every pixel gets before its noise is judged, too few and a pixel whose first rays
+    /// all happen to agree stops early
+    pub min_samples: usize,
+    /// Standard error of a pixel's luminance it stops at
+    pub threshold: f64,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Adaptive {
+            min_samples: 4,
+            threshold: 0.01,
+        }
+    }
+}
+
+/// Running mean and variance of a pixel's sample luminances (Welford's algorithm)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PixelStats {
+    pub samples: usize,
+    mean: f64,
+    // sum of squared differences from the mean
+    m2: f64,
+}
+
+impl PixelStats {
+    pub fn add(&mut self, color: Color) {
+        let luminance = color.luminance();
+        self.samples += 1;
+        let delta = luminance - self.mean;
+        self.mean += delta / self.samples as f64;
+        self.m2 += delta * (luminance - self.mean);
+    }
+
+    /// Estimated variance of the pixel's value, the spread of its samples over how many were
+    /// averaged. 0 with fewer than two samples.
+    pub fn variance(&self) -> f64 {
+        if self.samples < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.samples - 1) as f64 / self.samples as f64
+    }
+
+    /// Whether the pixel has had enough rays
+    pub fn converged(&self, adaptive: &Adaptive) -> bool {
+        self.samples >= adaptive.min_samples.max(2) && self.variance().sqrt() <= adaptive.threshold
+    }
+}
+
+/// Sample count and variance of every pixel of a render, see `Camera::render_with_sample_map`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampleMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<PixelStats>,
+}
+
+impl SampleMap {
+    pub(crate) fn new(width: usize, height: usize) -> Self {
+        SampleMap {
+            width,
+            height,
+            pixels: vec![PixelStats::default(); width * height],
+        }
+    }
+
+    pub(crate) fn set(&mut self, x: usize, y: usize, stats: PixelStats) {
+        self.pixels[y * self.width + x] = stats;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn samples(&self, x: usize, y: usize) -> usize {
+        self.pixels[y * self.width + x].samples
+    }
+
+    pub fn variance(&self, x: usize, y: usize) -> f64 {
+        self.pixels[y * self.width + x].variance()
+    }
+
+    /// Rays traced for the whole image
+    pub fn total_samples(&self) -> usize {
+        self.pixels.iter().map(|p| p.samples).sum()
+    }
+
+    /// Samples per pixel as grey, white for pixels that got `max_samples`
+    pub fn samples_image(&self, max_samples: usize) -> Canvas {
+        let max = max_samples.max(1) as f64;
+        self.image(|p| p.samples as f64 / max)
+    }
+
+    /// Standard error per pixel as grey, white for the noisiest pixel
+    pub fn variance_image(&self) -> Canvas {
+        let noisiest = self.pixels.iter().map(|p| p.variance()).fold(0.0, f64::max);
+        if noisiest == 0.0 {
+            return Canvas::new(self.width, self.height);
+        }
+        self.image(|p| (p.variance() / noisiest).sqrt())
+    }
+
+    fn image(&self, shade: impl Fn(&PixelStats) -> f64) -> Canvas {
+        let mut canvas = Canvas::new(self.width, self.height);
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            let shade = shade(pixel);
+            canvas.write_pixel(
+                i % self.width,
+                i / self.width,
+                Color::new(shade, shade, shade),
+            );
+        }
+        canvas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adaptive::{Adaptive, PixelStats, SampleMap};
+    use crate::color::Color;
+
+    #[test]
+    fn pixel_stats() {
+        let mut stats = PixelStats::default();
+        for shade in [0.2, 0.4, 0.6, 0.8] {
+            stats.add(Color::new(shade, shade, shade));
+        }
+        assert_eq!(stats.samples, 4);
+        // sample variance 0.0667, over 4 samples
+        assert!((stats.variance() - 0.2 / 12.0).abs() < 1e-12);
+        assert!(!stats.converged(&Adaptive::default()));
+
+        let mut flat = PixelStats::default();
+        for _ in 0..4 {
+            flat.add(Color::WHITE);
+        }
+        assert_eq!(flat.variance(), 0.0);
+        assert!(flat.converged(&Adaptive::default()));
+    }
+
+    #[test]
+    fn sample_map_images() {
+        let mut map = SampleMap::new(2, 1);
+        let mut noisy = PixelStats::default();
+        noisy.add(Color::BLACK);
+        noisy.add(Color::WHITE);
+        map.set(0, 0, noisy);
+        assert_eq!(map.total_samples(), 2);
+        assert_eq!(
+            map.samples_image(4).get_pixel(0, 0),
+            Some(Color::new(0.5, 0.5, 0.5))
+        );
+        assert_eq!(map.variance_image().get_pixel(0, 0), Some(Color::WHITE));
+        assert_eq!(map.variance_image().get_pixel(1, 0), Some(Color::BLACK));
+    }
+}
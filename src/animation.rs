@@ -0,0 +1,554 @@
+//! Keyframed animation of a world and its camera.
+//!
+//! Each animated property (an object's transform, the camera, a light's intensity) gets a
+//! [`Track`] of keyframes. Sampling a track between two keyframes interpolates their values,
+//! eased by the later keyframe's [`Easing`]. Before the first and after the last keyframe the
+//! value holds still.
+//!
+//! ```
+//! use ray_tracer::animation::{render_animation, Animation, Easing, TransformKey};
+//! use ray_tracer::prelude::*;
+//!
+//! let world = default_world();
+//! let camera = Camera::new(20, 10, 1.0);
+//! let mut animation = Animation::new();
+//! animation
+//!     .object(0)
+//!     .add(0.0, TransformKey::default(), Easing::Linear)
+//!     .add(
+//!         10.0,
+//!         TransformKey { translation: Vector::new(0.0, 1.0, 0.0), ..TransformKey::default() },
+//!         Easing::EaseInOut,
+//!     );
+//! let frames = render_animation(&world, &camera, &animation, 0..3).unwrap();
+//! assert_eq!(frames.len(), 3);
+//! ```
+
+use std::ops::Range;
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::Result;
+use crate::matrix::M4x4;
+use crate::matrix_transformations::{scaling, translation_from, view_transform};
+use crate::tuple::{Point, Tuple, Vector};
+use crate::world::World;
+
+/// How the in-between values of a segment are spread out over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Jumps to the next value when its keyframe is reached
+    Step,
+    #[default]
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Slow at both ends, smoothstep
+    EaseInOut,
+}
+
+impl Easing {
+    /// Remaps the progress `t` through a segment, both ends stay put at 0 and 1
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Values that can be blended, `t` going from 0 (all `self`) to 1 (all `other`)
+pub trait Interpolate {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self.lerp(*other, t)
+    }
+}
+
+/// A value at a point in time, `easing` shapes the segment leading up to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    pub frame: f64,
+    pub value: T,
+    pub easing: Easing,
+}
+
+/// Keyframes of a single property, kept sorted by frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Track { keys: Vec::new() }
+    }
+}
+
+impl<T: Interpolate + Clone> Track<T> {
+    pub fn new() -> Self {
+        Track::default()
+    }
+
+    /// Adds a keyframe, replacing one already at the same frame
+    pub fn add(&mut self, frame: f64, value: T, easing: Easing) -> &mut Self {
+        let key = Keyframe {
+            frame,
+            value,
+            easing,
+        };
+        match self.keys.binary_search_by(|k| k.frame.total_cmp(&frame)) {
+            Ok(i) => self.keys[i] = key,
+            Err(i) => self.keys.insert(i, key),
+        }
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// Value of the property at the given (possibly fractional) frame, `None` without keyframes
+    pub fn sample(&self, frame: f64) -> Option<T> {
+        let first = self.keys.first()?;
+        // index of the first key after the frame
+        let next = self.keys.partition_point(|k| k.frame <= frame);
+        if next == 0 {
+            return Some(first.value.clone());
+        }
+        let before = &self.keys[next - 1];
+        let Some(after) = self.keys.get(next) else {
+            return Some(before.value.clone());
+        };
+        let t = (frame - before.frame) / (after.frame - before.frame);
+        Some(
+            before
+                .value
+                .interpolate(&after.value, after.easing.apply(t)),
+        )
+    }
+}
+
+/// A rotation, as a unit quaternion so rotations can be blended smoothly with `slerp`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Quaternion = Quaternion {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    /// Rotation by `angle` radians around `axis`, following the same handedness as `rotation_x` and friends
+    pub fn from_axis_angle(axis: Vector, angle: f64) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Quaternion {
+            w: cos,
+            x: axis.x * sin,
+            y: axis.y * sin,
+            z: axis.z * sin,
+        }
+    }
+
+    fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    fn normalize(self) -> Self {
+        let length = self.dot(&self).sqrt();
+        Quaternion {
+            w: self.w / length,
+            x: self.x / length,
+            y: self.y / length,
+            z: self.z / length,
+        }
+    }
+
+    /// Spherical interpolation, turning at a constant speed along the shortest way around
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut cos = self.dot(other);
+        // q and -q are the same rotation, flip one so the shorter arc is taken
+        let other = if cos < 0.0 {
+            cos = -cos;
+            Quaternion {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            }
+        } else {
+            *other
+        };
+        // nearly the same rotation, sin(angle) is too close to 0 to divide by
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Quaternion {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+        .normalize()
+    }
+
+    pub fn to_matrix(&self) -> M4x4 {
+        let Quaternion { w, x, y, z } = *self;
+        M4x4::from([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Interpolate for Quaternion {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        self.slerp(other, t)
+    }
+}
+
+/// An object transform split into parts that blend well: scaled, then rotated, then translated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransformKey {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    pub scale: Vector,
+}
+
+impl Default for TransformKey {
+    fn default() -> Self {
+        TransformKey {
+            translation: Vector::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::IDENTITY,
+            scale: Vector::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl TransformKey {
+    pub fn to_matrix(&self) -> M4x4 {
+        translation_from(self.translation)
+            * self.rotation.to_matrix()
+            * scaling(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl Interpolate for TransformKey {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        TransformKey {
+            translation: self.translation.interpolate(&other.translation, t),
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale.interpolate(&other.scale, t),
+        }
+    }
+}
+
+/// Where the camera is and what it looks at, see `view_transform`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKey {
+    pub from: Point,
+    pub to: Point,
+    pub up: Vector,
+}
+
+impl CameraKey {
+    pub fn to_matrix(&self) -> M4x4 {
+        view_transform(self.from, self.to, self.up)
+    }
+}
+
+impl Interpolate for CameraKey {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        CameraKey {
+            from: self.from.interpolate(&other.from, t),
+            to: self.to.interpolate(&other.to, t),
+            up: self.up.interpolate(&other.up, t),
+        }
+    }
+}
+
+/// All the animated properties of a scene. Objects and lights are referred to by their
+/// index in `World::objects` and `World::lights`.
+#[derive(Debug, Clone, Default)]
+pub struct Animation {
+    objects: Vec<(usize, Track<TransformKey>)>,
+    lights: Vec<(usize, Track<Color>)>,
+    camera: Option<Track<CameraKey>>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Animation::default()
+    }
+
+    /// Transform track of the object at `index`, created empty on first use
+    pub fn object(&mut self, index: usize) -> &mut Track<TransformKey> {
+        track_for(&mut self.objects, index)
+    }
+
+    /// Intensity track of the light at `index`, created empty on first use
+    pub fn light_intensity(&mut self, index: usize) -> &mut Track<Color> {
+        track_for(&mut self.lights, index)
+    }
+
+    pub fn camera(&mut self) -> &mut Track<CameraKey> {
+        self.camera.get_or_insert_with(Track::new)
+    }
+
+    /// Poses the world and camera for the given frame. Properties without keyframes keep their
+    /// current values, as do tracks pointing past the end of the world's objects or lights.
+    ///
+    /// Only the animated properties are written, and transforms that didn't change since the
+    /// last frame keep their cached inverses, so posing the same world frame after frame is cheap.
+    pub fn apply(&self, world: &mut World, camera: &mut Camera, frame: f64) -> Result<()> {
+        for (index, track) in &self.objects {
+            if let (Some(object), Some(key)) = (world.objects.get_mut(*index), track.sample(frame))
+            {
+                object.set_transform(key.to_matrix())?;
+            }
+        }
+        for (index, track) in &self.lights {
+            if let (Some(light), Some(intensity)) =
+                (world.lights.get_mut(*index), track.sample(frame))
+            {
+                light.set_intensity(intensity);
+            }
+        }
+        if let Some(key) = self.camera.as_ref().and_then(|track| track.sample(frame)) {
+            camera.set_transform(key.to_matrix())?;
+        }
+        Ok(())
+    }
+}
+
+fn track_for<T: Interpolate + Clone>(
+    tracks: &mut Vec<(usize, Track<T>)>,
+    index: usize,
+) -> &mut Track<T> {
+    let position = match tracks.iter().position(|(i, _)| *i == index) {
+        Some(position) => position,
+        None => {
+            tracks.push((index, Track::new()));
+            tracks.len() - 1
+        }
+    };
+    &mut tracks[position].1
+}
+
+/// Renders one canvas per frame, each posed by the animation. The world and camera passed in
+/// are left as they are, frames are rendered from a single copy of them that is re-posed in place,
+/// so only the animated properties are touched from one frame to the next.
+pub fn render_animation(
+    world: &World,
+    camera: &Camera,
+    animation: &Animation,
+    frames: Range<usize>,
+) -> Result<Vec<Canvas>> {
+    let mut world = world.clone();
+    let mut camera = *camera;
+    frames
+        .map(|frame| {
+            animation.apply(&mut world, &mut camera, frame as f64)?;
+            Ok(camera.render(&world))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::animation::{
+        render_animation, Animation, CameraKey, Easing, Quaternion, Track, TransformKey,
+    };
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::matrix_transformations::{rotation_x, rotation_y, translation, view_transform};
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+    use crate::utils::equal_f64;
+    use crate::world::default_world;
+
+    #[test]
+    fn easing_keeps_the_ends() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::Linear.apply(0.25), 0.25);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::Step.apply(0.99), 0.0);
+    }
+
+    #[test]
+    fn sampling_a_track() {
+        let mut track = Track::new();
+        assert_eq!(track.sample(0.0), None);
+        track
+            .add(10.0, 4.0, Easing::EaseIn)
+            .add(0.0, 0.0, Easing::Linear)
+            .add(20.0, 8.0, Easing::Linear);
+        assert_eq!(track.keys().len(), 3);
+        assert_eq!(track.sample(-5.0), Some(0.0));
+        assert_eq!(track.sample(5.0), Some(1.0));
+        assert_eq!(track.sample(10.0), Some(4.0));
+        assert_eq!(track.sample(15.0), Some(6.0));
+        assert_eq!(track.sample(30.0), Some(8.0));
+    }
+
+    #[test]
+    fn quaternions_match_rotation_matrices() {
+        let q = Quaternion::from_axis_angle(Vector::new(1.0, 0.0, 0.0), PI / 3.0);
+        assert_eq!(q.to_matrix(), rotation_x(PI / 3.0));
+        let q = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), -PI / 5.0);
+        assert_eq!(q.to_matrix(), rotation_y(-PI / 5.0));
+    }
+
+    #[test]
+    fn slerp_turns_at_constant_speed() {
+        let a = Quaternion::IDENTITY;
+        let b = Quaternion::from_axis_angle(Vector::new(0.0, 1.0, 0.0), PI / 2.0);
+        assert_eq!(a.slerp(&b, 0.5).to_matrix(), rotation_y(PI / 4.0));
+        assert_eq!(a.slerp(&b, 0.0).to_matrix(), a.to_matrix());
+        assert_eq!(a.slerp(&b, 1.0).to_matrix(), b.to_matrix());
+    }
+
+    #[test]
+    fn applying_an_animation() {
+        let mut world = default_world();
+        let mut camera = Camera::new(10, 10, PI / 2.0);
+        let mut animation = Animation::new();
+        animation
+            .object(1)
+            .add(0.0, TransformKey::default(), Easing::Linear)
+            .add(
+                4.0,
+                TransformKey {
+                    translation: Vector::new(0.0, 2.0, 0.0),
+                    ..TransformKey::default()
+                },
+                Easing::Linear,
+            );
+        animation
+            .light_intensity(0)
+            .add(0.0, Color::WHITE, Easing::Linear)
+            .add(2.0, Color::BLACK, Easing::Linear);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let key = |z| CameraKey {
+            from: Point::new_point(0.0, 0.0, z),
+            to: Point::new_point(0.0, 0.0, 0.0),
+            up,
+        };
+        animation
+            .camera()
+            .add(0.0, key(-10.0), Easing::Linear)
+            .add(4.0, key(-6.0), Easing::Linear);
+
+        animation.apply(&mut world, &mut camera, 1.0).unwrap();
+        assert_eq!(world.objects[1].transform(), translation(0.0, 0.5, 0.0));
+        assert_eq!(
+            world.objects[0].transform(),
+            default_world().objects[0].transform()
+        );
+        assert_eq!(world.lights[0].intensity(), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(
+            camera.transform(),
+            view_transform(
+                Point::new_point(0.0, 0.0, -9.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                up
+            )
+        );
+    }
+
+    #[test]
+    fn rendering_frames() {
+        let world = default_world();
+        let mut camera = Camera::new(11, 11, PI / 2.0);
+        camera
+            .set_transform(view_transform(
+                Point::new_point(0.0, 0.0, -5.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let mut animation = Animation::new();
+        animation
+            .light_intensity(0)
+            .add(0.0, Color::WHITE, Easing::Linear)
+            .add(2.0, Color::BLACK, Easing::Linear);
+
+        let frames = render_animation(&world, &camera, &animation, 0..3).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames[0].get_pixel(5, 5),
+            Some(Color::new(0.38066, 0.47583, 0.2855))
+        );
+        assert_eq!(frames[2].get_pixel(5, 5), Some(Color::BLACK));
+        assert!(equal_f64(world.lights[0].intensity().red(), 1.0));
+    }
+}
@@ -0,0 +1,272 @@
+//! Baking the light falling on an object into a texture (a lightmap), so static lighting can
+//! be computed once and reused by viewers that can't trace shadows every frame.
+//!
+//! Each texel is the irradiance at the point of the object it maps to: the light arriving
+//! there from every light, with shadows, weighted by how squarely it hits, whatever the
+//! object's material. Ambient light can be darkened in creases with ambient occlusion, and
+//! light bounced off the rest of the scene gathered with a few extra rays per texel.
+//!
+//! Spheres are mapped with `uv::spherical_map`.
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use uuid::Uuid;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, PointLight};
+use crate::random::{Rng, Seedable};
+use crate::rays::{Intersection, Ray, Sphere};
+use crate::shape::Shape;
+use crate::tuple::{Point, Vector};
+use crate::uv::spherical_point;
+use crate::world::{World, SUN_DISTANCE};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bake {
+    pub width: usize,
+    pub height: usize,
+    /// Darkens ambient light by how much of the sky each texel sees, none by default
+    pub occlusion: Option<Occlusion>,
+    /// Rays per texel gathering light bounced off other objects, 0 (direct light only) by
+    /// default
+    pub indirect_samples: usize,
+    pub seed: u64,
+}
+
+impl Default for Bake {
+    fn default() -> Self {
+        Bake {
+            width: 256,
+            height: 128,
+            occlusion: None,
+            indirect_samples: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// Ambient occlusion, `samples` rays per texel looking for anything closer than `distance`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Occlusion {
+    pub samples: usize,
+    pub distance: f64,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Occlusion {
+            samples: 16,
+            distance: 1.0,
+        }
+    }
+}
+
+impl Bake {
+    /// The lightmap of the object with the given id, failing when it isn't in the world
+    pub fn irradiance(&self, world: &World, object: Uuid) -> Result<Canvas> {
+        let sphere = world
+            .object(object)
+            .ok_or_else(|| Error::Image(format!("no object {} to bake", object)))?
+            .as_sphere()
+            .ok_or_else(|| Error::Image(format!("object {} isn't a sphere", object)))?;
+        let texel = |i: usize| {
+            let (x, y) = (i % self.width, i / self.width);
+            let u = (x as f64 + 0.5) / self.width as f64;
+            let v = 1.0 - (y as f64 + 0.5) / self.height as f64;
+            let mut rng = Rng::for_pixel(self.seed, x, y);
+            self.texel(world, sphere, spherical_point(u, v), &mut rng)
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let texels: Vec<Color> = (0..self.width * self.height)
+            .into_par_iter()
+            .map(texel)
+            .collect();
+        #[cfg(target_arch = "wasm32")]
+        let texels: Vec<Color> = (0..self.width * self.height).map(texel).collect();
+
+        let mut lightmap = Canvas::new(self.width, self.height);
+        for (i, color) in texels.into_iter().enumerate() {
+            lightmap.write_pixel(i % self.width, i / self.width, color);
+        }
+        Ok(lightmap)
+    }
+
+    /// Irradiance at the point of the sphere at `object_point` in its own space
+    fn texel(&self, world: &World, sphere: &Sphere, object_point: Point, rng: &mut Rng) -> Color {
+        let point = sphere.transform() * object_point;
+        let normal = sphere.normal_at(point, &Intersection::new(0.0, *sphere));
+        let over_point = point + normal * world.bias;
+        // light from a point at `position`, `visibility` of the way unblocked
+        let arriving = |position: Point, intensity: Color, visibility: f64| {
+            let lightv = (position - point).normalize();
+            intensity * (lightv.dot(&normal).max(0.0) * visibility)
+        };
+        let linked = |groups: LightGroups| groups.overlaps(sphere.light_groups);
+        let seen = |to: Point| {
+            if world.is_visible(over_point, to) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        let mut irradiance = Color::BLACK;
+        let mut point_light = |light: PointLight| {
+            if linked(light.groups()) {
+                irradiance += arriving(
+                    light.position(),
+                    light.intensity_at(point),
+                    seen(light.position()),
+                );
+            }
+        };
+        for light in &world.lights {
+            point_light(*light);
+        }
+        for light in &world.spot_lights {
+            point_light(light.as_point_light_at(point));
+        }
+        for light in world.sphere_lights.iter().filter(|l| linked(l.groups())) {
+            let visibility = world.light_visibility(over_point, light.samples(), rng, |rng| {
+                light.sample_point(rng)
+            });
+            irradiance += arriving(light.position(), light.intensity(), visibility);
+        }
+        for light in &world.area_lights {
+            if linked(light.groups()) && light.faces(point) {
+                let visibility = world.light_visibility(over_point, light.samples(), rng, |rng| {
+                    light.quad().sample_point(rng)
+                });
+                irradiance += arriving(light.quad().center(), light.intensity(), visibility);
+            }
+        }
+        if let Some(sky) = world.sky {
+            let sun = point + sky.sun_direction() * SUN_DISTANCE;
+            irradiance += arriving(sun, sky.sun_color(), seen(sun));
+        }
+        if let Some(ambient) = world.ambient {
+            let open = match self.occlusion {
+                Some(occlusion) => {
+                    let unblocked = (0..occlusion.samples)
+                        .filter(|_| {
+                            let direction = cosine_direction(normal, rng);
+                            world
+                                .is_visible(over_point, over_point + direction * occlusion.distance)
+                        })
+                        .count();
+                    unblocked as f64 / occlusion.samples.max(1) as f64
+                }
+                None => 1.0,
+            };
+            irradiance += ambient.intensity_at(normal) * open;
+        }
+        if self.indirect_samples > 0 {
+            let bounced: Color = (0..self.indirect_samples)
+                .map(|_| {
+                    let ray = Ray::new(over_point, cosine_direction(normal, rng));
+                    match world.first_hit(&ray) {
+                        Some(_) => world.color_at_with(&ray, rng),
+                        // the sky's light is already counted by `ambient`
+                        None => Color::BLACK,
+                    }
+                })
+                .sum();
+            irradiance += bounced * (1.0 / self.indirect_samples as f64);
+        }
+        irradiance
+    }
+}
+
+/// Random direction around `normal`, more of them close to it than glancing off the surface,
+/// the way diffuse light arrives
+fn cosine_direction(normal: Vector, rng: &mut Rng) -> Vector {
+    // any vector not parallel to the normal gives the other two axes
+    let helper = if normal.x.abs() < 0.9 {
+        Vector::new(1.0, 0.0, 0.0)
+    } else {
+        Vector::new(0.0, 1.0, 0.0)
+    };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    let r = rng.next_f64().sqrt();
+    let angle = rng.range(0.0, std::f64::consts::TAU);
+    tangent * (r * angle.cos()) + bitangent * (r * angle.sin()) + normal * (1.0 - r * r).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::bake::{Bake, Occlusion};
+    use crate::color::Color;
+    use crate::light::{AmbientLight, PointLight};
+    use crate::matrix_transformations::translation;
+    use crate::rays::Sphere;
+    use crate::tuple::Point;
+    use crate::world::World;
+
+    fn lit_from_above() -> (World, Uuid) {
+        let mut world = World::new();
+        let sphere = Sphere::new();
+        let id = sphere.id;
+        world.objects.push(sphere.into());
+        world.lights.push(PointLight::new(
+            Point::new_point(0.0, 100.0, 0.0),
+            Color::WHITE,
+        ));
+        (world, id)
+    }
+
+    #[test]
+    fn baking_direct_light() {
+        let (world, id) = lit_from_above();
+        let bake = Bake {
+            width: 8,
+            height: 4,
+            ..Bake::default()
+        };
+        let lightmap = bake.irradiance(&world, id).unwrap();
+        assert_eq!((lightmap.width(), lightmap.height()), (8, 4));
+        // the top rows face the light, the bottom ones are in the sphere's own shadow
+        let top = lightmap.get_pixel(3, 0).unwrap();
+        let upper = lightmap.get_pixel(3, 1).unwrap();
+        assert!(top.red() > upper.red() && upper.red() > 0.0);
+        assert_eq!(lightmap.get_pixel(3, 3), Some(Color::BLACK));
+        // lit the same all the way around
+        assert!((lightmap.get_pixel(0, 0).unwrap().red() - top.red()).abs() < 1e-9);
+
+        assert!(bake.irradiance(&world, Uuid::new_v4()).is_err());
+    }
+
+    #[test]
+    fn occlusion_darkens_ambient_light_near_other_objects() {
+        let (mut world, id) = lit_from_above();
+        world.lights.clear();
+        world.ambient = Some(AmbientLight::Flat(Color::WHITE));
+        let mut neighbor = Sphere::new();
+        neighbor.set_transform(translation(2.2, 0.0, 0.0)).unwrap();
+        world.objects.push(neighbor.into());
+        let bake = Bake {
+            width: 8,
+            height: 4,
+            occlusion: Some(Occlusion::default()),
+            ..Bake::default()
+        };
+        let lightmap = bake.irradiance(&world, id).unwrap();
+        // u = 0.25 faces +x toward the neighbor, u = 0.75 faces away from it
+        let facing = lightmap.get_pixel(2, 2).unwrap().red();
+        let away = lightmap.get_pixel(6, 2).unwrap().red();
+        assert!(facing < away, "{} {}", facing, away);
+        assert_eq!(away, 1.0);
+
+        let without = Bake {
+            occlusion: None,
+            ..bake
+        };
+        let flat = without.irradiance(&world, id).unwrap();
+        assert_eq!(flat.get_pixel(2, 2), Some(Color::WHITE));
+    }
+}
@@ -0,0 +1,202 @@
+//! Axis-aligned bounding boxes, cheap to test rays against before testing what's inside them.
+
+use crate::matrix::M4x4;
+use crate::rays::Ray;
+use crate::tuple::Point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    min: Point,
+    max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        BoundingBox { min, max }
+    }
+
+    /// A box around nothing, grows to fit whatever is added to it
+    pub fn empty() -> Self {
+        BoundingBox {
+            min: Point::new_point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new_point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn add_point(&mut self, point: Point) {
+        self.min = Point::new_point(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Point::new_point(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+    }
+
+    /// Grows the box to fit another one
+    pub fn add_box(&mut self, other: &BoundingBox) {
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// The eight corners, the first four at min z
+    pub fn corners(&self) -> [Point; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point::new_point(a.x, a.y, a.z),
+            Point::new_point(b.x, a.y, a.z),
+            Point::new_point(b.x, b.y, a.z),
+            Point::new_point(a.x, b.y, a.z),
+            Point::new_point(a.x, a.y, b.z),
+            Point::new_point(b.x, a.y, b.z),
+            Point::new_point(b.x, b.y, b.z),
+            Point::new_point(a.x, b.y, b.z),
+        ]
+    }
+
+    /// Axis-aligned box around the transformed corners, usually a bit larger than the shape
+    /// inside it needs once rotated
+    pub fn transformed(&self, m: &M4x4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let mut transformed = BoundingBox::empty();
+        for corner in self.corners() {
+            transformed.add_point(*m * corner);
+        }
+        transformed
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Where the ray enters and leaves the box (the slab test), `None` when it misses or the box
+    /// is entirely behind the ray. The entry t is negative when the ray starts inside.
+    pub fn intersects(&self, ray: &Ray) -> Option<(f64, f64)> {
+        let (origin, direction) = (ray.origin(), ray.direction());
+        let (xmin, xmax) = slab(origin.x, direction.x, self.min.x, self.max.x);
+        let (ymin, ymax) = slab(origin.y, direction.y, self.min.y, self.max.y);
+        let (zmin, zmax) = slab(origin.z, direction.z, self.min.z, self.max.z);
+        let tmin = xmin.max(ymin).max(zmin);
+        let tmax = xmax.min(ymax).min(zmax);
+        if tmin > tmax || tmax < 0.0 {
+            None
+        } else {
+            Some((tmin, tmax))
+        }
+    }
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox::empty()
+    }
+}
+
+/// Range of t where the ray is between the two planes of one axis
+fn slab(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    if direction == 0.0 {
+        // parallel to the planes, either always between them or never
+        return if (min..=max).contains(&origin) {
+            (f64::NEG_INFINITY, f64::INFINITY)
+        } else {
+            (f64::INFINITY, f64::NEG_INFINITY)
+        };
+    }
+    let t0 = (min - origin) / direction;
+    let t1 = (max - origin) / direction;
+    if t0 > t1 {
+        (t1, t0)
+    } else {
+        (t0, t1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4, SQRT_2};
+
+    use crate::bounds::BoundingBox;
+    use crate::matrix_transformations::{rotation_x, rotation_y, scaling};
+    use crate::rays::Ray;
+    use crate::tuple::{Point, Vector};
+
+    fn unit_box() -> BoundingBox {
+        BoundingBox::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn adding_points_grows_the_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(Point::new_point(-5.0, 2.0, 0.0));
+        b.add_point(Point::new_point(7.0, 0.0, -3.0));
+        assert_eq!(b.min(), Point::new_point(-5.0, 0.0, -3.0));
+        assert_eq!(b.max(), Point::new_point(7.0, 2.0, 0.0));
+        assert!(b.contains_point(Point::new_point(0.0, 1.0, -1.0)));
+        assert!(!b.contains_point(Point::new_point(0.0, 3.0, -1.0)));
+    }
+
+    #[test]
+    fn transforming_a_box() {
+        let b = unit_box().transformed(&(rotation_x(FRAC_PI_4) * rotation_y(FRAC_PI_4)));
+        let (x, yz) = (SQRT_2, 1.0 + FRAC_1_SQRT_2);
+        assert_eq!(b.min(), Point::new_point(-x, -yz, -yz));
+        assert_eq!(b.max(), Point::new_point(x, yz, yz));
+        assert!(BoundingBox::empty()
+            .transformed(&scaling(2.0, 2.0, 2.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn ray_enters_and_leaves() {
+        let r = Ray::new(Point::new_point(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0));
+        assert_eq!(unit_box().intersects(&r), Some((4.0, 6.0)));
+
+        let inside = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 2.0));
+        assert_eq!(unit_box().intersects(&inside), Some((-0.5, 0.5)));
+    }
+
+    #[test]
+    fn ray_misses() {
+        let cases = [
+            (
+                Point::new_point(-2.0, 0.0, 0.0),
+                Vector::new(0.2673, 0.5345, 0.8018),
+            ),
+            (Point::new_point(2.0, 0.0, 2.0), Vector::new(0.0, 0.0, -1.0)),
+            (Point::new_point(2.0, 2.0, 0.0), Vector::new(-1.0, 0.0, 0.0)),
+            // box behind the ray
+            (Point::new_point(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0)),
+        ];
+        for (origin, direction) in cases {
+            assert_eq!(unit_box().intersects(&Ray::new(origin, direction)), None);
+        }
+    }
+
+    #[test]
+    fn ray_along_a_face() {
+        let r = Ray::new(Point::new_point(1.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(unit_box().intersects(&r), Some((4.0, 6.0)));
+    }
+}
@@ -0,0 +1,925 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+use crate::adaptive::{Adaptive, PixelStats, SampleMap};
+use crate::canvas::Canvas;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::canvas::PpmWriter;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::exposure::{self, Exposure, ToneMap};
+use crate::filter::Filter;
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::metrics::{self, RenderMetrics, TileMetrics};
+use crate::packet::RayPacket;
+use crate::random::{Rng, Seedable};
+use crate::rays::Ray;
+use crate::tuple::Point;
+use crate::world::{RayHit, World};
+use uuid::Uuid;
+
+/// Width and height of the square blocks an image is rendered in
+pub const TILE_SIZE: usize = 16;
+
+/// Rows rendered at once by `Camera::render_in_bands`, four rows of tiles
+pub const BAND_HEIGHT: usize = 4 * TILE_SIZE;
+
+/// Largest camera width or height accepted from outside the crate, by `parse_scene` and the
+/// C API
+pub const MAX_RESOLUTION: usize = 16_384;
+
+/// A rectangle of pixels rendered together, `x` and `y` are its top left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tile {
+    /// Copies the tile's pixels, as returned by `Camera::render_tile`, into place on the canvas
+    pub fn write_to(&self, canvas: &mut Canvas, pixels: &[Color]) {
+        for (i, color) in pixels.iter().enumerate() {
+            canvas.write_pixel(self.x + i % self.width, self.y + i / self.width, *color);
+        }
+    }
+}
+
+/// What the camera shows at each hit. The debug modes skip lighting entirely, which makes them
+/// quick ways to check geometry: flipped normals, objects out of place, missing pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RenderMode {
+    /// Lit and shaded, the actual image
+    #[default]
+    Shaded,
+    /// The surface normal's x, y and z as red, green and blue, mapped from -1..1 to 0..1
+    Normals,
+    /// White up close fading to black at `far` units from the camera
+    Depth { far: f64 },
+    /// A flat color per object, derived from its id
+    ObjectId,
+    /// White where the camera sees an object, black where it doesn't. Averaged over several
+    /// samples it's the alpha to composite the image over a background with.
+    Coverage,
+}
+
+/// Bright, well spread color for `RenderMode::ObjectId`, the same for an object every render
+fn object_color(id: Uuid) -> Color {
+    let (high, low) = id.as_u64_pair();
+    let mut rng = Rng::from_seed(high ^ low);
+    Color::new(
+        rng.range(0.2, 1.0),
+        rng.range(0.2, 1.0),
+        rng.range(0.2, 1.0),
+    )
+}
+
+/// How close in front of the camera points still get projected onto the canvas
+pub const NEAR_PLANE: f64 = 1e-3;
+
+/// Maps the pixels of a canvas onto a view of the world
+///
+/// The camera sits at the origin looking down -z, with the canvas one unit in front of it.
+/// Moving the camera around is done with `set_transform`, usually with a `view_transform`.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f64,
+    transform: M4x4,
+    // cached so every ray doesn't have to invert the transform again
+    inverse_transform: M4x4,
+    half_width: f64,
+    half_height: f64,
+    pixel_size: f64,
+    // rays per pixel, more than one jitters them across the pixel for antialiasing
+    samples: usize,
+    adaptive: Option<Adaptive>,
+    seed: u64,
+    mode: RenderMode,
+    filter: Filter,
+    exposure: Exposure,
+    tone_map: ToneMap,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+        // the canvas is one unit away, so half of it spans tan(fov / 2) units across
+        // the larger of the two dimensions
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f64 / vsize as f64;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            half_width,
+            half_height,
+            pixel_size: (half_width * 2.0) / hsize as f64,
+            samples: 1,
+            adaptive: None,
+            seed: 0,
+            mode: RenderMode::Shaded,
+            filter: Filter::default(),
+            exposure: Exposure::default(),
+            tone_map: ToneMap::default(),
+        }
+    }
+
+    /// Same view at a different size, e.g. a quick low resolution pass while the camera is
+    /// being moved around, followed by the full size render once it settles
+    pub fn with_resolution(&self, hsize: usize, vsize: usize) -> Camera {
+        Camera {
+            transform: self.transform,
+            inverse_transform: self.inverse_transform,
+            samples: self.samples,
+            adaptive: self.adaptive,
+            seed: self.seed,
+            mode: self.mode,
+            filter: self.filter,
+            exposure: self.exposure,
+            tone_map: self.tone_map,
+            ..Camera::new(hsize, vsize, self.field_of_view)
+        }
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn pixel_size(&self) -> f64 {
+        self.pixel_size
+    }
+
+    pub fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Rays averaged per pixel. With more than one they are jittered randomly across the pixel,
+    /// which smooths out jagged edges.
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    pub fn adaptive(&self) -> Option<Adaptive> {
+        self.adaptive
+    }
+
+    /// Stops tracing rays into a pixel once it's no longer noisy, making `samples` the most a
+    /// pixel gets rather than what every pixel gets. See the `adaptive` module.
+    pub fn set_adaptive(&mut self, adaptive: Option<Adaptive>) {
+        self.adaptive = adaptive;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Seed for everything random in a render, the same seed always gives the same image
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// How samples are weighted into pixels, plain averaging over each pixel by default.
+    /// Filters wider than half a pixel also count samples toward the pixels next to theirs.
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    pub fn exposure(&self) -> Exposure {
+        self.exposure
+    }
+
+    /// Brightens or darkens finished renders, `Exposure::Auto` meters each image by itself.
+    /// Tiles rendered on their own are left as they are.
+    pub fn set_exposure(&mut self, exposure: Exposure) {
+        self.exposure = exposure;
+    }
+
+    pub fn tone_map(&self) -> ToneMap {
+        self.tone_map
+    }
+
+    /// How exposed colors are brought into displayable range, see `ToneMap`
+    pub fn set_tone_map(&mut self, tone_map: ToneMap) {
+        self.tone_map = tone_map;
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        self.mode = mode;
+    }
+
+    /// Sets the view transform, failing if it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// Where a point in the world lands on the canvas, in pixels from its top left corner
+    /// (possibly off the canvas), `None` for points behind the camera
+    pub fn project(&self, point: Point) -> Option<(f64, f64)> {
+        let p = self.transform * point;
+        if p.z > -NEAR_PLANE {
+            return None;
+        }
+        // scale onto the canvas one unit in front of the camera
+        let (x, y) = (p.x / -p.z, p.y / -p.z);
+        Some((
+            (self.half_width - x) / self.pixel_size,
+            (self.half_height - y) / self.pixel_size,
+        ))
+    }
+
+    /// Ray from the camera through the center of the given pixel
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
+        self.ray_through(px as f64 + 0.5, py as f64 + 0.5)
+    }
+
+    /// Rays through the center of every pixel of the tile, row by row
+    pub fn tile_rays(&self, tile: &Tile) -> RayPacket {
+        (tile.y..tile.y + tile.height)
+            .flat_map(|y| (tile.x..tile.x + tile.width).map(move |x| self.ray_for_pixel(x, y)))
+            .collect()
+    }
+
+    /// Ray from the camera through a point on the canvas, in pixels from its top left corner
+    fn ray_through(&self, x: f64, y: f64) -> Ray {
+        // offset from the edge of the canvas to the point
+        let xoffset = x * self.pixel_size;
+        let yoffset = y * self.pixel_size;
+
+        // untransformed coordinates of the pixel in world space,
+        // the camera looks toward -z so +x is to the left
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let pixel = self.inverse_transform * Point::new_point(world_x, world_y, -1.0);
+        let origin = self.inverse_transform * Point::new_point(0.0, 0.0, 0.0);
+        let direction = (pixel - origin).normalize();
+        Ray::new(origin, direction)
+    }
+
+    /// Color of a single pixel, averaged over `samples` rays, or weighted by the camera's
+    /// filter over the samples of the pixels around it
+    pub fn color_at_pixel(&self, world: &World, px: usize, py: usize) -> Color {
+        if self.filter != Filter::default() {
+            let pixel = Tile {
+                x: px,
+                y: py,
+                width: 1,
+                height: 1,
+            };
+            return self.render_filtered(world, &pixel).0[0];
+        }
+        self.pixel_with_stats(world, px, py).0
+    }
+
+    fn pixel_with_stats(&self, world: &World, px: usize, py: usize) -> (Color, PixelStats) {
+        let mut total = Color::BLACK;
+        let stats = self.for_each_sample(world, px, py, |_, _, color| total += color);
+        (total * (1.0 / stats.samples as f64), stats)
+    }
+
+    /// Traces the pixel's rays, calling `f` with where each crossed the canvas and the color
+    /// it saw. A single ray goes through the center, more are jittered across the pixel, and
+    /// stop early once the pixel converges with adaptive sampling. The samples are the same
+    /// every time for the same seed.
+    fn for_each_sample(
+        &self,
+        world: &World,
+        px: usize,
+        py: usize,
+        mut f: impl FnMut(f64, f64, Color),
+    ) -> PixelStats {
+        let mut rng = Rng::for_pixel(self.seed, px, py);
+        let mut stats = PixelStats::default();
+        if self.samples == 1 {
+            let (x, y) = (px as f64 + 0.5, py as f64 + 0.5);
+            let color = self.color_along(world, &self.ray_through(x, y), &mut rng);
+            stats.add(color);
+            f(x, y, color);
+            return stats;
+        }
+        for _ in 0..self.samples {
+            let (x, y) = (px as f64 + rng.next_f64(), py as f64 + rng.next_f64());
+            let color = self.color_along(world, &self.ray_through(x, y), &mut rng);
+            stats.add(color);
+            f(x, y, color);
+            if self
+                .adaptive
+                .is_some_and(|adaptive| stats.converged(&adaptive))
+            {
+                break;
+            }
+        }
+        stats
+    }
+
+    /// Colors of the pixels in a tile, each the filter weighted average of every sample within
+    /// its radius. Samples of pixels just outside the tile are traced again rather than shared
+    /// with the neighboring tile, so tiles still render independently.
+    fn render_filtered(&self, world: &World, tile: &Tile) -> (Vec<Color>, Vec<PixelStats>) {
+        let radius = self.filter.radius();
+        // how many pixels away a sample can land and still count, never further than across
+        // the image however wide the filter
+        let reach = ((radius - 0.5).ceil().max(0.0) as usize).min(self.hsize.max(self.vsize));
+        let (right, bottom) = (tile.x + tile.width, tile.y + tile.height);
+        let mut totals = vec![(Color::BLACK, 0.0); tile.width * tile.height];
+        let mut stats = vec![PixelStats::default(); tile.width * tile.height];
+        for sy in tile.y.saturating_sub(reach)..(bottom + reach).min(self.vsize) {
+            for sx in tile.x.saturating_sub(reach)..(right + reach).min(self.hsize) {
+                let pixel_stats = self.for_each_sample(world, sx, sy, |x, y, color| {
+                    for py in sy.saturating_sub(reach).max(tile.y)..(sy + reach + 1).min(bottom) {
+                        for px in sx.saturating_sub(reach).max(tile.x)..(sx + reach + 1).min(right)
+                        {
+                            let weight = self
+                                .filter
+                                .weight(x - (px as f64 + 0.5), y - (py as f64 + 0.5));
+                            let total = &mut totals[(py - tile.y) * tile.width + px - tile.x];
+                            total.0 += color * weight;
+                            total.1 += weight;
+                        }
+                    }
+                });
+                if (tile.x..right).contains(&sx) && (tile.y..bottom).contains(&sy) {
+                    stats[(sy - tile.y) * tile.width + sx - tile.x] = pixel_stats;
+                }
+            }
+        }
+        let pixels = totals
+            .into_iter()
+            .map(|(color, weight)| {
+                if weight > 0.0 {
+                    color * (1.0 / weight)
+                } else {
+                    Color::BLACK
+                }
+            })
+            .collect();
+        (pixels, stats)
+    }
+
+    /// Color seen along the ray in the camera's mode, black for misses in the debug modes
+    fn color_along(&self, world: &World, ray: &Ray, rng: &mut Rng) -> Color {
+        let unlit =
+            |shade: &dyn Fn(RayHit) -> Color| world.first_hit(ray).map_or(Color::BLACK, shade);
+        match self.mode {
+            RenderMode::Shaded => world.color_at_with(ray, rng),
+            RenderMode::Normals => unlit(&|hit| {
+                Color::new(
+                    (hit.normal.x + 1.0) / 2.0,
+                    (hit.normal.y + 1.0) / 2.0,
+                    (hit.normal.z + 1.0) / 2.0,
+                )
+            }),
+            RenderMode::Depth { far } => unlit(&|hit| {
+                let shade = (1.0 - hit.distance / far).clamp(0.0, 1.0);
+                Color::new(shade, shade, shade)
+            }),
+            RenderMode::ObjectId => unlit(&|hit| object_color(hit.object)),
+            RenderMode::Coverage => unlit(&|_| Color::WHITE),
+        }
+    }
+
+    /// Splits the image into tiles of at most `TILE_SIZE` x `TILE_SIZE` pixels, row by row from the top left
+    pub fn tiles(&self) -> Vec<Tile> {
+        self.tiles_between(0, self.vsize)
+    }
+
+    /// Tiles covering the rows from `top` up to `bottom`
+    fn tiles_between(&self, top: usize, bottom: usize) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for y in (top..bottom).step_by(TILE_SIZE) {
+            for x in (0..self.hsize).step_by(TILE_SIZE) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: TILE_SIZE.min(self.hsize - x),
+                    height: TILE_SIZE.min(bottom - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Colors of the pixels in a tile, row by row
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip_all, fields(x = tile.x, y = tile.y))
+    )]
+    pub fn render_tile(&self, world: &World, tile: &Tile) -> Vec<Color> {
+        self.render_tile_with_stats(world, tile).0
+    }
+
+    /// The tile's pixels along with how many samples each took and how noisy they were
+    fn render_tile_with_stats(&self, world: &World, tile: &Tile) -> (Vec<Color>, Vec<PixelStats>) {
+        if self.filter != Filter::default() {
+            return self.render_filtered(world, tile);
+        }
+        let mut pixels = Vec::with_capacity(tile.width * tile.height);
+        let mut stats = Vec::with_capacity(tile.width * tile.height);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                let (color, pixel_stats) = self.pixel_with_stats(world, x, y);
+                pixels.push(color);
+                stats.push(pixel_stats);
+            }
+        }
+        (pixels, stats)
+    }
+
+    /// Renders the world `samples` rays per pixel
+    ///
+    /// Tiles are rendered in parallel, except on wasm32 where there are no threads to spread them over.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(width = self.hsize, height = self.vsize))
+    )]
+    pub fn render(&self, world: &World) -> Canvas {
+        let tiles = self.tiles();
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let rendered = self.render_tiles(&tiles, |tile| self.render_tile(world, tile));
+        for (tile, pixels) in tiles.iter().zip(rendered) {
+            tile.write_to(&mut image, &pixels);
+        }
+        self.develop(&mut image);
+        image
+    }
+
+    /// Renders like `render`, also returning how many samples each pixel took and how noisy it
+    /// came out, to see where adaptive sampling spent its rays
+    pub fn render_with_sample_map(&self, world: &World) -> (Canvas, SampleMap) {
+        let tiles = self.tiles();
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut map = SampleMap::new(self.hsize, self.vsize);
+        let rendered = self.render_tiles(&tiles, |tile| self.render_tile_with_stats(world, tile));
+        for (tile, (pixels, stats)) in tiles.iter().zip(rendered) {
+            tile.write_to(&mut image, &pixels);
+            for (i, pixel_stats) in stats.into_iter().enumerate() {
+                map.set(
+                    tile.x + i % tile.width,
+                    tile.y + i / tile.width,
+                    pixel_stats,
+                );
+            }
+        }
+        self.develop(&mut image);
+        (image, map)
+    }
+
+    /// Runs `render` on every tile, in parallel where there are threads
+    fn render_tiles<T: Send>(
+        &self,
+        tiles: &[Tile],
+        render: impl Fn(&Tile) -> T + Sync + Send,
+    ) -> Vec<T> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let rendered = tiles.par_iter().map(render).collect();
+        #[cfg(target_arch = "wasm32")]
+        let rendered = tiles.iter().map(render).collect();
+        rendered
+    }
+
+    /// Renders the image `BAND_HEIGHT` rows at a time from the top, handing each finished band
+    /// to `write` along with the row it starts at. Only one band is kept in memory, so the
+    /// image can be far bigger than a whole canvas would fit in RAM.
+    ///
+    /// Auto exposure can't look at the whole image before the first band goes out, so it's
+    /// metered from a small preview render instead, see `exposure::meter`.
+    pub fn render_in_bands(
+        &self,
+        world: &World,
+        mut write: impl FnMut(usize, &Canvas) -> Result<()>,
+    ) -> Result<()> {
+        let multiplier = match self.exposure {
+            Exposure::Auto { key } => exposure::meter(self, world, key),
+            Exposure::Fixed(exposure) => exposure,
+        };
+        for top in (0..self.vsize).step_by(BAND_HEIGHT) {
+            let bottom = (top + BAND_HEIGHT).min(self.vsize);
+            let tiles = self.tiles_between(top, bottom);
+            let mut band = Canvas::new(self.hsize, bottom - top);
+            let rendered = self.render_tiles(&tiles, |tile| self.render_tile(world, tile));
+            for (tile, pixels) in tiles.iter().zip(rendered) {
+                let within_band = Tile {
+                    y: tile.y - top,
+                    ..*tile
+                };
+                within_band.write_to(&mut band, &pixels);
+            }
+            if multiplier != 1.0 || self.tone_map != ToneMap::Linear {
+                exposure::develop(&mut band, multiplier, self.tone_map);
+            }
+            write(top, &band)?;
+        }
+        Ok(())
+    }
+
+    /// Renders straight into a PPM file band by band, for poster-size images, see
+    /// `render_in_bands`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_to_ppm(&self, world: &World, filename: &str) -> Result<()> {
+        let mut ppm = PpmWriter::create(filename, self.hsize, self.vsize)?;
+        self.render_in_bands(world, |_, band| ppm.write_band(band))?;
+        ppm.finish()
+    }
+
+    /// Applies the exposure and tone map to a finished render
+    fn develop(&self, image: &mut Canvas) {
+        if self.exposure == Exposure::default() && self.tone_map == ToneMap::Linear {
+            return;
+        }
+        let multiplier = self.exposure.multiplier(image);
+        exposure::develop(image, multiplier, self.tone_map);
+    }
+
+    /// Renders like `render`, also recording how long each tile took and how many rays it cast
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_with_metrics(&self, world: &World) -> (Canvas, RenderMetrics) {
+        let start = Instant::now();
+        let tiles = self.tiles();
+        let rendered: Vec<(Vec<Color>, TileMetrics)> = tiles
+            .par_iter()
+            .map(|tile| {
+                let tile_start = Instant::now();
+                // a tile is rendered start to finish on one thread, so the thread's count
+                // going up is this tile's rays
+                let rays_before = metrics::rays_cast();
+                let (pixels, stats) = self.render_tile_with_stats(world, tile);
+                let tile_metrics = TileMetrics {
+                    tile: *tile,
+                    duration: tile_start.elapsed(),
+                    rays: metrics::rays_cast() - rays_before,
+                    samples: stats.iter().map(|s| s.samples as u64).sum(),
+                };
+                (pixels, tile_metrics)
+            })
+            .collect();
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut render_metrics = RenderMetrics::default();
+        for (tile, (pixels, tile_metrics)) in tiles.iter().zip(rendered) {
+            tile.write_to(&mut image, &pixels);
+            render_metrics.tiles.push(tile_metrics);
+        }
+        self.develop(&mut image);
+        render_metrics.duration = start.elapsed();
+        (image, render_metrics)
+    }
+
+    /// Renders the world straight into an 8 bit RGBA buffer, see `Canvas::to_rgba`
+    ///
+    /// Meant for hosts without a filesystem, e.g. drawing into a browser canvas from wasm.
+    pub fn render_to_rgba(&self, world: &World) -> Vec<u8> {
+        self.render(world).to_rgba()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::adaptive::Adaptive;
+    use crate::camera::{Camera, RenderMode, Tile, BAND_HEIGHT, TILE_SIZE};
+    use crate::color::Color;
+    use crate::exposure::{auto_exposure, meter, Exposure, MIDDLE_GREY};
+    use crate::filter::Filter;
+    use crate::matrix::IDENTITY_MATRIX_4X4;
+    use crate::matrix_transformations::{rotation_y, translation, view_transform};
+    use crate::tuple::{Point, Vector};
+    use crate::utils::equal_f64;
+    use crate::world::default_world;
+
+    #[test]
+    fn constructing_a_camera() {
+        let c = Camera::new(160, 120, PI / 2.0);
+        assert_eq!(c.hsize(), 160);
+        assert_eq!(c.vsize(), 120);
+        assert_eq!(c.field_of_view(), PI / 2.0);
+        assert_eq!(c.transform(), IDENTITY_MATRIX_4X4);
+    }
+
+    #[test]
+    fn pixel_size_for_horizontal_canvas() {
+        let c = Camera::new(200, 125, PI / 2.0);
+        assert!(equal_f64(c.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn pixel_size_for_vertical_canvas() {
+        let c = Camera::new(125, 200, PI / 2.0);
+        assert!(equal_f64(c.pixel_size(), 0.01));
+    }
+
+    #[test]
+    fn changing_resolution_keeps_the_view() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0))
+            .unwrap();
+        let small = c.with_resolution(21, 11);
+        assert_eq!((small.hsize(), small.vsize()), (21, 11));
+        assert_eq!(small.transform(), c.transform());
+        assert_eq!(
+            small.ray_for_pixel(10, 5).direction(),
+            c.ray_for_pixel(100, 50).direction()
+        );
+    }
+
+    #[test]
+    fn debug_render_modes() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        assert_eq!(c.mode(), RenderMode::Shaded);
+
+        c.set_mode(RenderMode::Normals);
+        assert_eq!(c.color_at_pixel(&w, 5, 5), Color::new(0.5, 0.5, 0.0));
+        assert_eq!(c.color_at_pixel(&w, 0, 0), Color::BLACK);
+        assert_eq!(c.with_resolution(5, 5).mode(), RenderMode::Normals);
+
+        c.set_mode(RenderMode::Depth { far: 10.0 });
+        assert_eq!(c.color_at_pixel(&w, 5, 5), Color::new(0.6, 0.6, 0.6));
+
+        c.set_mode(RenderMode::ObjectId);
+        let outer = c.color_at_pixel(&w, 5, 5);
+        assert_ne!(outer, Color::BLACK);
+        assert_eq!(c.color_at_pixel(&w, 5, 4), outer, "same object, same color");
+        let mut inner_only = default_world();
+        inner_only.objects.remove(0);
+        assert_ne!(c.color_at_pixel(&inner_only, 5, 5), outer);
+
+        c.set_mode(RenderMode::Coverage);
+        assert_eq!(c.color_at_pixel(&w, 5, 5), Color::WHITE);
+        assert_eq!(c.color_at_pixel(&w, 0, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn ray_through_center_of_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin(), Point::new_point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction(), Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn ray_through_corner_of_canvas() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(r.origin(), Point::new_point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction(), Vector::new(0.66519, 0.33259, -0.66851));
+    }
+
+    #[test]
+    fn ray_when_camera_is_transformed() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0))
+            .unwrap();
+        let r = c.ray_for_pixel(100, 50);
+        assert_eq!(r.origin(), Point::new_point(0.0, 2.0, -5.0));
+        let half_sqrt = 2.0_f64.sqrt() / 2.0;
+        assert_eq!(r.direction(), Vector::new(half_sqrt, 0.0, -half_sqrt));
+    }
+
+    #[test]
+    fn rendering_world_with_camera() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Point::new_point(0.0, 0.0, -5.0);
+        let to = Point::new_point(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        c.set_transform(view_transform(from, to, up)).unwrap();
+        let image = c.render(&w);
+        assert_eq!(
+            image.get_pixel(5, 5),
+            Some(Color::new(0.38066, 0.47583, 0.2855))
+        );
+    }
+
+    #[test]
+    fn splitting_into_tiles() {
+        let c = Camera::new(TILE_SIZE * 2 + 3, TILE_SIZE + 1, PI / 2.0);
+        let tiles = c.tiles();
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(
+            tiles[2],
+            Tile {
+                x: TILE_SIZE * 2,
+                y: 0,
+                width: 3,
+                height: TILE_SIZE
+            }
+        );
+        assert_eq!(
+            tiles[5],
+            Tile {
+                x: TILE_SIZE * 2,
+                y: TILE_SIZE,
+                width: 3,
+                height: 1
+            }
+        );
+        let covered: usize = tiles.iter().map(|t| t.width * t.height).sum();
+        assert_eq!(covered, c.hsize() * c.vsize());
+    }
+
+    #[test]
+    fn rendering_to_rgba_buffer() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Point::new_point(0.0, 0.0, -5.0);
+        let to = Point::new_point(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        c.set_transform(view_transform(from, to, up)).unwrap();
+        let rgba = c.render_to_rgba(&w);
+        assert_eq!(rgba.len(), 11 * 11 * 4);
+        let center = (5 * 11 + 5) * 4;
+        assert_eq!(&rgba[center..center + 4], &[98, 122, 73, 255]);
+        // the corners miss the spheres
+        assert_eq!(&rgba[0..4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn antialiasing_is_reproducible_from_the_seed() {
+        let world = default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        let single = c.render(&world);
+
+        c.set_samples(8);
+        c.set_seed(3);
+        let image = c.render(&world);
+        assert_eq!(image.to_rgba(), c.render(&world).to_rgba());
+        // jittering barely changes a pixel in the middle of the sphere
+        let center = image.get_pixel(5, 5).unwrap();
+        assert!((center.red() - single.get_pixel(5, 5).unwrap().red()).abs() < 0.05);
+
+        // while pixels along its edge come out differently with another seed
+        c.set_seed(4);
+        assert_ne!(c.render(&world).to_rgba(), image.to_rgba());
+    }
+
+    #[test]
+    fn auto_exposure_undoes_dim_lights() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.set_exposure(Exposure::Auto { key: MIDDLE_GREY });
+        let bright = default_world();
+        let mut dim = default_world();
+        dim.lights[0].set_intensity(Color::new(0.01, 0.01, 0.01));
+        let (a, b) = (c.render(&bright), c.render(&dim));
+        let (a, b) = (a.get_pixel(5, 5).unwrap(), b.get_pixel(5, 5).unwrap());
+        assert!((a.red() - b.red()).abs() < 1e-6, "{} {}", a, b);
+        // a quick preview meters about the same exposure as the full image
+        // the spheres nearly fill a narrower view, so the tiny preview still sees them
+        let mut c = Camera::new(48, 48, PI / 6.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        let metered = meter(&c, &dim, MIDDLE_GREY);
+        let full = auto_exposure(&c.render(&dim), MIDDLE_GREY);
+        assert!((metered / full - 1.0).abs() < 0.25, "{} {}", metered, full);
+    }
+
+    #[test]
+    fn filters_spread_samples_across_tiles() {
+        let world = default_world();
+        let mut c = Camera::new(TILE_SIZE + 4, 8, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.set_samples(2);
+        let plain = c.render(&world);
+
+        c.set_filter(Filter::Tent { radius: 1.5 });
+        let image = c.render(&world);
+        // the same samples as rendering pixel by pixel, even next to the seam between tiles
+        for x in TILE_SIZE - 2..TILE_SIZE + 2 {
+            assert_eq!(image.get_pixel(x, 4), Some(c.color_at_pixel(&world, x, 4)));
+        }
+        assert_ne!(image.to_rgba(), plain.to_rgba());
+        // flat areas stay the same, the corners miss the spheres either way
+        assert_eq!(image.get_pixel(0, 0), Some(Color::BLACK));
+        let center = image.get_pixel(10, 4).unwrap();
+        assert!((center.red() - plain.get_pixel(10, 4).unwrap().red()).abs() < 0.05);
+
+        // a filter wider than the image averages all of it into every pixel
+        let mut small = Camera::new(3, 2, PI / 2.0);
+        small.set_filter(Filter::Box {
+            radius: f64::INFINITY,
+        });
+        let image = small.render(&world);
+        assert_eq!(image.get_pixel(0, 0), image.get_pixel(2, 1));
+    }
+
+    #[test]
+    fn adaptive_sampling_spends_rays_on_edges() {
+        let world = default_world();
+        let mut c = Camera::new(16, 16, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.set_samples(32);
+        c.set_adaptive(Some(Adaptive::default()));
+        let (image, map) = c.render_with_sample_map(&world);
+        assert_eq!(image.to_rgba(), c.render(&world).to_rgba());
+        // the empty corner settles as soon as it can, the sphere's edge takes every ray
+        assert_eq!(map.samples(0, 0), 4);
+        assert_eq!(map.variance(0, 0), 0.0);
+        let most = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .map(|(x, y)| map.samples(x, y))
+            .max();
+        assert_eq!(most, Some(32));
+        assert!(map.total_samples() < 16 * 16 * 32 / 2);
+    }
+
+    #[test]
+    fn rendering_in_bands() {
+        let world = default_world();
+        let mut c = Camera::new(24, BAND_HEIGHT + 10, PI / 2.0);
+        c.set_transform(view_transform(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        c.set_samples(2);
+        c.set_filter(Filter::Tent { radius: 1.5 });
+        c.set_exposure(Exposure::Fixed(2.0));
+        let whole = c.render(&world);
+
+        let mut bands = Vec::new();
+        c.render_in_bands(&world, |top, band| {
+            bands.push((top, band.height()));
+            for y in 0..band.height() {
+                for x in 0..band.width() {
+                    assert_eq!(band.get_pixel(x, y), whole.get_pixel(x, top + y));
+                }
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(bands, vec![(0, BAND_HEIGHT), (BAND_HEIGHT, 10)]);
+    }
+}
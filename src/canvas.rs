@@ -1,9 +1,15 @@
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
-use std::io::Write;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{BufWriter, Write};
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 use crate::color::Color;
+use crate::error::{Error, Result};
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canvas {
     height: usize,
     width: usize,
@@ -18,6 +24,14 @@ impl Canvas {
             pixels: [Color::default()].repeat(width * height),
         }
     }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
     pub fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
         self.pixels.get(x + y * self.width).copied()
     }
@@ -32,7 +46,108 @@ impl Canvas {
         true
     }
 
-    pub fn to_ppm(&self, filename: &str) -> std::io::Result<()> {
+    /// The pixel at texture coordinates `u`, `v` (see `uv`), the nearest one without filtering.
+    /// Coordinates outside 0 to 1 get the edge, an empty canvas is black.
+    pub fn sample_uv(&self, u: f64, v: f64) -> Color {
+        if self.width == 0 || self.height == 0 {
+            return Color::BLACK;
+        }
+        let x = ((u * self.width as f64).max(0.0) as usize).min(self.width - 1);
+        let y = (((1.0 - v) * self.height as f64).max(0.0) as usize).min(self.height - 1);
+        self.pixels[x + y * self.width]
+    }
+
+    /// Packs the canvas into 8 bit RGBA, row by row from the top left, with every pixel opaque
+    ///
+    /// This is the layout browsers expect for `ImageData`, and what most image crates take as a raw buffer.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            let scaled = pixel.scale();
+            rgba.extend_from_slice(&[
+                scaled.red() as u8,
+                scaled.green() as u8,
+                scaled.blue() as u8,
+                u8::MAX,
+            ]);
+        }
+        rgba
+    }
+
+    /// Shrinks (or stretches) the canvas to the given size, averaging the pixels that fall
+    /// into each new one
+    pub fn resize(&self, width: usize, height: usize) -> Canvas {
+        let mut resized = Canvas::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return resized;
+        }
+        // range of source pixels covered by target pixel i, at least one wide
+        let span = |i: usize, target: usize, source: usize| {
+            let start = i * source / target;
+            start..((i + 1) * source / target).max(start + 1)
+        };
+        for y in 0..height {
+            let rows = span(y, height, self.height);
+            for x in 0..width {
+                let columns = span(x, width, self.width);
+                let count = (rows.len() * columns.len()) as f64;
+                let sum: Color = rows
+                    .clone()
+                    .flat_map(|sy| columns.clone().map(move |sx| (sx, sy)))
+                    .filter_map(|(sx, sy)| self.get_pixel(sx, sy))
+                    .sum();
+                resized.write_pixel(x, y, sum * (1.0 / count));
+            }
+        }
+        resized
+    }
+
+    /// Draws the canvas with ANSI truecolor escapes, at most `max_columns` characters wide.
+    ///
+    /// Each character is an upper half block showing two pixels, the top one as the foreground
+    /// color and the bottom one as the background, so pixels come out roughly square in a terminal.
+    pub fn to_ansi(&self, max_columns: usize) -> String {
+        let columns = self.width.min(max_columns);
+        if columns == 0 {
+            return String::new();
+        }
+        let rows = (self.height * columns).div_ceil(self.width);
+        let preview = self.resize(columns, rows);
+
+        let mut out = String::new();
+        for y in (0..rows).step_by(2) {
+            for x in 0..columns {
+                let top = preview.pixels[x + y * columns].scale();
+                out.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m",
+                    top.red(),
+                    top.green(),
+                    top.blue()
+                ));
+                // an odd number of rows leaves the last half block with the terminal's own background
+                if let Some(bottom) = preview.get_pixel(x, y + 1) {
+                    let bottom = bottom.scale();
+                    out.push_str(&format!(
+                        "\x1b[48;2;{};{};{}m",
+                        bottom.red(),
+                        bottom.green(),
+                        bottom.blue()
+                    ));
+                }
+                out.push('\u{2580}');
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+
+    /// Writing files isn't available in the browser, use `to_rgba` there instead
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, name = "export", fields(filename = filename))
+    )]
+    pub fn to_ppm(&self, filename: &str) -> Result<()> {
         let path = Path::new(filename);
         let mut file = File::create(path)?;
         let _ = file.write(self.get_ppm_header().as_bytes())?;
@@ -40,51 +155,327 @@ impl Canvas {
         Ok(())
     }
 
+    /// Writes an 8 bit RGB PNG, which unlike PPM opens in about any image viewer
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, name = "export", fields(filename = filename))
+    )]
+    pub fn to_png(&self, filename: &str) -> Result<()> {
+        let too_large = || {
+            Error::Image(format!(
+                "{}x{} is too large for a PNG",
+                self.width, self.height
+            ))
+        };
+        let width = u32::try_from(self.width).map_err(|_| too_large())?;
+        let height = u32::try_from(self.height).map_err(|_| too_large())?;
+        let file = BufWriter::new(File::create(filename)?);
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let rgb: Vec<u8> = self
+            .to_rgba()
+            .chunks(4)
+            .flat_map(|pixel| &pixel[..3])
+            .copied()
+            .collect();
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&rgb).map_err(png_error)?;
+        writer.finish().map_err(png_error)
+    }
+
+    /// Writes a Radiance RGBE (.hdr) image. Unlike `to_ppm` and `to_png` it keeps colors brighter
+    /// than white as they are, for tone mapping or bloom afterwards.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, name = "export", fields(filename = filename))
+    )]
+    pub fn to_hdr(&self, filename: &str) -> Result<()> {
+        let mut file = BufWriter::new(File::create(filename)?);
+        file.write_all(&self.get_hdr_data())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// The whole .hdr file `to_hdr` writes, header and pixels
+    pub fn get_hdr_data(&self) -> Vec<u8> {
+        let mut hdr = format!(
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )
+        .into_bytes();
+        for row in self.pixels.chunks(self.width.max(1)) {
+            push_hdr_row(&mut hdr, row);
+        }
+        hdr
+    }
+
+    /// Reads back a plain (P3) PPM like `to_ppm` writes, e.g. a pass to composite
+    pub fn from_ppm(ppm: &str) -> Result<Canvas> {
+        let invalid = |message: &str| Error::Image(format!("not a plain PPM, {}", message));
+        // comments run from a # to the end of the line
+        let mut fields = ppm
+            .lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace());
+        if fields.next() != Some("P3") {
+            return Err(invalid("it doesn't start with P3"));
+        }
+        let mut number = || -> Result<usize> {
+            fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(|| invalid("a number is missing"))
+        };
+        let (width, height, max) = (number()?, number()?, number()?);
+        // every pixel takes at least 6 characters, "0 0 0 ", checked before allocating for a
+        // header that claims more than the file could hold
+        if max == 0 || width.saturating_mul(height) > ppm.len() / 6 + 1 {
+            return Err(invalid("the header is out of range"));
+        }
+        let mut canvas = Canvas::new(width, height);
+        let max = max as f64;
+        for i in 0..width * height {
+            let (r, g, b) = (number()?, number()?, number()?);
+            canvas.pixels[i] = Color::new(r as f64 / max, g as f64 / max, b as f64 / max);
+        }
+        Ok(canvas)
+    }
+
+    /// Reads a PPM file, see `from_ppm`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_ppm(filename: &str) -> Result<Canvas> {
+        Canvas::from_ppm(&std::fs::read_to_string(filename)?)
+    }
+
+    /// Reads a PNG of any color type, e.g. a texture. Channels are taken as stored, 0 to 255
+    /// becoming 0 to 1 with no gamma undone, and alpha is dropped.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_png(filename: &str) -> Result<Canvas> {
+        let mut decoder = png::Decoder::new(File::open(filename)?);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(png_read_error)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(png_read_error)?;
+        let (width, height) = (info.width as usize, info.height as usize);
+        let channels = info.color_type.samples();
+        let mut canvas = Canvas::new(width, height);
+        let rows = buffer.chunks(info.line_size).take(height);
+        for (pixels, row) in canvas.pixels.chunks_mut(width.max(1)).zip(rows) {
+            for (pixel, samples) in pixels.iter_mut().zip(row.chunks(channels)) {
+                let channel = |i: usize| samples[i] as f64 / 255.0;
+                *pixel = if channels < 3 {
+                    Color::new(channel(0), channel(0), channel(0))
+                } else {
+                    Color::new(channel(0), channel(1), channel(2))
+                };
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Reads a .png or a PPM, going by the file's extension
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(filename: &str) -> Result<Canvas> {
+        let is_png = Path::new(filename)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if is_png {
+            Canvas::read_png(filename)
+        } else {
+            Canvas::read_ppm(filename)
+        }
+    }
+
     pub fn get_ppm_header(&self) -> String {
         format!("P3\n{} {}\n255\n", self.width, self.height)
     }
 
-    /// PPM doesnt allow lines longer than 70
+    /// PPM doesnt allow lines longer than 70. Empty for a canvas with no pixels.
     pub fn get_ppm_pixel_data(&self) -> String {
+        if self.width == 0 {
+            return String::new();
+        }
         // Initiate with very bold approximate size
         let mut content_lines: String = String::with_capacity(self.width * self.width);
         self.pixels
             .chunks(self.width) // chunk by pixel line
-            .for_each(|l| {
-                l.iter().fold(0, |current_line_size, c| {
-                    let raw_scaled_color = format!("{}", c.scale());
-                    let raw_scaled_color_len = raw_scaled_color.chars().count();
-                    if current_line_size == 0 {
-                        // first line
-                        content_lines.push_str(&raw_scaled_color);
-                        raw_scaled_color_len
-                    } else {
-                        let next_line_size = current_line_size + raw_scaled_color_len + 1;
-                        if next_line_size <= 69 {
-                            // continue line
-                            content_lines.push(' ');
-                            content_lines.push_str(&raw_scaled_color);
-                            next_line_size
-                        } else {
-                            // new line
-                            content_lines.push('\n');
-                            content_lines.push_str(&raw_scaled_color);
-                            raw_scaled_color_len
-                        }
-                    }
-                });
-                // separate lines
-                content_lines.push('\n');
-            });
+            .for_each(|l| push_ppm_row(&mut content_lines, l));
         content_lines
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn png_error(error: png::EncodingError) -> Error {
+    match error {
+        png::EncodingError::IoError(error) => Error::Io(error),
+        error => Error::Image(error.to_string()),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn png_read_error(error: png::DecodingError) -> Error {
+    match error {
+        png::DecodingError::IoError(error) => Error::Io(error),
+        error => Error::Image(error.to_string()),
+    }
+}
+
+/// A color as three 8 bit mantissas sharing the power of two they're scaled by, negative
+/// channels are written as 0
+fn rgbe(color: Color) -> [u8; 4] {
+    let (r, g, b) = (
+        color.red().max(0.0),
+        color.green().max(0.0),
+        color.blue().max(0.0),
+    );
+    let brightest = r.max(g).max(b);
+    if brightest < 1e-32 {
+        return [0; 4];
+    }
+    // brightest is in [2^(exponent - 1), 2^exponent), so its mantissa lands in [128, 256)
+    let exponent = (brightest.log2().floor() as i32).clamp(-129, 126) + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+
+/// Appends one row of pixels as RGBE. Rows the run length format can describe are written in
+/// it, a channel at a time, so a reader can't mistake a pixel for the start of a run length row.
+fn push_hdr_row(hdr: &mut Vec<u8>, row: &[Color]) {
+    let pixels: Vec<[u8; 4]> = row.iter().map(|color| rgbe(*color)).collect();
+    if !(8..0x8000).contains(&row.len()) {
+        hdr.extend(pixels.iter().flatten());
+        return;
+    }
+    hdr.extend_from_slice(&[2, 2, (row.len() >> 8) as u8, row.len() as u8]);
+    for channel in 0..4 {
+        let bytes: Vec<u8> = pixels.iter().map(|pixel| pixel[channel]).collect();
+        // no runs, only literal stretches of up to 128 bytes each led by its length
+        for stretch in bytes.chunks(128) {
+            hdr.push(stretch.len() as u8);
+            hdr.extend_from_slice(stretch);
+        }
+    }
+}
+
+/// Appends one row of pixels as PPM text, wrapped before 70 characters
+fn push_ppm_row(content_lines: &mut String, row: &[Color]) {
+    row.iter().fold(0, |current_line_size, c| {
+        let raw_scaled_color = format!("{}", c.scale());
+        let raw_scaled_color_len = raw_scaled_color.chars().count();
+        if current_line_size == 0 {
+            // first line
+            content_lines.push_str(&raw_scaled_color);
+            raw_scaled_color_len
+        } else {
+            let next_line_size = current_line_size + raw_scaled_color_len + 1;
+            if next_line_size <= 69 {
+                // continue line
+                content_lines.push(' ');
+                content_lines.push_str(&raw_scaled_color);
+                next_line_size
+            } else {
+                // new line
+                content_lines.push('\n');
+                content_lines.push_str(&raw_scaled_color);
+                raw_scaled_color_len
+            }
+        }
+    });
+    // separate lines
+    content_lines.push('\n');
+}
+
+/// Writes a PPM a band of rows at a time, top to bottom, so an image never has to be in memory
+/// all at once. Meant for poster-size renders, see `Camera::render_to_ppm`.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct PpmWriter {
+    file: BufWriter<File>,
+    width: usize,
+    height: usize,
+    rows_written: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PpmWriter {
+    /// Creates the file and writes the header for a `width` x `height` image
+    pub fn create(filename: &str, width: usize, height: usize) -> Result<PpmWriter> {
+        let mut file = BufWriter::new(File::create(Path::new(filename))?);
+        file.write_all(format!("P3\n{} {}\n255\n", width, height).as_bytes())?;
+        Ok(PpmWriter {
+            file,
+            width,
+            height,
+            rows_written: 0,
+        })
+    }
+
+    pub fn rows_written(&self) -> usize {
+        self.rows_written
+    }
+
+    /// Appends the band's rows below the ones written so far. The band has to be as wide as
+    /// the image and can't run past its bottom.
+    pub fn write_band(&mut self, band: &Canvas) -> Result<()> {
+        if band.width != self.width {
+            return Err(Error::Band(format!(
+                "band is {} pixels wide, the image {}",
+                band.width, self.width
+            )));
+        }
+        if self.rows_written + band.height > self.height {
+            return Err(Error::Band(format!(
+                "{} more rows don't fit below row {} of {}",
+                band.height, self.rows_written, self.height
+            )));
+        }
+        let mut text = String::new();
+        for row in band.pixels.chunks(self.width.max(1)) {
+            text.clear();
+            push_ppm_row(&mut text, row);
+            self.file.write_all(text.as_bytes())?;
+        }
+        self.rows_written += band.height;
+        Ok(())
+    }
+
+    /// Flushes the file, checking every row of the image was written
+    pub fn finish(mut self) -> Result<()> {
+        if self.rows_written != self.height {
+            return Err(Error::Band(format!(
+                "only {} of {} rows were written",
+                self.rows_written, self.height
+            )));
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::canvas::Canvas;
+    use crate::canvas::{Canvas, PpmWriter};
     use crate::color::Color;
 
+    #[test]
+    fn sampling_by_uv() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::RED);
+        canvas.write_pixel(1, 1, Color::BLUE);
+        // v goes up the image, row 0 is the top
+        assert_eq!(canvas.sample_uv(0.25, 0.75), Color::RED);
+        assert_eq!(canvas.sample_uv(0.75, 0.25), Color::BLUE);
+        assert_eq!(canvas.sample_uv(1.0, 0.0), Color::BLUE);
+        assert_eq!(canvas.sample_uv(-3.0, 5.0), Color::RED);
+        assert_eq!(Canvas::new(0, 0).sample_uv(0.5, 0.5), Color::BLACK);
+    }
+
     #[test]
     fn create_canvas() {
         let width = 30;
@@ -115,6 +506,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rgba_buffer() {
+        let mut test_canvas = Canvas::new(2, 2);
+        test_canvas.write_pixel(1, 0, Color::new(1.5, 0.5, 0.0));
+        test_canvas.write_pixel(0, 1, Color::new(-0.5, 0.0, 1.0));
+        let expected: Vec<u8> = vec![
+            0, 0, 0, 255, 255, 128, 0, 255, //
+            0, 0, 255, 255, 0, 0, 0, 255,
+        ];
+        assert_eq!(expected, test_canvas.to_rgba());
+    }
+
+    #[test]
+    fn resize_averages_pixels() {
+        let mut test_canvas = Canvas::new(4, 2);
+        test_canvas.write_pixel(0, 0, Color::WHITE);
+        test_canvas.write_pixel(3, 1, Color::new(0.0, 0.0, 1.0));
+        let resized = test_canvas.resize(2, 1);
+        assert_eq!(resized.get_pixel(0, 0), Some(Color::new(0.25, 0.25, 0.25)));
+        assert_eq!(resized.get_pixel(1, 0), Some(Color::new(0.0, 0.0, 0.25)));
+    }
+
+    #[test]
+    fn ansi_half_blocks() {
+        let mut test_canvas = Canvas::new(2, 3);
+        test_canvas.write_pixel(0, 0, Color::RED);
+        test_canvas.write_pixel(0, 1, Color::BLUE);
+        test_canvas.write_pixel(1, 2, Color::WHITE);
+        let expected = "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m\u{2580}\x1b[38;2;0;0;0m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n\
+                        \x1b[38;2;0;0;0m\u{2580}\x1b[38;2;255;255;255m\u{2580}\x1b[0m\n";
+        assert_eq!(expected, test_canvas.to_ansi(80));
+        // wider than allowed, so it's shrunk to fit
+        assert_eq!(Canvas::new(100, 50).to_ansi(10).lines().count(), 3);
+    }
+
     #[test]
     fn ppm_header() {
         let width = 5;
@@ -143,6 +569,12 @@ mod tests {
         assert_eq!(expected, test_canvas.get_ppm_pixel_data())
     }
 
+    #[test]
+    fn empty_canvases_have_no_pixel_data() {
+        assert_eq!(Canvas::new(0, 3).get_ppm_pixel_data(), "");
+        assert_eq!(Canvas::new(3, 0).get_ppm_pixel_data(), "");
+    }
+
     #[test]
     fn splitting_long_line_ppms() {
         let width = 10;
@@ -175,4 +607,103 @@ mod tests {
             Some("255 204 153 255 204 153 255 204 153 255 204 153 255 204 153")
         );
     }
+
+    #[test]
+    fn reading_a_ppm() {
+        let mut test_canvas = Canvas::new(5, 3);
+        test_canvas.write_pixel(0, 0, Color::RED);
+        test_canvas.write_pixel(4, 2, Color::new(0.0, 0.2, 1.0));
+        let ppm = test_canvas.get_ppm_header() + &test_canvas.get_ppm_pixel_data();
+        let read = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(read.width(), 5);
+        assert_eq!(read.get_pixel(0, 0), Some(Color::RED));
+        assert_eq!(read.get_pixel(4, 2), Some(Color::new(0.0, 0.2, 1.0)));
+
+        let commented = Canvas::from_ppm("P3 # made by hand\n1 1\n10\n5 0 10\n").unwrap();
+        assert_eq!(commented.get_pixel(0, 0), Some(Color::new(0.5, 0.0, 1.0)));
+        assert!(Canvas::from_ppm("P6\n1 1\n255\n").is_err());
+        assert!(Canvas::from_ppm("P3\n2 1\n255\n0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn writing_a_ppm_in_bands() {
+        let mut whole = Canvas::new(3, 4);
+        let mut top = Canvas::new(3, 3);
+        let mut bottom = Canvas::new(3, 1);
+        whole.write_pixel(1, 2, Color::RED);
+        top.write_pixel(1, 2, Color::RED);
+        whole.write_pixel(2, 3, Color::WHITE);
+        bottom.write_pixel(2, 0, Color::WHITE);
+
+        let path = std::env::temp_dir().join(format!("bands_{}.ppm", std::process::id()));
+        let filename = path.to_str().unwrap();
+        let mut ppm = PpmWriter::create(filename, 3, 4).unwrap();
+        ppm.write_band(&top).unwrap();
+        assert!(ppm.write_band(&Canvas::new(2, 1)).is_err());
+        assert!(ppm.write_band(&top).is_err());
+        ppm.write_band(&bottom).unwrap();
+        assert_eq!(ppm.rows_written(), 4);
+        ppm.finish().unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written,
+            whole.get_ppm_header() + &whole.get_ppm_pixel_data()
+        );
+
+        let path = std::env::temp_dir().join(format!("short_{}.ppm", std::process::id()));
+        let short = PpmWriter::create(path.to_str().unwrap(), 3, 4).unwrap();
+        assert!(short.finish().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn writing_a_png() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, Color::new(1.0, 0.5, 0.0));
+        let path = std::env::temp_dir().join(format!("canvas_{}.png", std::process::id()));
+        canvas.to_png(path.to_str().unwrap()).unwrap();
+
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, png::ColorType::Rgb);
+        assert_eq!(&pixels[15..18], &[255, 128, 0]);
+        assert!(pixels[..15].iter().all(|&channel| channel == 0));
+
+        let read = Canvas::read(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((read.width(), read.height()), (3, 2));
+        assert_eq!(
+            read.get_pixel(2, 1),
+            Some(Color::new(1.0, 128.0 / 255.0, 0.0))
+        );
+        assert_eq!(read.get_pixel(1, 1), Some(Color::BLACK));
+
+        assert!(Canvas::new(0, 0).to_png(path.to_str().unwrap()).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn hdr_keeps_colors_brighter_than_white() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25));
+        canvas.write_pixel(1, 0, Color::new(10.0, 0.0, -1.0));
+        let header = "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        let mut expected = header.as_bytes().to_vec();
+        expected.extend_from_slice(&[128, 64, 32, 129, 160, 0, 0, 132]);
+        assert_eq!(canvas.get_hdr_data(), expected);
+
+        // rows of 8 or more are run length encoded, one channel after the other
+        let mut wide = Canvas::new(8, 1);
+        wide.write_pixel(7, 0, Color::WHITE);
+        let hdr = wide.get_hdr_data();
+        let pixels = &hdr["#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".len()..];
+        assert_eq!(&pixels[..5], &[2, 2, 0, 8, 8]);
+        assert_eq!(&pixels[5..13], &[0, 0, 0, 0, 0, 0, 0, 128]);
+        assert_eq!(pixels.len(), 4 + 4 * 9);
+        assert_eq!(&pixels[31..], &[8, 0, 0, 0, 0, 0, 0, 0, 129]);
+    }
 }
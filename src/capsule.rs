@@ -0,0 +1,324 @@
+//! Capsules, every point within `radius` of the segment between two endpoints: a cylinder with
+//! a half sphere on each end, e.g. for limbs or rounded bars.
+//!
+//! The ends and radius are in the capsule's own space and its transform places it in the
+//! world, like any other primitive. It's also a `DistanceEstimator`, so it can be marched next
+//! to the fractals in `sdf`, in its own space.
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, Material};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::rays::{Ray, Visibility};
+use crate::sdf::DistanceEstimator;
+use crate::shape::{LocalHits, Shape};
+use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+use crate::uv::UvMap;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capsule {
+    pub id: Uuid,
+    a: Point,
+    b: Point,
+    radius: f64,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    pub material: Material,
+    /// Lights that shine on the capsule, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the capsule, the others pass straight through it
+    pub visibility: Visibility,
+}
+
+impl Capsule {
+    pub fn new(a: Point, b: Point, radius: f64) -> Self {
+        Capsule {
+            id: Uuid::new_v4(),
+            a,
+            b,
+            radius,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
+        }
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The capsule as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Capsule {
+        Capsule {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn a(&self) -> Point {
+        self.a
+    }
+
+    pub fn b(&self) -> Point {
+        self.b
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// The nearest point to `point` on the segment running through the middle
+    fn closest_on_axis(&self, point: Point) -> Point {
+        let axis = self.b - self.a;
+        let length_squared = axis.dot(&axis);
+        if length_squared < EPSILON {
+            return self.a;
+        }
+        let along = ((point - self.a).dot(&axis) / length_squared).clamp(0.0, 1.0);
+        self.a + axis * along
+    }
+
+    /// Where the ray enters and leaves the capsule, the entry first, in the capsule's own space.
+    /// Both can be behind the ray's origin, like `intersect` does for spheres.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64)> {
+        // a capsule is convex, so the ray is inside it over a single stretch, from the first
+        // entry into either end's ball or the middle cylinder to the last exit from them
+        [
+            self.ball_span(ray, self.a),
+            self.ball_span(ray, self.b),
+            self.cylinder_span(ray),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(|(t0, t1), (u0, u1)| (t0.min(u0), t1.max(u1)))
+    }
+
+    fn ball_span(&self, ray: &Ray, center: Point) -> Option<(f64, f64)> {
+        let to_origin = ray.origin() - center;
+        let direction = ray.direction();
+        solve(
+            direction.dot(&direction),
+            2.0 * direction.dot(&to_origin),
+            to_origin.dot(&to_origin) - self.radius * self.radius,
+        )
+    }
+
+    /// Stretch of the ray inside the cylinder between the two ends, without the ends' balls
+    fn cylinder_span(&self, ray: &Ray) -> Option<(f64, f64)> {
+        let axis = self.b - self.a;
+        let length = axis.magnitude();
+        if length < EPSILON {
+            return None;
+        }
+        let k = axis / length;
+        let to_origin = ray.origin() - self.a;
+        let direction = ray.direction();
+        // across the axis, the ray has to be within the radius
+        let d = direction - k * direction.dot(&k);
+        let o = to_origin - k * to_origin.dot(&k);
+        let radial = if d.dot(&d) < EPSILON {
+            (o.dot(&o) <= self.radius * self.radius).then_some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            solve(
+                d.dot(&d),
+                2.0 * d.dot(&o),
+                o.dot(&o) - self.radius * self.radius,
+            )
+        }?;
+        // and along it, between the two ends
+        let (start, speed) = (to_origin.dot(&k), direction.dot(&k));
+        let axial = if speed.abs() < EPSILON {
+            (0.0..=length)
+                .contains(&start)
+                .then_some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            let (t0, t1) = (-start / speed, (length - start) / speed);
+            Some((t0.min(t1), t0.max(t1)))
+        }?;
+        let span = (radial.0.max(axial.0), radial.1.min(axial.1));
+        (span.0 <= span.1).then_some(span)
+    }
+}
+
+impl Shape for Capsule {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        let mut hits = LocalHits::new();
+        if let Some((t0, t1)) = self.intersect(ray) {
+            hits.push(t0);
+            hits.push(t1);
+        }
+        hits
+    }
+
+    /// Straight out from the segment through the middle
+    fn local_normal_at(&self, point: Point, _uv: Option<(f64, f64)>) -> Vector {
+        (point - self.closest_on_axis(point)).normalize()
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let r = Vector::new(self.radius, self.radius, self.radius);
+        let mut bounds = BoundingBox::empty();
+        for end in [self.a, self.b] {
+            bounds.add_point(end - r);
+            bounds.add_point(end + r);
+        }
+        bounds
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        UvMap::Cylindrical
+    }
+}
+
+impl PartialEq for Capsule {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl DistanceEstimator for Capsule {
+    fn distance(&self, point: Point) -> f64 {
+        (point - self.closest_on_axis(point)).magnitude() - self.radius
+    }
+}
+
+/// Both roots of `a t^2 + b t + c`, smaller first, `None` without real ones
+fn solve(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some(((-b - root) / (2.0 * a), (-b + root) / (2.0 * a)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::capsule::Capsule;
+    use crate::matrix_transformations::{rotation_z, translation};
+    use crate::rays::Ray;
+    use crate::sdf::{DistanceEstimator, Marcher};
+    use crate::shape::{Object, Shape};
+    use crate::tuple::{Point, Vector};
+    use crate::world::World;
+
+    /// Standing up along y from 0 to 2, radius 0.5
+    fn capsule() -> Capsule {
+        Capsule::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Point::new_point(0.0, 2.0, 0.0),
+            0.5,
+        )
+    }
+
+    #[test]
+    fn intersecting_a_capsule() {
+        let c = capsule();
+        // through the cylinder in the middle
+        let side = Ray::new(Point::new_point(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&side), Some((4.5, 5.5)));
+        // down the axis, through both rounded ends
+        let down = Ray::new(Point::new_point(0.0, 5.0, 0.0), Vector::new(0.0, -2.0, 0.0));
+        assert_eq!(c.intersect(&down), Some((1.25, 2.75)));
+        // through the round top, above where the cylinder ends
+        let top = Ray::new(Point::new_point(0.0, 2.3, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let (t0, t1) = c.intersect(&top).unwrap();
+        assert!((t0 - 4.6).abs() < 1e-9 && (t1 - 5.4).abs() < 1e-9);
+        let miss = Ray::new(Point::new_point(0.0, 2.6, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(c.intersect(&miss), None);
+        let beside = Ray::new(Point::new_point(0.6, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(c.intersect(&beside), None);
+    }
+
+    #[test]
+    fn capsule_normals_and_distances() {
+        let c = capsule();
+        let side = c.local_normal_at(Point::new_point(0.5, 1.0, 0.0), None);
+        assert_eq!(side, Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            c.local_normal_at(Point::new_point(0.0, 2.5, 0.0), None),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(c.distance(Point::new_point(2.0, 1.0, 0.0)), 1.5);
+        assert_eq!(c.distance(Point::new_point(0.0, -1.0, 0.0)), 0.5);
+        assert_eq!(c.local_bounds().min(), Point::new_point(-0.5, -0.5, -0.5));
+        assert_eq!(c.local_bounds().max(), Point::new_point(0.5, 2.5, 0.5));
+
+        // marching finds the same surface as intersecting
+        let ray = Ray::new(Point::new_point(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = Marcher::default().march(&c, &ray).unwrap();
+        assert!((hit.t - 4.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn capsules_in_the_world() {
+        // lying along x from 1 to 3
+        let mut c = capsule();
+        c.set_transform(translation(1.0, 0.0, 0.0) * rotation_z(-std::f64::consts::FRAC_PI_2))
+            .unwrap();
+        let mut w = World::new();
+        w.objects.push(Object::from(c));
+
+        let r = Ray::new(Point::new_point(2.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(xs.size(), 2);
+        assert!((xs[0].t - 4.5).abs() < 1e-9 && (xs[1].t - 5.5).abs() < 1e-9);
+        let normal = xs[0].object.normal_at(r.position(xs[0].t), &xs[0]);
+        assert!((normal - Vector::new(0.0, 0.0, -1.0)).magnitude() < 1e-9);
+
+        // the round end past x = 3
+        let end = Ray::new(
+            Point::new_point(10.0, 0.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+        );
+        assert!((w.intersect(&end)[0].t - 6.5).abs() < 1e-9);
+        let bounds = c.bounds();
+        assert!((bounds.max().x - 3.5).abs() < 1e-9 && (bounds.min().x - 0.5).abs() < 1e-9);
+        // where the capsule was before it was moved
+        let upright = Ray::new(Point::new_point(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.intersect(&upright).size(), 0);
+    }
+}
@@ -0,0 +1,81 @@
+//! Clipping planes for cutaway views: everything on one side of a plane is left out of the
+//! render, showing the inside of closed objects without editing them.
+//!
+//! A cut through a solid object leaves it hollow. Giving the plane a cap material fills the cut
+//! with a flat surface instead, as if the object had been sawn through.
+
+use crate::light::Material;
+use crate::rays::Ray;
+use crate::tuple::{Point, Vector};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClipPlane {
+    point: Point,
+    normal: Vector,
+    /// Fills the cut through objects, leaving them hollow when `None`
+    pub cap: Option<Material>,
+}
+
+impl ClipPlane {
+    /// The plane through `point`, removing everything on the side `normal` points to
+    pub fn new(point: Point, normal: Vector) -> Self {
+        ClipPlane {
+            point,
+            normal: normal.normalize(),
+            cap: None,
+        }
+    }
+
+    pub fn with_cap(mut self, material: Material) -> Self {
+        self.cap = Some(material);
+        self
+    }
+
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    /// Unit normal, pointing toward the removed side
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    /// Whether the point is on the removed side
+    pub fn clips(&self, point: Point) -> bool {
+        (point - self.point).dot(&self.normal) > 0.0
+    }
+
+    /// The t where the ray crosses the plane, `None` when it runs parallel to it
+    pub fn crossing(&self, ray: &Ray) -> Option<f64> {
+        let denominator = ray.direction().dot(&self.normal);
+        if denominator == 0.0 {
+            return None;
+        }
+        Some((self.point - ray.origin()).dot(&self.normal) / denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clip::ClipPlane;
+    use crate::rays::Ray;
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn points_beyond_the_plane_are_clipped() {
+        let plane = ClipPlane::new(Point::new_point(0.0, 1.0, 0.0), Vector::new(0.0, 2.0, 0.0));
+        assert_eq!(plane.normal(), Vector::new(0.0, 1.0, 0.0));
+        assert!(plane.clips(Point::new_point(5.0, 1.5, 0.0)));
+        assert!(!plane.clips(Point::new_point(5.0, 0.5, 0.0)));
+    }
+
+    #[test]
+    fn rays_crossing_the_plane() {
+        let plane = ClipPlane::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new_point(1.0, 2.0, -5.0), Vector::new(0.0, 0.0, 2.0));
+        assert_eq!(plane.crossing(&r), Some(2.5));
+        let parallel = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(plane.crossing(&parallel), None);
+    }
+}
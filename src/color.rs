@@ -1,25 +1,40 @@
 use std::fmt::{Error, Formatter};
-use std::ops::{Add, Mul, Sub};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub};
+
+use thiserror::Error;
 
 use crate::tuple::{Point, Tuple};
 
+#[derive(Error, Debug, PartialEq)]
+pub enum ColorError {
+    #[error("invalid hex color {0:?}, expected #rrggbb or #rgb")]
+    InvalidHex(String),
+}
+
+/// Laid out as four f64s (red, green, blue and an unused 1.0), so a pixel buffer can
+/// be uploaded to the GPU or passed over FFI without copying
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
 pub struct Color {
     components: Tuple,
 }
 
+// SAFETY: Color is a repr(C) wrapper around a single Tuple, which is Pod
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Color {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Color {}
+
 impl Color {
     /// Returns a new Color
-    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
         Color {
             components: Point::new_point(red, green, blue),
         }
     }
 
-    pub fn default() -> Self {
-        Color::new(0.0, 0.0, 0.0)
-    }
-
     pub fn red(&self) -> f64 {
         self.components.x
     }
@@ -30,6 +45,147 @@ impl Color {
         self.components.z
     }
 
+    /// Builds a color from 8 bit channels, the way most design tools present colors
+    pub const fn from_rgb8(red: u8, green: u8, blue: u8) -> Self {
+        Color::new(
+            red as f64 / 255.0,
+            green as f64 / 255.0,
+            blue as f64 / 255.0,
+        )
+    }
+
+    /// Parses a CSS style hex color such as `#ff8800` or the shorthand `#f80`.
+    /// The leading `#` is optional
+    pub fn from_hex(hex: &str) -> Result<Self, ColorError> {
+        let invalid = || ColorError::InvalidHex(hex.to_string());
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let channel =
+            |i: usize, len: usize| u8::from_str_radix(&digits[i * len..(i + 1) * len], 16);
+        match digits.len() {
+            6 => Ok(Color::from_rgb8(
+                channel(0, 2).map_err(|_| invalid())?,
+                channel(1, 2).map_err(|_| invalid())?,
+                channel(2, 2).map_err(|_| invalid())?,
+            )),
+            // each shorthand digit is doubled, #f80 is #ff8800
+            3 => Ok(Color::from_rgb8(
+                channel(0, 1).map_err(|_| invalid())? * 17,
+                channel(1, 1).map_err(|_| invalid())? * 17,
+                channel(2, 1).map_err(|_| invalid())? * 17,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Builds a color from hue (in degrees, wrapped into 0..360), saturation and value (0..1)
+    ///
+    /// Sweeping the hue while keeping saturation and value fixed gives evenly distinct
+    /// colors, which is handy for procedurally generated scenes.
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Builds a color from hue (in degrees, wrapped into 0..360), saturation and lightness (0..1)
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Shared tail of the HSV and HSL conversions: picks the RGB sextant the hue falls in,
+    /// then lifts every component by `min` so the lightest/darkest component is right
+    fn from_hue_chroma(hue: f64, chroma: f64, min: f64) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        Color::new(r + min, g + min, b + min)
+    }
+
+    /// Returns the color as (hue in degrees, saturation, value)
+    ///
+    /// Grays have no hue, they report a hue of 0
+    pub fn to_hsv(self) -> (f64, f64, f64) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (hue, saturation, max)
+    }
+
+    /// Returns the color as (hue in degrees, saturation, lightness)
+    ///
+    /// Grays have no hue, they report a hue of 0
+    pub fn to_hsl(self) -> (f64, f64, f64) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    fn hue_max_min(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (self.red(), self.green(), self.blue());
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    /// Linearly interpolates between this color (t = 0) and `other` (t = 1), used for gradients
+    pub fn lerp(self, other: Color, t: f64) -> Color {
+        self + (other - self) * t
+    }
+
+    /// Subtracts like `-` but never lets a component drop below zero,
+    /// e.g. when removing a light's contribution from an accumulated color
+    pub fn saturating_sub(self, other: Color) -> Color {
+        Color::new(
+            (self.red() - other.red()).max(0.0),
+            (self.green() - other.green()).max(0.0),
+            (self.blue() - other.blue()).max(0.0),
+        )
+    }
+
+    /// Clamps every component into 0..1
+    pub fn clamp01(self) -> Color {
+        Color::new(
+            self.red().clamp(0.0, 1.0),
+            self.green().clamp(0.0, 1.0),
+            self.blue().clamp(0.0, 1.0),
+        )
+    }
+
+    /// Perceived brightness of the color using the Rec. 709 weights, green contributes
+    /// far more to how bright a color looks than blue does
+    pub fn luminance(self) -> f64 {
+        0.2126 * self.red() + 0.7152 * self.green() + 0.0722 * self.blue()
+    }
+
+    /// The largest of the red, green and blue components
+    pub fn max_component(self) -> f64 {
+        self.red().max(self.green()).max(self.blue())
+    }
+
     pub fn scale(self) -> Color {
         Color::new(
             scale_color_val(self.red()),
@@ -39,6 +195,116 @@ impl Color {
     }
 }
 
+/// Defaults to black, the color of a canvas nothing has been drawn on yet
+impl Default for Color {
+    fn default() -> Self {
+        Color::BLACK
+    }
+}
+
+/// Common colors, so scenes and tests can say `Color::WHITE` instead of `Color::new(1.0, 1.0, 1.0)`
+///
+/// The primaries are full intensity channels, so unlike CSS "green" (half intensity)
+/// `GREEN` is the same color as CSS "lime", which is also available as `LIME`.
+impl Color {
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    pub const RED: Color = Color::new(1.0, 0.0, 0.0);
+    pub const GREEN: Color = Color::new(0.0, 1.0, 0.0);
+    pub const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+    pub const YELLOW: Color = Color::new(1.0, 1.0, 0.0);
+    pub const CYAN: Color = Color::new(0.0, 1.0, 1.0);
+    pub const MAGENTA: Color = Color::new(1.0, 0.0, 1.0);
+    pub const GRAY: Color = Color::new(0.5, 0.5, 0.5);
+
+    // CSS named colors
+    pub const ORANGE: Color = Color::from_rgb8(255, 165, 0);
+    pub const PURPLE: Color = Color::from_rgb8(128, 0, 128);
+    pub const PINK: Color = Color::from_rgb8(255, 192, 203);
+    pub const BROWN: Color = Color::from_rgb8(165, 42, 42);
+    pub const NAVY: Color = Color::from_rgb8(0, 0, 128);
+    pub const TEAL: Color = Color::from_rgb8(0, 128, 128);
+    pub const OLIVE: Color = Color::from_rgb8(128, 128, 0);
+    pub const MAROON: Color = Color::from_rgb8(128, 0, 0);
+    pub const LIME: Color = Color::from_rgb8(0, 255, 0);
+    pub const SILVER: Color = Color::from_rgb8(192, 192, 192);
+    pub const GOLD: Color = Color::from_rgb8(255, 215, 0);
+    pub const INDIGO: Color = Color::from_rgb8(75, 0, 130);
+    pub const VIOLET: Color = Color::from_rgb8(238, 130, 238);
+    pub const CORAL: Color = Color::from_rgb8(255, 127, 80);
+    pub const SALMON: Color = Color::from_rgb8(250, 128, 114);
+    pub const KHAKI: Color = Color::from_rgb8(240, 230, 140);
+    pub const TURQUOISE: Color = Color::from_rgb8(64, 224, 208);
+    pub const CRIMSON: Color = Color::from_rgb8(220, 20, 60);
+    pub const CHOCOLATE: Color = Color::from_rgb8(210, 105, 30);
+    pub const SKY_BLUE: Color = Color::from_rgb8(135, 206, 235);
+    pub const STEEL_BLUE: Color = Color::from_rgb8(70, 130, 180);
+    pub const FOREST_GREEN: Color = Color::from_rgb8(34, 139, 34);
+    pub const SLATE_GRAY: Color = Color::from_rgb8(112, 128, 144);
+    pub const BEIGE: Color = Color::from_rgb8(245, 245, 220);
+    pub const IVORY: Color = Color::from_rgb8(255, 255, 240);
+    pub const LAVENDER: Color = Color::from_rgb8(230, 230, 250);
+    pub const TAN: Color = Color::from_rgb8(210, 180, 140);
+    pub const ORCHID: Color = Color::from_rgb8(218, 112, 214);
+    pub const TOMATO: Color = Color::from_rgb8(255, 99, 71);
+
+    /// Looks up a named color, ignoring case, spaces, dashes and underscores,
+    /// so "Sky Blue", "sky-blue" and "SKY_BLUE" all find `Color::SKY_BLUE`
+    pub fn from_name(name: &str) -> Option<Color> {
+        let key: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        NAMED_COLORS
+            .iter()
+            .find(|(n, _)| *n == key)
+            .map(|(_, color)| *color)
+    }
+}
+
+/// Every named constant keyed by its lowercase name without separators, see `Color::from_name`
+pub const NAMED_COLORS: &[(&str, Color)] = &[
+    ("white", Color::WHITE),
+    ("black", Color::BLACK),
+    ("red", Color::RED),
+    ("green", Color::GREEN),
+    ("blue", Color::BLUE),
+    ("yellow", Color::YELLOW),
+    ("cyan", Color::CYAN),
+    ("magenta", Color::MAGENTA),
+    ("gray", Color::GRAY),
+    ("orange", Color::ORANGE),
+    ("purple", Color::PURPLE),
+    ("pink", Color::PINK),
+    ("brown", Color::BROWN),
+    ("navy", Color::NAVY),
+    ("teal", Color::TEAL),
+    ("olive", Color::OLIVE),
+    ("maroon", Color::MAROON),
+    ("lime", Color::LIME),
+    ("silver", Color::SILVER),
+    ("gold", Color::GOLD),
+    ("indigo", Color::INDIGO),
+    ("violet", Color::VIOLET),
+    ("coral", Color::CORAL),
+    ("salmon", Color::SALMON),
+    ("khaki", Color::KHAKI),
+    ("turquoise", Color::TURQUOISE),
+    ("crimson", Color::CRIMSON),
+    ("chocolate", Color::CHOCOLATE),
+    ("skyblue", Color::SKY_BLUE),
+    ("steelblue", Color::STEEL_BLUE),
+    ("forestgreen", Color::FOREST_GREEN),
+    ("slategray", Color::SLATE_GRAY),
+    ("beige", Color::BEIGE),
+    ("ivory", Color::IVORY),
+    ("lavender", Color::LAVENDER),
+    ("tan", Color::TAN),
+    ("orchid", Color::ORCHID),
+    ("tomato", Color::TOMATO),
+];
+
 impl Sub for Color {
     type Output = Self;
 
@@ -66,6 +332,46 @@ impl Mul<f64> for Color {
     }
 }
 
+/// Lets scalars go on either side, `0.5 * color` reads like the math
+impl Mul<Color> for f64 {
+    type Output = Color;
+
+    fn mul(self, color: Color) -> Color {
+        color * self
+    }
+}
+
+impl AddAssign for Color {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl MulAssign<f64> for Color {
+    fn mul_assign(&mut self, scalar: f64) {
+        *self = *self * scalar;
+    }
+}
+
+impl MulAssign for Color {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+/// Adds up colors, e.g. the contribution of every light or every sample of a pixel
+impl Sum for Color {
+    fn sum<I: Iterator<Item = Color>>(iter: I) -> Self {
+        iter.fold(Color::BLACK, |acc, c| acc + c)
+    }
+}
+
+impl<'a> Sum<&'a Color> for Color {
+    fn sum<I: Iterator<Item = &'a Color>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
 impl Mul for Color {
     type Output = Self;
 
@@ -93,6 +399,31 @@ impl PartialEq for Color {
     }
 }
 
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for Color {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.components.abs_diff_eq(&other.components, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for Color {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.components
+            .relative_eq(&other.components, epsilon, max_relative)
+    }
+}
+
 impl std::fmt::Display for Color {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(f, "{} {} {}", self.red(), self.green(), self.blue(),)
@@ -126,7 +457,9 @@ fn scale_color_val(val: f64) -> f64 {
 
 #[cfg(test)]
 mod tests {
-    use crate::color::Color;
+    use crate::color::{Color, ColorError};
+    #[cfg(feature = "approx")]
+    use approx::{assert_relative_eq, assert_relative_ne};
 
     #[test]
     fn create_color_success() {
@@ -136,6 +469,125 @@ mod tests {
         assert_eq!(test_color.blue(), 1.7);
     }
 
+    #[test]
+    fn named_constants() {
+        assert_eq!(Color::WHITE, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(Color::BLACK, Color::default());
+        assert_eq!(Color::RED, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(Color::ORANGE, Color::from_hex("#ffa500").unwrap());
+        assert_eq!(Color::SKY_BLUE, Color::from_rgb8(135, 206, 235));
+    }
+
+    #[test]
+    fn from_name_finds_named_colors() {
+        assert_eq!(Color::from_name("white"), Some(Color::WHITE));
+        assert_eq!(Color::from_name("Sky Blue"), Some(Color::SKY_BLUE));
+        assert_eq!(Color::from_name("forest-green"), Some(Color::FOREST_GREEN));
+        assert_eq!(Color::from_name("SLATE_GRAY"), Some(Color::SLATE_GRAY));
+        assert_eq!(Color::from_name("not a color"), None);
+    }
+
+    #[test]
+    fn from_rgb8_scales_channels() {
+        assert_eq!(Color::from_rgb8(255, 0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(
+            Color::from_rgb8(255, 136, 0),
+            Color::new(1.0, 136.0 / 255.0, 0.0)
+        );
+        assert_eq!(Color::from_rgb8(0, 0, 0), Color::default());
+    }
+
+    #[test]
+    fn from_hex_parses_long_and_short_forms() {
+        assert_eq!(
+            Color::from_hex("#ff8800"),
+            Ok(Color::from_rgb8(255, 136, 0))
+        );
+        assert_eq!(Color::from_hex("FF8800"), Ok(Color::from_rgb8(255, 136, 0)));
+        assert_eq!(Color::from_hex("#f80"), Ok(Color::from_rgb8(255, 136, 0)));
+        assert_eq!(Color::from_hex("#000"), Ok(Color::default()));
+    }
+
+    #[test]
+    fn from_hex_rejects_malformed_input() {
+        for bad in ["", "#", "#ff88", "#ff88001", "#gg8800", "#+f8800", "#ff88é"] {
+            assert_eq!(
+                Color::from_hex(bad),
+                Err(ColorError::InvalidHex(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn from_hsv_primary_and_secondary_hues() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(Color::from_hsv(60.0, 1.0, 1.0), Color::new(1.0, 1.0, 0.0));
+        assert_eq!(Color::from_hsv(120.0, 1.0, 1.0), Color::new(0.0, 1.0, 0.0));
+        assert_eq!(Color::from_hsv(240.0, 1.0, 0.5), Color::new(0.0, 0.0, 0.5));
+        assert_eq!(Color::from_hsv(300.0, 0.5, 1.0), Color::new(1.0, 0.5, 1.0));
+        // hue wraps around
+        assert_eq!(Color::from_hsv(360.0, 1.0, 1.0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(Color::from_hsv(-120.0, 1.0, 1.0), Color::new(0.0, 0.0, 1.0));
+        // no saturation is a gray
+        assert_eq!(
+            Color::from_hsv(200.0, 0.0, 0.25),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn from_hsl_matches_known_colors() {
+        assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(Color::from_hsl(120.0, 1.0, 0.25), Color::new(0.0, 0.5, 0.0));
+        assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(Color::from_hsl(30.0, 1.0, 0.75), Color::new(1.0, 0.75, 0.5));
+    }
+
+    #[test]
+    fn hsv_and_hsl_round_trip() {
+        let c = Color::new(0.2, 0.6, 0.9);
+        let (h, s, v) = c.to_hsv();
+        assert_eq!(Color::from_hsv(h, s, v), c);
+        let (h, s, l) = c.to_hsl();
+        assert_eq!(Color::from_hsl(h, s, l), c);
+
+        assert_eq!(Color::new(1.0, 0.0, 0.0).to_hsv(), (0.0, 1.0, 1.0));
+        assert_eq!(Color::new(0.5, 0.5, 0.5).to_hsl(), (0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn lerp_between_colors() {
+        let a = Color::BLACK;
+        let b = Color::new(1.0, 0.5, 0.25);
+        assert_eq!(a.lerp(b, 0.0), a);
+        assert_eq!(a.lerp(b, 1.0), b);
+        assert_eq!(a.lerp(b, 0.5), Color::new(0.5, 0.25, 0.125));
+        assert_eq!(
+            Color::RED.lerp(Color::BLUE, 0.25),
+            Color::new(0.75, 0.0, 0.25)
+        );
+    }
+
+    #[test]
+    fn clamp01_limits_components() {
+        let c = Color::new(-0.5, 0.4, 1.7);
+        assert_eq!(c.clamp01(), Color::new(0.0, 0.4, 1.0));
+    }
+
+    #[test]
+    fn luminance_weights_green_highest() {
+        assert_eq!(Color::WHITE.luminance(), 1.0);
+        assert_eq!(Color::BLACK.luminance(), 0.0);
+        assert!(Color::GREEN.luminance() > Color::RED.luminance());
+        assert!(Color::RED.luminance() > Color::BLUE.luminance());
+    }
+
+    #[test]
+    fn max_component_of_color() {
+        assert_eq!(Color::new(0.2, 1.5, 0.7).max_component(), 1.5);
+        assert_eq!(Color::new(-1.0, -0.5, -2.0).max_component(), -0.5);
+    }
+
     #[test]
     fn add_colors() {
         let c1 = Color::new(0.9, 0.6, 0.75);
@@ -165,6 +617,43 @@ mod tests {
         assert_eq!(expected, res);
     }
 
+    #[test]
+    fn multi_scalar_by_color() {
+        let c1 = Color::new(0.2, 0.3, 0.4);
+        assert_eq!(2.0 * c1, c1 * 2.0);
+    }
+
+    #[test]
+    fn assign_operators() {
+        let mut c = Color::new(0.2, 0.3, 0.4);
+        c += Color::new(0.1, 0.1, 0.1);
+        assert_eq!(c, Color::new(0.3, 0.4, 0.5));
+        c *= 2.0;
+        assert_eq!(c, Color::new(0.6, 0.8, 1.0));
+        c *= Color::new(0.5, 1.0, 0.0);
+        assert_eq!(c, Color::new(0.3, 0.8, 0.0));
+    }
+
+    #[test]
+    fn saturating_sub_stops_at_zero() {
+        let c1 = Color::new(0.9, 0.1, 0.75);
+        let c2 = Color::new(0.7, 0.6, 0.25);
+        assert_eq!(c1.saturating_sub(c2), Color::new(0.2, 0.0, 0.5));
+        assert_eq!(c1 - c2, Color::new(0.2, -0.5, 0.5));
+    }
+
+    #[test]
+    fn sum_colors() {
+        let colors = vec![
+            Color::new(0.1, 0.2, 0.3),
+            Color::new(0.4, 0.5, 0.6),
+            Color::new(0.5, 0.3, 0.1),
+        ];
+        assert_eq!(colors.iter().sum::<Color>(), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(colors.into_iter().sum::<Color>(), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(Vec::<Color>::new().into_iter().sum::<Color>(), Color::BLACK);
+    }
+
     #[test]
     fn multi_colors() {
         let c1 = Color::new(1.0, 0.2, 0.4);
@@ -174,4 +663,21 @@ mod tests {
         let res = c1 * c2;
         assert_eq!(expected, res);
     }
+
+    #[cfg(feature = "approx")]
+    #[test]
+    fn approx_comparisons() {
+        let c1 = Color::new(0.9, 0.6, 0.75);
+        let c2 = Color::new(0.7, 0.1, 0.25);
+        assert_relative_eq!(c1 + c2, Color::new(1.6, 0.7, 1.0));
+        assert_relative_ne!(c1, c2);
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn cast_colors_to_floats() {
+        let pixels = [Color::RED, Color::new(0.0, 0.5, 0.25)];
+        let floats: &[f64] = bytemuck::cast_slice(&pixels);
+        assert_eq!(floats, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.25, 1.0]);
+    }
 }
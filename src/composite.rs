@@ -0,0 +1,142 @@
+//! Recombining separately rendered passes into a final image, so it can be rebalanced without
+//! rendering again.
+//!
+//! Passes are added up, each scaled by its own gain: direct light (`ray_limits.bounces` 0) and
+//! what the full render adds on top of it, or each light group rendered on its own to turn the
+//! key light down after the fact. The sum can then be laid over a background, using a
+//! `RenderMode::Coverage` render as its alpha.
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Default)]
+pub struct Compositor<'a> {
+    passes: Vec<(&'a Canvas, Color)>,
+    background: Option<(&'a Canvas, &'a Canvas)>,
+}
+
+impl<'a> Compositor<'a> {
+    pub fn new() -> Self {
+        Compositor::default()
+    }
+
+    /// Adds a pass, multiplied by `gain`
+    pub fn with_pass(self, image: &'a Canvas, gain: f64) -> Self {
+        self.with_tinted_pass(image, Color::new(gain, gain, gain))
+    }
+
+    /// Adds a pass with a gain per channel, e.g. to warm up a fill light
+    pub fn with_tinted_pass(mut self, image: &'a Canvas, gain: Color) -> Self {
+        self.passes.push((image, gain));
+        self
+    }
+
+    /// Lays the sum of the passes over `background`, letting it through where `alpha` (its
+    /// red channel) is below 1. The passes have to be rendered without a sky, so where they
+    /// cover only part of a pixel they're already darkened by as much (premultiplied).
+    pub fn with_background(mut self, alpha: &'a Canvas, background: &'a Canvas) -> Self {
+        self.background = Some((alpha, background));
+        self
+    }
+
+    /// The composited image, failing when the images aren't all the same size
+    pub fn composite(&self) -> Result<Canvas> {
+        let Some((first, _)) = self.passes.first() else {
+            return Err(Error::Image("nothing to composite".to_string()));
+        };
+        let (width, height) = (first.width(), first.height());
+        let images = self
+            .passes
+            .iter()
+            .map(|(image, _)| *image)
+            .chain(self.background.iter().flat_map(|(a, b)| [*a, *b]));
+        for image in images {
+            if (image.width(), image.height()) != (width, height) {
+                return Err(Error::Image(format!(
+                    "a {}x{} pass doesn't fit a {}x{} composite",
+                    image.width(),
+                    image.height(),
+                    width,
+                    height
+                )));
+            }
+        }
+
+        let mut composite = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = |image: &Canvas| image.get_pixel(x, y).unwrap_or_default();
+                let mut color = self.passes.iter().fold(Color::BLACK, |sum, (image, gain)| {
+                    sum + pixel(image) * *gain
+                });
+                if let Some((alpha, background)) = self.background {
+                    let alpha = pixel(alpha).red().clamp(0.0, 1.0);
+                    color += pixel(background) * (1.0 - alpha);
+                }
+                composite.write_pixel(x, y, color);
+            }
+        }
+        Ok(composite)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::composite::Compositor;
+
+    fn filled(width: usize, color: Color) -> Canvas {
+        let mut canvas = Canvas::new(width, 1);
+        for x in 0..width {
+            canvas.write_pixel(x, 0, color);
+        }
+        canvas
+    }
+
+    #[test]
+    fn rebalancing_passes() {
+        let direct = filled(2, Color::new(0.4, 0.2, 0.0));
+        let indirect = filled(2, Color::new(0.2, 0.2, 0.2));
+        let image = Compositor::new()
+            .with_pass(&direct, 1.0)
+            .with_pass(&indirect, 0.5)
+            .composite()
+            .unwrap();
+        assert_eq!(image.get_pixel(1, 0), Some(Color::new(0.5, 0.3, 0.1)));
+
+        let tinted = Compositor::new()
+            .with_tinted_pass(&indirect, Color::new(2.0, 1.0, 0.0))
+            .composite()
+            .unwrap();
+        assert_eq!(tinted.get_pixel(0, 0), Some(Color::new(0.4, 0.2, 0.0)));
+
+        assert!(Compositor::new().composite().is_err());
+        assert!(Compositor::new()
+            .with_pass(&direct, 1.0)
+            .with_pass(&filled(3, Color::WHITE), 1.0)
+            .composite()
+            .is_err());
+    }
+
+    #[test]
+    fn over_a_background() {
+        // an object covering the first pixel, half of the second and none of the third
+        let mut beauty = Canvas::new(3, 1);
+        let mut alpha = Canvas::new(3, 1);
+        beauty.write_pixel(0, 0, Color::RED);
+        beauty.write_pixel(1, 0, Color::new(0.5, 0.0, 0.0));
+        alpha.write_pixel(0, 0, Color::WHITE);
+        alpha.write_pixel(1, 0, Color::new(0.5, 0.5, 0.5));
+        let background = filled(3, Color::BLUE);
+        let image = Compositor::new()
+            .with_pass(&beauty, 1.0)
+            .with_background(&alpha, &background)
+            .composite()
+            .unwrap();
+        assert_eq!(image.get_pixel(0, 0), Some(Color::RED));
+        assert_eq!(image.get_pixel(1, 0), Some(Color::new(0.5, 0.0, 0.5)));
+        assert_eq!(image.get_pixel(2, 0), Some(Color::BLUE));
+    }
+}
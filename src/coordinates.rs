@@ -0,0 +1,140 @@
+//! Coordinate conventions of other tools, so their scenes and meshes come in the right way up.
+//!
+//! The tracer works left-handed with y up, like the book. Blender is right-handed with z up,
+//! Maya right-handed with y up and Unreal left-handed with z up. Rather than teaching
+//! `view_transform`, the rotations and everything else about each of those, points, vectors
+//! and transforms are converted into the tracer's own convention once, when they're loaded.
+//! The conversion keeps x and maps the up axis onto y, mirroring z when the handedness differs.
+//!
+//! Mirroring turns every cross product around, so converted triangles have their winding
+//! reversed as well (see `Mesh::convert_from`) to keep their normals facing the same way.
+
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::tuple::Tuple;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Handedness {
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+/// Which way the axes of a scene or asset point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Convention {
+    pub handedness: Handedness,
+    pub up: UpAxis,
+}
+
+impl Convention {
+    /// The tracer's own, left-handed with y up
+    pub const NATIVE: Convention = Convention::new(Handedness::Left, UpAxis::Y);
+    pub const BLENDER: Convention = Convention::new(Handedness::Right, UpAxis::Z);
+    pub const MAYA: Convention = Convention::new(Handedness::Right, UpAxis::Y);
+    pub const UNREAL: Convention = Convention::new(Handedness::Left, UpAxis::Z);
+
+    pub const fn new(handedness: Handedness, up: UpAxis) -> Self {
+        Convention { handedness, up }
+    }
+
+    /// Change of basis from this convention into the tracer's
+    pub fn to_native(&self) -> M4x4 {
+        let rows = match (self.handedness, self.up) {
+            (Handedness::Left, UpAxis::Y) => return IDENTITY_MATRIX_4X4,
+            // z points out of the screen instead of into it
+            (Handedness::Right, UpAxis::Y) => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]],
+            // y points into the screen, swapping it with z mirrors as well
+            (Handedness::Right, UpAxis::Z) => [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+            (Handedness::Left, UpAxis::Z) => [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]],
+        };
+        let [x, y, z] = rows;
+        M4x4::from([
+            [x[0], x[1], x[2], 0.0],
+            [y[0], y[1], y[2], 0.0],
+            [z[0], z[1], z[2], 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Whether converting mirrors the scene, reversing the winding of triangles
+    pub fn mirrors(&self) -> bool {
+        self.handedness != Handedness::Left
+    }
+
+    /// A point or vector in this convention, in the tracer's
+    pub fn convert(&self, tuple: Tuple) -> Tuple {
+        self.to_native() * tuple
+    }
+
+    /// A transform written for this convention, doing the same thing in the tracer's
+    pub fn convert_transform(&self, transform: M4x4) -> M4x4 {
+        let to_native = self.to_native();
+        // the change of basis only rotates and mirrors, so its inverse is its transpose
+        to_native * transform * to_native.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::coordinates::{Convention, Handedness, UpAxis};
+    use crate::matrix::IDENTITY_MATRIX_4X4;
+    use crate::matrix_transformations::{rotation_x, rotation_z, translation};
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn up_ends_up_as_y() {
+        for convention in [
+            Convention::NATIVE,
+            Convention::BLENDER,
+            Convention::MAYA,
+            Convention::UNREAL,
+        ] {
+            let up = match convention.up {
+                UpAxis::Y => Vector::new(0.0, 1.0, 0.0),
+                UpAxis::Z => Vector::new(0.0, 0.0, 1.0),
+            };
+            assert_eq!(convention.convert(up), Vector::new(0.0, 1.0, 0.0));
+            let right = Point::new_point(1.0, 0.0, 0.0);
+            assert_eq!(convention.convert(right), right);
+            assert_eq!(
+                convention.to_native().determinant() < 0.0,
+                convention.mirrors()
+            );
+        }
+        assert_eq!(Convention::default(), Convention::NATIVE);
+        assert_eq!(Convention::NATIVE.to_native(), IDENTITY_MATRIX_4X4);
+        // out of a right-handed screen is into the tracer's
+        assert_eq!(
+            Convention::new(Handedness::Right, UpAxis::Y).convert(Vector::new(0.0, 0.0, 1.0)),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn transforms_do_the_same_thing_after_converting() {
+        let blender = Convention::BLENDER;
+        // a quarter turn around Blender's up axis, then a step along it
+        let transform = translation(0.0, 0.0, 2.0) * rotation_z(FRAC_PI_2);
+        let p = Point::new_point(1.0, 0.0, 0.0);
+        let native = blender.convert_transform(transform) * blender.convert(p);
+        assert_eq!(native, blender.convert(transform * p));
+        assert_eq!(native.y, 2.0);
+
+        let maya = Convention::MAYA;
+        let turn = rotation_x(FRAC_PI_2);
+        let v = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            maya.convert_transform(turn) * maya.convert(v),
+            maya.convert(turn * v)
+        );
+    }
+}
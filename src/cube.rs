@@ -0,0 +1,246 @@
+//! Axis aligned cubes, for boxes of any size: tables, walls, whole rooms. In its own space a
+//! cube spans -1 to 1 on every axis, transforms stretch it into any box.
+//!
+//! A ray is intersected with the six faces as three pairs of parallel planes (slabs), the
+//! ray is inside the cube where it's between all three pairs at once.
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, Material};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::rays::{Ray, Visibility};
+use crate::shape::{LocalHits, Shape};
+use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+use crate::uv::UvMap;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cube {
+    pub id: Uuid,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    pub material: Material,
+    /// Lights that shine on the cube, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the cube, the others pass straight through it
+    pub visibility: Visibility,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Cube {
+            id: Uuid::new_v4(),
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
+        }
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The cube as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Cube {
+        Cube {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+}
+
+/// The t where a ray enters and leaves the slab between the planes at -1 and 1 along one
+/// axis, given the ray's origin and direction along it
+fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+    // parallel to the planes, the ray is in the slab everywhere or nowhere
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    /// The latest of the three slabs' entries and the earliest of their exits, a miss when
+    /// the ray leaves one slab before entering another
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        let mut hits = LocalHits::new();
+        let (origin, direction) = (ray.origin(), ray.direction());
+        let (xtmin, xtmax) = check_axis(origin.x, direction.x);
+        let (ytmin, ytmax) = check_axis(origin.y, direction.y);
+        let (ztmin, ztmax) = check_axis(origin.z, direction.z);
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin <= tmax {
+            hits.push(tmin);
+            hits.push(tmax);
+        }
+        hits
+    }
+
+    /// Straight out of the face the point is on, the axis it's furthest along
+    fn local_normal_at(&self, point: Point, _uv: Option<(f64, f64)>) -> Vector {
+        let max = point.x.abs().max(point.y.abs()).max(point.z.abs());
+        if max == point.x.abs() {
+            Vector::new(point.x, 0.0, 0.0)
+        } else if max == point.y.abs() {
+            Vector::new(0.0, point.y, 0.0)
+        } else {
+            Vector::new(0.0, 0.0, point.z)
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        UvMap::Cube
+    }
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Cube::new()
+    }
+}
+
+impl PartialEq for Cube {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cube::Cube;
+    use crate::matrix_transformations::{scaling, translation};
+    use crate::rays::{hit, intersect, Ray};
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn ray_intersects_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            ((5.0, 0.5, 0.0), (-1.0, 0.0, 0.0), 4.0, 6.0),
+            ((-5.0, 0.5, 0.0), (1.0, 0.0, 0.0), 4.0, 6.0),
+            ((0.5, 5.0, 0.0), (0.0, -1.0, 0.0), 4.0, 6.0),
+            ((0.5, -5.0, 0.0), (0.0, 1.0, 0.0), 4.0, 6.0),
+            ((0.5, 0.0, 5.0), (0.0, 0.0, -1.0), 4.0, 6.0),
+            ((0.5, 0.0, -5.0), (0.0, 0.0, 1.0), 4.0, 6.0),
+            // from inside
+            ((0.0, 0.5, 0.0), (0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz), t1, t2) in cases {
+            let r = Ray::new(Point::new_point(ox, oy, oz), Vector::new(dx, dy, dz));
+            let xs: Vec<f64> = c.local_intersect(&r).iter().map(|(t, _)| t).collect();
+            assert_eq!(xs, vec![t1, t2]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            ((-2.0, 0.0, 0.0), (0.2673, 0.5345, 0.8018)),
+            ((0.0, -2.0, 0.0), (0.8018, 0.2673, 0.5345)),
+            ((0.0, 0.0, -2.0), (0.5345, 0.8018, 0.2673)),
+            ((2.0, 0.0, 2.0), (0.0, 0.0, -1.0)),
+            ((0.0, 2.0, 2.0), (0.0, -1.0, 0.0)),
+            ((2.0, 2.0, 0.0), (-1.0, 0.0, 0.0)),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz)) in cases {
+            let r = Ray::new(Point::new_point(ox, oy, oz), Vector::new(dx, dy, dz));
+            assert!(c.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn normal_on_the_surface_of_a_cube() {
+        let c = Cube::new();
+        let cases = [
+            ((1.0, 0.5, -0.8), (1.0, 0.0, 0.0)),
+            ((-1.0, -0.2, 0.9), (-1.0, 0.0, 0.0)),
+            ((-0.4, 1.0, -0.1), (0.0, 1.0, 0.0)),
+            ((0.3, -1.0, -0.7), (0.0, -1.0, 0.0)),
+            ((-0.6, 0.3, 1.0), (0.0, 0.0, 1.0)),
+            ((0.4, 0.4, -1.0), (0.0, 0.0, -1.0)),
+            // corners pick the x face
+            ((1.0, 1.0, 1.0), (1.0, 0.0, 0.0)),
+            ((-1.0, -1.0, -1.0), (-1.0, 0.0, 0.0)),
+        ];
+        for ((px, py, pz), (nx, ny, nz)) in cases {
+            let normal = c.local_normal_at(Point::new_point(px, py, pz), None);
+            assert_eq!(normal, Vector::new(nx, ny, nz));
+        }
+    }
+
+    #[test]
+    fn transformed_cube() {
+        let mut c = Cube::new();
+        c.set_transform(translation(0.0, 1.0, 0.0) * scaling(2.0, 1.0, 2.0))
+            .unwrap();
+        let r = Ray::new(Point::new_point(1.5, 5.0, 1.5), Vector::new(0.0, -1.0, 0.0));
+        let i = hit(&intersect(&r, c)).unwrap();
+        assert_eq!(i.t, 3.0);
+        let normal = c.normal_at(r.position(i.t), &i);
+        assert_eq!(normal, Vector::new(0.0, 1.0, 0.0));
+        assert!(c.set_transform(scaling(1.0, 0.0, 1.0)).is_err());
+    }
+}
@@ -1,18 +1,368 @@
-use crate::projectile::{new_projectile, Projectile};
-use crate::tuple::Tuple;
+//! The world the projectile demo flies through: gravity, wind and air resistance, and optionally
+//! a ground and walls for it to bounce off.
 
-#[derive(Copy, Clone)]
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+use crate::projectile::Projectile;
+use crate::tuple::{Point, Tuple, Vector};
+
+/// How `tick` steps the projectile forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// Moves with the old velocity, then updates it. Simplest, but gains energy every step and
+    /// drifts off quickly with large steps.
+    #[default]
+    Euler,
+    /// Updates the velocity first and moves with the new one, stays stable for orbits and
+    /// bounces at no extra cost
+    SemiImplicitEuler,
+    /// Fourth order Runge-Kutta, four evaluations of the forces per step but accurate even
+    /// with large ones
+    Rk4,
+}
+
+/// A flat surface projectiles bounce off, solid on the side its normal points away from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Barrier {
+    point: Point,
+    normal: Vector,
+}
+
+impl Barrier {
+    pub fn new(point: Point, normal: Vector) -> Self {
+        Barrier {
+            point,
+            normal: normal.normalize(),
+        }
+    }
+
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    /// How far in front of the surface the point is, negative once it's inside
+    pub fn distance(&self, point: Point) -> f64 {
+        (point - self.point).dot(&self.normal)
+    }
+}
+
+/// Wind at a position and time, see `Environment::with_wind_field`
+pub type WindField = Arc<dyn Fn(Point, f64) -> Vector + Send + Sync>;
+
+#[derive(Clone)]
 pub struct Environment {
     gravity: Tuple, // vector
-    wind: Tuple,    // vector
+    wind: WindField,
+    /// Fraction of its velocity a projectile loses to air resistance per unit of time
+    drag: f64,
+    integrator: Integrator,
+    /// Time that passes every tick
+    timestep: f64,
+    barriers: Vec<Barrier>,
+    /// Fraction of its speed into a barrier a projectile keeps when bouncing off it
+    restitution: f64,
 }
 
+/// An environment with the same wind everywhere and no drag
 pub fn new_environment(gravity: Tuple, wind: Tuple) -> Environment {
-    Environment { gravity, wind }
+    Environment {
+        gravity,
+        wind: Arc::new(move |_, _| wind),
+        drag: 0.0,
+        integrator: Integrator::Euler,
+        timestep: 1.0,
+        barriers: Vec::new(),
+        restitution: 1.0,
+    }
+}
+
+impl Environment {
+    /// Slows projectiles down in proportion to their speed, 0 for none
+    pub fn with_drag(mut self, drag: f64) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    /// Wind that changes from place to place and over time, e.g. gusts or a breeze that
+    /// picks up with height
+    pub fn with_wind_field(
+        mut self,
+        wind: impl Fn(Point, f64) -> Vector + Send + Sync + 'static,
+    ) -> Self {
+        self.wind = Arc::new(wind);
+        self
+    }
+
+    pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    /// Adds a floor at the given height, projectiles bounce off it instead of falling through
+    pub fn with_ground(self, height: f64) -> Self {
+        self.with_barrier(Barrier::new(
+            Point::new_point(0.0, height, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+    }
+
+    /// Adds a wall (or ceiling, or slope) to bounce off
+    pub fn with_barrier(mut self, barrier: Barrier) -> Self {
+        self.barriers.push(barrier);
+        self
+    }
+
+    /// How bouncy barriers are, from 0 (projectiles stop dead against them) to 1 (no speed lost)
+    pub fn with_restitution(mut self, restitution: f64) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn barriers(&self) -> &[Barrier] {
+        &self.barriers
+    }
+
+    pub fn restitution(&self) -> f64 {
+        self.restitution
+    }
+
+    /// How much time passes every tick, 1 by default. Smaller steps follow the forces more
+    /// closely and draw smoother paths, at the cost of more ticks for the same flight.
+    pub fn with_timestep(mut self, timestep: f64) -> Self {
+        self.timestep = timestep;
+        self
+    }
+
+    pub fn timestep(&self) -> f64 {
+        self.timestep
+    }
+
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    pub fn gravity(&self) -> Vector {
+        self.gravity
+    }
+
+    pub fn drag(&self) -> f64 {
+        self.drag
+    }
+
+    pub fn wind_at(&self, position: Point, time: f64) -> Vector {
+        (self.wind)(position, time)
+    }
+
+    /// Change in velocity per unit of time. Wind and drag are forces, so they're divided by the
+    /// mass, gravity is already an acceleration.
+    fn acceleration(&self, position: Point, velocity: Vector, time: f64, mass: f64) -> Vector {
+        self.gravity + (self.wind_at(position, time) - velocity * self.drag) / mass
+    }
+
+    /// Pushes a projectile that went into a barrier back out and turns it around, losing speed
+    /// into the barrier according to the restitution
+    fn bounce(&self, mut projectile: Projectile) -> Projectile {
+        let scale = 1.0 + self.restitution;
+        for barrier in &self.barriers {
+            let depth = barrier.distance(projectile.position);
+            if depth >= 0.0 {
+                continue;
+            }
+            projectile.position = projectile.position - barrier.normal * (depth * scale);
+            let speed_in = projectile.velocity.dot(&barrier.normal);
+            if speed_in < 0.0 {
+                projectile.velocity = projectile.velocity - barrier.normal * (speed_in * scale);
+            }
+        }
+        projectile
+    }
+}
+
+/// Moves the projectile one timestep forward with the environment's integrator
+pub fn tick(env: &Environment, projectile: Projectile) -> Projectile {
+    let (p, v, t) = (projectile.position, projectile.velocity, projectile.time);
+    let dt = env.timestep;
+    let acceleration = |p, v, t| env.acceleration(p, v, t, projectile.mass);
+    let (position, velocity) = match env.integrator {
+        Integrator::Euler => (p + v * dt, v + acceleration(p, v, t) * dt),
+        Integrator::SemiImplicitEuler => {
+            let velocity = v + acceleration(p, v, t) * dt;
+            (p + velocity * dt, velocity)
+        }
+        Integrator::Rk4 => {
+            let half = dt / 2.0;
+            let (p1, v1) = (v, acceleration(p, v, t));
+            let (p2, v2) = {
+                let v_mid = v + v1 * half;
+                (v_mid, acceleration(p + p1 * half, v_mid, t + half))
+            };
+            let (p3, v3) = {
+                let v_mid = v + v2 * half;
+                (v_mid, acceleration(p + p2 * half, v_mid, t + half))
+            };
+            let (p4, v4) = {
+                let v_end = v + v3 * dt;
+                (v_end, acceleration(p + p3 * dt, v_end, t + dt))
+            };
+            (
+                p + (p1 + p2 * 2.0 + p3 * 2.0 + p4) * (dt / 6.0),
+                v + (v1 + v2 * 2.0 + v3 * 2.0 + v4) * (dt / 6.0),
+            )
+        }
+    };
+    let moved = Projectile {
+        position,
+        velocity,
+        time: t + dt,
+        ..projectile
+    };
+    env.bounce(moved)
 }
 
-pub fn tick(env: Environment, projectile: Projectile) -> Projectile {
-    let new_pos = projectile.position + projectile.velocity;
-    let new_vel = projectile.velocity + env.gravity + env.wind;
-    new_projectile(new_pos, new_vel)
+/// Moves every projectile one timestep forward, spread over all cores except on wasm32
+pub fn tick_all(env: &Environment, projectiles: &mut [Projectile]) {
+    #[cfg(not(target_arch = "wasm32"))]
+    projectiles
+        .par_iter_mut()
+        .for_each(|projectile| *projectile = tick(env, *projectile));
+    #[cfg(target_arch = "wasm32")]
+    projectiles
+        .iter_mut()
+        .for_each(|projectile| *projectile = tick(env, *projectile));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::environment::{new_environment, tick, tick_all, Barrier, Integrator};
+    use crate::projectile::new_projectile;
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn drag_slows_projectiles_down() {
+        let env =
+            new_environment(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 0.0)).with_drag(0.5);
+        let p = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(4.0, 0.0, 0.0));
+        let p = tick(&env, p);
+        assert_eq!(p.position, Point::new_point(4.0, 0.0, 0.0));
+        assert_eq!(p.velocity, Vector::new(2.0, 0.0, 0.0));
+        assert_eq!(p.time, 1.0);
+    }
+
+    #[test]
+    fn heavier_projectiles_feel_less_drag() {
+        let env =
+            new_environment(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 0.0, 0.0)).with_drag(0.5);
+        let mut p = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(4.0, 0.0, 0.0));
+        p.mass = 2.0;
+        assert_eq!(tick(&env, p).velocity, Vector::new(3.0, -1.0, 0.0));
+        assert_eq!(tick(&env, p).mass, 2.0);
+    }
+
+    #[test]
+    fn stepping_many_projectiles() {
+        let env = new_environment(Vector::new(0.0, -0.1, 0.0), Vector::new(0.01, 0.0, 0.0))
+            .with_drag(0.1)
+            .with_ground(0.0);
+        let mut projectiles: Vec<_> = (0..1000)
+            .map(|i| {
+                let mut p = new_projectile(
+                    Point::new_point(0.0, 0.0, 0.0),
+                    Vector::new(i as f64 * 0.001, 1.0, 0.0),
+                );
+                p.mass = 1.0 + i as f64 * 0.01;
+                p
+            })
+            .collect();
+        let expected: Vec<_> = projectiles
+            .iter()
+            .map(|p| tick(&env, tick(&env, *p)))
+            .collect();
+        tick_all(&env, &mut projectiles);
+        tick_all(&env, &mut projectiles);
+        assert_eq!(projectiles, expected);
+    }
+
+    #[test]
+    fn integrators_under_constant_gravity() {
+        let env = new_environment(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 0.0, 0.0));
+        let fall = |integrator| {
+            let env = env.clone().with_integrator(integrator);
+            let p = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+            let p = tick(&env, tick(&env, p));
+            assert_eq!(p.velocity, Vector::new(1.0, -2.0, 0.0));
+            p.position
+        };
+        assert_eq!(fall(Integrator::Euler), Point::new_point(2.0, -1.0, 0.0));
+        assert_eq!(
+            fall(Integrator::SemiImplicitEuler),
+            Point::new_point(2.0, -3.0, 0.0)
+        );
+        // exactly -g t^2 / 2, the other two miss by half a step either way
+        assert_eq!(fall(Integrator::Rk4), Point::new_point(2.0, -2.0, 0.0));
+    }
+
+    #[test]
+    fn smaller_timesteps_follow_the_path_closer() {
+        let env = new_environment(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 0.0, 0.0));
+        let fall = |timestep: f64, integrator| {
+            let env = env
+                .clone()
+                .with_timestep(timestep)
+                .with_integrator(integrator);
+            let mut p = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+            for _ in 0..(2.0 / timestep) as usize {
+                p = tick(&env, p);
+            }
+            assert!((p.time - 2.0).abs() < 1e-9);
+            assert_eq!(p.velocity, Vector::new(1.0, -2.0, 0.0));
+            p.position.y
+        };
+        // -2 after two time units, whatever the step
+        assert_eq!(fall(1.0, Integrator::Rk4), -2.0);
+        assert!((fall(0.25, Integrator::Rk4) + 2.0).abs() < 1e-9);
+        // Euler misses by half a step
+        assert_eq!(fall(1.0, Integrator::Euler), -1.0);
+        assert!((fall(0.01, Integrator::Euler) + 1.99).abs() < 1e-9);
+    }
+
+    #[test]
+    fn projectiles_bounce_off_the_ground() {
+        let env = new_environment(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 0.0))
+            .with_ground(0.0)
+            .with_restitution(0.5);
+        let p = new_projectile(Point::new_point(0.0, 1.0, 0.0), Vector::new(1.0, -3.0, 0.0));
+        let p = tick(&env, p);
+        // 2 below the ground, bounced back up half as far
+        assert_eq!(p.position, Point::new_point(1.0, 1.0, 0.0));
+        assert_eq!(p.velocity, Vector::new(1.0, 1.5, 0.0));
+    }
+
+    #[test]
+    fn projectiles_bounce_off_walls() {
+        let wall = Barrier::new(Point::new_point(5.0, 0.0, 0.0), Vector::new(-2.0, 0.0, 0.0));
+        let env = new_environment(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 0.0))
+            .with_barrier(wall);
+        let p = new_projectile(Point::new_point(4.0, 0.0, 0.0), Vector::new(2.0, 0.0, 0.0));
+        let p = tick(&env, p);
+        assert_eq!(p.position, Point::new_point(4.0, 0.0, 0.0));
+        assert_eq!(p.velocity, Vector::new(-2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn wind_varies_with_position_and_time() {
+        let env = new_environment(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 0.0, 0.0))
+            .with_wind_field(|position, time| Vector::new(position.y * 0.1 + time, 0.0, 0.0));
+        let p = new_projectile(Point::new_point(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 0.0));
+        assert_eq!(env.wind_at(p.position, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let p = tick(&env, tick(&env, p));
+        // 1 at y = 10 and t = 0, then 2 at y = 10 and t = 1
+        assert_eq!(p.velocity, Vector::new(3.0, -2.0, 0.0));
+    }
 }
@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+use crate::color::ColorError;
+use crate::matrix::{M4x4, MatrixError};
+
+/// Everything that can go wrong building a scene, rendering it or writing the image
+#[derive(Error, Debug)]
+pub enum Error {
+    /// A shape was given a transform that can't be inverted (e.g. scaled by 0 along an axis),
+    /// so rays could never be moved into its object space. Caught when the transform is set
+    /// instead of panicking halfway through a render.
+    #[error("degenerate transform, it has no inverse:\n{0}")]
+    DegenerateTransform(Box<M4x4>),
+    #[error(transparent)]
+    Matrix(#[from] MatrixError),
+    #[error(transparent)]
+    Color(#[from] ColorError),
+    #[cfg(feature = "scene")]
+    #[error("invalid scene: {0}")]
+    Scene(#[from] serde_json::Error),
+    /// A scene that parsed but asks for more than the renderer is willing to allocate,
+    /// e.g. a camera resolution that would need gigabytes of canvas
+    #[cfg(feature = "scene")]
+    #[error("scene too large: {0}")]
+    SceneTooLarge(String),
+    #[cfg(feature = "batch")]
+    #[error("invalid job file: {0}")]
+    Jobs(#[from] serde_yaml::Error),
+    /// An image that couldn't be read, or images that don't fit together, e.g. passes of
+    /// different sizes handed to the compositor
+    #[error("invalid image: {0}")]
+    Image(String),
+    /// An image written a band at a time got bands that don't fit it, or too few of them
+    #[error("banded image: {0}")]
+    Band(String),
+    /// Encoding a video from rendered frames failed
+    #[error("video encoding failed: {0}")]
+    Encoder(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The preview window couldn't be opened, e.g. without a display
+    #[cfg(all(feature = "egui", not(target_arch = "wasm32")))]
+    #[error("preview window failed: {0}")]
+    Window(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
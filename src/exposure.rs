@@ -0,0 +1,168 @@
+//! Exposure and tone mapping, turning the light a render collected into displayable colors.
+//!
+//! Scenes can be lit with any intensities, a sun of 50 or a candle of 0.01. Rather than tuning
+//! the lights until the image looks right, `Exposure::Auto` scales the image so its average
+//! brightness lands on middle grey, the way a camera's light meter does. The average is the
+//! log-average luminance (Reinhard et al.), so a few very bright pixels don't darken the rest.
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::world::World;
+
+/// The brightness auto exposure aims for, middle grey
+pub const MIDDLE_GREY: f64 = 0.18;
+
+/// What every pixel gets multiplied by before tone mapping
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exposure {
+    Fixed(f64),
+    /// Chosen per image, so its log-average luminance comes out as `key`. `MIDDLE_GREY` for a
+    /// typical scene, lower for a night scene, higher for a snowy one.
+    Auto {
+        key: f64,
+    },
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Exposure::Fixed(1.0)
+    }
+}
+
+impl Exposure {
+    /// The multiplier for this image
+    pub fn multiplier(&self, canvas: &Canvas) -> f64 {
+        match *self {
+            Exposure::Fixed(exposure) => exposure,
+            Exposure::Auto { key } => auto_exposure(canvas, key),
+        }
+    }
+}
+
+/// How exposed colors are squeezed into 0..1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMap {
+    /// Leaves colors as they are, anything brighter than 1 clips when the image is written out
+    #[default]
+    Linear,
+    /// `c / (1 + c)`, bright colors roll off toward white instead of clipping
+    Reinhard,
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: Color) -> Color {
+        match self {
+            ToneMap::Linear => color,
+            ToneMap::Reinhard => Color::new(
+                color.red() / (1.0 + color.red()),
+                color.green() / (1.0 + color.green()),
+                color.blue() / (1.0 + color.blue()),
+            ),
+        }
+    }
+}
+
+/// `exp(mean(ln(luminance)))` over the pixels that aren't black, 0 when they all are. Black
+/// pixels are mostly rays that missed everything, counting them would make the exposure depend
+/// on how much of the frame is empty.
+pub fn log_average_luminance(canvas: &Canvas) -> f64 {
+    let (mut total, mut count) = (0.0, 0);
+    for y in 0..canvas.height() {
+        for x in 0..canvas.width() {
+            let luminance = canvas.get_pixel(x, y).map_or(0.0, Color::luminance);
+            if luminance > 0.0 {
+                total += luminance.ln();
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        return 0.0;
+    }
+    (total / count as f64).exp()
+}
+
+/// The exposure that brings the image's log-average luminance to `key`
+pub fn auto_exposure(canvas: &Canvas, key: f64) -> f64 {
+    let average = log_average_luminance(canvas);
+    if average > 0.0 {
+        key / average
+    } else {
+        1.0
+    }
+}
+
+/// Multiplies every pixel by `exposure` and tone maps it
+pub fn develop(canvas: &mut Canvas, exposure: f64, tone_map: ToneMap) {
+    for y in 0..canvas.height() {
+        for x in 0..canvas.width() {
+            if let Some(color) = canvas.get_pixel(x, y) {
+                canvas.write_pixel(x, y, tone_map.apply(color * exposure));
+            }
+        }
+    }
+}
+
+/// Auto exposure from a quick render at an eighth of the size with one ray per pixel, e.g. to
+/// fix the exposure once for every frame of an animation instead of letting it flicker
+pub fn meter(camera: &Camera, world: &World, key: f64) -> f64 {
+    let mut preview =
+        camera.with_resolution((camera.hsize() / 8).max(1), (camera.vsize() / 8).max(1));
+    preview.set_samples(1);
+    preview.set_exposure(Exposure::Fixed(1.0));
+    preview.set_tone_map(ToneMap::Linear);
+    auto_exposure(&preview.render(world), key)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::exposure::{
+        auto_exposure, develop, log_average_luminance, Exposure, ToneMap, MIDDLE_GREY,
+    };
+
+    fn canvas(colors: &[Color]) -> Canvas {
+        let mut canvas = Canvas::new(colors.len(), 1);
+        for (x, color) in colors.iter().enumerate() {
+            canvas.write_pixel(x, 0, *color);
+        }
+        canvas
+    }
+
+    #[test]
+    fn log_average_ignores_outliers() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let flat = canvas(&[grey, grey, grey, grey]);
+        assert!((log_average_luminance(&flat) - 0.5).abs() < 1e-9);
+        // one blown out pixel moves the log-average far less than the plain average
+        let hot = canvas(&[grey, grey, grey, Color::new(100.0, 100.0, 100.0)]);
+        let average = log_average_luminance(&hot);
+        assert!(average > 0.5 && average < 3.0, "{}", average);
+        // black pixels are left out
+        let empty = canvas(&[grey, Color::BLACK]);
+        assert!((log_average_luminance(&empty) - 0.5).abs() < 1e-9);
+        assert_eq!(log_average_luminance(&Canvas::new(2, 2)), 0.0);
+    }
+
+    #[test]
+    fn auto_exposure_hits_middle_grey_whatever_the_lights() {
+        for intensity in [0.01, 1.0, 50.0] {
+            let mut image = canvas(&[Color::new(intensity, intensity, intensity); 4]);
+            let exposure = Exposure::Auto { key: MIDDLE_GREY }.multiplier(&image);
+            assert!((exposure - auto_exposure(&image, MIDDLE_GREY)).abs() < 1e-12);
+            develop(&mut image, exposure, ToneMap::Linear);
+            let luminance = image.get_pixel(0, 0).unwrap().luminance();
+            assert!((luminance - MIDDLE_GREY).abs() < 1e-9, "{}", luminance);
+        }
+        assert_eq!(Exposure::default().multiplier(&Canvas::new(1, 1)), 1.0);
+    }
+
+    #[test]
+    fn reinhard_rolls_off_highlights() {
+        let mapped = ToneMap::Reinhard.apply(Color::new(1.0, 3.0, 0.0));
+        assert_eq!(mapped, Color::new(0.5, 0.75, 0.0));
+        assert_eq!(ToneMap::Linear.apply(Color::WHITE), Color::WHITE);
+    }
+}
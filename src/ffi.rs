@@ -0,0 +1,437 @@
+//! C API for embedding the renderer, see `include/ray_tracer.h`.
+//!
+//! Worlds and cameras are handed out as opaque pointers which have to be released with
+//! their matching `_free` function. Functions that can fail return an `RtStatus`.
+//!
+//! ```c
+//! RtWorld *world = rt_world_new();
+//! rt_world_add_light(world, -10, 10, -10, 1, 1, 1);
+//! RtMaterial material = rt_material_default();
+//! rt_world_add_sphere(world, NULL, &material);
+//!
+//! RtCamera *camera = rt_camera_new(320, 240, 1.0472);
+//! double from[3] = {0, 0, -5}, to[3] = {0, 0, 0}, up[3] = {0, 1, 0};
+//! rt_camera_look_at(camera, from, to, up);
+//!
+//! uint8_t *pixels = malloc(320 * 240 * 4);
+//! rt_render(camera, world, pixels, 320 * 240 * 4);
+//!
+//! rt_camera_free(camera);
+//! rt_world_free(world);
+//! ```
+//!
+//! Spheres and planes can be added from C so far, other shapes will be added here as they're
+//! needed.
+
+use std::ptr;
+use std::slice;
+
+use crate::camera::{Camera, MAX_RESOLUTION};
+use crate::color::Color;
+use crate::light::{Material, PointLight};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::matrix_transformations::view_transform;
+use crate::quad::Quad;
+use crate::rays::Sphere;
+use crate::tuple::{Point, Vector};
+use crate::world::World;
+
+/// How far a plane from `rt_world_add_plane` reaches from its center along both of its axes.
+/// There's no infinite plane, a quad this big stands in for one.
+const PLANE_EXTENT: f64 = 1.0e4;
+
+/// Opaque handle to a `World`
+pub struct RtWorld(World);
+
+/// Opaque handle to a `Camera`
+pub struct RtCamera(Camera);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtStatus {
+    Ok = 0,
+    NullPointer = 1,
+    DegenerateTransform = 2,
+    BufferTooSmall = 3,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RtMaterial {
+    pub color: [f64; 3],
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+}
+
+impl From<RtMaterial> for Material {
+    fn from(m: RtMaterial) -> Self {
+        Material {
+            color: Color::new(m.color[0], m.color[1], m.color[2]),
+            ambient: m.ambient,
+            diffuse: m.diffuse,
+            specular: m.specular,
+            shininess: m.shininess,
+            ..Material::new()
+        }
+    }
+}
+
+/// The material spheres and planes get when none is given
+#[no_mangle]
+pub extern "C" fn rt_material_default() -> RtMaterial {
+    let m = Material::new();
+    RtMaterial {
+        color: [m.color.red(), m.color.green(), m.color.blue()],
+        ambient: m.ambient,
+        diffuse: m.diffuse,
+        specular: m.specular,
+        shininess: m.shininess,
+    }
+}
+
+/// Creates an empty world, release it with `rt_world_free`
+#[no_mangle]
+pub extern "C" fn rt_world_new() -> *mut RtWorld {
+    Box::into_raw(Box::new(RtWorld(World::new())))
+}
+
+/// # Safety
+///
+/// `world` must come from `rt_world_new` and not have been freed already, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_free(world: *mut RtWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// Adds a point light at (x, y, z) with the given intensity
+///
+/// # Safety
+///
+/// `world` must be a live pointer from `rt_world_new`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_light(
+    world: *mut RtWorld,
+    x: f64,
+    y: f64,
+    z: f64,
+    red: f64,
+    green: f64,
+    blue: f64,
+) -> RtStatus {
+    let Some(world) = world.as_mut() else {
+        return RtStatus::NullPointer;
+    };
+    world.0.lights.push(PointLight::new(
+        Point::new_point(x, y, z),
+        Color::new(red, green, blue),
+    ));
+    RtStatus::Ok
+}
+
+/// Adds a sphere with the given transform (16 values, row major) and material.
+/// Either can be null for the identity transform and the default material.
+///
+/// # Safety
+///
+/// `world` must be a live pointer from `rt_world_new`, or null. `transform` must point to
+/// 16 doubles and `material` to an `RtMaterial`, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_sphere(
+    world: *mut RtWorld,
+    transform: *const f64,
+    material: *const RtMaterial,
+) -> RtStatus {
+    let Some(world) = world.as_mut() else {
+        return RtStatus::NullPointer;
+    };
+    let mut sphere = Sphere::new();
+    if sphere.set_transform(read_transform(transform)).is_err() {
+        return RtStatus::DegenerateTransform;
+    }
+    if let Some(material) = material.as_ref() {
+        sphere.set_material((*material).into());
+    }
+    world.0.objects.push(sphere.into());
+    RtStatus::Ok
+}
+
+/// Adds the plane y = 0, facing +y, placed by the given transform (16 values, row major) and
+/// with the given material. Either can be null like for `rt_world_add_sphere`.
+/// The plane is a square reaching 10000 units from its center, not endless.
+///
+/// # Safety
+///
+/// `world` must be a live pointer from `rt_world_new`, or null. `transform` must point to
+/// 16 doubles and `material` to an `RtMaterial`, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_world_add_plane(
+    world: *mut RtWorld,
+    transform: *const f64,
+    material: *const RtMaterial,
+) -> RtStatus {
+    let Some(world) = world.as_mut() else {
+        return RtStatus::NullPointer;
+    };
+    let mut plane = Quad::new(
+        Point::new_point(-PLANE_EXTENT, 0.0, -PLANE_EXTENT),
+        Vector::new(0.0, 0.0, 2.0 * PLANE_EXTENT),
+        Vector::new(2.0 * PLANE_EXTENT, 0.0, 0.0),
+    );
+    if plane.set_transform(read_transform(transform)).is_err() {
+        return RtStatus::DegenerateTransform;
+    }
+    if let Some(material) = material.as_ref() {
+        plane.set_material((*material).into());
+    }
+    world.0.objects.push(plane.into());
+    RtStatus::Ok
+}
+
+/// The matrix in 16 row major values, the identity for null
+///
+/// # Safety
+///
+/// `transform` must point to 16 doubles, or be null.
+unsafe fn read_transform(transform: *const f64) -> M4x4 {
+    if transform.is_null() {
+        return IDENTITY_MATRIX_4X4;
+    }
+    let values = slice::from_raw_parts(transform, 16);
+    let mut m = M4x4::from([[0.0; 4]; 4]);
+    for (i, value) in values.iter().enumerate() {
+        m[(i / 4, i % 4)] = *value;
+    }
+    m
+}
+
+/// Creates a camera rendering `width` x `height` pixels, looking down -z from the origin
+/// until moved with `rt_camera_look_at`. Release it with `rt_camera_free`.
+/// Returns null when either side is 0 or larger than 16384.
+#[no_mangle]
+pub extern "C" fn rt_camera_new(width: usize, height: usize, field_of_view: f64) -> *mut RtCamera {
+    if !(1..=MAX_RESOLUTION).contains(&width) || !(1..=MAX_RESOLUTION).contains(&height) {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(RtCamera(Camera::new(
+        width,
+        height,
+        field_of_view,
+    ))))
+}
+
+/// # Safety
+///
+/// `camera` must come from `rt_camera_new` and not have been freed already, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_camera_free(camera: *mut RtCamera) {
+    if !camera.is_null() {
+        drop(Box::from_raw(camera));
+    }
+}
+
+/// Points the camera from one point to another, see `view_transform`
+///
+/// # Safety
+///
+/// `camera` must be a live pointer from `rt_camera_new`, or null.
+/// `from`, `to` and `up` must each point to 3 doubles, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_camera_look_at(
+    camera: *mut RtCamera,
+    from: *const f64,
+    to: *const f64,
+    up: *const f64,
+) -> RtStatus {
+    let Some(camera) = camera.as_mut() else {
+        return RtStatus::NullPointer;
+    };
+    if from.is_null() || to.is_null() || up.is_null() {
+        return RtStatus::NullPointer;
+    }
+    let [fx, fy, fz] = *(from as *const [f64; 3]);
+    let [tx, ty, tz] = *(to as *const [f64; 3]);
+    let [ux, uy, uz] = *(up as *const [f64; 3]);
+    let transform = view_transform(
+        Point::new_point(fx, fy, fz),
+        Point::new_point(tx, ty, tz),
+        Vector::new(ux, uy, uz),
+    );
+    match camera.0.set_transform(transform) {
+        Ok(()) => RtStatus::Ok,
+        Err(_) => RtStatus::DegenerateTransform,
+    }
+}
+
+/// Renders the world into `buffer` as 8 bit RGBA, row by row from the top left.
+/// The buffer has to hold at least width * height * 4 bytes.
+///
+/// # Safety
+///
+/// `camera` and `world` must be live pointers from their `_new` functions, or null.
+/// `buffer` must be valid for writes of `len` bytes, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn rt_render(
+    camera: *const RtCamera,
+    world: *const RtWorld,
+    buffer: *mut u8,
+    len: usize,
+) -> RtStatus {
+    let (Some(camera), Some(world)) = (camera.as_ref(), world.as_ref()) else {
+        return RtStatus::NullPointer;
+    };
+    if buffer.is_null() {
+        return RtStatus::NullPointer;
+    }
+    // no buffer holds more bytes than fit in a usize
+    let needed = camera
+        .0
+        .hsize()
+        .checked_mul(camera.0.vsize())
+        .and_then(|pixels| pixels.checked_mul(4));
+    if needed.is_none_or(|needed| len < needed) {
+        return RtStatus::BufferTooSmall;
+    }
+    let rgba = camera.0.render_to_rgba(&world.0);
+    slice::from_raw_parts_mut(buffer, rgba.len()).copy_from_slice(&rgba);
+    RtStatus::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::ptr;
+
+    use crate::camera::MAX_RESOLUTION;
+    use crate::ffi::{
+        rt_camera_free, rt_camera_look_at, rt_camera_new, rt_material_default, rt_render,
+        rt_world_add_light, rt_world_add_plane, rt_world_add_sphere, rt_world_free, rt_world_new,
+        RtStatus,
+    };
+
+    #[test]
+    fn render_through_c_api() {
+        unsafe {
+            let world = rt_world_new();
+            assert_eq!(
+                rt_world_add_light(world, -10.0, 10.0, -10.0, 1.0, 1.0, 1.0),
+                RtStatus::Ok
+            );
+            let mut material = rt_material_default();
+            material.color = [0.8, 1.0, 0.6];
+            material.diffuse = 0.7;
+            material.specular = 0.2;
+            assert_eq!(
+                rt_world_add_sphere(world, ptr::null(), &material),
+                RtStatus::Ok
+            );
+            let half: [f64; 16] = [
+                0.5, 0.0, 0.0, 0.0, //
+                0.0, 0.5, 0.0, 0.0, //
+                0.0, 0.0, 0.5, 0.0, //
+                0.0, 0.0, 0.0, 1.0,
+            ];
+            assert_eq!(
+                rt_world_add_sphere(world, half.as_ptr(), ptr::null()),
+                RtStatus::Ok
+            );
+
+            let camera = rt_camera_new(11, 11, PI / 2.0);
+            let (from, to, up) = ([0.0, 0.0, -5.0], [0.0; 3], [0.0, 1.0, 0.0]);
+            assert_eq!(
+                rt_camera_look_at(camera, from.as_ptr(), to.as_ptr(), up.as_ptr()),
+                RtStatus::Ok
+            );
+
+            let mut small = vec![0u8; 10];
+            assert_eq!(
+                rt_render(camera, world, small.as_mut_ptr(), small.len()),
+                RtStatus::BufferTooSmall
+            );
+            let mut pixels = vec![0u8; 11 * 11 * 4];
+            assert_eq!(
+                rt_render(camera, world, pixels.as_mut_ptr(), pixels.len()),
+                RtStatus::Ok
+            );
+            let center = (5 * 11 + 5) * 4;
+            assert_eq!(&pixels[center..center + 4], &[98, 122, 73, 255]);
+
+            rt_camera_free(camera);
+            rt_world_free(world);
+        }
+    }
+
+    #[test]
+    fn planes_through_c_api() {
+        unsafe {
+            let world = rt_world_new();
+            rt_world_add_light(world, 0.0, 10.0, 0.0, 1.0, 1.0, 1.0);
+            // a floor one unit down
+            let down: [f64; 16] = [
+                1.0, 0.0, 0.0, 0.0, //
+                0.0, 1.0, 0.0, -1.0, //
+                0.0, 0.0, 1.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0,
+            ];
+            let mut material = rt_material_default();
+            material.specular = 0.0;
+            assert_eq!(
+                rt_world_add_plane(world, down.as_ptr(), &material),
+                RtStatus::Ok
+            );
+
+            let camera = rt_camera_new(3, 3, PI / 2.0);
+            let (from, to, up) = ([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]);
+            rt_camera_look_at(camera, from.as_ptr(), to.as_ptr(), up.as_ptr());
+            let mut pixels = vec![0u8; 3 * 3 * 4];
+            assert_eq!(
+                rt_render(camera, world, pixels.as_mut_ptr(), pixels.len()),
+                RtStatus::Ok
+            );
+            // the floor fills the bottom row, the top row looks over it into nothing
+            let (top, bottom) = (4, (2 * 3 + 1) * 4);
+            assert!(pixels[bottom] > 0);
+            assert_eq!(&pixels[top..top + 4], &[0, 0, 0, 255]);
+
+            rt_camera_free(camera);
+            rt_world_free(world);
+        }
+    }
+
+    #[test]
+    fn errors_through_c_api() {
+        unsafe {
+            assert_eq!(
+                rt_world_add_light(ptr::null_mut(), 0.0, 0.0, 0.0, 1.0, 1.0, 1.0),
+                RtStatus::NullPointer
+            );
+            let world = rt_world_new();
+            let flat = [0.0; 16];
+            assert_eq!(
+                rt_world_add_sphere(world, flat.as_ptr(), ptr::null()),
+                RtStatus::DegenerateTransform
+            );
+            assert_eq!(
+                rt_world_add_plane(world, flat.as_ptr(), ptr::null()),
+                RtStatus::DegenerateTransform
+            );
+            assert_eq!(
+                rt_world_add_plane(ptr::null_mut(), ptr::null(), ptr::null()),
+                RtStatus::NullPointer
+            );
+            rt_world_free(world);
+            rt_world_free(ptr::null_mut());
+
+            assert!(rt_camera_new(0, 10, PI / 2.0).is_null());
+            assert!(rt_camera_new(10, 0, PI / 2.0).is_null());
+            assert!(rt_camera_new(MAX_RESOLUTION + 1, 10, PI / 2.0).is_null());
+            assert!(rt_camera_new(10, usize::MAX, PI / 2.0).is_null());
+            let camera = rt_camera_new(MAX_RESOLUTION, 1, PI / 2.0);
+            assert!(!camera.is_null());
+            rt_camera_free(camera);
+        }
+    }
+}
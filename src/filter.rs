@@ -0,0 +1,78 @@
+//! Reconstruction filters, deciding how much each sample counts toward the pixels around it.
+//!
+//! Plain averaging only lets a sample count toward the pixel it landed in, so an edge falls
+//! into whichever pixel happens to hold it. A filter wider than half a pixel spreads each
+//! sample over its neighbors too, weighted by distance, which gives smoother edges at the cost
+//! of a little sharpness. See `Camera::set_filter`.
+
+/// Weighting of a sample by its offset from a pixel's center, in pixels. Samples further than
+/// `radius` from the center along either axis don't count toward the pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Every sample within the radius counts the same, radius 0.5 is plain averaging
+    Box { radius: f64 },
+    /// Falls off linearly to nothing at the radius
+    Tent { radius: f64 },
+    /// Bell curve `exp(-alpha * d^2)`, shifted down so it reaches 0 at the radius. Larger
+    /// `alpha` is sharper.
+    Gaussian { radius: f64, alpha: f64 },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius } | Filter::Tent { radius } | Filter::Gaussian { radius, .. } => {
+                radius
+            }
+        }
+    }
+
+    /// Weight of a sample `dx`, `dy` pixels away from the pixel's center
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        let radius = self.radius();
+        if dx.abs() > radius || dy.abs() > radius {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => (radius - dx.abs()) * (radius - dy.abs()),
+            Filter::Gaussian { radius, alpha } => {
+                let gaussian = |d: f64| (-alpha * d * d).exp() - (-alpha * radius * radius).exp();
+                gaussian(dx).max(0.0) * gaussian(dy).max(0.0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::Filter;
+
+    #[test]
+    fn filter_weights() {
+        let boxed = Filter::default();
+        assert_eq!(boxed.weight(0.4, -0.2), 1.0);
+        assert_eq!(boxed.weight(0.6, 0.0), 0.0);
+
+        let tent = Filter::Tent { radius: 1.0 };
+        assert_eq!(tent.weight(0.0, 0.0), 1.0);
+        assert_eq!(tent.weight(0.5, 0.0), 0.5);
+        assert_eq!(tent.weight(0.5, 0.5), 0.25);
+        assert_eq!(tent.weight(1.5, 0.0), 0.0);
+
+        let gaussian = Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        };
+        assert!(gaussian.weight(0.0, 0.0) > gaussian.weight(0.5, 0.0));
+        assert!(gaussian.weight(0.5, 0.0) > gaussian.weight(1.0, 0.0));
+        assert_eq!(gaussian.weight(1.5, 0.0), 0.0);
+        assert_eq!(gaussian.weight(0.3, 0.0), gaussian.weight(-0.3, 0.0));
+    }
+}
@@ -0,0 +1,48 @@
+//! Homogeneous fog filling the whole world, so distant objects fade into the haze instead of
+//! staying as crisp as nearby ones.
+
+use crate::color::Color;
+
+/// Fog of the same density everywhere
+///
+/// Light traveling through it is attenuated exponentially with distance (Beer-Lambert) and
+/// replaced by the fog's own color, the light it scatters toward the eye. Rays that miss
+/// everything see only fog.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fog {
+    pub color: Color,
+    /// Fraction of light scattered per unit of distance, 0.1 hides things about 30 units away
+    pub density: f64,
+}
+
+impl Fog {
+    pub fn new(color: Color, density: f64) -> Self {
+        Fog { color, density }
+    }
+
+    /// Fraction of light that makes it through `distance` units of fog
+    pub fn transmittance(&self, distance: f64) -> f64 {
+        (-self.density * distance).exp()
+    }
+
+    /// `color` as seen from `distance` units away through the fog
+    pub fn apply(&self, color: Color, distance: f64) -> Color {
+        self.color.lerp(color, self.transmittance(distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::fog::Fog;
+
+    #[test]
+    fn fog_thickens_with_distance() {
+        let fog = Fog::new(Color::new(0.5, 0.5, 0.5), 0.1);
+        assert_eq!(fog.apply(Color::RED, 0.0), Color::RED);
+        assert_eq!(fog.apply(Color::RED, f64::INFINITY), fog.color);
+        let halfway = fog.apply(Color::RED, 2.0_f64.ln() / 0.1);
+        assert_eq!(halfway, Color::new(0.75, 0.25, 0.25));
+    }
+}
@@ -0,0 +1,356 @@
+//! Shapes grouped under a shared transform, nested as deep as needed (a wheel inside a car
+//! inside a scene).
+//!
+//! Objects don't point back at the group they're in, the chain of groups around a shape is
+//! passed along instead, outermost first. `world_to_object` and `normal_to_world` walk that
+//! chain to move points and normals between world space and a nested shape's own space.
+//!
+//! A ray that misses a group's bounding box skips everything in it, so a big model in a group
+//! only costs one box test for the rays that don't come near it. The box is worked out on the
+//! first ray and kept until the group's contents change, which is why they're only reachable
+//! through methods.
+
+use std::sync::OnceLock;
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::packet::RayPacket;
+use crate::rays::{intersect_into, Intersections, Ray};
+use crate::shape::{Object, Shape};
+use crate::tuple::{Point, Vector};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    pub id: Uuid,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    objects: Vec<Object>,
+    groups: Vec<Group>,
+    // around the contents in the group's own space, empty until first needed
+    #[cfg_attr(feature = "scene", serde(skip))]
+    local_bounds: OnceLock<BoundingBox>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Group {
+            id: Uuid::new_v4(),
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            objects: Vec::new(),
+            groups: Vec::new(),
+            local_bounds: OnceLock::new(),
+        }
+    }
+
+    pub fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    pub fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    /// Sets the transform of everything in the group, failing like `Sphere::set_transform`
+    /// when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    pub fn add_object(&mut self, object: impl Into<Object>) {
+        self.local_bounds.take();
+        self.objects.push(object.into());
+    }
+
+    pub fn add_group(&mut self, group: Group) {
+        self.local_bounds.take();
+        self.groups.push(group);
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// The objects, to move or change in place
+    pub fn objects_mut(&mut self) -> &mut [Object] {
+        self.local_bounds.take();
+        &mut self.objects
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    pub fn groups_mut(&mut self) -> &mut [Group] {
+        self.local_bounds.take();
+        &mut self.groups
+    }
+
+    /// Box around everything in the group, in the space the group sits in (world space unless
+    /// it's nested in another group), empty for an empty group
+    pub fn bounds(&self) -> BoundingBox {
+        self.local_bounds().transformed(&self.transform)
+    }
+
+    /// Box around everything in the group in its own space, before its transform
+    pub fn local_bounds(&self) -> BoundingBox {
+        *self.local_bounds.get_or_init(|| {
+            let mut bounds = BoundingBox::empty();
+            for object in &self.objects {
+                bounds.add_box(&object.bounds());
+            }
+            for group in &self.groups {
+                bounds.add_box(&group.bounds());
+            }
+            bounds
+        })
+    }
+
+    /// Intersects the ray with everything in the group and its subgroups. The objects in the
+    /// intersections are placed in world space (their transform combined with every group's
+    /// around them) so they can be shaded like any other object.
+    pub fn intersect(&self, ray: &Ray) -> Intersections<Object> {
+        let mut xs = Intersections::new();
+        self.intersect_into(ray, &mut xs);
+        xs
+    }
+
+    /// `intersect`, adding the intersections to `xs` instead of allocating a new collection
+    pub fn intersect_into(&self, ray: &Ray, xs: &mut Intersections<Object>) {
+        self.intersect_within(ray, IDENTITY_MATRIX_4X4, IDENTITY_MATRIX_4X4, xs);
+    }
+
+    fn intersect_within(
+        &self,
+        ray: &Ray,
+        parent: M4x4,
+        parent_inverse: M4x4,
+        xs: &mut Intersections<Object>,
+    ) {
+        let transform = parent * self.transform;
+        let inverse = self.inverse_transform * parent_inverse;
+        if self
+            .local_bounds()
+            .intersects(&ray.transformed(&inverse))
+            .is_none()
+        {
+            return;
+        }
+        for object in &self.objects {
+            intersect_into(ray, object.placed_in(transform, inverse), xs);
+        }
+        for group in &self.groups {
+            group.intersect_within(ray, transform, inverse, xs);
+        }
+    }
+
+    /// Adds the objects in the group and its subgroups to `objects`, placed in world space the
+    /// way `intersect` places them, for intersecting a whole packet of rays with them. Groups
+    /// none of the packet's rays come near are left out.
+    pub(crate) fn placed_objects_into(&self, packet: &RayPacket, objects: &mut Vec<Object>) {
+        self.placed_within(packet, IDENTITY_MATRIX_4X4, IDENTITY_MATRIX_4X4, objects);
+    }
+
+    fn placed_within(
+        &self,
+        packet: &RayPacket,
+        parent: M4x4,
+        parent_inverse: M4x4,
+        objects: &mut Vec<Object>,
+    ) {
+        let transform = parent * self.transform;
+        let inverse = self.inverse_transform * parent_inverse;
+        let bounds = self.local_bounds();
+        let near = (0..packet.len()).any(|lane| {
+            bounds
+                .intersects(&packet.ray(lane).transformed(&inverse))
+                .is_some()
+        });
+        if !near {
+            return;
+        }
+        for object in &self.objects {
+            objects.push(object.placed_in(transform, inverse));
+        }
+        for group in &self.groups {
+            group.placed_within(packet, transform, inverse, objects);
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Group::new()
+    }
+}
+
+/// Converts a world space point into the space of `shape`, inside the nested `groups`
+/// (outermost first)
+pub fn world_to_object(groups: &[&Group], shape: &impl Shape, point: Point) -> Point {
+    match groups.split_last() {
+        Some((parent, ancestors)) => {
+            shape.inverse_transform() * group_to_local(ancestors, parent, point)
+        }
+        None => shape.inverse_transform() * point,
+    }
+}
+
+/// The point in the space of `group`, after converting it through each of its ancestors
+fn group_to_local(ancestors: &[&Group], group: &Group, point: Point) -> Point {
+    let point = match ancestors.split_last() {
+        Some((parent, rest)) => group_to_local(rest, parent, point),
+        None => point,
+    };
+    group.inverse_transform() * point
+}
+
+/// Converts a normal in the space of `shape`, inside the nested `groups` (outermost first),
+/// into world space
+pub fn normal_to_world(groups: &[&Group], shape: &impl Shape, normal: Vector) -> Vector {
+    let normal = to_parent_space(shape.inverse_transform(), normal);
+    match groups.split_last() {
+        Some((parent, ancestors)) => group_normal_to_world(ancestors, parent, normal),
+        None => normal,
+    }
+}
+
+fn group_normal_to_world(ancestors: &[&Group], group: &Group, normal: Vector) -> Vector {
+    let normal = to_parent_space(group.inverse_transform(), normal);
+    match ancestors.split_last() {
+        Some((parent, rest)) => group_normal_to_world(rest, parent, normal),
+        None => normal,
+    }
+}
+
+/// Normals go through the transposed inverse to stay perpendicular to the surface
+fn to_parent_space(inverse_transform: M4x4, normal: Vector) -> Vector {
+    let mut normal = inverse_transform.transpose() * normal;
+    // a translation in the transform leaks into w, see `Shape::normal_at`
+    normal.w = 0.0;
+    normal.normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::group::{normal_to_world, world_to_object, Group};
+    use crate::matrix_transformations::{rotation_y, scaling, translation};
+    use crate::rays::{Ray, Sphere};
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+
+    fn nested(inner_scale: (f64, f64, f64)) -> (Group, Group, Sphere) {
+        let mut g1 = Group::new();
+        g1.set_transform(rotation_y(FRAC_PI_2)).unwrap();
+        let mut g2 = Group::new();
+        let (x, y, z) = inner_scale;
+        g2.set_transform(scaling(x, y, z)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(translation(5.0, 0.0, 0.0)).unwrap();
+        (g1, g2, s)
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let (g1, g2, s) = nested((2.0, 2.0, 2.0));
+        let p = world_to_object(&[&g1, &g2], &s, Point::new_point(-2.0, 0.0, -10.0));
+        assert_eq!(p, Point::new_point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let (g1, g2, s) = nested((1.0, 2.0, 3.0));
+        let third = 3.0_f64.sqrt() / 3.0;
+        let n = normal_to_world(&[&g1, &g2], &s, Vector::new(third, third, third));
+        assert_eq!(n, Vector::new(2.0 / 7.0, 3.0 / 7.0, -6.0 / 7.0));
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(translation(5.0, 0.0, 0.0)).unwrap();
+        g.add_object(s);
+        let r = Ray::new(
+            Point::new_point(10.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let xs = g.intersect(&r);
+        assert_eq!(xs.size(), 2);
+        assert_eq!(xs[0].object.id(), s.id);
+    }
+
+    #[test]
+    fn rays_missing_the_bounds_skip_the_group() {
+        let mut g = Group::new();
+        g.set_transform(translation(0.0, 0.0, 5.0)).unwrap();
+        let mut s = Sphere::new();
+        s.set_transform(translation(2.0, 0.0, 0.0)).unwrap();
+        g.add_object(s);
+        assert_eq!(g.local_bounds().min(), Point::new_point(1.0, -1.0, -1.0));
+        assert_eq!(g.bounds().max(), Point::new_point(3.0, 1.0, 6.0));
+
+        let origin = Point::new_point(-2.0, 0.0, -5.0);
+        let toward = |to: Point| Ray::new(origin, (to - origin).normalize());
+        assert_eq!(
+            g.intersect(&toward(Point::new_point(2.0, 0.0, 5.0))).size(),
+            2
+        );
+        assert_eq!(
+            g.intersect(&toward(Point::new_point(-2.0, 0.0, 5.0)))
+                .size(),
+            0
+        );
+
+        // the box grows with what's added after it was first needed
+        let mut other = Sphere::new();
+        other.set_transform(translation(-2.0, 0.0, 0.0)).unwrap();
+        g.add_object(other);
+        assert_eq!(
+            g.intersect(&toward(Point::new_point(-2.0, 0.0, 5.0)))
+                .size(),
+            2
+        );
+        // and moves with what's moved inside it
+        g.objects_mut()[1]
+            .set_transform(translation(-2.0, 10.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            g.intersect(&toward(Point::new_point(-2.0, 0.0, 5.0)))
+                .size(),
+            0
+        );
+        assert_eq!(g.bounds().max().y, 11.0);
+    }
+
+    #[test]
+    fn hits_in_nested_groups_have_world_space_normals() {
+        let (mut g1, mut g2, s) = nested((1.0, 2.0, 3.0));
+        g2.add_object(s);
+        g1.add_group(g2.clone());
+
+        let point = Point::new_point(1.7321, 1.1547, -5.5774);
+        let r = Ray::new(
+            point - Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let hit = *g1.intersect(&r).hit().unwrap();
+        let normal = hit.object.normal_at(point, &hit);
+        // the point is only given to four places
+        assert!((normal - Vector::new(2.0 / 7.0, 3.0 / 7.0, -6.0 / 7.0)).magnitude() < 1e-3);
+
+        let local = world_to_object(&[&g1, &g2], &s, point);
+        let object_normal = local - Point::new_point(0.0, 0.0, 0.0);
+        assert_eq!(normal_to_world(&[&g1, &g2], &s, object_normal), normal);
+    }
+}
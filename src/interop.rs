@@ -0,0 +1,152 @@
+//! Conversions between this crate's types and the math types of other engines and tools,
+//! so the tracer can be embedded without copying fields by hand.
+//!
+//! Every conversion is behind a feature named after the crate it targets (`glam`, `nalgebra`).
+//! Matrices here are row-major (`matrix[row][col]`) while glam stores columns, so the glam
+//! conversions transpose on the way in and out.
+
+#[cfg(feature = "glam")]
+mod glam_impls {
+    use glam::{DMat4, DVec4, Mat4, Vec4};
+
+    use crate::matrix::M4x4;
+    use crate::tuple::Tuple;
+
+    impl From<Tuple> for DVec4 {
+        fn from(t: Tuple) -> Self {
+            DVec4::new(t.x, t.y, t.z, t.w)
+        }
+    }
+
+    impl From<DVec4> for Tuple {
+        fn from(v: DVec4) -> Self {
+            Tuple {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+                w: v.w,
+            }
+        }
+    }
+
+    impl From<Tuple> for Vec4 {
+        fn from(t: Tuple) -> Self {
+            Vec4::new(t.x as f32, t.y as f32, t.z as f32, t.w as f32)
+        }
+    }
+
+    impl From<Vec4> for Tuple {
+        fn from(v: Vec4) -> Self {
+            v.as_dvec4().into()
+        }
+    }
+
+    impl From<M4x4> for DMat4 {
+        fn from(m: M4x4) -> Self {
+            DMat4::from_cols_array_2d(&m.matrix).transpose()
+        }
+    }
+
+    impl From<DMat4> for M4x4 {
+        fn from(m: DMat4) -> Self {
+            M4x4::from(m.transpose().to_cols_array_2d())
+        }
+    }
+
+    impl From<M4x4> for Mat4 {
+        fn from(m: M4x4) -> Self {
+            DMat4::from(m).as_mat4()
+        }
+    }
+
+    impl From<Mat4> for M4x4 {
+        fn from(m: Mat4) -> Self {
+            m.as_dmat4().into()
+        }
+    }
+}
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_impls {
+    use nalgebra::{Matrix4, Vector4};
+
+    use crate::matrix::M4x4;
+    use crate::tuple::Tuple;
+
+    impl From<Tuple> for Vector4<f64> {
+        fn from(t: Tuple) -> Self {
+            Vector4::new(t.x, t.y, t.z, t.w)
+        }
+    }
+
+    impl From<Vector4<f64>> for Tuple {
+        fn from(v: Vector4<f64>) -> Self {
+            Tuple {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+                w: v.w,
+            }
+        }
+    }
+
+    impl From<M4x4> for Matrix4<f64> {
+        fn from(m: M4x4) -> Self {
+            Matrix4::from_fn(|row, col| m.matrix[row][col])
+        }
+    }
+
+    impl From<Matrix4<f64>> for M4x4 {
+        fn from(m: Matrix4<f64>) -> Self {
+            let mut matrix = [[0.0; 4]; 4];
+            for (row, vals) in matrix.iter_mut().enumerate() {
+                for (col, val) in vals.iter_mut().enumerate() {
+                    *val = m[(row, col)];
+                }
+            }
+            M4x4::from(matrix)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam_round_trips() {
+        use glam::{DMat4, DVec4, Mat4, Vec4};
+
+        use crate::matrix::M4x4;
+        use crate::matrix_transformations::translation;
+        use crate::tuple::{Point, Tuple};
+
+        let p = Point::new_point(1.0, 2.0, 3.0);
+        assert_eq!(DVec4::from(p), DVec4::new(1.0, 2.0, 3.0, 1.0));
+        assert_eq!(Tuple::from(Vec4::from(p)), p);
+
+        let t = translation(5.0, -3.0, 2.0);
+        let gt = DMat4::from(t);
+        // glam agrees on what the transform does to a point
+        assert_eq!(Tuple::from(gt * DVec4::from(p)), t * p);
+        assert_eq!(M4x4::from(gt), t);
+        assert_eq!(M4x4::from(Mat4::from(t)), t);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_round_trips() {
+        use nalgebra::{Matrix4, Vector4};
+
+        use crate::matrix::M4x4;
+        use crate::matrix_transformations::{rotation_y, translation};
+        use crate::tuple::{Point, Tuple};
+
+        let p = Point::new_point(1.0, 2.0, 3.0);
+        assert_eq!(Vector4::from(p), Vector4::new(1.0, 2.0, 3.0, 1.0));
+
+        let t = translation(5.0, -3.0, 2.0) * rotation_y(0.5);
+        let nt = Matrix4::from(t);
+        assert_eq!(Tuple::from(nt * Vector4::from(p)), t * p);
+        assert_eq!(M4x4::from(nt), t);
+    }
+}
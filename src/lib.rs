@@ -0,0 +1,89 @@
+//! A ray tracer following The Ray Tracer Challenge book.
+//!
+//! The building blocks live in their own modules (tuples, colors, matrices and transforms,
+//! rays and shapes, lights and materials, worlds and cameras, canvas output) and the most commonly used items
+//! are collected in [`prelude`]:
+//!
+//! ```
+//! use ray_tracer::prelude::*;
+//!
+//! # fn main() -> ray_tracer::Result<()> {
+//! let mut sphere = Sphere::new();
+//! // fails on a transform that can't be inverted, like scaling by 0
+//! sphere.set_transform(scaling(2.0, 2.0, 2.0))?;
+//! let ray = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+//! let xs = intersect(&ray, sphere);
+//! assert_eq!(hit(&xs).unwrap().t, 3.0);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod adaptive;
+pub mod animation;
+pub mod bake;
+pub mod bounds;
+pub mod camera;
+pub mod canvas;
+pub mod capsule;
+pub mod clip;
+pub mod color;
+pub mod composite;
+pub mod coordinates;
+pub mod cube;
+pub mod environment;
+pub mod error;
+pub mod exposure;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod fog;
+pub mod group;
+mod interop;
+pub mod light;
+pub mod limits;
+pub mod matrix;
+pub mod matrix_transformations;
+pub mod mesh;
+pub mod metrics;
+pub mod noise;
+pub mod normal_map;
+pub mod orbit;
+pub mod overlay;
+pub mod packet;
+#[cfg(all(feature = "egui", not(target_arch = "wasm32")))]
+pub mod panel;
+pub mod pattern;
+pub mod prelude;
+pub mod preview;
+pub mod projectile;
+pub mod quad;
+#[cfg(all(feature = "batch", not(target_arch = "wasm32")))]
+pub mod queue;
+pub mod random;
+pub mod rays;
+#[cfg(feature = "scene")]
+pub mod scene;
+pub mod scenes;
+pub mod sdf;
+pub mod sequence;
+pub mod shape;
+pub mod simplify;
+pub mod sky;
+pub mod skybox;
+pub mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+pub mod trajectory;
+pub mod triangle;
+pub mod tuple;
+pub mod utils;
+pub mod uv;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod world;
+
+pub use crate::error::{Error, Result};
+pub use crate::matrix_transformations::{
+    rotation_x, rotation_y, rotation_z, scaling, shearing, translation, translation_from,
+    uniform_scaling, view_transform,
+};
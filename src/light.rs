@@ -1,11 +1,98 @@
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
 use crate::color::Color;
+use crate::pattern::MaterialPattern;
+use crate::quad::Quad;
+use crate::random::Rng;
 use crate::rays::reflect;
 use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+use crate::uv::UvMap;
+
+/// Up to 32 light groups as a bit set, for light linking
+///
+/// Lights and objects both belong to groups and a light only shines on objects it shares a group
+/// with, so e.g. a rim light can be kept on the hero object without blowing out the floor.
+/// Everything is in every group by default.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct LightGroups(pub u32);
+
+impl LightGroups {
+    pub const ALL: LightGroups = LightGroups(u32::MAX);
+    pub const NONE: LightGroups = LightGroups(0);
+
+    /// Just the group with the given index, 0 to 31
+    pub fn group(index: u32) -> Self {
+        LightGroups(1 << index)
+    }
+
+    pub fn with(self, other: LightGroups) -> Self {
+        LightGroups(self.0 | other.0)
+    }
+
+    /// Whether the two share at least one group
+    pub fn overlaps(self, other: LightGroups) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for LightGroups {
+    fn default() -> Self {
+        LightGroups::ALL
+    }
+}
+
+/// How a light fades with distance: its intensity is divided by
+/// `constant + linear * distance + quadratic * distance²`
+///
+/// Lights don't fade by default (`Attenuation::NONE`). Real lights fall off with the square of
+/// the distance, some `constant` keeps objects right next to the light from blowing out.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attenuation {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Attenuation {
+    pub const NONE: Attenuation = Attenuation {
+        constant: 1.0,
+        linear: 0.0,
+        quadratic: 0.0,
+    };
+
+    pub fn new(constant: f64, linear: f64, quadratic: f64) -> Self {
+        Attenuation {
+            constant,
+            linear,
+            quadratic,
+        }
+    }
+
+    /// What the intensity is multiplied by `distance` away from the light
+    pub fn factor(&self, distance: f64) -> f64 {
+        let falloff = self.constant + self.linear * distance + self.quadratic * distance * distance;
+        1.0 / falloff.max(EPSILON)
+    }
+}
 
-#[derive(Clone, Copy)]
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation::NONE
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointLight {
     position: Point,
     intensity: Color,
+    groups: LightGroups,
+    attenuation: Attenuation,
 }
 
 impl PointLight {
@@ -13,40 +100,442 @@ impl PointLight {
         PointLight {
             position,
             intensity,
+            groups: LightGroups::ALL,
+            attenuation: Attenuation::NONE,
+        }
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    pub fn set_intensity(&mut self, intensity: Color) {
+        self.intensity = intensity;
+    }
+
+    pub fn groups(&self) -> LightGroups {
+        self.groups
+    }
+
+    /// Limits the light to objects in one of these groups
+    pub fn set_groups(&mut self, groups: LightGroups) {
+        self.groups = groups;
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    /// Light arriving at `point`, faded by the distance to it
+    pub fn intensity_at(&self, point: Point) -> Color {
+        self.intensity * self.attenuation.factor((point - self.position).magnitude())
+    }
+}
+
+/// Light shining from a ball instead of a single point, so shadows get soft edges
+///
+/// Shadows are tested toward `samples` random points on the ball's surface and the light
+/// is scaled by how many of them are visible. The penumbra widens the further the shadow
+/// falls from whatever casts it, the way real shadows do.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct SphereLight {
+    position: Point,
+    radius: f64,
+    intensity: Color,
+    samples: usize,
+    groups: LightGroups,
+}
+
+impl SphereLight {
+    pub fn new(position: Point, radius: f64, intensity: Color) -> Self {
+        SphereLight {
+            position,
+            radius,
+            intensity,
+            samples: 16,
+            groups: LightGroups::ALL,
+        }
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn groups(&self) -> LightGroups {
+        self.groups
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        self.radius = radius;
+    }
+
+    pub fn set_intensity(&mut self, intensity: Color) {
+        self.intensity = intensity;
+    }
+
+    /// Limits the light to objects in one of these groups
+    pub fn set_groups(&mut self, groups: LightGroups) {
+        self.groups = groups;
+    }
+
+    /// Shadow rays per shaded point, more gives smoother penumbras
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// A random point on the surface of the light, uniformly distributed
+    pub fn sample_point(&self, rng: &mut Rng) -> Point {
+        let z = rng.range(-1.0, 1.0);
+        let angle = rng.range(0.0, TAU);
+        let r = (1.0 - z * z).sqrt();
+        self.position + Vector::new(r * angle.cos(), r * angle.sin(), z) * self.radius
+    }
+
+    /// The light as seen from far enough away that it's just a point
+    pub fn as_point_light(&self) -> PointLight {
+        PointLight {
+            position: self.position,
+            intensity: self.intensity,
+            groups: self.groups,
+            attenuation: Attenuation::NONE,
+        }
+    }
+}
+
+/// Light shining from a rectangle (or any parallelogram), like a window or a ceiling panel
+///
+/// Soft shadows work the same as for `SphereLight`, toward random points on the quad. It only
+/// shines out of the side the quad's normal faces. The quad's corner and edges are taken as
+/// world space, its transform and material only matter for quads in the world.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct AreaLight {
+    quad: Quad,
+    intensity: Color,
+    samples: usize,
+    groups: LightGroups,
+}
+
+impl AreaLight {
+    pub fn new(quad: Quad, intensity: Color) -> Self {
+        AreaLight {
+            quad,
+            intensity,
+            samples: 16,
+            groups: LightGroups::ALL,
+        }
+    }
+
+    pub fn quad(&self) -> Quad {
+        self.quad
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    pub fn groups(&self) -> LightGroups {
+        self.groups
+    }
+
+    pub fn set_quad(&mut self, quad: Quad) {
+        self.quad = quad;
+    }
+
+    pub fn set_intensity(&mut self, intensity: Color) {
+        self.intensity = intensity;
+    }
+
+    /// Limits the light to objects in one of these groups
+    pub fn set_groups(&mut self, groups: LightGroups) {
+        self.groups = groups;
+    }
+
+    /// Shadow rays per shaded point, more gives smoother penumbras
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    /// Whether `point` is on the side of the quad the light shines out of
+    pub fn faces(&self, point: Point) -> bool {
+        (point - self.quad.center()).dot(&self.quad.normal()) > 0.0
+    }
+
+    /// The light as seen from far enough away that it's just a point, at the quad's center
+    pub fn as_point_light(&self) -> PointLight {
+        PointLight {
+            position: self.quad.center(),
+            intensity: self.intensity,
+            groups: self.groups,
+            attenuation: Attenuation::NONE,
+        }
+    }
+}
+
+/// Light shining from a point in a cone around `direction`, `angle` radians either side of it
+///
+/// A gobo (an image in front of the light) can be set to tint and block the light across the
+/// cone, for window frames or dappled shade. The image covers the cone's circular cross section
+/// edge to edge, with its top toward world up (or +z when the light points straight up or down).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpotLight {
+    position: Point,
+    direction: Vector,
+    angle: f64,
+    intensity: Color,
+    groups: LightGroups,
+    attenuation: Attenuation,
+    gobo: Option<Arc<Canvas>>,
+}
+
+impl SpotLight {
+    pub fn new(position: Point, direction: Vector, angle: f64, intensity: Color) -> Self {
+        SpotLight {
+            position,
+            direction: direction.normalize(),
+            angle,
+            intensity,
+            groups: LightGroups::ALL,
+            attenuation: Attenuation::NONE,
+            gobo: None,
+        }
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    pub fn groups(&self) -> LightGroups {
+        self.groups
+    }
+
+    pub fn attenuation(&self) -> Attenuation {
+        self.attenuation
+    }
+
+    pub fn gobo(&self) -> Option<&Canvas> {
+        self.gobo.as_deref()
+    }
+
+    pub fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    pub fn set_direction(&mut self, direction: Vector) {
+        self.direction = direction.normalize();
+    }
+
+    pub fn set_angle(&mut self, angle: f64) {
+        self.angle = angle;
+    }
+
+    pub fn set_intensity(&mut self, intensity: Color) {
+        self.intensity = intensity;
+    }
+
+    /// Limits the light to objects in one of these groups
+    pub fn set_groups(&mut self, groups: LightGroups) {
+        self.groups = groups;
+    }
+
+    pub fn set_attenuation(&mut self, attenuation: Attenuation) {
+        self.attenuation = attenuation;
+    }
+
+    /// Projects an image across the cone, shared so copies of the light don't copy the image
+    pub fn set_gobo(&mut self, gobo: Option<Arc<Canvas>>) {
+        self.gobo = gobo;
+    }
+
+    /// Light arriving at `point`: black outside the cone, tinted by the gobo and faded by the
+    /// distance inside it
+    pub fn intensity_at(&self, point: Point) -> Color {
+        let to_point = point - self.position;
+        self.intensity
+            * self.tint(to_point.normalize())
+            * self.attenuation.factor(to_point.magnitude())
+    }
+
+    /// What the cone and the gobo let through toward `to_point`
+    fn tint(&self, to_point: Vector) -> Color {
+        let cos = to_point.dot(&self.direction);
+        if cos <= 0.0 || cos < self.angle.cos() {
+            return Color::BLACK;
+        }
+        let Some(gobo) = &self.gobo else {
+            return Color::WHITE;
+        };
+
+        let up = if self.direction.x.abs() < EPSILON && self.direction.z.abs() < EPSILON {
+            Vector::new(0.0, 0.0, 1.0)
+        } else {
+            Vector::new(0.0, 1.0, 0.0)
+        };
+        let right = self.direction.cross(&up).normalize();
+        let up = right.cross(&self.direction);
+        // where the direction to the point crosses the plane one unit in front of the light,
+        // scaled so the cone's edge is at -1 and 1
+        let on_plane = to_point / cos / self.angle.tan();
+        let u = (on_plane.dot(&right) + 1.0) / 2.0;
+        let v = (1.0 - on_plane.dot(&up)) / 2.0;
+        let x = ((u * gobo.width() as f64) as usize).min(gobo.width().saturating_sub(1));
+        let y = ((v * gobo.height() as f64) as usize).min(gobo.height().saturating_sub(1));
+        gobo.get_pixel(x, y).unwrap_or(Color::WHITE)
+    }
+
+    /// The light as a point light with its intensity at `point`, for shading that point
+    pub fn as_point_light_at(&self, point: Point) -> PointLight {
+        PointLight {
+            position: self.position,
+            intensity: self.intensity_at(point),
+            groups: self.groups,
+            // already faded by `intensity_at`
+            attenuation: Attenuation::NONE,
+        }
+    }
+}
+
+/// Base illumination reaching every surface from all around, on top of each material's own
+/// `ambient` term, so the fill light of a whole scene can be turned up or down in one place
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub enum AmbientLight {
+    /// The same color from every direction
+    Flat(Color),
+    /// Sky color from above fading into ground color from below, surfaces facing up get
+    /// all sky and surfaces facing down all ground
+    Hemisphere { sky: Color, ground: Color },
+}
+
+impl AmbientLight {
+    /// Light arriving at a surface facing along `normal`
+    pub fn intensity_at(&self, normal: Vector) -> Color {
+        match *self {
+            AmbientLight::Flat(color) => color,
+            AmbientLight::Hemisphere { sky, ground } => ground.lerp(sky, (normal.y + 1.0) / 2.0),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub color: Color,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    /// How much of the surrounding scene the surface mirrors, 0 (none) to 1 (a perfect mirror)
+    pub reflective: f64,
+    /// How much light passes through the surface, 0 (opaque) to 1 (clear glass)
+    pub transparency: f64,
+    /// How much light bends entering the material, 1 for air and vacuum, about 1.5 for glass
+    pub refractive_index: f64,
+    /// Colors the surface in place of `color`, none by default
+    pub pattern: Option<MaterialPattern>,
+    /// How uv patterns wrap around the surface, the shape's own way by default (see
+    /// `Shape::uv_map`)
+    pub uv_map: Option<UvMap>,
+    /// Index into `World::normal_maps` of the map bumping the surface, none by default
+    pub normal_map: Option<usize>,
 }
 
 impl Material {
     pub fn new() -> Self {
         Material {
-            color: Color::new(1.0, 1.0, 1.0),
+            color: Color::WHITE,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            pattern: None,
+            uv_map: None,
+            normal_map: None,
         }
     }
 }
 
+impl Default for Material {
+    fn default() -> Self {
+        Material::new()
+    }
+}
+
+/// Phong shading of a point by a single light. In shadow, only the ambient term is left.
 pub fn lighting(
     material: Material,
     light: PointLight,
     point: Point,
     eyev: Vector,
     normalv: Vector,
+    in_shadow: bool,
+) -> Color {
+    let visibility = if in_shadow { 0.0 } else { 1.0 };
+    lighting_with_visibility(material, light, point, eyev, normalv, visibility)
+}
+
+/// `lighting` for a light that is only partly visible from the point, `visibility` going from
+/// 0 (fully in shadow, only the ambient term is left) to 1 (fully lit)
+pub fn lighting_with_visibility(
+    material: Material,
+    light: PointLight,
+    point: Point,
+    eyev: Vector,
+    normalv: Vector,
+    visibility: f64,
 ) -> Color {
-    // Combine the surface color with the light's color/intensity
-    let effective_color = material.color * light.intensity;
+    // Combine the surface color with the light's color/intensity where it reaches the point
+    let intensity = light.intensity_at(point);
+    let effective_color = material.color * intensity;
 
     // Find the direction to the light source
     let lightv = (light.position - point).normalize();
@@ -61,8 +550,8 @@ pub fn lighting(
     let (diffuse, specular);
 
     if light_dot_normal < 0.0 {
-        diffuse = Color::new(0.0, 0.0, 0.0); // black
-        specular = Color::new(0.0, 0.0, 0.0); // black
+        diffuse = Color::BLACK;
+        specular = Color::BLACK;
     } else {
         // Compute the diffuse contribution
         diffuse = effective_color * material.diffuse * light_dot_normal;
@@ -74,28 +563,181 @@ pub fn lighting(
         let reflect_dot_eye = reflectv.dot(&eyev);
 
         if reflect_dot_eye <= 0.0 {
-            specular = Color::new(0.0, 0.0, 0.0); // black
+            specular = Color::BLACK;
         } else {
             // Compute the specular contribution
             let factor = reflect_dot_eye.powf(material.shininess);
-            specular = light.intensity * material.specular * factor;
+            specular = intensity * material.specular * factor;
         }
     }
 
-    // Add the three contributions together to get the final shading
-    ambient + diffuse + specular
+    // Add the three contributions together to get the final shading,
+    // only the ambient part reaches the point when the light is blocked
+    ambient + (diffuse + specular) * visibility
+}
+
+/// Contribution of an ambient light to a surface, regardless of the material's `ambient`
+pub fn ambient_lighting(material: Material, ambient: AmbientLight, normalv: Vector) -> Color {
+    material.color * ambient.intensity_at(normalv)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::canvas::Canvas;
     use crate::color::Color;
-    use crate::light::{lighting, Material, PointLight};
+    use crate::light::{
+        ambient_lighting, lighting, lighting_with_visibility, AmbientLight, Attenuation, Material,
+        PointLight, SphereLight, SpotLight,
+    };
+    use crate::random::{Rng, Seedable};
     use crate::tuple::{Point, Vector};
+    use crate::utils::equal_f64;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    #[test]
+    fn spot_lights_only_light_their_cone() {
+        let light = SpotLight::new(
+            Point::new_point(0.0, 10.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            PI / 4.0,
+            Color::WHITE,
+        );
+        assert_eq!(
+            light.intensity_at(Point::new_point(9.0, 0.0, 0.0)),
+            Color::WHITE
+        );
+        assert_eq!(
+            light.intensity_at(Point::new_point(11.0, 0.0, 0.0)),
+            Color::BLACK
+        );
+        assert_eq!(
+            light.intensity_at(Point::new_point(0.0, 20.0, 0.0)),
+            Color::BLACK
+        );
+    }
+
+    #[test]
+    fn gobos_tint_the_cone() {
+        let mut light = SpotLight::new(
+            Point::new_point(0.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+            PI / 4.0,
+            Color::WHITE,
+        );
+        // left half red, right half blue, as seen from the light
+        let mut gobo = Canvas::new(2, 2);
+        for y in 0..2 {
+            gobo.write_pixel(0, y, Color::RED);
+            gobo.write_pixel(1, y, Color::BLUE);
+        }
+        light.set_gobo(Some(Arc::new(gobo)));
+        let right = light.direction().cross(&Vector::new(0.0, 1.0, 0.0));
+        let center = Point::new_point(0.0, 0.0, 0.0);
+        assert_eq!(light.intensity_at(center - right * 5.0), Color::RED);
+        assert_eq!(light.intensity_at(center + right * 5.0), Color::BLUE);
+    }
+
+    #[test]
+    fn sphere_light_samples_its_surface() {
+        let light = SphereLight::new(Point::new_point(1.0, 2.0, 3.0), 0.5, Color::WHITE);
+        let mut rng = Rng::from_seed(1);
+        for _ in 0..100 {
+            let p = light.sample_point(&mut rng);
+            assert!(equal_f64((p - light.position()).magnitude(), 0.5));
+        }
+    }
+
+    #[test]
+    fn lighting_a_partly_visible_light_keeps_the_ambient_term() {
+        let m = Material::new();
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new_point(0.0, 0.0, -10.0), Color::WHITE);
+        let origin = Point::new_point(0.0, 0.0, 0.0);
+        assert_eq!(
+            lighting_with_visibility(m, light, origin, eyev, normalv, 0.0),
+            Color::new(0.1, 0.1, 0.1)
+        );
+        assert_eq!(
+            lighting_with_visibility(m, light, origin, eyev, normalv, 0.5),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            lighting_with_visibility(m, light, origin, eyev, normalv, 1.0),
+            lighting(m, light, origin, eyev, normalv, false)
+        );
+    }
+
+    #[test]
+    fn hemisphere_ambient_light_blends_by_normal() {
+        let sky = Color::new(0.2, 0.4, 0.8);
+        let ground = Color::new(0.4, 0.2, 0.0);
+        let ambient = AmbientLight::Hemisphere { sky, ground };
+        assert_eq!(ambient.intensity_at(Vector::new(0.0, 1.0, 0.0)), sky);
+        assert_eq!(ambient.intensity_at(Vector::new(0.0, -1.0, 0.0)), ground);
+        assert_eq!(
+            ambient.intensity_at(Vector::new(1.0, 0.0, 0.0)),
+            Color::new(0.3, 0.3, 0.4)
+        );
+
+        let m = Material {
+            color: Color::new(1.0, 0.5, 1.0),
+            ambient: 0.0,
+            ..Material::new()
+        };
+        assert_eq!(
+            ambient_lighting(m, AmbientLight::Flat(sky), Vector::new(0.0, 0.0, -1.0)),
+            Color::new(0.2, 0.2, 0.8)
+        );
+    }
+
+    #[test]
+    fn lights_fade_with_distance() {
+        let mut light = PointLight::new(Point::new_point(0.0, 0.0, 0.0), Color::WHITE);
+        let far = Point::new_point(0.0, 0.0, 10.0);
+        assert_eq!(light.intensity_at(far), Color::WHITE);
+
+        light.set_attenuation(Attenuation::new(1.0, 0.0, 0.03));
+        assert_eq!(light.intensity_at(far), Color::new(0.25, 0.25, 0.25));
+        let near = Point::new_point(0.0, 0.0, 1.0);
+        assert!(light.intensity_at(near).red() > light.intensity_at(far).red());
+
+        // shading uses the faded intensity, ambient term included
+        let m = Material::new();
+        let lit = lighting(
+            m,
+            light,
+            far,
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            false,
+        );
+        assert_eq!(lit, Color::new(0.475, 0.475, 0.475));
+
+        let mut spot = SpotLight::new(
+            Point::new_point(0.0, 10.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            PI / 4.0,
+            Color::WHITE,
+        );
+        spot.set_attenuation(Attenuation::new(0.0, 0.2, 0.0));
+        let below = Point::new_point(0.0, 0.0, 0.0);
+        assert_eq!(spot.intensity_at(below), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(
+            spot.as_point_light_at(below).intensity_at(below),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            spot.intensity_at(Point::new_point(11.0, 0.0, 0.0)),
+            Color::BLACK
+        );
+    }
 
     #[test]
     fn point_light_has_position_and_intensity() {
         let pos = Point::new_point(0.0, 0.0, 0.0);
-        let intensity = Color::new(1.0, 1.0, 1.0);
+        let intensity = Color::WHITE;
         let light = PointLight::new(pos, intensity);
         assert_eq!(light.position, pos);
         assert_eq!(light.intensity, intensity);
@@ -110,6 +752,9 @@ mod tests {
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
     }
 
     #[test]
@@ -118,9 +763,9 @@ mod tests {
         let position = Point::new(0.0, 0.0, 0.0);
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
 
-        let result = lighting(m, light, position, eyev, normalv);
+        let result = lighting(m, light, position, eyev, normalv, false);
 
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
     }
@@ -131,9 +776,9 @@ mod tests {
         let position = Point::new(0.0, 0.0, 0.0);
         let eyev = Vector::new(0.0, (2.0_f64).sqrt() / 2.0, -(2.0_f64).sqrt() / 2.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
 
-        let result = lighting(m, light, position, eyev, normalv);
+        let result = lighting(m, light, position, eyev, normalv, false);
 
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
@@ -144,9 +789,9 @@ mod tests {
         let position = Point::new(0.0, 0.0, 0.0);
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::WHITE);
 
-        let result = lighting(m, light, position, eyev, normalv);
+        let result = lighting(m, light, position, eyev, normalv, false);
 
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
@@ -157,9 +802,9 @@ mod tests {
         let position = Point::new(0.0, 0.0, 0.0);
         let eyev = Vector::new(0.0, -(2.0_f64).sqrt() / 2.0, -(2.0_f64).sqrt() / 2.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::WHITE);
 
-        let result = lighting(m, light, position, eyev, normalv);
+        let result = lighting(m, light, position, eyev, normalv, false);
 
         assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
     }
@@ -170,9 +815,22 @@ mod tests {
         let position = Point::new(0.0, 0.0, 0.0);
         let eyev = Vector::new(0.0, 0.0, -1.0);
         let normalv = Vector::new(0.0, 0.0, -1.0);
-        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+        let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::WHITE);
+
+        let result = lighting(m, light, position, eyev, normalv, false);
+
+        assert_eq!(result, Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_the_surface_in_shadow() {
+        let m = Material::new();
+        let position = Point::new(0.0, 0.0, 0.0);
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::WHITE);
 
-        let result = lighting(m, light, position, eyev, normalv);
+        let result = lighting(m, light, position, eyev, normalv, true);
 
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
@@ -0,0 +1,98 @@
+//! How far reflected and refracted rays are followed.
+//!
+//! Two mirrors facing each other reflect a ray forever, so every bounce counts against a limit
+//! and a ray that runs out just stops contributing light. Reflections and refractions have
+//! their own limits (glass needs a couple of refractions just to get through a single pane)
+//! plus one on the total, which keeps a hall of glass mirrors from branching out of control.
+
+/// Most bounces allowed per ray, see `World::ray_limits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct RayLimits {
+    pub reflection_depth: u32,
+    pub refraction_depth: u32,
+    /// Reflections and refractions together
+    pub bounces: u32,
+}
+
+impl RayLimits {
+    pub fn new(reflection_depth: u32, refraction_depth: u32, bounces: u32) -> Self {
+        RayLimits {
+            reflection_depth,
+            refraction_depth,
+            bounces,
+        }
+    }
+
+    /// The depth of the ray reflected off a surface hit at `depth`, `None` when that's one
+    /// bounce too many
+    pub fn reflect(&self, depth: RayDepth) -> Option<RayDepth> {
+        let reflected = RayDepth {
+            reflections: depth.reflections + 1,
+            ..depth
+        };
+        (reflected.reflections <= self.reflection_depth && reflected.bounces() <= self.bounces)
+            .then_some(reflected)
+    }
+
+    /// Like `reflect`, for the ray refracted through the surface
+    pub fn refract(&self, depth: RayDepth) -> Option<RayDepth> {
+        let refracted = RayDepth {
+            refractions: depth.refractions + 1,
+            ..depth
+        };
+        (refracted.refractions <= self.refraction_depth && refracted.bounces() <= self.bounces)
+            .then_some(refracted)
+    }
+}
+
+impl Default for RayLimits {
+    fn default() -> Self {
+        RayLimits::new(5, 8, 10)
+    }
+}
+
+/// How many times a ray has been reflected and refracted on its way from the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RayDepth {
+    pub reflections: u32,
+    pub refractions: u32,
+}
+
+impl RayDepth {
+    /// A ray straight from the camera
+    pub const PRIMARY: RayDepth = RayDepth {
+        reflections: 0,
+        refractions: 0,
+    };
+
+    pub fn bounces(&self) -> u32 {
+        self.reflections + self.refractions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::limits::{RayDepth, RayLimits};
+
+    #[test]
+    fn each_kind_of_bounce_has_its_own_limit() {
+        let limits = RayLimits::new(1, 2, 10);
+        let once = limits.reflect(RayDepth::PRIMARY).unwrap();
+        assert_eq!(once.reflections, 1);
+        assert_eq!(limits.reflect(once), None);
+        let through = limits.refract(limits.refract(once).unwrap()).unwrap();
+        assert_eq!(through.bounces(), 3);
+        assert_eq!(limits.refract(through), None);
+    }
+
+    #[test]
+    fn total_bounces_are_limited() {
+        let limits = RayLimits::new(5, 5, 2);
+        let depth = limits.reflect(RayDepth::PRIMARY).unwrap();
+        let depth = limits.refract(depth).unwrap();
+        assert_eq!(limits.reflect(depth), None);
+        assert_eq!(limits.refract(depth), None);
+        assert_eq!(RayLimits::new(0, 0, 0).reflect(RayDepth::PRIMARY), None);
+    }
+}
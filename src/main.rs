@@ -1,40 +1,665 @@
-use crate::canvas::Canvas;
-use crate::color::Color;
-use crate::environment::new_environment;
-use crate::light::{lighting, Material, PointLight};
-use crate::matrix_transformations::rotation_y;
-use crate::projectile::new_projectile;
-use crate::rays::{hit, intersect, Ray, Sphere};
-use crate::tuple::{Point, Vector};
+use clap::{Args, Parser, Subcommand};
+use ray_tracer::environment::{self, new_environment, Integrator};
+use ray_tracer::prelude::*;
+use ray_tracer::projectile::new_projectile;
+use ray_tracer::scenes::Preset;
+use ray_tracer::trajectory::{draw_trajectories, ColorBy, Trajectory, TrajectoryStyle};
 use rayon::prelude::*;
 use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
-mod canvas;
-mod color;
-mod environment;
-mod light;
-mod matrix;
-mod matrix_transformations;
-mod projectile;
-mod rays;
-mod tuple;
-mod utils;
-
-fn main() {
-    // analog_clock();
-    // create_test_image();
-    // simulate_projectile();
-    // cast_ray_onto_sphere();
-    cast_ray_onto_sphere_par();
-}
-
-fn create_test_image() {
-    let width = 500;
-    let height = 500;
+/// Renders scenes from The Ray Tracer Challenge, and the demos from along the way
+#[derive(Parser)]
+#[command(name = "ray_tracer")]
+struct Cli {
+    /// Also prints a preview of the image, handy when rendering on a server over ssh
+    #[arg(long, global = true)]
+    terminal: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Renders a scene file
+    Render(RenderArgs),
+    /// Renders a JSON scene file every time it or an image it uses changes, until interrupted
+    Watch(WatchArgs),
+    /// Renders every job in a YAML file, see `ray_tracer::queue` for the format
+    Batch { jobs: String },
+    /// Prints what's in a scene file or preset, see `World::stats`
+    Inspect {
+        /// A scene file or the name of a preset
+        scene: String,
+    },
+    /// Moves around a scene file or preset in the terminal or a window, with the mouse and WASD
+    Preview(PreviewArgs),
+    /// Bakes the light falling on one of a scene's objects into a lightmap
+    Bake(BakeArgs),
+    /// Adds up rendered passes, each scaled by its gain
+    Composite(CompositeArgs),
+    /// Renders one of the built-in scenes
+    Preset(PresetArgs),
+    /// The twelve hours of a clock face, chapter 4
+    Clock(ImageArgs),
+    /// A red and blue test pattern, chapter 2
+    TestImage(ImageArgs),
+    /// A projectile's flight colored by its speed, chapter 1
+    Projectile {
+        /// Also writes the flight as CSV next to the image
+        #[arg(long)]
+        csv: bool,
+        /// Also writes the flight as JSON next to the image
+        #[arg(long)]
+        json: bool,
+        #[command(flatten)]
+        image: ImageArgs,
+    },
+    /// A ball bouncing along the floor
+    BouncingBall(ImageArgs),
+    /// The projectile as a rendered ball, one numbered frame per tick (`-o frame_###.ppm`)
+    ProjectileAnimation(ImageArgs),
+    /// Thousands of particles sprayed up and blown sideways
+    Fountain(ImageArgs),
+    /// A ray marched Mandelbulb
+    Mandelbulb(ImageArgs),
+    /// A ray marched Menger sponge
+    MengerSponge(ImageArgs),
+    /// A shaded sphere cast by hand, chapter 6
+    Sphere {
+        /// One pixel after the other, the way the book does it
+        #[arg(long)]
+        serial: bool,
+        #[command(flatten)]
+        image: ImageArgs,
+    },
+}
+
+/// The size of the image and where it goes, each command has its own defaults
+#[derive(Args)]
+struct ImageArgs {
+    #[arg(long, value_parser = size)]
+    width: Option<usize>,
+    #[arg(long, value_parser = size)]
+    height: Option<usize>,
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+impl ImageArgs {
+    /// The width, height and output, falling back to the given ones where they weren't passed
+    fn or(self, width: usize, height: usize, output: &str) -> (usize, usize, String) {
+        (
+            self.width.unwrap_or(width),
+            self.height.unwrap_or(height),
+            self.output.unwrap_or_else(|| output.to_string()),
+        )
+    }
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    scene: String,
+    /// The size and output default to the scene's camera and the scene's name as a .ppm
+    #[command(flatten)]
+    image: ImageArgs,
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// A JSON scene, the only format watched
+    scene: String,
+    #[arg(short, long, default_value = "out.ppm")]
+    output: String,
+    /// Outlines every object's bounding box (green) and every group's (magenta)
+    #[arg(long)]
+    bounds: bool,
+    #[arg(long, value_name = "N")]
+    max_reflections: Option<u32>,
+    #[arg(long, value_name = "N")]
+    max_refractions: Option<u32>,
+    #[arg(long, value_name = "N")]
+    max_bounces: Option<u32>,
+}
+
+#[derive(Args)]
+struct PreviewArgs {
+    /// A scene file or the name of a preset
+    scene: String,
+    /// Characters across, the image is two pixels per character tall. Pixels across with
+    /// `--window`, 640 by default there.
+    #[arg(long, value_parser = size)]
+    width: Option<usize>,
+    /// Opens a window with sliders for the lights and materials instead, see
+    /// `ray_tracer::panel`. Needs the egui feature.
+    #[arg(long)]
+    window: bool,
+}
+
+#[derive(Args)]
+struct BakeArgs {
+    scene: String,
+    /// Which object, from 0 counting the scene's spheres, then its cubes, then its triangles
+    object: usize,
+    /// 256 x 128 to lightmap.ppm by default
+    #[command(flatten)]
+    image: ImageArgs,
+    /// Darkens ambient light in creases, with this many rays per texel
+    #[arg(long, value_name = "RAYS")]
+    occlusion: Option<usize>,
+    /// Gathers light bounced off the rest of the scene, with this many rays per texel
+    #[arg(long, value_name = "RAYS", default_value_t = 0)]
+    indirect: usize,
+}
+
+#[derive(Args)]
+struct CompositeArgs {
+    /// `pass.ppm` or `pass.ppm:gain`, the gain is 1 by default
+    #[arg(required = true, value_parser = pass)]
+    passes: Vec<(String, f64)>,
+    #[arg(short, long, default_value = "out.ppm")]
+    output: String,
+    /// Lays the passes over a background, where the alpha image is white
+    #[arg(long, num_args = 2, value_names = ["ALPHA", "BACKGROUND"])]
+    over: Option<Vec<String>>,
+}
+
+#[derive(Args)]
+struct PresetArgs {
+    #[arg(value_parser = preset_name)]
+    preset: Preset,
+    /// 640 x 360 to `<preset>.ppm` by default
+    #[command(flatten)]
+    image: ImageArgs,
+    /// Writes the image out as it renders, for posters too big to keep in memory
+    #[arg(long, conflicts_with = "terminal")]
+    bands: bool,
+}
+
+/// A width or height
+fn size(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("needs a size of at least 1".to_string()),
+    }
+}
+
+fn preset_name(name: &str) -> Result<Preset, String> {
+    Preset::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Preset::ALL.iter().map(|preset| preset.name()).collect();
+        format!("unknown preset, try one of {}", names.join(", "))
+    })
+}
+
+/// A pass to composite and its gain
+fn pass(arg: &str) -> Result<(String, f64), String> {
+    match arg.rsplit_once(':') {
+        Some((path, gain)) => match gain.parse() {
+            Ok(gain) => Ok((path.to_string(), gain)),
+            Err(_) => Err(format!("{} isn't a gain", gain)),
+        },
+        None => Ok((arg.to_string(), 1.0)),
+    }
+}
+
+fn main() -> ray_tracer::Result<()> {
+    let cli = Cli::parse();
+    TERMINAL.store(cli.terminal, Ordering::Relaxed);
+    match cli.command {
+        Command::Render(args) => render(args),
+        Command::Watch(args) => watch(args),
+        Command::Batch { jobs } => batch(&jobs),
+        Command::Inspect { scene } => inspect(&scene),
+        Command::Preview(args) => preview(args),
+        Command::Bake(args) => bake(args),
+        Command::Composite(args) => composite(args),
+        Command::Preset(args) => preset(args),
+        Command::Clock(image) => analog_clock(image.or(100, 100, "analog_clock.ppm")),
+        Command::TestImage(image) => create_test_image(image.or(500, 500, "test_ppm.ppm")),
+        Command::Projectile { csv, json, image } => {
+            simulate_projectile(image.or(500, 250, "rocket_shot.ppm"), csv, json)
+        }
+        Command::BouncingBall(image) => bouncing_ball(image.or(500, 250, "bouncing_ball.ppm")),
+        Command::ProjectileAnimation(image) => {
+            projectile_animation(image.or(320, 180, "projectile_###.ppm"))
+        }
+        Command::Fountain(image) => particle_fountain(image.or(500, 500, "particle_fountain.ppm")),
+        Command::Mandelbulb(image) => fractal(
+            &ray_tracer::sdf::Mandelbulb::default(),
+            image.or(400, 400, "mandelbulb.ppm"),
+        ),
+        Command::MengerSponge(image) => fractal(
+            &ray_tracer::sdf::MengerSponge::default(),
+            image.or(400, 400, "menger_sponge.ppm"),
+        ),
+        Command::Sphere {
+            serial: true,
+            image,
+        } => cast_ray_onto_sphere(image.or(400, 400, "sphere.ppm")),
+        Command::Sphere {
+            serial: false,
+            image,
+        } => cast_ray_onto_sphere_par(image.or(1000, 1000, "sphere.ppm")),
+    }
+}
+
+/// The image's width, height and file name
+type Image = (usize, usize, String);
+
+/// Widest terminal preview printed with `--terminal`
+const TERMINAL_COLUMNS: usize = 80;
+
+/// Whether `--terminal` was passed
+static TERMINAL: AtomicBool = AtomicBool::new(false);
+
+/// Writes the image, as a PNG or Radiance HDR when the file name ends in .png or .hdr and a PPM
+/// otherwise, and with `--terminal` also prints a preview of it
+fn save(canvas: &Canvas, filename: &str) -> ray_tracer::Result<()> {
+    if TERMINAL.load(Ordering::Relaxed) {
+        print!("{}", canvas.to_ansi(TERMINAL_COLUMNS));
+    }
+    let extension = Path::new(filename)
+        .extension()
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_ref().and_then(|extension| extension.to_str()) {
+        Some("png") => canvas.to_png(filename),
+        Some("hdr") => canvas.to_hdr(filename),
+        _ => canvas.to_ppm(filename),
+    }
+}
+
+/// `render scene.json`: renders the scene once, at the camera's resolution unless `--width` or
+/// `--height` say otherwise
+#[cfg(feature = "scene")]
+fn render(args: RenderArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::scene::Scene;
+
+    let scene = Scene::from_json(&std::fs::read_to_string(&args.scene)?)?;
+    let default_output = Path::new(&args.scene).with_extension("ppm");
+    let (width, height, output) = args.image.or(
+        scene.camera.hsize(),
+        scene.camera.vsize(),
+        &default_output.to_string_lossy(),
+    );
+    let camera = scene.camera.with_resolution(width, height);
+    save(&camera.render(&scene.world), &output)
+}
+
+#[cfg(not(feature = "scene"))]
+fn render(_args: RenderArgs) -> ray_tracer::Result<()> {
+    eprintln!("rendering scene files needs the scene feature: cargo run --features scene -- render scene.json");
+    std::process::exit(2);
+}
+
+/// How often `watch` checks the scene file for changes
+#[cfg(feature = "scene")]
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// `watch scene.json [-o out.ppm]`: renders the scene every time the file or an image it uses
+/// changes, until interrupted. A scene that fails to load is reported and the previous image is
+/// left alone.
+#[cfg(feature = "scene")]
+fn watch(args: WatchArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::overlay::draw_bounding_boxes;
+    use ray_tracer::scene::parse_scene;
+    use std::time::{Instant, SystemTime};
+
+    // the files can briefly disappear while an editor saves them, they're just looked at again
+    // next time
+    fn modified(paths: &[String]) -> Vec<Option<SystemTime>> {
+        paths
+            .iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    let WatchArgs {
+        scene: scene_path,
+        output,
+        bounds,
+        max_reflections,
+        max_refractions,
+        max_bounces,
+    } = args;
+    println!("watching {} (ctrl-c to stop)", scene_path);
+    // the scene file first, then the images it used when it was last read
+    let mut watched = vec![scene_path.clone()];
+    let mut last_modified = Vec::new();
+    loop {
+        let now = modified(&watched);
+        if now[0].is_some() && now != last_modified {
+            let loaded = std::fs::read(&scene_path)
+                .map_err(ray_tracer::Error::from)
+                .and_then(|bytes| parse_scene(&bytes))
+                .and_then(|description| {
+                    // the images are watched even when they fail to load, so fixing one
+                    // renders again
+                    watched.truncate(1);
+                    watched.extend(description.assets().map(String::from));
+                    description.build()
+                });
+            last_modified = modified(&watched);
+            match loaded {
+                Ok(mut scene) => {
+                    let limits = &mut scene.world.ray_limits;
+                    limits.reflection_depth = max_reflections.unwrap_or(limits.reflection_depth);
+                    limits.refraction_depth = max_refractions.unwrap_or(limits.refraction_depth);
+                    limits.bounces = max_bounces.unwrap_or(limits.bounces);
+                    let start = Instant::now();
+                    let mut canvas = if scene.camera.adaptive().is_some() {
+                        // where the rays went, next to the image
+                        let (canvas, map) = scene.camera.render_with_sample_map(&scene.world);
+                        let aov = |pass| Path::new(&output).with_extension(format!("{}.ppm", pass));
+                        map.samples_image(scene.camera.samples())
+                            .to_ppm(&aov("samples").to_string_lossy())?;
+                        map.variance_image()
+                            .to_ppm(&aov("variance").to_string_lossy())?;
+                        canvas
+                    } else {
+                        scene.camera.render(&scene.world)
+                    };
+                    if bounds {
+                        draw_bounding_boxes(&mut canvas, &scene.camera, &scene.world);
+                    }
+                    save(&canvas, &output)?;
+                    println!("rendered {} in {:.2?}", output, start.elapsed());
+                }
+                Err(error) => eprintln!("{}: {}", scene_path, error),
+            }
+        }
+        std::thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+#[cfg(not(feature = "scene"))]
+fn watch(_args: WatchArgs) -> ray_tracer::Result<()> {
+    eprintln!("watching scene files needs the scene feature: cargo run --features scene -- watch scene.json");
+    std::process::exit(2);
+}
+
+/// The preset with the given name, or else the scene file
+#[cfg(feature = "scene")]
+fn load(name: &str) -> ray_tracer::Result<(Camera, World)> {
+    use ray_tracer::scene::Scene;
+
+    Ok(match Preset::from_name(name) {
+        Some(preset) => (preset.camera(640, 360), preset.world()),
+        None => {
+            let scene = Scene::from_json(&std::fs::read_to_string(name)?)?;
+            (scene.camera, scene.world)
+        }
+    })
+}
+
+#[cfg(not(feature = "scene"))]
+fn load(name: &str) -> ray_tracer::Result<(Camera, World)> {
+    match Preset::from_name(name) {
+        Some(preset) => Ok((preset.camera(640, 360), preset.world())),
+        None => {
+            eprintln!("loading scene files needs the scene feature, presets work without it");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// `inspect <scene.json | preset>`: prints what's in a scene, see `World::stats`
+#[cfg(feature = "scene")]
+fn inspect(name: &str) -> ray_tracer::Result<()> {
+    let (camera, world) = load(name)?;
+    println!(
+        "camera: {}x{}, {} samples per pixel",
+        camera.hsize(),
+        camera.vsize(),
+        camera.samples()
+    );
+    print!("{}", world.stats());
+    Ok(())
+}
+
+#[cfg(not(feature = "scene"))]
+fn inspect(_name: &str) -> ray_tracer::Result<()> {
+    eprintln!("inspecting scene files needs the scene feature: cargo run --features scene -- inspect scene.json");
+    std::process::exit(2);
+}
+
+/// Printed under the image in `preview`
+const PREVIEW_HELP: &str = "\x1b[0Kdrag: orbit  shift+drag: pan  scroll: zoom  wasd: walk  q: quit";
+
+/// `preview <scene.json | preset> [--width N] [--window]`: shows the scene in the terminal and
+/// moves the camera with the mouse and WASD, see `ray_tracer::preview`. Each view is rendered
+/// blocky first and sharpened until the camera moves again. Needs `stty` and a terminal with
+/// mouse reporting.
+fn preview(args: PreviewArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::preview::{parse_input, Input};
+    use std::io::{Read, Write};
+    use std::sync::mpsc;
+
+    if args.window {
+        return preview_window(args);
+    }
+    let (camera, world) = load(&args.scene)?;
+    let width = args.width.unwrap_or(TERMINAL_COLUMNS);
+    let height = (width * camera.vsize() / camera.hsize()).max(1);
+    let mut preview = Preview::new(camera.with_resolution(width, height))?;
+    let _terminal = RawTerminal::enter()?;
+
+    let (sender, input) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = [0; 256];
+        while let Ok(n @ 1..) = std::io::stdin().read(&mut buffer) {
+            if sender.send(buffer[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    let mut stdout = std::io::stdout();
+    loop {
+        // keep sharpening while nothing happens, and wait for input once it's as sharp as it gets
+        let bytes = if preview.is_done() {
+            match input.recv() {
+                Ok(bytes) => bytes,
+                // stdin closed
+                Err(_) => return Ok(()),
+            }
+        } else {
+            input.try_recv().unwrap_or_default()
+        };
+        for event in parse_input(&bytes) {
+            // raw mode turns ctrl-c into a plain byte
+            if matches!(event, Input::Key('q') | Input::Key('\u{3}')) {
+                return Ok(());
+            }
+            preview.handle(event)?;
+        }
+        if let Some(canvas) = preview.next_pass(&world) {
+            // raw mode doesn't go back to the start of the line on its own
+            let image = canvas.to_ansi(width).replace('\n', "\r\n");
+            write!(stdout, "\x1b[H{}{}", image, PREVIEW_HELP)?;
+            stdout.flush()?;
+        }
+    }
+}
+
+/// `preview --window`: the same in a window, with the lights and materials on sliders
+#[cfg(feature = "egui")]
+fn preview_window(args: PreviewArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::panel::PanelApp;
+
+    let (camera, world) = load(&args.scene)?;
+    let width = args.width.unwrap_or(640);
+    let height = (width * camera.vsize() / camera.hsize()).max(1);
+    PanelApp::new(camera.with_resolution(width, height), world)?.run(&args.scene)
+}
+
+#[cfg(not(feature = "egui"))]
+fn preview_window(_args: PreviewArgs) -> ray_tracer::Result<()> {
+    eprintln!("the preview window needs the egui feature: cargo run --features egui -- preview --window scene.json");
+    std::process::exit(2);
+}
+
+/// The terminal in raw mode with mouse reporting on for `preview`, put back the way it was
+/// when dropped
+struct RawTerminal {
+    /// `stty -g`, the settings to restore
+    settings: String,
+}
+
+impl RawTerminal {
+    fn enter() -> ray_tracer::Result<Self> {
+        use std::io::Write;
+
+        let settings = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        // the alternate screen, no cursor, and presses, drags and releases reported as SGR
+        print!("\x1b[?1049h\x1b[?25l\x1b[?1002h\x1b[?1006h");
+        std::io::stdout().flush()?;
+        Ok(RawTerminal {
+            settings: settings.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        use std::io::Write;
+
+        print!("\x1b[?1006l\x1b[?1002l\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        let _ = stty(&[&self.settings]);
+    }
+}
+
+/// Runs `stty` on the terminal stdin is, returning what it printed
+fn stty(args: &[&str]) -> std::io::Result<String> {
+    let output = std::process::Command::new("stty")
+        .args(args)
+        .stdin(std::process::Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "stty {} failed, is stdin a terminal?",
+            args.join(" ")
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `bake <scene.json> <object>`: bakes the light falling on the scene's object at the given index
+/// into a lightmap, see `ray_tracer::bake`
+#[cfg(feature = "scene")]
+fn bake(args: BakeArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::bake::{Bake, Occlusion};
+    use ray_tracer::scene::Scene;
+
+    let defaults = Bake::default();
+    let (width, height, output) = args
+        .image
+        .or(defaults.width, defaults.height, "lightmap.ppm");
+    let settings = Bake {
+        width,
+        height,
+        occlusion: args.occlusion.map(|samples| Occlusion {
+            samples,
+            ..Occlusion::default()
+        }),
+        indirect_samples: args.indirect,
+        ..defaults
+    };
+    let scene = Scene::from_json(&std::fs::read_to_string(&args.scene)?)?;
+    let Some(object) = scene.world.objects.get(args.object) else {
+        eprintln!(
+            "{} has {} objects, there's no object {}",
+            args.scene,
+            scene.world.objects.len(),
+            args.object
+        );
+        std::process::exit(2);
+    };
+    save(&settings.irradiance(&scene.world, object.id())?, &output)
+}
+
+#[cfg(not(feature = "scene"))]
+fn bake(_args: BakeArgs) -> ray_tracer::Result<()> {
+    eprintln!("baking scene files needs the scene feature: cargo run --features scene -- bake scene.json 0");
+    std::process::exit(2);
+}
+
+/// `batch jobs.yaml`: renders every job in the file, see `ray_tracer::queue` for the format.
+/// Exits with status 1 if any job failed.
+#[cfg(feature = "batch")]
+fn batch(jobs_file: &str) -> ray_tracer::Result<()> {
+    use ray_tracer::queue::RenderQueue;
+
+    let queue = RenderQueue::load(jobs_file)?;
+    let failed = queue.run(|report| match &report.result {
+        Ok(duration) => println!(
+            "[{}/{}] {} in {:.2?}",
+            report.finished, report.total, report.job.output, duration
+        ),
+        Err(error) => eprintln!(
+            "[{}/{}] {} failed: {}",
+            report.finished, report.total, report.job.scene, error
+        ),
+    });
+    if failed > 0 {
+        eprintln!("{} of {} jobs failed", failed, queue.jobs.len());
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "batch"))]
+fn batch(_jobs_file: &str) -> ray_tracer::Result<()> {
+    eprintln!(
+        "batch rendering needs the batch feature: cargo run --features batch -- batch jobs.yaml"
+    );
+    std::process::exit(2);
+}
+
+/// `preset <name> [--bands]`: renders one of the built-in scenes. `--bands` writes the image out
+/// as it renders.
+fn preset(args: PresetArgs) -> ray_tracer::Result<()> {
+    let preset = args.preset;
+    let (width, height, output) = args.image.or(640, 360, &format!("{}.ppm", preset.name()));
+    let camera = preset.camera(width, height);
+    if args.bands {
+        return camera.render_to_ppm(&preset.world(), &output);
+    }
+    save(&camera.render(&preset.world()), &output)
+}
+
+/// `composite -o out.ppm <pass.ppm[:gain]>... [--over <alpha.ppm> <background.ppm>]`: adds up
+/// rendered passes, each scaled by its gain, optionally over a background
+fn composite(args: CompositeArgs) -> ray_tracer::Result<()> {
+    use ray_tracer::composite::Compositor;
+
+    let passes = args
+        .passes
+        .iter()
+        .map(|(path, gain)| Ok((Canvas::read_ppm(path)?, *gain)))
+        .collect::<ray_tracer::Result<Vec<_>>>()?;
+    let over = match args.over.as_deref() {
+        Some([alpha, background]) => {
+            Some((Canvas::read_ppm(alpha)?, Canvas::read_ppm(background)?))
+        }
+        _ => None,
+    };
+    let mut compositor = Compositor::new();
+    for (pass, gain) in &passes {
+        compositor = compositor.with_pass(pass, *gain);
+    }
+    if let Some((alpha, background)) = &over {
+        compositor = compositor.with_background(alpha, background);
+    }
+    save(&compositor.composite()?, &args.output)
+}
+
+fn create_test_image((width, height, output): Image) -> ray_tracer::Result<()> {
     let mut canvas = Canvas::new(width, height);
-    let red = Color::new(1.0, 0.0, 0.0);
-    let blue = Color::new(0.0, 0.0, 1.0);
+    let red = Color::RED;
+    let blue = Color::BLUE;
     for x in 0..width {
         canvas.write_pixel(x, 0, red);
         canvas.write_pixel(x, 1, red);
@@ -43,49 +668,204 @@ fn create_test_image() {
     for y in 1..height {
         canvas.write_pixel(0, y, blue);
     }
-    canvas.to_ppm("test_ppm.ppm").expect("while creating ppm");
+    save(&canvas, &output)
 }
 
-fn simulate_projectile() {
-    let width = 500;
-    let height = 250;
+fn simulate_projectile(
+    (width, height, output): Image,
+    csv: bool,
+    json: bool,
+) -> ray_tracer::Result<()> {
     let start = Point::new_point(0.0, 0.0, 0.0);
     let velocity = Vector::new(1.0, 1.8, 0.0) * 11.25;
     let velocity = velocity.normalize();
-    let mut p = new_projectile(start, velocity);
+    let p = new_projectile(start, velocity);
     let gravity = Vector::new(0.0, -0.1, 0.0);
     let wind = Vector::new(0.01, 0.0, 0.0);
     let mut c = Canvas::new(width, height);
     let env = new_environment(gravity, wind);
-    let white = Color::new(1.0, 1.0, 1.0);
 
-    let alpha = 40.0;
-    c.write_pixel(
-        (p.position.x * alpha) as usize,
-        height - 1 - (p.position.y * alpha) as usize,
-        white,
-    );
-    while p.position.x >= 0.0 && p.position.y >= 0.0 {
-        p = environment::tick(env, p);
+    let flight = Trajectory::simulate(&env, p, 10_000, |p| {
+        p.position.x >= 0.0 && p.position.y >= 0.0
+    });
+    if let Some(landing) = flight.points().last() {
         println!(
-            "projectile now at:\n\t{}\n\tvelocity {}",
-            p.position, p.velocity
+            "landed at {} at t = {}, velocity {}",
+            landing.position, landing.time, landing.velocity
         );
+    }
+    let style = TrajectoryStyle {
+        color_by: ColorBy::Speed,
+        ..TrajectoryStyle::default()
+    };
+    // the time series for plotting elsewhere
+    let series = |extension| Path::new(&output).with_extension(extension);
+    if csv {
+        flight.to_csv_file(&series("csv").to_string_lossy())?;
+    }
+    if json {
+        flight.to_json_file(&series("json").to_string_lossy())?;
+    }
+    draw_trajectories(&mut c, &[flight], &style);
+    save(&c, &output)
+}
+
+fn bouncing_ball((width, height, output): Image) -> ray_tracer::Result<()> {
+    let mut p = new_projectile(Point::new_point(0.0, 5.0, 0.0), Vector::new(0.05, 0.0, 0.0));
+    let env = new_environment(Vector::new(0.0, -0.01, 0.0), Vector::new(0.0, 0.0, 0.0))
+        .with_integrator(Integrator::SemiImplicitEuler)
+        .with_ground(0.0)
+        .with_restitution(0.8);
+    let mut c = Canvas::new(width, height);
+
+    let alpha = 40.0;
+    for _ in 0..10_000 {
+        let (x, y) = (p.position.x * alpha, p.position.y * alpha);
+        if x >= width as f64 {
+            break;
+        }
         c.write_pixel(
-            (p.position.x * alpha) as usize,
-            height - 1 - (p.position.y * alpha) as usize,
-            white,
+            x as usize,
+            height - 1 - (y as usize).min(height - 1),
+            Color::WHITE,
         );
+        p = environment::tick(&env, p);
     }
-    c.to_ppm("rocket_shot.ppm").expect("while creating ppm");
+    save(&c, &output)
+}
+
+/// The projectile as a ball bouncing across a floor, one rendered frame per tick, `###` in the
+/// output numbering them
+fn projectile_animation((width, height, output): Image) -> ray_tracer::Result<()> {
+    use ray_tracer::animation::Animation;
+    use ray_tracer::sequence::{render_animation_to, ImageSequence};
+
+    let radius = 0.25;
+    let env = new_environment(Vector::new(0.0, -0.02, 0.0), Vector::new(0.0, 0.0, 0.0))
+        .with_integrator(Integrator::SemiImplicitEuler)
+        .with_ground(0.0)
+        .with_restitution(0.7);
+    let launch = new_projectile(
+        Point::new_point(-4.0, 0.0, 0.0),
+        Vector::new(0.08, 0.3, 0.0),
+    );
+    let flight = Trajectory::simulate(&env, launch, 100, |_| true);
+
+    let mut world = World::new();
+    let mut ball = Sphere::new();
+    ball.material.color = Color::CORAL;
+    world.objects.push(ball.into());
+    // a flattened sphere as the floor, its top where the bottom of the ball touches down
+    let mut floor = Sphere::new();
+    floor.set_transform(translation(0.0, -radius - 0.01, 0.0) * scaling(20.0, 0.01, 20.0))?;
+    floor.material.specular = 0.0;
+    world.objects.push(floor.into());
+    world.lights.push(PointLight::new(
+        Point::new_point(-10.0, 10.0, -10.0),
+        Color::WHITE,
+    ));
+
+    let mut camera = Camera::new(width, height, PI / 3.0);
+    camera.set_transform(view_transform(
+        Point::new_point(0.0, 2.0, -9.0),
+        Point::new_point(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    ))?;
+
+    let mut animation = Animation::new();
+    *animation.object(0) = flight.to_track(radius);
+    let mut frames = ImageSequence::new(&output);
+    render_animation_to(&world, &camera, &animation, 0..flight.len(), &mut frames)?;
+    println!("wrote {} frames", frames.files().len());
+    Ok(())
 }
 
-fn analog_clock() {
-    let width = 100;
-    let height = 100;
-    let rad = width as f64 * 0.45;
+/// Ray marches a distance estimated fractal, colored by its orbit trap and darkened where rays
+/// had to squeeze past a lot of surface
+fn fractal(
+    shape: &dyn ray_tracer::sdf::DistanceEstimator,
+    (width, height, output): Image,
+) -> ray_tracer::Result<()> {
+    use ray_tracer::sdf::{trap_color, Marcher};
+
+    let mut camera = Camera::new(width, height, PI / 4.0);
+    camera.set_transform(view_transform(
+        Point::new_point(2.0, 2.0, -3.0),
+        Point::new_point(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    ))?;
+    let light = PointLight::new(Point::new_point(-5.0, 10.0, -10.0), Color::WHITE);
+    let marcher = Marcher::default();
+
+    let rows: Vec<Vec<Color>> = (0..height)
+        .into_par_iter()
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let ray = camera.ray_for_pixel(x, y);
+                    let Some(hit) = marcher.march(shape, &ray) else {
+                        return Color::BLACK;
+                    };
+                    let material = Material {
+                        color: trap_color(shape, hit.point, Color::GOLD, Color::INDIGO),
+                        ..Material::new()
+                    };
+                    let normal = marcher.normal_at(shape, hit.point);
+                    let lit = lighting(material, light, hit.point, -ray.direction(), normal, false);
+                    let occlusion = 1.0 - hit.steps as f64 / marcher.max_steps as f64;
+                    lit * occlusion
+                })
+                .collect()
+        })
+        .collect();
+    let mut canvas = Canvas::new(width, height);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, color) in row.iter().enumerate() {
+            canvas.write_pixel(x, y, *color);
+        }
+    }
+    save(&canvas, &output)
+}
+
+fn particle_fountain((width, height, output): Image) -> ray_tracer::Result<()> {
+    use ray_tracer::random::{Rng, Seedable};
+
+    let mut rng = Rng::from_seed(1);
+    let mut particles: Vec<_> = (0..5000)
+        .map(|_| {
+            let velocity = Vector::new(rng.range(-0.3, 0.3), rng.range(1.5, 2.0), 0.0);
+            let mut p = new_projectile(Point::new_point(0.0, 0.0, 0.0), velocity);
+            p.mass = rng.range(0.5, 2.0);
+            p
+        })
+        .collect();
+    let env = new_environment(Vector::new(0.0, -0.05, 0.0), Vector::new(0.02, 0.0, 0.0))
+        .with_drag(0.02)
+        .with_integrator(Integrator::SemiImplicitEuler)
+        .with_ground(0.0)
+        .with_restitution(0.3);
+    let mut c = Canvas::new(width, height);
+
+    let scale = 10.0;
+    for step in 0..150 {
+        environment::tick_all(&env, &mut particles);
+        // fades from white to blue as the particles age
+        let color = Color::WHITE.lerp(Color::BLUE, step as f64 / 150.0);
+        for p in &particles {
+            let x = width as f64 / 2.0 + p.position.x * scale;
+            let y = height as f64 - 1.0 - p.position.y * scale;
+            if (0.0..width as f64).contains(&x) && (0.0..height as f64).contains(&y) {
+                c.write_pixel(x as usize, y as usize, color);
+            }
+        }
+    }
+    save(&c, &output)
+}
+
+fn analog_clock((width, height, output): Image) -> ray_tracer::Result<()> {
+    let rad = width.min(height) as f64 * 0.45;
     let mut c = Canvas::new(width, height);
-    let white = Color::new(1.0, 1.0, 1.0);
+    let white = Color::WHITE;
 
     let origin = Point::new_point(width as f64 / 2.0_f64, 0.0, height as f64 / 2.0_f64);
     let noon = Point::new_point(0.0, 0.0, 1.0);
@@ -99,22 +879,20 @@ fn analog_clock() {
         c.write_pixel(final_pos.x as usize, final_pos.z as usize, white);
     }
 
-    c.to_ppm("analog_clock.ppm").expect("while creating ppm");
+    save(&c, &output)
 }
 
-fn cast_ray_onto_sphere() {
-    let canvas_pixels = 400;
-    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
-    let mut color = Color::new(1.0, 0.0, 0.0); // red color
+fn cast_ray_onto_sphere((width, height, output): Image) -> ray_tracer::Result<()> {
+    let mut canvas = Canvas::new(width, height);
 
     let mut shape = Sphere::new();
     shape.set_material(Material::new());
     shape.material.color = Color::new(1.0, 0.2, 1.0);
     // can mess around with various transformations here
-    // shape.set_transform(shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * scaling(0.5, 1.0, 1.0));
+    // shape.set_transform(shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * scaling(0.5, 1.0, 1.0))?;
 
     let light_pos = Point::new_point(-10.0, 10.0, -10.0);
-    let light_color = Color::new(1.0, 1.0, 1.0);
+    let light_color = Color::WHITE;
     let light = PointLight::new(light_pos, light_color);
 
     let wall_z = 10.0;
@@ -122,10 +900,10 @@ fn cast_ray_onto_sphere() {
 
     let ray_origin = Point::new_point(0.0, 0.0, -5.0);
 
-    for y in 0..canvas_pixels {
-        for x in 0..canvas_pixels {
+    for y in 0..height {
+        for x in 0..width {
             let (world_x, world_y, world_z) =
-                compute_world_coordinates(canvas_pixels, wall_size, wall_z, x, y);
+                compute_world_coordinates(width, height, wall_size, wall_z, x, y);
 
             let pos = Point::new_point(world_x, world_y, world_z);
             // create a new ray that originates from the camera (or eye)
@@ -140,30 +918,28 @@ fn cast_ray_onto_sphere() {
             if hit(&xs).is_some() {
                 let closest_hit = xs[0];
                 let point = r.position(closest_hit.t);
-                let norm = closest_hit.object.normal_at(point);
-                let eye = -r.direction;
+                let norm = closest_hit.object.normal_at(point, &closest_hit);
+                let eye = -r.direction();
 
                 // apply lighting to color
-                color = lighting(closest_hit.object.material, light, point, eye, norm);
+                let color = lighting(closest_hit.object.material, light, point, eye, norm, false);
                 canvas.write_pixel(x, y, color);
             }
         }
     }
 
-    canvas.to_ppm("sphere.ppm").unwrap();
+    save(&canvas, &output)
 }
 
-fn cast_ray_onto_sphere_par() {
-    let canvas_pixels = 1000;
-    let canvas = Mutex::new(Canvas::new(canvas_pixels, canvas_pixels)); // Wrap the canvas in a Mutex
-    let color = Color::new(1.0, 0.0, 0.0); // Initial color (will be updated)
+fn cast_ray_onto_sphere_par((width, height, output): Image) -> ray_tracer::Result<()> {
+    let canvas = Mutex::new(Canvas::new(width, height)); // Wrap the canvas in a Mutex
 
     let mut shape = Sphere::new();
     shape.set_material(Material::new());
     shape.material.color = Color::new(1.0, 0.2, 1.0);
 
     let light_pos = Point::new_point(-10.0, 10.0, -10.0);
-    let light_color = Color::new(1.0, 1.0, 1.0);
+    let light_color = Color::WHITE;
     let light = PointLight::new(light_pos, light_color);
 
     let wall_z = 10.0;
@@ -171,10 +947,10 @@ fn cast_ray_onto_sphere_par() {
 
     let ray_origin = Point::new_point(0.0, 0.0, -5.0);
 
-    (0..canvas_pixels).into_par_iter().for_each(|y| {
-        for x in 0..canvas_pixels {
+    (0..height).into_par_iter().for_each(|y| {
+        for x in 0..width {
             let (world_x, world_y, world_z) =
-                compute_world_coordinates(canvas_pixels, wall_size, wall_z, x, y);
+                compute_world_coordinates(width, height, wall_size, wall_z, x, y);
 
             let pos = Point::new_point(world_x, world_y, world_z);
             let r = Ray::new(ray_origin, (pos - ray_origin).normalize());
@@ -182,11 +958,12 @@ fn cast_ray_onto_sphere_par() {
             let xs = intersect(&r, shape);
             if let Some(closest_hit) = hit(&xs) {
                 let point = r.position(closest_hit.t);
-                let norm = closest_hit.object.normal_at(point);
-                let eye = -r.direction;
+                let norm = closest_hit.object.normal_at(point, &closest_hit);
+                let eye = -r.direction();
 
                 // Apply lighting to determine color
-                let pixel_color = lighting(closest_hit.object.material, light, point, eye, norm);
+                let pixel_color =
+                    lighting(closest_hit.object.material, light, point, eye, norm, false);
 
                 // Safely write to the canvas
                 canvas.lock().unwrap().write_pixel(x, y, pixel_color);
@@ -194,7 +971,8 @@ fn cast_ray_onto_sphere_par() {
         }
     });
 
-    canvas.lock().unwrap().to_ppm("sphere2.ppm").unwrap();
+    let canvas = canvas.into_inner().expect("a render thread panicked");
+    save(&canvas, &output)
 }
 
 /// Computes the world coordinates on a 3D wall for a given pixel on a 2D canvas.
@@ -202,14 +980,15 @@ fn cast_ray_onto_sphere_par() {
 /// This function converts the 2D pixel coordinates on a canvas to 3D world coordinates on a wall
 /// in the scene. The wall is centered on the z-axis, and the function takes into account the size
 /// of the canvas and the wall to ensure correct mapping. The wall's z-coordinate remains constant.
+/// The wall's height fills the canvas, a wider canvas sees more of it to the sides.
 ///
 /// # Parameters
 ///
-/// - `canvas_size`: The size of the canvas (assumed to be square, so both width and height are the same).
-/// - `wall_size`: The physical size of the wall in the 3D scene (assumed to be square).
+/// - `canvas_width`, `canvas_height`: The size of the canvas in pixels.
+/// - `wall_size`: The physical height of the wall in the 3D scene.
 /// - `wall_z`: The z-coordinate of the wall, representing its distance from the ray origin.
-/// - `pixel_x`: The x-coordinate of the pixel on the canvas (0 to canvas_size - 1).
-/// - `pixel_y`: The y-coordinate of the pixel on the canvas (0 to canvas_size - 1).
+/// - `pixel_x`: The x-coordinate of the pixel on the canvas (0 to canvas_width - 1).
+/// - `pixel_y`: The y-coordinate of the pixel on the canvas (0 to canvas_height - 1).
 ///
 /// # Returns
 ///
@@ -221,34 +1000,36 @@ fn cast_ray_onto_sphere_par() {
 /// # Example
 ///
 /// ```rust
-/// let canvas_size = 100;
+/// let (canvas_width, canvas_height) = (100, 100);
 /// let wall_size = 7.0;
 /// let wall_z = 10.0;
 /// let pixel_x = 50;
 /// let pixel_y = 50;
-/// let (world_x, world_y, world_z) = compute_world_coordinates(canvas_size, wall_size, wall_z, pixel_x, pixel_y);
+/// let (world_x, world_y, world_z) = compute_world_coordinates(canvas_width, canvas_height, wall_size, wall_z, pixel_x, pixel_y);
 /// println!("World coordinates: ({}, {}, {})", world_x, world_y, world_z);
 /// ```
 ///
 /// This will output the world coordinates for the center pixel of a 100x100 canvas.
 fn compute_world_coordinates(
-    canvas_size: usize,
+    canvas_width: usize,
+    canvas_height: usize,
     wall_size: f64,
     wall_z: f64,
     pixel_x: usize,
     pixel_y: usize,
 ) -> (f64, f64, f64) {
-    // Calculate half of the wall size to determine the range of coordinates
-    // The wall's x and y coordinates will range from -half to +half
-    let half = wall_size / 2.0;
-
     // Determine the size of each pixel on the canvas in world units
     // This tells us how much space each pixel on the canvas corresponds to on the wall
-    let pixel_size = wall_size / canvas_size as f64;
+    let pixel_size = wall_size / canvas_height as f64;
+
+    // Calculate half of the wall's visible width and height to determine the range of coordinates
+    // The wall's x coordinates will range from -half_width to +half_width, y from -half to +half
+    let half = wall_size / 2.0;
+    let half_width = pixel_size * canvas_width as f64 / 2.0;
 
     // Calculate the x-coordinate in the 3D world corresponding to the pixel's x-coordinate on the canvas
-    // -half is the leftmost edge of the wall, and we add the offset for the specific pixel
-    let world_x = -half + pixel_size * pixel_x as f64;
+    // -half_width is the leftmost edge of the wall, and we add the offset for the specific pixel
+    let world_x = -half_width + pixel_size * pixel_x as f64;
 
     // Calculate the y-coordinate in the 3D world corresponding to the pixel's y-coordinate on the canvas
     // half is the topmost edge of the wall, and we subtract the offset for the specific pixel
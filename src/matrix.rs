@@ -1,5 +1,5 @@
 use std::fmt;
-use std::ops::{Index, Mul};
+use std::ops::{Index, IndexMut, Mul};
 
 use thiserror::Error;
 
@@ -54,35 +54,127 @@ pub enum MatrixError {
     // Unknown,
 }
 
-// ----------------------------- 4x4 ------------------------------------
+/// Pivots smaller than this (after every row has been scaled to a largest magnitude of 1)
+/// mean the rows are linearly dependent up to rounding error, so the matrix is treated as singular
+const SINGULARITY_TOLERANCE: f64 = 1e-12;
+
+/// Writes each row of a matrix on its own line with every column right-aligned,
+/// so a transform can be read at a glance instead of as a flat dump of nested arrays.
+///
+/// Honors the formatter's precision, e.g. `format!("{:.2}", m)`
+fn fmt_rows<const N: usize>(f: &mut fmt::Formatter, rows: &[[f64; N]; N]) -> fmt::Result {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|val| match f.precision() {
+                    Some(precision) => format!("{:.*}", precision, val),
+                    None => format!("{}", val),
+                })
+                .collect()
+        })
+        .collect();
+    let width = cells.iter().flatten().map(|c| c.len()).max().unwrap_or(0);
+
+    for (i, row) in cells.iter().enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        write!(f, "|")?;
+        for cell in row {
+            write!(f, " {:>width$}", cell, width = width)?;
+        }
+        write!(f, " |")?;
+    }
+    Ok(())
+}
+
+/// A square matrix of `N` rows and `N` columns, stored row-major (`matrix[row][col]`)
+///
+/// Everything that works for any size (multiplication, transposing, submatrices, minors,
+/// cofactors, determinants and inversion) is written once here; the ray tracer itself
+/// only ever transforms tuples with 4x4 matrices, the smaller sizes show up as submatrices.
+///
+/// `repr(C)` makes the memory layout the row-major array itself, e.g. 16 f64s for a `M4x4`
 #[derive(Debug, Copy, Clone)]
-pub struct M4x4 {
-    pub matrix: [[f64; 4]; 4],
+#[repr(C)]
+pub struct Matrix<const N: usize> {
+    pub matrix: [[f64; N]; N],
 }
 
-impl From<[[f64; 4]; 4]> for M4x4 {
-    fn from(matrix: [[f64; 4]; 4]) -> Self {
-        M4x4 { matrix }
+// SAFETY: a repr(C) struct holding only an array of f64 has no padding, and any bit pattern is a valid f64
+#[cfg(feature = "bytemuck")]
+unsafe impl<const N: usize> bytemuck::Zeroable for Matrix<N> {}
+#[cfg(feature = "bytemuck")]
+unsafe impl<const N: usize> bytemuck::Pod for Matrix<N> {}
+
+pub type M4x4 = Matrix<4>;
+pub type M3x3 = Matrix<3>;
+pub type M2x2 = Matrix<2>;
+
+impl<const N: usize> From<[[f64; N]; N]> for Matrix<N> {
+    fn from(matrix: [[f64; N]; N]) -> Self {
+        Matrix { matrix }
     }
 }
 
-impl Index<MatrixIndex> for M4x4 {
+impl<const N: usize> fmt::Display for Matrix<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_rows(f, &self.matrix)
+    }
+}
+
+impl<const N: usize> Index<MatrixIndex> for Matrix<N> {
     type Output = f64;
 
+    /// Panics when the index falls outside the matrix, handing back some
+    /// made up value instead would silently corrupt every calculation after it
     fn index(&self, index: MatrixIndex) -> &Self::Output {
-        match index {
-            MatrixIndex { x: 0..=3, y: 0..=3 } => &self.matrix[index.y][index.x],
-            _ => &-99.0,
-        }
+        assert!(
+            index.x < N && index.y < N,
+            "index out of bounds for {}x{} matrix: {}",
+            N,
+            N,
+            index
+        );
+        &self.matrix[index.y][index.x]
+    }
+}
+
+impl<const N: usize> IndexMut<MatrixIndex> for Matrix<N> {
+    fn index_mut(&mut self, index: MatrixIndex) -> &mut Self::Output {
+        assert!(
+            index.x < N && index.y < N,
+            "index out of bounds for {}x{} matrix: {}",
+            N,
+            N,
+            index
+        );
+        &mut self.matrix[index.y][index.x]
+    }
+}
+
+/// Indexes by `(row, col)`, e.g. `m[(0, 3)]` is the x translation of a transform
+impl<const N: usize> Index<(usize, usize)> for Matrix<N> {
+    type Output = f64;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        &self[MatrixIndex { x: col, y: row }]
+    }
+}
+
+impl<const N: usize> IndexMut<(usize, usize)> for Matrix<N> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Self::Output {
+        &mut self[MatrixIndex { x: col, y: row }]
     }
 }
 
-impl Eq for M4x4 {}
+impl<const N: usize> Eq for Matrix<N> {}
 
-impl PartialEq for M4x4 {
+impl<const N: usize> PartialEq for Matrix<N> {
     fn eq(&self, other: &Self) -> bool {
-        for y in 0..3 {
-            for x in 0..3 {
+        for y in 0..N {
+            for x in 0..N {
                 let mi = MatrixIndex { x, y };
                 if !(equal_f64(self[mi], other[mi])) {
                     return false;
@@ -93,18 +185,77 @@ impl PartialEq for M4x4 {
     }
 }
 
+/// Element-wise fuzzy comparison, so downstream tests can pick their own tolerance
+#[cfg(feature = "approx")]
+impl<const N: usize> approx::AbsDiffEq for Matrix<N> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.matrix
+            .iter()
+            .flatten()
+            .zip(other.matrix.iter().flatten())
+            .all(|(a, b)| a.abs_diff_eq(b, epsilon))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl<const N: usize> approx::RelativeEq for Matrix<N> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.matrix
+            .iter()
+            .flatten()
+            .zip(other.matrix.iter().flatten())
+            .all(|(a, b)| a.relative_eq(b, epsilon, max_relative))
+    }
+}
+
+/// Rows of numbers, the way the matrix is written out. serde only covers fixed size arrays up to
+/// 32, not `[f64; N]` for any `N`.
+#[cfg(feature = "scene")]
+impl<const N: usize> serde::Serialize for Matrix<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.matrix.iter().map(|row| &row[..]))
+    }
+}
+
+#[cfg(feature = "scene")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Matrix<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let rows = Vec::<Vec<f64>>::deserialize(deserializer)?;
+        if rows.len() != N || rows.iter().any(|row| row.len() != N) {
+            return Err(D::Error::custom(format!("expected {} rows of {}", N, N)));
+        }
+        let mut matrix = [[0.0; N]; N];
+        for (to, from) in matrix.iter_mut().zip(rows) {
+            to.copy_from_slice(&from);
+        }
+        Ok(Matrix::from(matrix))
+    }
+}
+
 /// Matrix multiplication computes the dot product of every row-column combination in the two matrices
-impl Mul<M4x4> for M4x4 {
+impl<const N: usize> Mul<Matrix<N>> for Matrix<N> {
     type Output = Self;
 
     fn mul(self, other: Self) -> Self {
-        let mut new_matrix = [[0.0; 4]; 4];
-        for y in 0..4 {
-            for x in 0..4 {
-                new_matrix[y][x] = cal_index_matrix_multi(&self.matrix, &other.matrix, x, y);
+        let mut new_matrix = [[0.0; N]; N];
+        for (y, row) in new_matrix.iter_mut().enumerate() {
+            for (x, val) in row.iter_mut().enumerate() {
+                *val = cal_index_matrix_multi(&self.matrix, &other.matrix, x, y);
             }
         }
-        M4x4::from(new_matrix)
+        Matrix::from(new_matrix)
     }
 }
 
@@ -122,15 +273,19 @@ impl Mul<Tuple> for M4x4 {
     }
 }
 
-fn cal_index_matrix_multi(m1: &[[f64; 4]; 4], m2: &[[f64; 4]; 4], x: usize, y: usize) -> f64 {
+fn cal_index_matrix_multi<const N: usize>(
+    m1: &[[f64; N]; N],
+    m2: &[[f64; N]; N],
+    x: usize,
+    y: usize,
+) -> f64 {
     // for y 1, x 0 of new matrix
     // line up row 1 for m1 and col 1 for m2
     let row = m1[y];
-    let col = [m2[0][x], m2[1][x], m2[2][x], m2[3][x]];
 
     let mut final_val = 0.0;
-    for i in 0..4 {
-        final_val += row[i] * col[i]
+    for (i, row_val) in row.iter().enumerate() {
+        final_val += row_val * m2[i][x]
     }
     final_val
 }
@@ -142,271 +297,259 @@ fn cal_index_tuple_multi(m1: &[[f64; 4]; 4], t: Tuple, r: usize) -> f64 {
     t.x * row[0] + t.y * row[1] + t.z * row[2] + t.w * row[3]
 }
 
-/// Transposing a matrix turn its rows into columns and its columns into rows
-///
-/// Transposing the identity matrix will return the identity matrix
-///
-/// Useful when translating vectors between object space and world space
-pub fn transpose(m: M4x4) -> M4x4 {
-    let mut tx_m = [[0.0; 4]; 4];
-    for y in 0..4 {
-        for x in 0..4 {
-            tx_m[x][y] = m.matrix[y][x];
-        }
+impl<const N: usize> Matrix<N> {
+    /// Returns the value at `row`, `col` or None when that falls outside the matrix
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        self.matrix.get(row).and_then(|r| r.get(col)).copied()
     }
-    M4x4::from(tx_m)
-}
 
-/// Returns submatrix with given row and column removed
-pub fn submatrix_4x4(matrix: &M4x4, row: usize, col: usize) -> M3x3 {
-    let mut new_m = [[0.0; 3]; 3];
-    let mut write_x = 0;
-    let mut write_y = 0;
-    for y in 0..4 {
-        if y == row {
-            continue;
+    /// The identity matrix of this size, see `IDENTITY_MATRIX_4X4`
+    pub fn identity() -> Self {
+        let mut matrix = [[0.0; N]; N];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
         }
-        for x in 0..4 {
-            if x == col {
-                continue;
-            }
-            let val = matrix.matrix[y][x];
-            new_m[write_y][write_x] = val;
-            write_x += 1;
-        }
-        write_x = 0;
-        write_y += 1;
+        Matrix::from(matrix)
     }
-    M3x3::from(new_m)
-}
 
-/// Minor is the determinant of given matrix's submatrix given row and column
-///
-pub fn minor_4x4(matrix: &M4x4, row: usize, col: usize) -> f64 {
-    determinant_3x3(&submatrix_4x4(matrix, row, col))
-}
-
-pub fn cofactor_4x4(matrix: &M4x4, row: usize, col: usize) -> f64 {
-    let cofactor = minor_4x4(matrix, row, col);
-    if (row + col) % 2 == 0 {
-        return cofactor;
+    /// Transposing a matrix turn its rows into columns and its columns into rows
+    ///
+    /// Transposing the identity matrix will return the identity matrix
+    ///
+    /// Useful when translating vectors between object space and world space
+    pub fn transpose(&self) -> Self {
+        let mut tx_m = [[0.0; N]; N];
+        for (y, row) in self.matrix.iter().enumerate() {
+            for (x, val) in row.iter().enumerate() {
+                tx_m[x][y] = *val;
+            }
+        }
+        Matrix::from(tx_m)
     }
-    -1.0 * cofactor
-}
 
-/// The determinant is a number that is derived from the elements of a matrix.
-/// The name comes from the use of matrices to solve systems of equations,
-/// where it’s used to determine whether or not the system has a solution.
-/// If the determinant is zero, then the corresponding system of equations has no solution.
-///
-/// ChatGPT-4 explanation
-/// The determinant serves as a tool to determine whether a set of vectors is linearly independent
-/// by providing information about the "size" of the region they span.
-/// If the determinant is non-zero, the vectors are linearly independent, whereas
-/// a determinant of zero indicates that the vectors are linearly dependent.
-pub fn determinant_4x4(matrix: &M4x4) -> f64 {
-    let mut det = 0.0;
-    for col in 0..4 {
-        det += matrix.matrix[0][col] * cofactor_4x4(matrix, 0, col)
-    }
-    det
-}
-
-/// invertible_4x4
-///
-/// ChatGPT-4 explanation
-/// An invertible matrix, also known as a non-singular matrix, is a matrix that has an inverse.
-/// If a matrix A is invertible, there exists another matrix A^(-1) such that their product
-/// results in the identity matrix:
-///
-/// A * A^(-1) = A^(-1) * A = I
-///
-/// A matrix is invertible if and only if its determinant is non-zero. This might seem like a simple
-/// statement, but there is a deeper intuition behind it.
-///
-/// Recall that the determinant measures the "size" of the region spanned by the column vectors
-/// of a matrix. In the 2D case, it represents the area of the parallelogram formed by the vectors;
-/// in the 3D case, it represents the volume of the parallelepiped.
-///
-/// If the determinant is zero, it means that the region spanned by the
-/// column vectors is degenerate, which implies that the vectors are linearly dependent.
-/// In this case, the matrix cannot be inverted. This is because when you try to solve a
-/// system of linear equations using a matrix with linearly dependent columns, there will be
-/// either no unique solution (inconsistent system) or infinitely many solutions (dependent system).
-///
-/// On the other hand, if the determinant is non-zero, the region spanned by the column vectors
-/// is non-degenerate, and the vectors are linearly independent. In this case,
-/// the matrix can be inverted. This is because, for each output vector in the target space,
-/// there exists a unique input vector that can be mapped to it using the matrix.
-/// The inverse matrix can then be used to reverse this mapping,
-/// taking output vectors back to their corresponding input vectors.
-///
-/// So, the intuition behind using the determinant to determine if a matrix is invertible is
-/// that a non-zero determinant signifies that the column vectors of the matrix are linearly independent,
-/// and the matrix defines a unique mapping from input vectors to output vectors. In this case,
-/// the matrix can be inverted, and the inverse matrix can be used to reverse the mapping.
-/// If the determinant is zero, the column vectors are linearly dependent, and the
-/// matrix cannot be inverted, as it does not define a unique mapping.
-pub fn invertible_4x4(matrix: &M4x4) -> bool {
-    determinant_4x4(matrix) != 0.0
-}
-
-/// The idea of inverting a matrix is similar to the idea of inverting multiplication by dividing
-/// If you multiply 5 * 4 you get 20. Divide 20 by 4 and you get 5.
-/// Same idea for matrices. If you multiple matrix A by B you get C.
-/// Multiply C by the inverse of B and you get A.
-///
-/// Inverting uses the cofactor expansion method
-pub fn invert_4x4(matrix: &M4x4) -> Result<M4x4, MatrixError> {
-    if !invertible_4x4(matrix) {
-        return Err(MatrixNotInvertible);
-    }
-    let mut cofactors = [[0.0; 4]; 4];
-    let det = determinant_4x4(matrix);
-    for y in 0..4 {
-        for x in 0..4 {
-            let c = cofactor_4x4(matrix, y, x);
-            // sneaky tricky to accomplish transpose operation
-            cofactors[x][y] = c / det;
+    /// Returns submatrix with given row and column removed
+    ///
+    /// `M` must be one less than `N`, which is usually inferred from the result,
+    /// e.g. `let sub: M3x3 = m.submatrix(0, 0);`
+    pub fn submatrix<const M: usize>(&self, row: usize, col: usize) -> Matrix<M> {
+        const {
+            assert!(
+                M + 1 == N,
+                "a submatrix is exactly one row and column smaller"
+            )
+        };
+        let mut new_m = [[0.0; M]; M];
+        let rows = (0..N).filter(|y| *y != row);
+        for (write_y, y) in rows.enumerate() {
+            let cols = (0..N).filter(|x| *x != col);
+            for (write_x, x) in cols.enumerate() {
+                new_m[write_y][write_x] = self.matrix[y][x];
+            }
         }
+        Matrix::from(new_m)
     }
-    Ok(M4x4::from(cofactors))
-}
-// ----------------------------- 3x3 ------------------------------------
-
-#[derive(Debug)]
-pub struct M3x3 {
-    matrix: [[f64; 3]; 3],
-}
-
-impl Index<MatrixIndex> for M3x3 {
-    type Output = f64;
 
-    fn index(&self, index: MatrixIndex) -> &Self::Output {
-        match index {
-            MatrixIndex { x: 0..=2, y: 0..=2 } => &self.matrix[index.y][index.x],
-            _ => &-99.0,
+    /// Minor is the determinant of given matrix's submatrix given row and column
+    pub fn minor(&self, row: usize, col: usize) -> f64 {
+        let mut rows = [0; N];
+        let mut cols = [0; N];
+        let mut len = 0;
+        for i in (0..N).filter(|i| *i != row) {
+            rows[len] = i;
+            len += 1;
+        }
+        len = 0;
+        for i in (0..N).filter(|i| *i != col) {
+            cols[len] = i;
+            len += 1;
         }
+        self.determinant_of(&rows[..len], &cols[..len])
     }
-}
 
-impl Eq for M3x3 {}
-
-impl PartialEq for M3x3 {
-    fn eq(&self, other: &Self) -> bool {
-        for y in 0..2 {
-            for x in 0..2 {
-                let mi = MatrixIndex { x, y };
-                if !(equal_f64(self[mi], other[mi])) {
-                    return false;
-                }
-            }
+    /// A cofactor is a minor that possibly had its sign changed,
+    /// which happens when row + col is odd
+    pub fn cofactor(&self, row: usize, col: usize) -> f64 {
+        let cofactor = self.minor(row, col);
+        if (row + col).is_multiple_of(2) {
+            return cofactor;
         }
-        true
+        -cofactor
     }
-}
 
-impl From<[[f64; 3]; 3]> for M3x3 {
-    fn from(matrix: [[f64; 3]; 3]) -> Self {
-        M3x3 { matrix }
+    /// The determinant is a number that is derived from the elements of a matrix.
+    /// The name comes from the use of matrices to solve systems of equations,
+    /// where it’s used to determine whether or not the system has a solution.
+    /// If the determinant is zero, then the corresponding system of equations has no solution.
+    ///
+    /// ChatGPT-4 explanation
+    /// The determinant serves as a tool to determine whether a set of vectors is linearly independent
+    /// by providing information about the "size" of the region they span.
+    /// If the determinant is non-zero, the vectors are linearly independent, whereas
+    /// a determinant of zero indicates that the vectors are linearly dependent.
+    pub fn determinant(&self) -> f64 {
+        let all: [usize; N] = std::array::from_fn(|i| i);
+        self.determinant_of(&all, &all)
     }
-}
 
-pub fn submatrix_3x3(matrix: &M3x3, row: usize, col: usize) -> M2x2 {
-    let mut new_m = [[0.0; 2]; 2];
-    let mut write_x = 0;
-    let mut write_y = 0;
-    for y in 0..3 {
-        if y == row {
-            continue;
-        }
-        for x in 0..3 {
-            if x == col {
-                continue;
+    /// Determinant of the submatrix made of only the given rows and columns, found by
+    /// cofactor expansion along the first of those rows.
+    ///
+    /// Working with lists of indices rather than smaller matrix types is what lets
+    /// minors and determinants be written once for every size.
+    fn determinant_of(&self, rows: &[usize], cols: &[usize]) -> f64 {
+        let m = &self.matrix;
+        match rows.len() {
+            0 => 1.0,
+            1 => m[rows[0]][cols[0]],
+            2 => {
+                m[rows[0]][cols[0]] * m[rows[1]][cols[1]]
+                    - m[rows[0]][cols[1]] * m[rows[1]][cols[0]]
+            }
+            len => {
+                let mut det = 0.0;
+                let mut sub_cols = [0; N];
+                for (i, col) in cols.iter().enumerate() {
+                    sub_cols[..i].copy_from_slice(&cols[..i]);
+                    sub_cols[i..len - 1].copy_from_slice(&cols[i + 1..]);
+                    let minor = self.determinant_of(&rows[1..], &sub_cols[..len - 1]);
+                    let sign = if i.is_multiple_of(2) { 1.0 } else { -1.0 };
+                    det += m[rows[0]][*col] * sign * minor;
+                }
+                det
             }
-            let val = matrix.matrix[y][x];
-            new_m[write_y][write_x] = val;
-            write_x += 1;
         }
-        write_x = 0;
-        write_y += 1;
     }
-    M2x2::from(new_m)
-}
 
-pub fn minor_3x3(matrix: &M3x3, row: usize, col: usize) -> f64 {
-    determinant_2x2(&submatrix_3x3(matrix, row, col))
-}
-
-pub fn cofactor_3x3(matrix: &M3x3, row: usize, col: usize) -> f64 {
-    let cofactor = minor_3x3(matrix, row, col);
-    if (row + col) % 2 == 0 {
-        return cofactor;
+    /// An invertible matrix, also known as a non-singular matrix, is a matrix that has an inverse.
+    ///
+    /// ChatGPT-4 explanation
+    /// If a matrix A is invertible, there exists another matrix A^(-1) such that their product
+    /// results in the identity matrix:
+    ///
+    /// A * A^(-1) = A^(-1) * A = I
+    ///
+    /// A matrix is invertible if and only if its determinant is non-zero. This might seem like a simple
+    /// statement, but there is a deeper intuition behind it.
+    ///
+    /// Recall that the determinant measures the "size" of the region spanned by the column vectors
+    /// of a matrix. In the 2D case, it represents the area of the parallelogram formed by the vectors;
+    /// in the 3D case, it represents the volume of the parallelepiped.
+    ///
+    /// If the determinant is zero, it means that the region spanned by the
+    /// column vectors is degenerate, which implies that the vectors are linearly dependent.
+    /// In this case, the matrix cannot be inverted. This is because when you try to solve a
+    /// system of linear equations using a matrix with linearly dependent columns, there will be
+    /// either no unique solution (inconsistent system) or infinitely many solutions (dependent system).
+    ///
+    /// On the other hand, if the determinant is non-zero, the region spanned by the column vectors
+    /// is non-degenerate, and the vectors are linearly independent. In this case,
+    /// the matrix can be inverted. This is because, for each output vector in the target space,
+    /// there exists a unique input vector that can be mapped to it using the matrix.
+    /// The inverse matrix can then be used to reverse this mapping,
+    /// taking output vectors back to their corresponding input vectors.
+    ///
+    /// So, the intuition behind using the determinant to determine if a matrix is invertible is
+    /// that a non-zero determinant signifies that the column vectors of the matrix are linearly independent,
+    /// and the matrix defines a unique mapping from input vectors to output vectors. In this case,
+    /// the matrix can be inverted, and the inverse matrix can be used to reverse the mapping.
+    /// If the determinant is zero, the column vectors are linearly dependent, and the
+    /// matrix cannot be inverted, as it does not define a unique mapping.
+    ///
+    /// In floating point a determinant is rarely exactly zero though, so this asks the
+    /// pivoting elimination in `inverse` instead, which judges singularity relative to
+    /// the scale of each row.
+    pub fn invertible(&self) -> bool {
+        self.gauss_jordan().is_some()
     }
-    -1.0 * cofactor
-}
 
-pub fn determinant_3x3(matrix: &M3x3) -> f64 {
-    let mut det = 0.0;
-    for col in 0..3 {
-        det += matrix.matrix[0][col] * cofactor_3x3(matrix, 0, col)
+    /// The idea of inverting a matrix is similar to the idea of inverting multiplication by dividing
+    /// If you multiply 5 * 4 you get 20. Divide 20 by 4 and you get 5.
+    /// Same idea for matrices. If you multiple matrix A by B you get C.
+    /// Multiply C by the inverse of B and you get A.
+    ///
+    /// Inverting uses Gauss-Jordan elimination with partial pivoting, which stays accurate
+    /// for near-singular and badly scaled transforms (e.g. `scaling(1e-8, 1.0, 1.0)`)
+    /// where dividing cofactors by a tiny determinant does not.
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        self.gauss_jordan().ok_or(MatrixNotInvertible)
     }
-    det
-}
 
-// ----------------------------- 2x2 ------------------------------------
-#[derive(Debug)]
-pub struct M2x2 {
-    matrix: [[f64; 2]; 2],
-}
-
-impl From<[[f64; 2]; 2]> for M2x2 {
-    fn from(matrix: [[f64; 2]; 2]) -> Self {
-        M2x2 { matrix }
+    /// Inverts with the cofactor expansion method from the book.
+    ///
+    /// Exact for small, well-scaled matrices but numerically fragile otherwise, prefer `inverse`
+    pub fn cofactor_inverse(&self) -> Result<Self, MatrixError> {
+        if self.determinant() == 0.0 {
+            return Err(MatrixNotInvertible);
+        }
+        let mut cofactors = [[0.0; N]; N];
+        let det = self.determinant();
+        for (x, row) in cofactors.iter_mut().enumerate() {
+            for (y, val) in row.iter_mut().enumerate() {
+                let c = self.cofactor(y, x);
+                // sneaky tricky to accomplish transpose operation
+                *val = c / det;
+            }
+        }
+        Ok(Matrix::from(cofactors))
     }
-}
-
-impl Index<MatrixIndex> for M2x2 {
-    type Output = f64;
 
-    fn index(&self, index: MatrixIndex) -> &Self::Output {
-        match index {
-            MatrixIndex { x: 0..=1, y: 0..=1 } => &self.matrix[index.y][index.x],
-            _ => &-99.0,
+    /// Gauss-Jordan elimination: row reduce `[self | I]` until the left side is the identity,
+    /// at which point the right side is the inverse. Returns None for singular matrices.
+    ///
+    /// Every row is first divided by its largest magnitude, so a pivot is judged small
+    /// relative to the rest of its row rather than against an absolute threshold. A uniformly
+    /// tiny (but perfectly invertible) scale is therefore fine, while rows that only differ
+    /// by rounding noise are caught as singular. Each step then swaps in the largest remaining
+    /// pivot of the column (partial pivoting) to keep the rounding error from growing.
+    fn gauss_jordan(&self) -> Option<Self> {
+        let mut a = self.matrix;
+        let mut inv = Self::identity().matrix;
+
+        // scaling row i of A by s means row i of the inverse's columns picks up the same
+        // factor, so the identity side is scaled along with it
+        for (row, inv_row) in a.iter_mut().zip(inv.iter_mut()) {
+            let scale = row.iter().fold(0.0_f64, |acc, v| acc.max(v.abs()));
+            if scale == 0.0 {
+                return None;
+            }
+            row.iter_mut().for_each(|v| *v /= scale);
+            inv_row.iter_mut().for_each(|v| *v /= scale);
         }
-    }
-}
 
-impl Eq for M2x2 {}
+        for col in 0..N {
+            let pivot_row =
+                (col..N).max_by(|r1, r2| a[*r1][col].abs().total_cmp(&a[*r2][col].abs()))?;
+            if a[pivot_row][col].abs() < SINGULARITY_TOLERANCE {
+                return None;
+            }
+            a.swap(col, pivot_row);
+            inv.swap(col, pivot_row);
 
-impl PartialEq for M2x2 {
-    fn eq(&self, other: &Self) -> bool {
-        for y in 0..1 {
-            for x in 0..1 {
-                let mi = MatrixIndex { x, y };
-                if !(equal_f64(self[mi], other[mi])) {
-                    return false;
+            let pivot = a[col][col];
+            a[col].iter_mut().for_each(|v| *v /= pivot);
+            inv[col].iter_mut().for_each(|v| *v /= pivot);
+
+            for row in (0..N).filter(|r| *r != col) {
+                let factor = a[row][col];
+                if factor == 0.0 {
+                    continue;
+                }
+                for x in 0..N {
+                    a[row][x] -= factor * a[col][x];
+                    inv[row][x] -= factor * inv[col][x];
                 }
             }
         }
-        true
+        Some(Matrix::from(inv))
     }
 }
 
-pub fn determinant_2x2(m: &M2x2) -> f64 {
-    (m.matrix[0][0] * m.matrix[1][1]) - (m.matrix[0][1] * m.matrix[1][0])
-}
-
 #[cfg(test)]
 mod tests {
-    use std::borrow::Borrow;
-
-    use crate::matrix::{
-        cofactor_3x3, cofactor_4x4, determinant_2x2, determinant_3x3, determinant_4x4, invert_4x4,
-        invertible_4x4, minor_3x3, submatrix_3x3, submatrix_4x4, transpose, M2x2, M3x3, M4x4,
-        MatrixIndex, IDENTITY_MATRIX_4X4,
-    };
+    use crate::matrix::{M2x2, M3x3, M4x4, MatrixIndex, IDENTITY_MATRIX_4X4};
     use crate::tuple::{Point, Tuple};
+    #[cfg(feature = "approx")]
+    use approx::{assert_abs_diff_eq, assert_relative_eq, assert_relative_ne};
 
     #[test]
     fn create_4x4_matrix() {
@@ -426,6 +569,39 @@ mod tests {
         assert_eq!(test_m4x4[MatrixIndex { x: 2, y: 3 }], 15.5);
     }
 
+    #[test]
+    #[should_panic(expected = "index out of bounds for 4x4 matrix: x: 4, y: 0")]
+    fn index_out_of_bounds_panics() {
+        let _ = IDENTITY_MATRIX_4X4[MatrixIndex { x: 4, y: 0 }];
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds for 2x2 matrix")]
+    fn tuple_index_out_of_bounds_panics() {
+        let m = M2x2::from([[-3.0, 5.0], [1.0, -2.0]]);
+        let _ = m[(2, 0)];
+    }
+
+    #[test]
+    fn get_returns_none_out_of_bounds() {
+        let m = M2x2::from([[-3.0, 5.0], [1.0, -2.0]]);
+        assert_eq!(m.get(0, 1), Some(5.0));
+        assert_eq!(m.get(1, 0), Some(1.0));
+        assert_eq!(m.get(2, 0), None);
+        assert_eq!(m.get(0, 2), None);
+    }
+
+    #[test]
+    fn index_by_row_and_col() {
+        let mut m = IDENTITY_MATRIX_4X4;
+        m[(0, 3)] = 5.0;
+        m[MatrixIndex { x: 1, y: 2 }] = -1.0;
+        assert_eq!(m[(0, 3)], 5.0);
+        assert_eq!(m[(2, 1)], -1.0);
+        assert_eq!(m.matrix[0][3], 5.0);
+        assert_eq!(m.matrix[2][1], -1.0);
+    }
+
     #[test]
     fn compare_4x4_matrices() {
         let m1 = M4x4::from([
@@ -458,6 +634,32 @@ mod tests {
         assert_ne!(m3, m4);
     }
 
+    #[test]
+    fn compare_matrices_checks_last_row_and_column() {
+        let mut m = IDENTITY_MATRIX_4X4;
+        m.matrix[3][3] = 2.0;
+        assert_ne!(m, IDENTITY_MATRIX_4X4);
+
+        let mut m = IDENTITY_MATRIX_4X4;
+        m.matrix[0][3] = 5.0;
+        assert_ne!(m, IDENTITY_MATRIX_4X4);
+
+        let m3 = M3x3::from([[1.0, 2.0, 3.0], [5.5, 6.5, 7.5], [9.0, 10.0, 11.0]]);
+        let m4 = M3x3::from([[1.0, 2.0, 3.0], [5.5, 6.5, 7.5], [9.0, 10.0, 12.0]]);
+        assert_ne!(m3, m4);
+
+        let m5 = M2x2::from([[1.0, 2.0], [5.5, 6.5]]);
+        let m6 = M2x2::from([[1.0, 2.0], [5.5, 7.5]]);
+        assert_ne!(m5, m6);
+    }
+
+    #[test]
+    fn identity_of_each_size() {
+        assert_eq!(M4x4::identity(), IDENTITY_MATRIX_4X4);
+        assert_eq!(M3x3::identity().determinant(), 1.0);
+        assert_eq!(M2x2::identity(), M2x2::from([[1.0, 0.0], [0.0, 1.0]]));
+    }
+
     #[test]
     fn multiply_4x4_matrices() {
         let m1 = M4x4::from([
@@ -516,8 +718,8 @@ mod tests {
             [0.0, 8.0, 3.0, 8.0],
         ]);
 
-        assert_eq!(transpose(m1), expected);
-        assert_eq!(transpose(IDENTITY_MATRIX_4X4), IDENTITY_MATRIX_4X4);
+        assert_eq!(m1.transpose(), expected);
+        assert_eq!(IDENTITY_MATRIX_4X4.transpose(), IDENTITY_MATRIX_4X4);
     }
 
     #[test]
@@ -561,10 +763,30 @@ mod tests {
         assert_ne!(m3, m4);
     }
 
+    #[test]
+    fn display_2x2_matrix_aligns_columns() {
+        let m = M2x2::from([[-3.0, 5.0], [1.0, -20.5]]);
+        assert_eq!(format!("{}", m), "|    -3     5 |\n|     1 -20.5 |");
+    }
+
+    #[test]
+    fn display_3x3_matrix_one_row_per_line() {
+        let m = M3x3::from([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(format!("{}", m), "| 1 2 3 |\n| 4 5 6 |\n| 7 8 9 |");
+    }
+
+    #[test]
+    fn display_4x4_matrix_honors_precision() {
+        let rendered = format!("{:.2}", IDENTITY_MATRIX_4X4);
+        let mut lines = rendered.lines();
+        assert_eq!(lines.next(), Some("| 1.00 0.00 0.00 0.00 |"));
+        assert_eq!(lines.last(), Some("| 0.00 0.00 0.00 1.00 |"));
+    }
+
     #[test]
     fn determinant_2x2_matrices() {
         let m1 = M2x2::from([[1.0, 5.0], [-3.0, 2.0]]);
-        assert_eq!(determinant_2x2(&m1), 17.0);
+        assert_eq!(m1.determinant(), 17.0);
     }
 
     #[test]
@@ -593,7 +815,7 @@ mod tests {
     fn submatrix_3x3_2x2() {
         let test_m3 = M3x3::from([[1.0, 5.0, 0.0], [-3.0, 2.0, 7.0], [0.0, 6.0, -3.0]]);
         let expected = M2x2::from([[-3.0, 2.0], [0.0, 6.0]]);
-        assert_eq!(submatrix_3x3(&test_m3, 0, 2), expected);
+        assert_eq!(test_m3.submatrix(0, 2), expected);
     }
 
     #[test]
@@ -605,26 +827,32 @@ mod tests {
             [-7.0, 1.0, -1.0, 1.0],
         ]);
         let expected = M3x3::from([[-6.0, 1.0, 6.0], [-8.0, 8.0, 6.0], [-7.0, -1.0, 1.0]]);
-        assert_eq!(submatrix_4x4(&test_m3, 2, 1), expected);
+        assert_eq!(test_m3.submatrix(2, 1), expected);
     }
 
     #[test]
     fn minor_3x3_test() {
         let a = M3x3::from([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
-        assert_eq!(minor_3x3(&a, 1, 0), 25.0);
+        assert_eq!(a.minor(1, 0), 25.0);
     }
 
     #[test]
     fn cofactor_3x3_test() {
         let a = M3x3::from([[3.0, 5.0, 0.0], [2.0, -1.0, -7.0], [6.0, -1.0, 5.0]]);
-        assert_eq!(cofactor_3x3(&a, 0, 0), -12.0);
-        assert_eq!(cofactor_3x3(&a, 1, 0), -25.0);
+        assert_eq!(a.cofactor(0, 0), -12.0);
+        assert_eq!(a.cofactor(1, 0), -25.0);
     }
 
     #[test]
     fn determinant_3x3_test() {
         let a = M3x3::from([[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]]);
-        assert_eq!(determinant_3x3(&a), -196.0);
+        assert_eq!(a.determinant(), -196.0);
+    }
+
+    #[test]
+    fn inverse_of_3x3_matrix() {
+        let a = M3x3::from([[1.0, 2.0, 6.0], [-5.0, 8.0, -4.0], [2.0, 6.0, 4.0]]);
+        assert_eq!(a * a.inverse().unwrap(), M3x3::identity());
     }
 
     #[test]
@@ -635,7 +863,7 @@ mod tests {
             [1.0, 2.0, -9.0, 6.0],
             [-6.0, 7.0, 7.0, -9.0],
         ]);
-        assert_eq!(determinant_4x4(&a), -4071.0);
+        assert_eq!(a.determinant(), -4071.0);
     }
 
     #[test]
@@ -646,8 +874,8 @@ mod tests {
             [4.0, -9.0, 3.0, -7.0],
             [9.0, 1.0, 7.0, -6.0],
         ]);
-        assert_eq!(determinant_4x4(&a), -2120.0);
-        assert!(invertible_4x4(&a));
+        assert_eq!(a.determinant(), -2120.0);
+        assert!(a.invertible());
 
         let a = M4x4::from([
             [-4.0, 2.0, -2.0, -3.0],
@@ -655,8 +883,8 @@ mod tests {
             [0.0, -5.0, 1.0, -5.0],
             [0.0, 0.0, 0.0, 0.0],
         ]);
-        assert_eq!(determinant_4x4(&a), 0.0);
-        assert!(!invertible_4x4(&a))
+        assert_eq!(a.determinant(), 0.0);
+        assert!(!a.invertible())
     }
 
     #[test]
@@ -667,11 +895,11 @@ mod tests {
             [7.0, 7.0, -6.0, -7.0],
             [1.0, -3.0, 7.0, 4.0],
         ]);
-        assert_eq!(determinant_4x4(&a), 532.0);
-        assert_eq!(cofactor_4x4(&a, 2, 3), -160.0);
-        assert_eq!(cofactor_4x4(&a, 3, 2), 105.0);
+        assert_eq!(a.determinant(), 532.0);
+        assert_eq!(a.cofactor(2, 3), -160.0);
+        assert_eq!(a.cofactor(3, 2), 105.0);
 
-        let b = invert_4x4(a.borrow()).unwrap();
+        let b = a.inverse().unwrap();
         let expected = M4x4::from([
             [0.21805, 0.45113, 0.24060, -0.04511],
             [-0.80827, -1.45677, -0.44361, 0.52068],
@@ -686,7 +914,7 @@ mod tests {
             [-6.0, 0.0, 9.0, 6.0],
             [-3.0, 0.0, -9.0, -4.0],
         ]);
-        let d = invert_4x4(&c).unwrap();
+        let d = c.inverse().unwrap();
         let e2 = M4x4::from([
             [-0.15385, -0.15385, -0.28205, -0.53846],
             [-0.07692, 0.12308, 0.02564, 0.03077],
@@ -701,16 +929,54 @@ mod tests {
             [-4.0, 9.0, 6.0, 4.0],
             [-7.0, 6.0, 6.0, 2.0],
         ]);
-        let f = invert_4x4(&e).unwrap();
+        let f = e.inverse().unwrap();
         let e3 = M4x4::from([
             [-0.04074, -0.07778, 0.14444, -0.22222],
             [-0.07778, 0.03333, 0.36667, -0.33333],
             [-0.02901, -0.14630, -0.10926, 0.12963],
-            [0.17778, 0.06663, -0.26667, 0.333333333333333],
+            [0.17778, 0.06667, -0.26667, 0.333333333333333],
         ]);
         assert_eq!(f, e3);
     }
 
+    #[test]
+    fn cofactor_inverse_matches_gauss_jordan() {
+        let a = M4x4::from([
+            [-5.0, 2.0, 6.0, -8.0],
+            [1.0, -5.0, 1.0, 8.0],
+            [7.0, 7.0, -6.0, -7.0],
+            [1.0, -3.0, 7.0, 4.0],
+        ]);
+        assert_eq!(a.cofactor_inverse().unwrap(), a.inverse().unwrap());
+        assert!(IDENTITY_MATRIX_4X4
+            .cofactor_inverse()
+            .is_ok_and(|m| m == IDENTITY_MATRIX_4X4));
+    }
+
+    #[test]
+    fn inverse_of_badly_scaled_matrix() {
+        let a = M4x4::from([
+            [1e-8, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 5.0],
+            [0.0, 0.0, 1e8, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inv = a.inverse().unwrap();
+        assert_eq!(inv[(0, 0)], 1e8);
+        assert_eq!(inv[(1, 3)], -5.0);
+        assert!((inv[(2, 2)] - 1e-8).abs() < 1e-20);
+        assert_eq!(a * inv, IDENTITY_MATRIX_4X4);
+    }
+
+    #[test]
+    fn nearly_singular_matrix_is_not_invertible() {
+        // the rows are linearly dependent, but rounding leaves a tiny non-zero determinant
+        let a = M3x3::from([[0.1, 0.2, 0.3], [0.4, 0.5, 0.6], [0.7, 0.8, 0.9]]);
+        assert_ne!(a.determinant(), 0.0);
+        assert!(!a.invertible());
+        assert!(a.inverse().is_err());
+    }
+
     #[test]
     fn matrix_product_by_its_inverse() {
         let a = M4x4::from([
@@ -727,6 +993,56 @@ mod tests {
             [6.0, -2.0, 0.0, 5.0],
         ]);
         let c = a * b;
-        assert_eq!(a, c * invert_4x4(&b).unwrap());
+        assert_eq!(a, c * b.inverse().unwrap());
+    }
+
+    #[cfg(feature = "approx")]
+    #[test]
+    fn approx_comparisons() {
+        let a = M4x4::from([
+            [3.0, -9.0, 7.0, 3.0],
+            [3.0, -8.0, 2.0, -9.0],
+            [-4.0, 4.0, 4.0, 1.0],
+            [-6.0, 5.0, -1.0, 1.0],
+        ]);
+        assert_relative_eq!(
+            a * a.inverse().unwrap(),
+            IDENTITY_MATRIX_4X4,
+            epsilon = 1e-12
+        );
+        assert_relative_ne!(a, IDENTITY_MATRIX_4X4);
+
+        let m3 = M3x3::from([[1.0, 2.0, 3.0], [5.5, 6.5, 7.5], [9.0, 10.0, 11.0]]);
+        assert_abs_diff_eq!(
+            m3,
+            M3x3::from([[1.0, 2.0, 3.0], [5.5, 6.5, 7.5], [9.0, 10.0, 11.05]]),
+            epsilon = 0.1
+        );
+        assert_relative_ne!(
+            M2x2::from([[1.0, 2.0], [5.5, 6.5]]),
+            M2x2::from([[1.0, 2.0], [5.5, 6.6]])
+        );
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn cast_matrix_to_floats() {
+        let floats: [f64; 16] = bytemuck::cast(IDENTITY_MATRIX_4X4);
+        assert_eq!(floats[0], 1.0);
+        assert_eq!(floats[1], 0.0);
+        assert_eq!(floats[5], 1.0);
+        assert_eq!(floats[15], 1.0);
+        assert_eq!(bytemuck::bytes_of(&IDENTITY_MATRIX_4X4).len(), 128);
+    }
+
+    #[cfg(feature = "scene")]
+    #[test]
+    fn matrices_as_json_rows() {
+        let m = M2x2::from([[1.0, 2.5], [-3.0, 4.0]]);
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(json, "[[1.0,2.5],[-3.0,4.0]]");
+        assert_eq!(serde_json::from_str::<M2x2>(&json).unwrap(), m);
+        assert!(serde_json::from_str::<M2x2>("[[1.0,2.5],[-3.0]]").is_err());
+        assert!(serde_json::from_str::<M3x3>(&json).is_err());
     }
 }
@@ -1,4 +1,5 @@
 use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::tuple::{Point, Vector};
 
 /// moves a point by taking the identity matrix
 /// adding x, y, and z to the 4th column
@@ -10,6 +11,11 @@ pub fn translation(x: f64, y: f64, z: f64) -> M4x4 {
     M4x4::from(base_matrix.matrix)
 }
 
+/// moves a point by the x, y, and z components of the given vector
+pub fn translation_from(offset: Vector) -> M4x4 {
+    translation(offset.x, offset.y, offset.z)
+}
+
 /// generates a scaling matrix
 ///
 /// when applied to an object centered at an origin
@@ -23,6 +29,11 @@ pub fn scaling(x: f64, y: f64, z: f64) -> M4x4 {
     M4x4::from(base_matrix.matrix)
 }
 
+/// generates a scaling matrix that scales by the same amount along every axis
+pub fn uniform_scaling(s: f64) -> M4x4 {
+    scaling(s, s, s)
+}
+
 /// multiplying a tuple by a rotation matrix will
 /// rotate the tuple around the axis
 pub fn rotation_x(radians: f64) -> M4x4 {
@@ -68,14 +79,34 @@ pub fn shearing(xy: f64, xx: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> M4x4 {
     M4x4::from(base_matrix.matrix)
 }
 
+/// Orients the world relative to the eye, as if looking `from` a point `to` another
+/// with `up` (roughly) pointing up.
+///
+/// It's really the world that moves, the camera stays at the origin looking down -z,
+/// so this is the transform that takes world space into that view.
+pub fn view_transform(from: Point, to: Point, up: Vector) -> M4x4 {
+    let forward = (to - from).normalize();
+    let left = forward.cross(&up.normalize());
+    // up only needs to be roughly right, the true up is recomputed from forward and left
+    let true_up = left.cross(&forward);
+    let orientation = M4x4::from([
+        [left.x, left.y, left.z, 0.0],
+        [true_up.x, true_up.y, true_up.z, 0.0],
+        [-forward.x, -forward.y, -forward.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    orientation * translation(-from.x, -from.y, -from.z)
+}
+
 #[cfg(test)]
 mod tests {
     use std::assert_eq;
     use std::f64::consts::PI;
 
-    use crate::matrix::invert_4x4;
+    use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
     use crate::matrix_transformations::{
-        rotation_x, rotation_y, rotation_z, scaling, shearing, translation,
+        rotation_x, rotation_y, rotation_z, scaling, shearing, translation, translation_from,
+        uniform_scaling, view_transform,
     };
     use crate::tuple::{Point, Vector};
 
@@ -90,7 +121,7 @@ mod tests {
     #[test]
     fn translation_matrix_inversion() {
         let transform = translation(5.0, -3.0, 2.0);
-        let inverted_translated = invert_4x4(&transform).unwrap();
+        let inverted_translated = transform.inverse().unwrap();
         let p = Point::new_point(-3.0, 4.0, 5.0);
         let expected = Point::new_point(-8.0, 7.0, 3.0);
         assert_eq!(inverted_translated * p, expected)
@@ -108,6 +139,18 @@ mod tests {
         assert_eq!(transform * v, v);
     }
 
+    #[test]
+    fn translation_from_vector_matches_translation() {
+        let offset = Vector::new(5.0, -3.0, 2.0);
+        assert_eq!(translation_from(offset), translation(5.0, -3.0, 2.0));
+
+        let p = Point::new_point(-3.0, 4.0, 5.0);
+        assert_eq!(
+            translation_from(offset) * p,
+            Point::new_point(2.0, 1.0, 7.0)
+        );
+    }
+
     #[test]
     fn scale_matrix_applied_to_point() {
         let transform = scaling(2.0, 3.0, 4.0);
@@ -127,12 +170,20 @@ mod tests {
     #[test]
     fn multi_inverse_of_scaling_matrix() {
         let transform = scaling(2.0, 3.0, 4.0);
-        let inv = invert_4x4(&transform).unwrap();
+        let inv = transform.inverse().unwrap();
         let v = Vector::new(-4.0, 6.0, 8.0);
         let expected = Vector::new(-2.0, 2.0, 2.0);
         assert_eq!(inv * v, expected);
     }
 
+    #[test]
+    fn uniform_scaling_scales_every_axis() {
+        let transform = uniform_scaling(2.0);
+        let p = Point::new_point(-4.0, 6.0, 8.0);
+        assert_eq!(transform, scaling(2.0, 2.0, 2.0));
+        assert_eq!(transform * p, Point::new_point(-8.0, 12.0, 16.0));
+    }
+
     #[test]
     fn reflection_is_scaling_by_neg() {
         let transform = scaling(-1.0, 1.0, 1.0);
@@ -158,7 +209,7 @@ mod tests {
         // inverse of a rotation matrix rotates in the opposite direction
         let p = Point::new_point(0.0, 1.0, 0.0);
         let half_quarter = rotation_x(PI / 4.0);
-        let inv = invert_4x4(&half_quarter).unwrap();
+        let inv = half_quarter.inverse().unwrap();
         let exp = Point::new_point(0.0, 2.0_f64.sqrt() / 2.0, -2.0_f64.sqrt() / 2.0);
 
         assert_eq!(inv * p, exp);
@@ -266,4 +317,42 @@ mod tests {
         let t = c * b * a;
         assert_eq!(t * p, Point::new_point(15.0, 0.0, 7.0));
     }
+
+    #[test]
+    fn view_transform_default_orientation() {
+        let from = Point::new_point(0.0, 0.0, 0.0);
+        let to = Point::new_point(0.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(view_transform(from, to, up), IDENTITY_MATRIX_4X4);
+    }
+
+    #[test]
+    fn view_transform_looking_in_positive_z() {
+        let from = Point::new_point(0.0, 0.0, 0.0);
+        let to = Point::new_point(0.0, 0.0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(view_transform(from, to, up), scaling(-1.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn view_transform_moves_the_world() {
+        let from = Point::new_point(0.0, 0.0, 8.0);
+        let to = Point::new_point(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(view_transform(from, to, up), translation(0.0, 0.0, -8.0));
+    }
+
+    #[test]
+    fn arbitrary_view_transform() {
+        let from = Point::new_point(1.0, 3.0, 2.0);
+        let to = Point::new_point(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+        let expected = M4x4::from([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(view_transform(from, to, up), expected);
+    }
 }
@@ -0,0 +1,468 @@
+//! Triangle meshes as plain data: vertex positions, normals and texture coordinates, and
+//! triangles indexing into them.
+//!
+//! Meshes are edited while a scene is being built, before anything is rendered. Displacement
+//! tessellates a flat surface into many small triangles and moves each vertex along its normal,
+//! which changes the silhouette (unlike a bump map, which only fakes the shading).
+//!
+//! Models that come without normals get them from `generate_normals`, smooth across gentle
+//! curves and sharp along creases. `group` turns the mesh into triangles a world can render,
+//! smooth shaded when it has normals.
+
+use crate::bounds::BoundingBox;
+use crate::canvas::Canvas;
+use crate::coordinates::Convention;
+use crate::group::Group;
+use crate::light::Material;
+use crate::shape::Object;
+use crate::triangle::{SmoothTriangle, Triangle};
+use crate::tuple::{Point, Vector};
+
+/// How much each face around a vertex counts toward its normal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalWeighting {
+    /// Bigger faces count more
+    #[default]
+    Area,
+    /// Faces count by their angle at the vertex, so splitting a face in two doesn't change the
+    /// normal
+    Angle,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Point>,
+    /// One per vertex, empty until computed
+    pub normals: Vec<Vector>,
+    /// Texture coordinates, one per vertex from 0 to 1, empty when the mesh has none
+    pub uvs: Vec<(f64, f64)>,
+    /// Counter-clockwise seen from the front
+    pub faces: Vec<[usize; 3]>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Mesh::default()
+    }
+
+    /// A square in the xz plane from -1 to 1 facing up, split into `divisions` by `divisions`
+    /// cells of two triangles each. u runs along x and v along z.
+    pub fn plane(divisions: usize) -> Self {
+        let n = divisions.max(1);
+        let mut mesh = Mesh::new();
+        for row in 0..=n {
+            for column in 0..=n {
+                let (u, v) = (column as f64 / n as f64, row as f64 / n as f64);
+                mesh.vertices
+                    .push(Point::new_point(u * 2.0 - 1.0, 0.0, v * 2.0 - 1.0));
+                mesh.normals.push(Vector::new(0.0, 1.0, 0.0));
+                mesh.uvs.push((u, v));
+            }
+        }
+        let index = |row: usize, column: usize| row * (n + 1) + column;
+        for row in 0..n {
+            for column in 0..n {
+                let (a, b) = (index(row, column), index(row, column + 1));
+                let (c, d) = (index(row + 1, column), index(row + 1, column + 1));
+                mesh.faces.push([a, c, b]);
+                mesh.faces.push([b, c, d]);
+            }
+        }
+        mesh
+    }
+
+    /// The triangle split into `divisions` squared smaller ones, with the corners' barycentric
+    /// coordinates (the weights of `b` and `c`) as texture coordinates
+    pub fn triangle(a: Point, b: Point, c: Point, divisions: usize) -> Self {
+        let n = divisions.max(1);
+        let mut mesh = Mesh::new();
+        // rows of vertices from `a` toward `c`, each one shorter than the last
+        let mut rows = Vec::new();
+        for i in 0..=n {
+            let mut row = Vec::new();
+            for j in 0..=(n - i) {
+                let (u, v) = (j as f64 / n as f64, i as f64 / n as f64);
+                row.push(mesh.vertices.len());
+                mesh.vertices.push(a + (b - a) * u + (c - a) * v);
+                mesh.uvs.push((u, v));
+            }
+            rows.push(row);
+        }
+        for i in 0..n {
+            for j in 0..(n - i) {
+                mesh.faces
+                    .push([rows[i][j], rows[i][j + 1], rows[i + 1][j]]);
+                if j + 1 < n - i {
+                    mesh.faces
+                        .push([rows[i][j + 1], rows[i + 1][j + 1], rows[i + 1][j]]);
+                }
+            }
+        }
+        mesh.recompute_normals();
+        mesh
+    }
+
+    /// Unit normal of a face, by the right hand rule from its winding
+    pub fn face_normal(&self, face: usize) -> Vector {
+        let [a, b, c] = self.faces[face].map(|i| self.vertices[i]);
+        (b - a).cross(&(c - a)).normalize()
+    }
+
+    pub fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for vertex in &self.vertices {
+            bounds.add_point(*vertex);
+        }
+        bounds
+    }
+
+    /// Sets every vertex normal to the average of the faces around it, weighted by their area
+    /// so slivers don't pull it off
+    pub fn recompute_normals(&mut self) {
+        let mut normals = vec![Vector::new(0.0, 0.0, 0.0); self.vertices.len()];
+        for face in &self.faces {
+            let [a, b, c] = face.map(|i| self.vertices[i]);
+            // the cross product's length is twice the area
+            let weighted = (b - a).cross(&(c - a));
+            for i in face {
+                normals[*i] = normals[*i] + weighted;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| {
+                if n.magnitude() > 0.0 {
+                    n.normalize()
+                } else {
+                    n
+                }
+            })
+            .collect();
+    }
+
+    /// Computes smooth vertex normals, keeping edges sharp where the faces on either side meet
+    /// at more than `crease_angle` (in radians). Vertices on a crease are split in one copy per
+    /// side, so the mesh can end up with more vertices than it started with, and vertices no
+    /// face uses are dropped.
+    pub fn generate_normals(&mut self, crease_angle: f64, weighting: NormalWeighting) {
+        let face_normals: Vec<Vector> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.map(|i| self.vertices[i]);
+                let n = (b - a).cross(&(c - a));
+                // a degenerate face has no direction and doesn't count toward anything
+                if n.magnitude() > 0.0 {
+                    n.normalize()
+                } else {
+                    n
+                }
+            })
+            .collect();
+        let mut corners_at = vec![Vec::new(); self.vertices.len()];
+        for (face, indices) in self.faces.iter().enumerate() {
+            for (corner, vertex) in indices.iter().enumerate() {
+                corners_at[*vertex].push((face, corner));
+            }
+        }
+        let weight = |face: usize, corner: usize| {
+            let [a, b, c] = [0, 1, 2].map(|k| self.vertices[self.faces[face][(corner + k) % 3]]);
+            match weighting {
+                NormalWeighting::Area => (b - a).cross(&(c - a)).magnitude(),
+                NormalWeighting::Angle => {
+                    let (ab, ac) = (b - a, c - a);
+                    let lengths = ab.magnitude() * ac.magnitude();
+                    if lengths > 0.0 {
+                        (ab.dot(&ac) / lengths).clamp(-1.0, 1.0).acos()
+                    } else {
+                        0.0
+                    }
+                }
+            }
+        };
+        let smooth_enough = crease_angle.cos() - 1e-9;
+        let has_uvs = self.uvs.len() == self.vertices.len();
+
+        let mut split = Mesh::new();
+        let mut faces = self.faces.clone();
+        for (vertex, corners) in corners_at.iter().enumerate() {
+            // the copies of this vertex made so far, by normal
+            let mut copies: Vec<(Vector, usize)> = Vec::new();
+            for &(face, corner) in corners {
+                let mut normal = Vector::new(0.0, 0.0, 0.0);
+                for &(other, other_corner) in corners {
+                    if face_normals[face].dot(&face_normals[other]) >= smooth_enough {
+                        normal = normal + face_normals[other] * weight(other, other_corner);
+                    }
+                }
+                let normal = if normal.magnitude() > 0.0 {
+                    normal.normalize()
+                } else {
+                    face_normals[face]
+                };
+                let index = match copies.iter().find(|(n, _)| *n == normal) {
+                    Some((_, index)) => *index,
+                    None => {
+                        split.vertices.push(self.vertices[vertex]);
+                        split.normals.push(normal);
+                        if has_uvs {
+                            split.uvs.push(self.uvs[vertex]);
+                        }
+                        copies.push((normal, split.vertices.len() - 1));
+                        split.vertices.len() - 1
+                    }
+                };
+                faces[face][corner] = index;
+            }
+        }
+        self.vertices = split.vertices;
+        self.normals = split.normals;
+        self.uvs = split.uvs;
+        self.faces = faces;
+    }
+
+    /// Moves every vertex along its normal by `height(point, uv)`, then recomputes the normals
+    /// to match the new shape. Vertices without texture coordinates get (0, 0).
+    pub fn displace(&mut self, height: impl Fn(Point, (f64, f64)) -> f64) {
+        if self.normals.len() != self.vertices.len() {
+            self.recompute_normals();
+        }
+        for (i, vertex) in self.vertices.iter_mut().enumerate() {
+            let uv = self.uvs.get(i).copied().unwrap_or((0.0, 0.0));
+            *vertex = *vertex + self.normals[i] * height(*vertex, uv);
+        }
+        self.recompute_normals();
+    }
+
+    /// `displace` by the brightness of an image, white raising the surface by `scale`.
+    /// The image is stretched over the texture coordinates, v = 0 at its top.
+    pub fn displace_by_texture(&mut self, texture: &Canvas, scale: f64) {
+        self.displace(|_, (u, v)| sample_luminance(texture, u, v) * scale);
+    }
+
+    /// Converts a mesh made in another tool's convention into the tracer's, reversing the
+    /// faces' winding when that mirrors it so they still face the same way
+    pub fn convert_from(&mut self, convention: Convention) {
+        for vertex in &mut self.vertices {
+            *vertex = convention.convert(*vertex);
+        }
+        for normal in &mut self.normals {
+            *normal = convention.convert(*normal);
+        }
+        if convention.mirrors() {
+            for face in &mut self.faces {
+                face.swap(1, 2);
+            }
+        }
+    }
+
+    /// A group of one triangle per face, all of the given material. They're `SmoothTriangle`s
+    /// blending the vertex normals when there's one per vertex, flat `Triangle`s otherwise.
+    pub fn group(&self, material: Material) -> Group {
+        let smooth = self.normals.len() == self.vertices.len();
+        let mut group = Group::new();
+        for face in &self.faces {
+            let [a, b, c] = face.map(|i| self.vertices[i]);
+            let object: Object = if smooth {
+                let [na, nb, nc] = face.map(|i| self.normals[i]);
+                SmoothTriangle::new(a, b, c, na, nb, nc).into()
+            } else {
+                Triangle::new(a, b, c).into()
+            };
+            group.add_object(object);
+        }
+        for object in group.objects_mut() {
+            object.set_material(material);
+        }
+        group
+    }
+}
+
+/// Bilinear filtered brightness of the image at (u, v)
+fn sample_luminance(texture: &Canvas, u: f64, v: f64) -> f64 {
+    let (width, height) = (texture.width(), texture.height());
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+    let (px, py) = (
+        u.clamp(0.0, 1.0) * (width - 1) as f64,
+        v.clamp(0.0, 1.0) * (height - 1) as f64,
+    );
+    let (x0, y0) = (px.floor() as usize, py.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (px - x0 as f64, py - y0 as f64);
+    let at = |x, y| texture.get_pixel(x, y).map_or(0.0, |c| c.luminance());
+    let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+    let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::coordinates::Convention;
+    use std::f64::consts::{FRAC_PI_4, PI};
+
+    use crate::light::Material;
+    use crate::mesh::{Mesh, NormalWeighting};
+    use crate::rays::Ray;
+    use crate::shape::{Object, Shape};
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn tessellating_a_plane() {
+        let plane = Mesh::plane(4);
+        assert_eq!(plane.vertices.len(), 25);
+        assert_eq!(plane.faces.len(), 32);
+        for face in 0..plane.faces.len() {
+            assert_eq!(plane.face_normal(face), Vector::new(0.0, 1.0, 0.0));
+        }
+        assert_eq!(plane.bounds().min(), Point::new_point(-1.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn rendering_a_mesh_as_triangles() {
+        let mut plane = Mesh::plane(2);
+        let shiny = Material {
+            specular: 1.0,
+            ..Material::new()
+        };
+        let smooth = plane.group(shiny);
+        assert_eq!(smooth.objects().len(), 8);
+        assert!(smooth
+            .objects()
+            .iter()
+            .all(|o| matches!(o, Object::SmoothTriangle(_))));
+        assert_eq!(smooth.objects()[0].material().specular, 1.0);
+        let down = Ray::new(Point::new_point(0.3, 1.0, 0.6), Vector::new(0.0, -1.0, 0.0));
+        let xs = smooth.intersect(&down);
+        assert_eq!(xs.size(), 1);
+        assert_eq!(xs[0].t, 1.0);
+
+        plane.normals.clear();
+        let flat = plane.group(shiny);
+        assert!(flat
+            .objects()
+            .iter()
+            .all(|o| matches!(o, Object::Triangle(_))));
+    }
+
+    #[test]
+    fn tessellating_a_triangle() {
+        let t = Mesh::triangle(
+            Point::new_point(0.0, 0.0, 0.0),
+            Point::new_point(3.0, 0.0, 0.0),
+            Point::new_point(0.0, 3.0, 0.0),
+            3,
+        );
+        assert_eq!(t.vertices.len(), 10);
+        assert_eq!(t.faces.len(), 9);
+        assert!(t.normals.iter().all(|n| *n == Vector::new(0.0, 0.0, 1.0)));
+        assert!(t.vertices.contains(&Point::new_point(1.0, 1.0, 0.0)));
+    }
+
+    /// Two squares folded along the x axis, one flat and one standing up behind it
+    fn folded() -> Mesh {
+        Mesh {
+            vertices: vec![
+                Point::new_point(0.0, 0.0, 0.0),
+                Point::new_point(1.0, 0.0, 0.0),
+                Point::new_point(0.0, 0.0, -1.0),
+                Point::new_point(1.0, 0.0, -1.0),
+                Point::new_point(0.0, 1.0, 0.0),
+                Point::new_point(1.0, 1.0, 0.0),
+            ],
+            faces: vec![[0, 1, 2], [2, 1, 3], [0, 4, 1], [1, 4, 5]],
+            ..Mesh::new()
+        }
+    }
+
+    #[test]
+    fn creases_stay_sharp() {
+        let mut mesh = folded();
+        mesh.generate_normals(FRAC_PI_4, NormalWeighting::Area);
+        // the two vertices on the fold are split in two
+        assert_eq!(mesh.vertices.len(), 8);
+        for (face, expected) in [
+            (0, Vector::new(0.0, 1.0, 0.0)),
+            (3, Vector::new(0.0, 0.0, -1.0)),
+        ] {
+            for vertex in mesh.faces[face] {
+                assert_eq!(mesh.normals[vertex], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_edges_are_averaged() {
+        let mut mesh = folded();
+        mesh.generate_normals(PI, NormalWeighting::Area);
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.normals[0], Vector::new(0.0, 1.0, -1.0).normalize());
+    }
+
+    #[test]
+    fn weighting_by_area_or_angle() {
+        // a long thin face facing up and a small wide one facing toward -z, meeting at the origin
+        let mesh = Mesh {
+            vertices: vec![
+                Point::new_point(0.0, 0.0, 0.0),
+                Point::new_point(10.0, 0.0, 0.0),
+                Point::new_point(10.0, 0.0, -1.0),
+                Point::new_point(0.0, 1.0, 0.0),
+                Point::new_point(-1.0, 0.0, 0.0),
+            ],
+            faces: vec![[0, 1, 2], [0, 4, 3]],
+            ..Mesh::new()
+        };
+        let normal_at_origin = |weighting| {
+            let mut mesh = mesh.clone();
+            mesh.generate_normals(PI, weighting);
+            mesh.normals[0]
+        };
+        let by_area = normal_at_origin(NormalWeighting::Area);
+        assert!(by_area.y > -by_area.z);
+        let by_angle = normal_at_origin(NormalWeighting::Angle);
+        assert!(by_angle.y < -by_angle.z);
+    }
+
+    #[test]
+    fn displacing_a_plane() {
+        let mut plane = Mesh::plane(2);
+        // a ramp rising along x
+        plane.displace(|point, _| point.x + 1.0);
+        assert_eq!(plane.vertices[0], Point::new_point(-1.0, 0.0, -1.0));
+        assert_eq!(plane.vertices[2], Point::new_point(1.0, 2.0, -1.0));
+        let tilted = Vector::new(-1.0, 1.0, 0.0).normalize();
+        assert!(plane.normals.iter().all(|n| *n == tilted));
+    }
+
+    #[test]
+    fn displacing_by_a_texture() {
+        let mut texture = Canvas::new(2, 1);
+        texture.write_pixel(1, 0, Color::WHITE);
+        let mut plane = Mesh::plane(2);
+        plane.displace_by_texture(&texture, 0.5);
+        let heights: Vec<f64> = plane.vertices[..3].iter().map(|p| p.y).collect();
+        assert_eq!(heights, vec![0.0, 0.25, 0.5]);
+    }
+
+    #[test]
+    fn converting_a_blender_floor() {
+        // the floor in Blender is the xy plane, facing up along z
+        let mut floor = Mesh::triangle(
+            Point::new_point(0.0, 0.0, 0.0),
+            Point::new_point(1.0, 0.0, 0.0),
+            Point::new_point(0.0, 1.0, 0.0),
+            2,
+        );
+        floor.convert_from(Convention::BLENDER);
+        assert!(floor.vertices.iter().all(|v| v.y == 0.0));
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert!(floor.normals.iter().all(|n| *n == up));
+        for face in 0..floor.faces.len() {
+            assert_eq!(floor.face_normal(face), up);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Where the time goes in a render, tile by tile.
+//!
+//! `Camera::render_with_metrics` records how long each tile took, how many rays it cast and
+//! how many samples it took, which can be exported as CSV or JSON for charting.
+
+use std::cell::Cell;
+use std::fmt::Write as _;
+use std::fs;
+use std::time::Duration;
+
+use crate::camera::Tile;
+use crate::error::Result;
+
+thread_local! {
+    /// Rays traced through the world on this thread, tiles read it before and after rendering
+    static RAYS_CAST: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Counts a ray traced through the world, called for every `World::intersect`
+pub(crate) fn count_ray() {
+    count_rays(1);
+}
+
+/// Counts a whole packet of rays
+pub(crate) fn count_rays(n: u64) {
+    RAYS_CAST.with(|rays| rays.set(rays.get() + n));
+}
+
+/// Rays traced on the current thread so far
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn rays_cast() -> u64 {
+    RAYS_CAST.with(|rays| rays.get())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileMetrics {
+    pub tile: Tile,
+    pub duration: Duration,
+    /// Every ray traced through the world, primary rays and any secondary ones they spawned
+    pub rays: u64,
+    /// Camera rays, `Camera::samples` per pixel
+    pub samples: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderMetrics {
+    /// Wall clock time of the whole render, less than the sum of the tiles when rendered in parallel
+    pub duration: Duration,
+    pub tiles: Vec<TileMetrics>,
+}
+
+impl RenderMetrics {
+    pub fn rays(&self) -> u64 {
+        self.tiles.iter().map(|t| t.rays).sum()
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.tiles.iter().map(|t| t.samples).sum()
+    }
+
+    /// One line per tile, times in seconds
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("x,y,width,height,seconds,rays,samples\n");
+        for t in &self.tiles {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                t.tile.x,
+                t.tile.y,
+                t.tile.width,
+                t.tile.height,
+                t.duration.as_secs_f64(),
+                t.rays,
+                t.samples
+            );
+        }
+        csv
+    }
+
+    /// Totals and one object per tile, times in seconds
+    pub fn to_json(&self) -> String {
+        let tiles: Vec<String> = self
+            .tiles
+            .iter()
+            .map(|t| {
+                format!(
+                    "{{\"x\":{},\"y\":{},\"width\":{},\"height\":{},\"seconds\":{},\"rays\":{},\"samples\":{}}}",
+                    t.tile.x,
+                    t.tile.y,
+                    t.tile.width,
+                    t.tile.height,
+                    t.duration.as_secs_f64(),
+                    t.rays,
+                    t.samples
+                )
+            })
+            .collect();
+        format!(
+            "{{\"seconds\":{},\"rays\":{},\"samples\":{},\"tiles\":[{}]}}",
+            self.duration.as_secs_f64(),
+            self.rays(),
+            self.samples(),
+            tiles.join(",")
+        )
+    }
+
+    /// Writes the metrics as JSON when the filename ends in `.json`, CSV otherwise
+    pub fn write(&self, filename: &str) -> Result<()> {
+        let content = if filename.ends_with(".json") {
+            self.to_json()
+        } else {
+            self.to_csv()
+        };
+        fs::write(filename, content)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::time::Duration;
+
+    use crate::camera::{Camera, Tile};
+    use crate::metrics::{RenderMetrics, TileMetrics};
+    use crate::world::default_world;
+
+    #[test]
+    fn metrics_of_a_render() {
+        let camera = Camera::new(20, 10, PI / 2.0);
+        let world = default_world();
+        let hits = (0..10)
+            .flat_map(|y| (0..20).map(move |x| (x, y)))
+            .filter(|&(x, y)| world.first_hit(&camera.ray_for_pixel(x, y)).is_some())
+            .count() as u64;
+        let (canvas, metrics) = camera.render_with_metrics(&world);
+        assert_eq!(metrics.tiles.len(), camera.tiles().len());
+        assert_eq!(metrics.samples(), 200);
+        // a camera ray per pixel, and a shadow ray toward the light from each hit
+        assert!(hits > 0);
+        assert_eq!(metrics.rays(), 200 + hits);
+        assert_eq!(metrics.tiles[1].samples, 4 * 10);
+        assert_eq!(
+            canvas.get_pixel(10, 5),
+            camera.render(&default_world()).get_pixel(10, 5)
+        );
+    }
+
+    #[test]
+    fn exporting_metrics() {
+        let metrics = RenderMetrics {
+            duration: Duration::from_millis(1500),
+            tiles: vec![TileMetrics {
+                tile: Tile {
+                    x: 16,
+                    y: 0,
+                    width: 4,
+                    height: 2,
+                },
+                duration: Duration::from_millis(250),
+                rays: 12,
+                samples: 8,
+            }],
+        };
+        assert_eq!(
+            metrics.to_csv(),
+            "x,y,width,height,seconds,rays,samples\n16,0,4,2,0.25,12,8\n"
+        );
+        assert_eq!(
+            metrics.to_json(),
+            "{\"seconds\":1.5,\"rays\":12,\"samples\":8,\"tiles\":[{\"x\":16,\"y\":0,\"width\":4,\"height\":2,\"seconds\":0.25,\"rays\":12,\"samples\":8}]}"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Ken Perlin's improved noise, for patterns that look grown rather than drawn.
+//!
+//! `noise` varies smoothly through space, about one bump per unit, and is 0 at every whole
+//! numbered point. `turbulence` stacks it at finer and finer scales for marble veins and
+//! other rough detail. The permutation is Perlin's own, so the same point always gets the
+//! same value.
+
+use crate::tuple::Point;
+
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn hash(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+/// Eases 0 to 1 with no kink at either end, 6t^5 - 15t^4 + 10t^3
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// The dot product of one of 12 edge directions of a cube, picked by `hash`, with x, y, z
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Smooth noise at `point`, between -1 and 1
+pub fn noise(point: Point) -> f64 {
+    let (xf, yf, zf) = (point.x.floor(), point.y.floor(), point.z.floor());
+    // the unit cube the point is in, wrapped onto the table
+    let (xi, yi, zi) = (
+        (xf as i64 & 255) as usize,
+        (yf as i64 & 255) as usize,
+        (zf as i64 & 255) as usize,
+    );
+    // and where in it
+    let (x, y, z) = (point.x - xf, point.y - yf, point.z - zf);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = hash(xi) + yi;
+    let aa = hash(a) + zi;
+    let ab = hash(a + 1) + zi;
+    let b = hash(xi + 1) + yi;
+    let ba = hash(b) + zi;
+    let bb = hash(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(hash(aa), x, y, z), grad(hash(ba), x - 1.0, y, z)),
+            lerp(
+                u,
+                grad(hash(ab), x, y - 1.0, z),
+                grad(hash(bb), x - 1.0, y - 1.0, z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(hash(aa + 1), x, y, z - 1.0),
+                grad(hash(ba + 1), x - 1.0, y, z - 1.0),
+            ),
+            lerp(
+                u,
+                grad(hash(ab + 1), x, y - 1.0, z - 1.0),
+                grad(hash(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+/// `noise` folded to its size and added up over `octaves` scales, each twice as fine and
+/// half as strong as the last. Zero or more, and below 2 however many octaves.
+pub fn turbulence(point: Point, octaves: u32) -> f64 {
+    let mut sum = 0.0;
+    let mut scale = 1.0;
+    for _ in 0..octaves {
+        let p = Point::new_point(point.x * scale, point.y * scale, point.z * scale);
+        sum += noise(p).abs() / scale;
+        scale *= 2.0;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::noise::{noise, turbulence};
+    use crate::tuple::Point;
+
+    #[test]
+    fn noise_is_zero_on_the_lattice() {
+        for (x, y, z) in [(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (-4.0, 7.0, -300.0)] {
+            assert_eq!(noise(Point::new_point(x, y, z)), 0.0);
+        }
+    }
+
+    #[test]
+    fn noise_is_smooth_and_bounded() {
+        let mut varies = false;
+        for i in 0..1000 {
+            let t = i as f64 * 0.037;
+            let p = Point::new_point(t, t * 0.7 - 3.0, 11.0 - t * 1.3);
+            let q = Point::new_point(p.x + 0.001, p.y, p.z);
+            let n = noise(p);
+            assert!((-1.0..=1.0).contains(&n));
+            // nearby points get nearby values
+            assert!((n - noise(q)).abs() < 0.01);
+            varies |= n.abs() > 0.1;
+        }
+        assert!(varies);
+    }
+
+    #[test]
+    fn turbulence_adds_octaves() {
+        let p = Point::new_point(0.3, 1.7, -2.2);
+        assert_eq!(turbulence(p, 0), 0.0);
+        assert_eq!(turbulence(p, 1), noise(p).abs());
+        assert!(turbulence(p, 6) >= turbulence(p, 1));
+        assert!(turbulence(p, 6) < 2.0);
+    }
+}
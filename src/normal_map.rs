@@ -0,0 +1,153 @@
+//! Normal maps: bumps, dents and grooves on a surface from an image, without the geometry
+//! for them.
+//!
+//! Each pixel is a normal in tangent space: red along the direction the surface's u grows,
+//! green along v and blue straight out of the surface, 0 to 1 in the image standing for -1 to
+//! 1, the way normal maps are usually baked (flat is (0.5, 0.5, 1)). The image is wrapped
+//! around the shape by its uv map like a uv pattern, see `Shape::uv_at`.
+//!
+//! Materials are `Copy` and can't hold an image, so the maps live in `World::normal_maps` and
+//! a material picks one with its `normal_map` index.
+
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::Result;
+use crate::shape::Shape;
+use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct NormalMap {
+    image: Arc<Canvas>,
+    /// How much the map tilts normals, 1 as baked, 0 for a flat surface and above 1 to
+    /// exaggerate the bumps
+    pub strength: f64,
+}
+
+impl NormalMap {
+    /// The image is shared so copies of the world don't copy it
+    pub fn new(image: Arc<Canvas>) -> Self {
+        NormalMap {
+            image,
+            strength: 1.0,
+        }
+    }
+
+    /// Reads the map from a .png or a PPM, see `Canvas::read`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(filename: &str) -> Result<NormalMap> {
+        Ok(NormalMap::new(Arc::new(Canvas::read(filename)?)))
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    /// The normal at `u`, `v` in tangent space: x along u, y along v and z out of the surface
+    pub fn tangent_normal(&self, u: f64, v: f64) -> Vector {
+        let color = self.image.sample_uv(u, v);
+        Vector::new(
+            (color.red() * 2.0 - 1.0) * self.strength,
+            (color.green() * 2.0 - 1.0) * self.strength,
+            color.blue() * 2.0 - 1.0,
+        )
+        .normalize()
+    }
+
+    /// `normal`, the normal of `shape` at `world_point` in world space, bent by the map.
+    /// Left as it is where the uv map has no direction, at the poles of a sphere.
+    pub fn normal_at<S: Shape + ?Sized>(
+        &self,
+        shape: &S,
+        world_point: Point,
+        normal: Vector,
+    ) -> Vector {
+        let object_point = shape.inverse_transform() * world_point;
+        let (u, v) = shape.uv_at(object_point);
+        let (tangent, bitangent) = shape.uv_tangents(object_point);
+        // directions along the surface move with the shape, unlike normals
+        let to_world = |direction: Vector| {
+            let mut moved = shape.transform() * direction;
+            moved.w = 0.0;
+            moved
+        };
+        let tangent = to_world(tangent);
+        let tangent = tangent - normal * normal.dot(&tangent);
+        if tangent.magnitude() < EPSILON {
+            return normal;
+        }
+        let tangent = tangent.normalize();
+        let mut bitangent_across = normal.cross(&tangent);
+        if bitangent_across.dot(&to_world(bitangent)) < 0.0 {
+            bitangent_across = -bitangent_across;
+        }
+        let bent = self.tangent_normal(u, v);
+        (tangent * bent.x + bitangent_across * bent.y + normal * bent.z).normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::cube::Cube;
+    use crate::matrix_transformations::rotation_y;
+    use crate::normal_map::NormalMap;
+    use crate::tuple::{Point, Vector};
+
+    fn filled(color: Color) -> Arc<Canvas> {
+        let mut image = Canvas::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            image.write_pixel(x, y, color);
+        }
+        Arc::new(image)
+    }
+
+    fn close(a: Vector, b: Vector) -> bool {
+        (a - b).magnitude() < 1e-9
+    }
+
+    #[test]
+    fn flat_maps_keep_the_normal() {
+        let map = NormalMap::new(filled(Color::new(0.5, 0.5, 1.0)));
+        let cube = Cube::new();
+        let normal = Vector::new(0.0, 0.0, 1.0);
+        let bent = map.normal_at(&cube, Point::new_point(0.2, 0.3, 1.0), normal);
+        assert!(close(bent, normal));
+    }
+
+    #[test]
+    fn maps_tilt_normals_along_the_surface() {
+        // tilted 45 degrees toward where u grows
+        let map = NormalMap::new(filled(Color::new(1.0, 0.5, 1.0)));
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let mut cube = Cube::new();
+        let point = Point::new_point(0.2, 0.3, 1.0);
+        let bent = map.normal_at(&cube, point, Vector::new(0.0, 0.0, 1.0));
+        assert!(close(bent, Vector::new(h, 0.0, h)));
+
+        // and turn with the shape, the front face now facing +x
+        cube.set_transform(rotation_y(std::f64::consts::FRAC_PI_2))
+            .unwrap();
+        let bent = map.normal_at(
+            &cube,
+            Point::new_point(1.0, 0.3, -0.2),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        assert!(close(bent, Vector::new(h, 0.0, -h)));
+
+        let mut flattened = map.clone();
+        flattened.strength = 0.0;
+        let bent = flattened.normal_at(
+            &cube,
+            Point::new_point(1.0, 0.3, -0.2),
+            Vector::new(1.0, 0.0, 0.0),
+        );
+        assert!(close(bent, Vector::new(1.0, 0.0, 0.0)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::matrix::M4x4;
+use crate::matrix_transformations::view_transform;
+use crate::tuple::{Point, Vector};
+
+/// Keeps the pitch just short of straight up/down, where the up vector would line up
+/// with the view direction and the view transform degenerates
+const MAX_PITCH: f64 = FRAC_PI_2 - 0.01;
+
+/// Closest the eye can get to the target when zooming in
+const MIN_DISTANCE: f64 = 0.01;
+
+/// A camera orbiting around a target point, driven by mouse/keyboard style input.
+///
+/// Yaw turns around the y axis and pitch tilts up and down, both in radians.
+/// With both at 0 the eye sits `distance` units in front of the target on -z, looking toward +z.
+/// `Preview` feeds it terminal input, this only turns input into a view transform:
+///
+/// - mouse drag: `orbit`
+/// - shift/middle drag: `pan`
+/// - scroll: `zoom`
+/// - WASD: `walk`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitCamera {
+    pub target: Point,
+    pub distance: f64,
+    pub yaw: f64,
+    pub pitch: f64,
+}
+
+impl OrbitCamera {
+    /// Orbit camera with the eye at `from` looking at `to`
+    pub fn new(from: Point, to: Point) -> Self {
+        let offset = from - to;
+        let distance = offset.magnitude().max(MIN_DISTANCE);
+        OrbitCamera {
+            target: to,
+            distance,
+            yaw: offset.x.atan2(-offset.z),
+            pitch: (offset.y / distance).clamp(-1.0, 1.0).asin(),
+        }
+    }
+
+    pub fn eye(&self) -> Point {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        self.target
+            + Vector::new(cos_pitch * sin_yaw, sin_pitch, -cos_pitch * cos_yaw) * self.distance
+    }
+
+    /// Direction the camera looks in
+    pub fn forward(&self) -> Vector {
+        (self.target - self.eye()).normalize()
+    }
+
+    /// Direction to the right of the view, always level with the ground
+    pub fn right(&self) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+            .cross(&self.forward())
+            .normalize()
+    }
+
+    /// Swings the eye around the target
+    pub fn orbit(&mut self, delta_yaw: f64, delta_pitch: f64) {
+        self.yaw += delta_yaw;
+        self.pitch = (self.pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Slides eye and target together across the view. The deltas are in fractions of
+    /// the distance to the target, so panning feels the same zoomed in or out.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        let up = self.right().cross(&self.forward());
+        self.target = self.target + (self.right() * dx + up * dy) * self.distance;
+    }
+
+    /// Moves toward (factor < 1) or away from (factor > 1) the target
+    pub fn zoom(&mut self, factor: f64) {
+        self.distance = (self.distance * factor).max(MIN_DISTANCE);
+    }
+
+    /// Moves eye and target along the ground, forward/back and sideways, in world units
+    pub fn walk(&mut self, forward: f64, right: f64) {
+        let ahead = self.forward();
+        let level = Vector::new(ahead.x, 0.0, ahead.z);
+        let level = if level.magnitude() > 0.0 {
+            level.normalize()
+        } else {
+            level
+        };
+        self.target = self.target + level * forward + self.right() * right;
+    }
+
+    pub fn view_transform(&self) -> M4x4 {
+        view_transform(self.eye(), self.target, Vector::new(0.0, 1.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::matrix_transformations::view_transform;
+    use crate::orbit::OrbitCamera;
+    use crate::tuple::{Point, Vector};
+    use crate::utils::equal_f64;
+
+    #[test]
+    fn orbit_camera_round_trips_eye() {
+        let from = Point::new_point(1.0, 1.5, -5.0);
+        let to = Point::new_point(0.0, 1.0, 0.0);
+        let orbit = OrbitCamera::new(from, to);
+        assert_eq!(orbit.eye(), from);
+        assert_eq!(
+            orbit.view_transform(),
+            view_transform(from, to, Vector::new(0.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn orbiting_keeps_distance_and_clamps_pitch() {
+        let mut orbit = OrbitCamera::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+        );
+        orbit.orbit(FRAC_PI_2, 0.0);
+        assert_eq!(orbit.eye(), Point::new_point(5.0, 0.0, 0.0));
+
+        orbit.orbit(0.0, 10.0);
+        assert!(orbit.pitch < FRAC_PI_2);
+        assert!(equal_f64((orbit.eye() - orbit.target).magnitude(), 5.0));
+    }
+
+    #[test]
+    fn panning_zooming_and_walking() {
+        let mut orbit = OrbitCamera::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Point::new_point(0.0, 0.0, 0.0),
+        );
+        orbit.pan(0.2, 0.0);
+        assert_eq!(orbit.target, Point::new_point(1.0, 0.0, 0.0));
+        assert_eq!(orbit.eye(), Point::new_point(1.0, 0.0, -5.0));
+
+        orbit.zoom(0.5);
+        assert_eq!(orbit.eye(), Point::new_point(1.0, 0.0, -2.5));
+
+        orbit.walk(1.0, -1.0);
+        assert_eq!(orbit.target, Point::new_point(0.0, 0.0, 1.0));
+    }
+}
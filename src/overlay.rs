@@ -0,0 +1,228 @@
+//! Debug drawings on top of rendered images.
+//!
+//! `draw_bounding_boxes` outlines the box around every object and group, to see how tight
+//! they are and why a ray wasn't culled where it should have been.
+
+use crate::bounds::BoundingBox;
+use crate::camera::{Camera, NEAR_PLANE};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::group::Group;
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::shape::Shape;
+use crate::tuple::Point;
+use crate::world::World;
+
+pub const OBJECT_BOX_COLOR: Color = Color::GREEN;
+pub const GROUP_BOX_COLOR: Color = Color::MAGENTA;
+
+/// Corner pairs of `BoundingBox::corners` joined by an edge
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Draws the wireframe box of every object (`OBJECT_BOX_COLOR`) and group (`GROUP_BOX_COLOR`)
+/// in the world onto a canvas rendered by `camera`
+pub fn draw_bounding_boxes(canvas: &mut Canvas, camera: &Camera, world: &World) {
+    for object in &world.objects {
+        draw_box(canvas, camera, &object.bounds(), OBJECT_BOX_COLOR);
+    }
+    for group in &world.groups {
+        draw_group(canvas, camera, group, IDENTITY_MATRIX_4X4);
+    }
+}
+
+/// `parent` takes the group's own space to world space
+fn draw_group(canvas: &mut Canvas, camera: &Camera, group: &Group, parent: M4x4) {
+    let to_world = parent * group.transform();
+    for object in group.objects() {
+        draw_box(
+            canvas,
+            camera,
+            &object.bounds().transformed(&to_world),
+            OBJECT_BOX_COLOR,
+        );
+    }
+    for subgroup in group.groups() {
+        draw_group(canvas, camera, subgroup, to_world);
+    }
+    // drawn last so it shows where it overlaps the boxes inside it
+    draw_box(
+        canvas,
+        camera,
+        &group.bounds().transformed(&parent),
+        GROUP_BOX_COLOR,
+    );
+}
+
+pub fn draw_box(canvas: &mut Canvas, camera: &Camera, bounds: &BoundingBox, color: Color) {
+    if bounds.is_empty() {
+        return;
+    }
+    let corners = bounds.corners();
+    for (a, b) in EDGES {
+        draw_edge(canvas, camera, corners[a], corners[b], color);
+    }
+}
+
+/// Draws the part of the edge in front of the camera
+fn draw_edge(canvas: &mut Canvas, camera: &Camera, a: Point, b: Point, color: Color) {
+    // a little past the near plane so clipped ends still project
+    let near = -2.0 * NEAR_PLANE;
+    let depth = |p: Point| (camera.transform() * p).z;
+    let (za, zb) = (depth(a), depth(b));
+    if za > near && zb > near {
+        return;
+    }
+    let clip = |from: Point, to: Point, z_from: f64, z_to: f64| {
+        from + (to - from) * ((near - z_from) / (z_to - z_from))
+    };
+    let (a, b) = match (za > near, zb > near) {
+        (true, _) => (clip(a, b, za, zb), b),
+        (_, true) => (a, clip(b, a, zb, za)),
+        _ => (a, b),
+    };
+    if let (Some(from), Some(to)) = (camera.project(a), camera.project(b)) {
+        draw_line(canvas, from, to, color);
+    }
+}
+
+/// Draws a line between two points in pixels, skipping whatever falls off the canvas
+pub fn draw_line(canvas: &mut Canvas, from: (f64, f64), to: (f64, f64), color: Color) {
+    let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+    let Some((from, to)) = clip_to_rect(from, to, width, height) else {
+        return;
+    };
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        if (0.0..width).contains(&x) && (0.0..height).contains(&y) {
+            canvas.write_pixel(x as usize, y as usize, color);
+        }
+    }
+}
+
+/// Liang-Barsky clipping of the segment to 0..width, 0..height
+fn clip_to_rect(
+    from: (f64, f64),
+    to: (f64, f64),
+    width: f64,
+    height: f64,
+) -> Option<((f64, f64), (f64, f64))> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-dx, from.0),
+        (dx, width - from.0),
+        (-dy, from.1),
+        (dy, height - from.1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some((
+        (from.0 + dx * t0, from.1 + dy * t0),
+        (from.0 + dx * t1, from.1 + dy * t1),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::camera::Camera;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::group::Group;
+    use crate::matrix_transformations::{scaling, translation, view_transform};
+    use crate::overlay::{draw_bounding_boxes, draw_line, GROUP_BOX_COLOR, OBJECT_BOX_COLOR};
+    use crate::rays::Sphere;
+    use crate::tuple::{Point, Vector};
+    use crate::world::World;
+
+    #[test]
+    fn projecting_points() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let (x, y) = c.project(Point::new_point(0.0, 0.0, -5.0)).unwrap();
+        assert!((x - 100.5).abs() < 1e-9 && (y - 50.5).abs() < 1e-9);
+        assert!(c.project(Point::new_point(0.0, 0.0, 5.0)).is_none());
+        let through_corner = c.ray_for_pixel(0, 0);
+        let (x, y) = c.project(through_corner.position(3.0)).unwrap();
+        assert!((x - 0.5).abs() < 1e-9 && (y - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_canvas() {
+        let mut canvas = Canvas::new(10, 5);
+        draw_line(&mut canvas, (-100.0, 2.5), (100.0, 2.5), Color::RED);
+        for x in 0..10 {
+            assert_eq!(canvas.get_pixel(x, 2), Some(Color::RED));
+        }
+        assert_eq!(canvas.get_pixel(0, 3), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn boxes_are_drawn_around_objects_and_groups() {
+        let mut w = World::new();
+        w.objects.push(Sphere::new().into());
+        let mut g = Group::new();
+        g.set_transform(translation(3.0, 0.0, 0.0)).unwrap();
+        g.add_object(Sphere::new());
+        w.groups.push(g);
+
+        let mut c = Camera::new(40, 20, FRAC_PI_2);
+        c.set_transform(view_transform(
+            Point::new_point(1.5, 0.0, -6.0),
+            Point::new_point(1.5, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ))
+        .unwrap();
+        let mut canvas = Canvas::new(40, 20);
+        draw_bounding_boxes(&mut canvas, &c, &w);
+        let colored = |color| {
+            (0..40)
+                .flat_map(|x| (0..20).map(move |y| (x, y)))
+                .filter(|&(x, y)| canvas.get_pixel(x, y) == Some(color))
+                .count()
+        };
+        assert!(colored(OBJECT_BOX_COLOR) > 20);
+        assert!(colored(GROUP_BOX_COLOR) > 20);
+        // the middle of the sphere's box stays clear
+        assert_eq!(canvas.get_pixel(15, 10), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn boxes_around_the_camera_are_clipped() {
+        let mut w = World::new();
+        let mut s = Sphere::new();
+        s.set_transform(scaling(10.0, 10.0, 10.0)).unwrap();
+        w.objects.push(s.into());
+        let c = Camera::new(20, 20, FRAC_PI_2);
+        let mut canvas = Canvas::new(20, 20);
+        draw_bounding_boxes(&mut canvas, &c, &w);
+        assert_eq!(canvas.get_pixel(10, 10), Some(Color::BLACK));
+    }
+}
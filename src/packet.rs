@@ -0,0 +1,310 @@
+//! Many rays intersected with the scene at once.
+//!
+//! A `RayPacket` stores its rays as structure-of-arrays, one array per coordinate, so the
+//! intersection loops run over contiguous lanes the compiler can vectorize, and each object's
+//! transform is loaded once per packet instead of once per ray. Camera rays through a tile are
+//! the natural packet: they start at the same point and point in similar directions.
+//!
+//! `World::intersect_packet` finds the same hits as `hit(&world.intersect(ray))` one ray at a
+//! time, which stays the reference the packet path is tested against.
+
+use crate::rays::{Intersection, Ray, Sphere, TANGENT_MAX_RELATIVE};
+use crate::shape::{Object, Shape};
+use crate::tuple::{Point, Vector};
+use crate::utils::equal_f64_relative;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RayPacket {
+    origin: [Vec<f64>; 3],
+    direction: [Vec<f64>; 3],
+}
+
+impl RayPacket {
+    pub fn new() -> Self {
+        RayPacket::default()
+    }
+
+    pub fn push(&mut self, ray: &Ray) {
+        let (o, d) = (ray.origin(), ray.direction());
+        for (axis, (origin, direction)) in
+            [(o.x, d.x), (o.y, d.y), (o.z, d.z)].into_iter().enumerate()
+        {
+            self.origin[axis].push(origin);
+            self.direction[axis].push(direction);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.origin[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The ray in lane `i`
+    pub fn ray(&self, i: usize) -> Ray {
+        Ray::new(
+            Point::new_point(self.origin[0][i], self.origin[1][i], self.origin[2][i]),
+            Vector::new(
+                self.direction[0][i],
+                self.direction[1][i],
+                self.direction[2][i],
+            ),
+        )
+    }
+
+    /// The closest hit of every ray against the objects, by lane
+    pub fn hits(&self, objects: &[Object]) -> Vec<Option<Intersection<Object>>> {
+        let mut hits = vec![None; self.len()];
+        let mut nearest = vec![f64::INFINITY; self.len()];
+        let mut ts = vec![f64::NAN; self.len()];
+        for object in objects {
+            match object {
+                Object::Sphere(sphere) => self.intersect_sphere(sphere, &mut ts),
+                Object::Cube(cube) => self.intersect_each(cube, &mut ts),
+                Object::Triangle(triangle) => self.intersect_each(triangle, &mut ts),
+                Object::SmoothTriangle(smooth) => self.intersect_each(smooth, &mut ts),
+                Object::Quad(quad) => self.intersect_each(quad, &mut ts),
+                Object::Capsule(capsule) => self.intersect_each(capsule, &mut ts),
+            }
+            for (lane, &t) in ts.iter().enumerate() {
+                if t < nearest[lane] {
+                    nearest[lane] = t;
+                    hits[lane] = Some(Intersection::new(t, *object));
+                }
+            }
+        }
+        hits
+    }
+
+    /// Fills `ts` like `intersect_sphere`, one ray at a time, for shapes without a packet
+    /// version of their intersection
+    fn intersect_each(&self, shape: &impl Shape, ts: &mut [f64]) {
+        for (lane, t) in ts.iter_mut().enumerate() {
+            let ray = self.ray(lane).transformed(&shape.inverse_transform());
+            *t = shape
+                .local_intersect(&ray)
+                .iter()
+                .map(|(t, _)| t)
+                .filter(|t| *t >= 0.0)
+                .fold(f64::NAN, f64::min);
+        }
+    }
+
+    /// Fills `ts` with each lane's lowest non-negative t on the sphere, NaN for a miss.
+    /// Same quadratic as `rays::intersect`, tangents included.
+    fn intersect_sphere(&self, sphere: &Sphere, ts: &mut [f64]) {
+        let m = sphere.inverse_transform().matrix;
+        let [ox, oy, oz] = &self.origin;
+        let [dx, dy, dz] = &self.direction;
+        for (lane, t) in ts.iter_mut().enumerate() {
+            let (x, y, z) = (ox[lane], oy[lane], oz[lane]);
+            // origins are points and directions vectors, only the origins pick up the translation
+            let o = [0, 1, 2].map(|r| m[r][0] * x + m[r][1] * y + m[r][2] * z + m[r][3]);
+            let (x, y, z) = (dx[lane], dy[lane], dz[lane]);
+            let d = [0, 1, 2].map(|r| m[r][0] * x + m[r][1] * y + m[r][2] * z);
+
+            let a = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+            let b = 2.0 * (d[0] * o[0] + d[1] * o[1] + d[2] * o[2]);
+            let c = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] - 1.0;
+            let discriminant = b * b - 4.0 * a * c;
+            let tangent = equal_f64_relative(b * b, 4.0 * a * c, TANGENT_MAX_RELATIVE);
+            *t = if tangent {
+                -b / (2.0 * a)
+            } else if discriminant < 0.0 {
+                f64::NAN
+            } else {
+                let root = discriminant.sqrt();
+                let t1 = (-b - root) / (2.0 * a);
+                let t2 = (-b + root) / (2.0 * a);
+                if t1 >= 0.0 {
+                    t1
+                } else {
+                    t2
+                }
+            };
+            if *t < 0.0 {
+                *t = f64::NAN;
+            }
+        }
+    }
+}
+
+impl FromIterator<Ray> for RayPacket {
+    fn from_iter<I: IntoIterator<Item = Ray>>(rays: I) -> Self {
+        let mut packet = RayPacket::new();
+        for ray in rays {
+            packet.push(&ray);
+        }
+        packet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::camera::{Camera, Tile};
+    use uuid::Uuid;
+
+    use crate::clip::ClipPlane;
+    use crate::cube::Cube;
+    use crate::group::Group;
+    use crate::light::Material;
+    use crate::matrix_transformations::{rotation_z, scaling, translation, view_transform};
+    use crate::packet::RayPacket;
+    use crate::rays::{hit, Ray, Sphere};
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+    use crate::world::default_world;
+
+    #[test]
+    fn packet_keeps_its_rays() {
+        let rays = [
+            Ray::new(Point::new_point(1.0, 2.0, 3.0), Vector::new(0.0, 0.0, 1.0)),
+            Ray::new(Point::new_point(-1.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0)),
+        ];
+        let packet: RayPacket = rays.into_iter().collect();
+        assert_eq!(packet.len(), 2);
+        assert_eq!(packet.ray(0), rays[0]);
+        assert_eq!(packet.ray(1), rays[1]);
+        assert!(RayPacket::new().is_empty());
+    }
+
+    #[test]
+    fn packet_hits_match_single_rays() {
+        let mut w = default_world();
+        let mut s = Sphere::new();
+        s.set_transform(translation(1.5, 0.5, -1.0) * scaling(0.5, 0.3, 0.5))
+            .unwrap();
+        w.objects.push(s.into());
+        let mut c = Cube::new();
+        c.set_transform(translation(-1.5, -0.5, 0.0) * scaling(0.4, 0.4, 0.4))
+            .unwrap();
+        w.objects.push(c.into());
+
+        let mut camera = Camera::new(24, 16, PI / 3.0);
+        camera
+            .set_transform(view_transform(
+                Point::new_point(0.0, 1.0, -5.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let packet = camera.tile_rays(&Tile {
+            x: 0,
+            y: 0,
+            width: 24,
+            height: 16,
+        });
+        let hits = w.intersect_packet(&packet);
+        assert_eq!(hits.len(), 24 * 16);
+        assert!(hits.iter().any(|h| h.is_none()));
+        for (lane, packet_hit) in hits.iter().enumerate() {
+            let single = hit(&w.intersect(&packet.ray(lane)));
+            assert_eq!(
+                packet_hit.map(|h| h.object.id()),
+                single.map(|h| h.object.id())
+            );
+            if let (Some(p), Some(s)) = (packet_hit, single) {
+                assert!((p.t - s.t).abs() < 1e-9);
+            }
+        }
+
+        let inside = RayPacket::from_iter([Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+        )]);
+        assert_eq!(w.intersect_packet(&inside)[0].unwrap().t, 0.5);
+    }
+
+    #[test]
+    fn packets_respect_clip_planes() {
+        let mut w = default_world();
+        let mut camera = Camera::new(16, 16, PI / 3.0);
+        camera
+            .set_transform(view_transform(
+                Point::new_point(0.0, 1.0, -5.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let packet = camera.tile_rays(&Tile {
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 16,
+        });
+        let whole = w.intersect_packet(&packet);
+        // cut away the front halves, capped
+        w.clip_planes.push(
+            ClipPlane::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0))
+                .with_cap(Material::new()),
+        );
+        let hits = w.intersect_packet(&packet);
+        assert_ne!(hits, whole);
+        for (lane, packet_hit) in hits.iter().enumerate() {
+            let single = hit(&w.intersect(&packet.ray(lane)));
+            assert_eq!(
+                packet_hit.map(|h| (h.t, h.object.id())),
+                single.map(|h| (h.t, h.object.id()))
+            );
+        }
+    }
+
+    #[test]
+    fn packets_hit_objects_in_nested_groups() {
+        let mut w = default_world();
+        let mut inner = Group::new();
+        inner
+            .set_transform(rotation_z(PI / 4.0) * scaling(0.5, 0.5, 0.5))
+            .unwrap();
+        let mut c = Cube::new();
+        c.set_transform(translation(0.0, 2.0, 0.0)).unwrap();
+        inner.add_object(c);
+        let mut outer = Group::new();
+        outer.set_transform(translation(1.5, 0.0, -1.5)).unwrap();
+        outer.add_object(Sphere::new());
+        outer.add_group(inner);
+        w.groups.push(outer);
+
+        let mut camera = Camera::new(24, 16, PI / 3.0);
+        camera
+            .set_transform(view_transform(
+                Point::new_point(0.0, 1.0, -6.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        let packet = camera.tile_rays(&Tile {
+            x: 0,
+            y: 0,
+            width: 24,
+            height: 16,
+        });
+        let grouped: Vec<Uuid> = w.groups[0]
+            .objects()
+            .iter()
+            .chain(w.groups[0].groups()[0].objects())
+            .map(|object| object.id())
+            .collect();
+        let hits = w.intersect_packet(&packet);
+        for id in &grouped {
+            assert!(hits.iter().flatten().any(|h| h.object.id() == *id));
+        }
+        for (lane, packet_hit) in hits.iter().enumerate() {
+            let single = hit(&w.intersect(&packet.ray(lane)));
+            assert_eq!(
+                packet_hit.map(|h| h.object.id()),
+                single.map(|h| h.object.id())
+            );
+            if let (Some(p), Some(s)) = (packet_hit, single) {
+                assert!((p.t - s.t).abs() < 1e-9);
+                // placed in world space, so the normals come out the same too
+                let point = packet.ray(lane).position(p.t);
+                assert_eq!(p.object.normal_at(point, p), s.object.normal_at(point, &s));
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! A preview window with a side panel of sliders for the lights and materials, so a look can be
+//! tuned without editing the scene file and re-rendering for every tweak.
+//!
+//! The image is rendered progressively by `Preview` and starts over whenever a slider moves or
+//! the camera does: drag to orbit, shift or middle drag to pan, scroll to zoom and WASD to walk,
+//! the same as in the terminal. The panel covers the point lights' position and intensity and
+//! each object's material.
+
+use eframe::egui;
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::light::{Material, PointLight};
+use crate::preview::{Input, Preview};
+use crate::world::World;
+
+/// The image is treated as this many character cells across, so a drag across it turns the
+/// camera as far as one across the terminal preview
+const CELLS_ACROSS: f32 = 80.0;
+
+/// The window's state: the world being tuned and the view of it
+pub struct PanelApp {
+    world: World,
+    preview: Preview,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl PanelApp {
+    /// Previews `world` through `camera`, at the camera's resolution
+    pub fn new(camera: Camera, world: World) -> Result<Self> {
+        Ok(PanelApp {
+            world,
+            preview: Preview::new(camera)?,
+            texture: None,
+        })
+    }
+
+    /// Opens the window and blocks until it's closed
+    pub fn run(self, title: &str) -> Result<()> {
+        eframe::run_native(
+            title,
+            eframe::NativeOptions::default(),
+            Box::new(|_| Ok(Box::new(self))),
+        )
+        .map_err(|error| Error::Window(error.to_string()))
+    }
+
+    /// Feeds the mouse over the image and the keyboard to the preview
+    fn camera_input(&mut self, ui: &egui::Ui, response: &egui::Response) -> Result<()> {
+        let mut inputs = Vec::new();
+        let cell = |pos: egui::Pos2| {
+            let size = response.rect.width() / CELLS_ACROSS;
+            let offset = pos - response.rect.min;
+            (
+                (offset.x / size).max(0.0) as usize,
+                (offset.y / size).max(0.0) as usize,
+            )
+        };
+        if let Some((column, row)) = response.interact_pointer_pos().map(cell) {
+            let pan =
+                ui.input(|i| i.modifiers.shift) || response.dragged_by(egui::PointerButton::Middle);
+            if response.drag_started() {
+                inputs.push(Input::Press { column, row, pan });
+            } else if response.dragged() {
+                inputs.push(Input::Drag { column, row, pan });
+            }
+        }
+        if response.drag_stopped() {
+            inputs.push(Input::Release);
+        }
+        if response.hovered() {
+            let scroll = ui.input(|i| i.raw_scroll_delta.y);
+            if scroll > 0.0 {
+                inputs.push(Input::ScrollUp);
+            } else if scroll < 0.0 {
+                inputs.push(Input::ScrollDown);
+            }
+        }
+        // typing into a slider's number shouldn't walk the camera
+        if !ui.ctx().wants_keyboard_input() {
+            for (key, c) in [
+                (egui::Key::W, 'w'),
+                (egui::Key::A, 'a'),
+                (egui::Key::S, 's'),
+                (egui::Key::D, 'd'),
+            ] {
+                if ui.input(|i| i.key_pressed(key)) {
+                    inputs.push(Input::Key(c));
+                }
+            }
+        }
+        for input in inputs {
+            self.preview.handle(input)?;
+        }
+        Ok(())
+    }
+}
+
+impl eframe::App for PanelApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("controls").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if controls(ui, &mut self.world) {
+                    self.preview.restart();
+                }
+            });
+        });
+        if let Some(canvas) = self.preview.next_pass(&self.world) {
+            let image = color_image(&canvas);
+            match &mut self.texture {
+                Some(texture) => texture.set(image, egui::TextureOptions::NEAREST),
+                None => {
+                    self.texture =
+                        Some(ctx.load_texture("preview", image, egui::TextureOptions::NEAREST))
+                }
+            }
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let Some(texture) = &self.texture else {
+                return;
+            };
+            let image = egui::Image::new(texture)
+                .fit_to_exact_size(ui.available_size())
+                .maintain_aspect_ratio(true)
+                .sense(egui::Sense::click_and_drag());
+            let response = ui.add(image);
+            if let Err(error) = self.camera_input(ui, &response) {
+                ui.label(error.to_string());
+            }
+        });
+        if !self.preview.is_done() {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// The canvas as an egui image, clamped to 0-255 like `Canvas::to_rgba`
+pub fn color_image(canvas: &Canvas) -> egui::ColorImage {
+    egui::ColorImage::from_rgba_unmultiplied([canvas.width(), canvas.height()], &canvas.to_rgba())
+}
+
+/// Sliders for every point light and every object's material, `true` when one of them moved
+pub fn controls(ui: &mut egui::Ui, world: &mut World) -> bool {
+    let mut changed = false;
+    ui.heading("Lights");
+    for (i, light) in world.lights.iter_mut().enumerate() {
+        egui::CollapsingHeader::new(format!("light {}", i))
+            .default_open(i == 0)
+            .show(ui, |ui| changed |= light_controls(ui, light));
+    }
+    ui.heading("Materials");
+    for (i, object) in world.objects.iter_mut().enumerate() {
+        egui::CollapsingHeader::new(format!("object {}", i)).show(ui, |ui| {
+            changed |= material_controls(ui, object.material_mut())
+        });
+    }
+    changed
+}
+
+fn light_controls(ui: &mut egui::Ui, light: &mut PointLight) -> bool {
+    let mut position = light.position();
+    let mut intensity = light.intensity();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("position");
+        for value in [&mut position.x, &mut position.y, &mut position.z] {
+            changed |= ui.add(egui::DragValue::new(value).speed(0.1)).changed();
+        }
+    });
+    // brighter than white is fine for a light
+    changed |= color_sliders(ui, "intensity", &mut intensity, 2.0);
+    if changed {
+        light.set_position(position);
+        light.set_intensity(intensity);
+    }
+    changed
+}
+
+fn material_controls(ui: &mut egui::Ui, material: &mut Material) -> bool {
+    let mut changed = color_sliders(ui, "color", &mut material.color, 1.0);
+    for (label, value) in [
+        ("ambient", &mut material.ambient),
+        ("diffuse", &mut material.diffuse),
+        ("specular", &mut material.specular),
+        ("reflective", &mut material.reflective),
+        ("transparency", &mut material.transparency),
+    ] {
+        changed |= ui
+            .add(egui::Slider::new(value, 0.0..=1.0).text(label))
+            .changed();
+    }
+    changed |= ui
+        .add(
+            egui::Slider::new(&mut material.shininess, 1.0..=400.0)
+                .logarithmic(true)
+                .text("shininess"),
+        )
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut material.refractive_index, 1.0..=3.0).text("refractive index"))
+        .changed();
+    changed
+}
+
+/// A slider per channel from 0 to `max`
+fn color_sliders(ui: &mut egui::Ui, label: &str, color: &mut Color, max: f64) -> bool {
+    let mut rgb = [color.red(), color.green(), color.blue()];
+    let mut changed = false;
+    ui.label(label);
+    for (value, channel) in rgb.iter_mut().zip(["red", "green", "blue"]) {
+        changed |= ui
+            .add(egui::Slider::new(value, 0.0..=max).text(channel))
+            .changed();
+    }
+    if changed {
+        *color = Color::new(rgb[0], rgb[1], rgb[2]);
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui;
+
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::panel::{color_image, controls};
+    use crate::shape::Shape;
+    use crate::world::default_world;
+
+    #[test]
+    fn canvases_become_egui_images() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, Color::new(1.5, 0.5, 0.0));
+        let image = color_image(&canvas);
+        assert_eq!(image.size, [3, 2]);
+        assert_eq!(image.pixels[5], egui::Color32::from_rgb(255, 128, 0));
+        assert_eq!(image.pixels[0], egui::Color32::BLACK);
+    }
+
+    #[test]
+    fn showing_the_panel_leaves_the_world_alone() {
+        let ctx = egui::Context::default();
+        let mut world = default_world();
+        let before = (world.lights.clone(), *world.objects[0].material());
+        for _ in 0..2 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    assert!(!controls(ui, &mut world));
+                });
+            });
+        }
+        assert_eq!(world.lights, before.0);
+        assert_eq!(*world.objects[0].material(), before.1);
+    }
+}
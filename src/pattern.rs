@@ -0,0 +1,662 @@
+//! Patterns color a surface by where on it a point is, instead of one color all over.
+//!
+//! Each pattern alternates or blends between two colors. A pattern sticks to the object it's
+//! on, and has a transform of its own on top of the object's to scale, turn or move it over
+//! the surface.
+//!
+//! `UvCheckers` is laid out on the flat uv square instead and wrapped around the object the
+//! way the object picks, see `Shape::uv_at`.
+//!
+//! `Marble`, `Wood` and `TurbulentStripes` are bent out of shape with Perlin noise (see
+//! `noise`) for materials that look natural without an image. Scale them with their
+//! transform, the noise changes about once per unit.
+
+use std::f64::consts::PI;
+
+use crate::color::Color;
+use crate::error::{Error, Result};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::noise::{noise, turbulence};
+use crate::shape::Shape;
+use crate::tuple::Point;
+use crate::uv::UvMap;
+
+pub trait Pattern {
+    /// The color of the pattern at `point`, in the pattern's own space
+    fn pattern_at(&self, point: Point) -> Color;
+
+    fn transform(&self) -> M4x4;
+
+    fn inverse_transform(&self) -> M4x4;
+
+    /// The color of the pattern on `shape` at `world_point`
+    fn pattern_at_shape<S: Shape + ?Sized>(&self, shape: &S, world_point: Point) -> Color
+    where
+        Self: Sized,
+    {
+        let object_point = shape.inverse_transform() * world_point;
+        self.pattern_at(self.inverse_transform() * object_point)
+    }
+}
+
+/// Octaves of turbulence in `Marble`
+const OCTAVES: u32 = 6;
+
+fn inverted(transform: M4x4) -> Result<M4x4> {
+    transform
+        .inverse()
+        .map_err(|_| Error::DegenerateTransform(Box::new(transform)))
+}
+
+/// Blends from `a` to `b` along x, starting over at every whole number
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+    pub a: Color,
+    pub b: Color,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl Gradient {
+    pub fn new(a: Color, b: Color) -> Self {
+        Gradient {
+            a,
+            b,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for Gradient {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        self.a.lerp(self.b, point.x - point.x.floor())
+    }
+}
+
+/// Rings one unit wide around the y axis, alternating `a` and `b` outward
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ring {
+    pub a: Color,
+    pub b: Color,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl Ring {
+    pub fn new(a: Color, b: Color) -> Self {
+        Ring {
+            a,
+            b,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for Ring {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        if (distance.floor() as i64).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// Unit cubes of `a` and `b`, like a checkerboard in all three directions
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkers {
+    pub a: Color,
+    pub b: Color,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl Checkers {
+    pub fn new(a: Color, b: Color) -> Self {
+        Checkers {
+            a,
+            b,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for Checkers {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let sum = point.x.floor() + point.y.floor() + point.z.floor();
+        if (sum as i64).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// Checkers `width` squares across and `height` squares up the uv square, so they keep their
+/// size wherever the object's uv map puts them
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvCheckers {
+    pub width: f64,
+    pub height: f64,
+    pub a: Color,
+    pub b: Color,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl UvCheckers {
+    pub fn new(width: f64, height: f64, a: Color, b: Color) -> Self {
+        UvCheckers {
+            width,
+            height,
+            a,
+            b,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The color at `u`, `v`
+    pub fn uv_pattern_at(&self, u: f64, v: f64) -> Color {
+        let sum = (u * self.width).floor() + (v * self.height).floor();
+        if (sum as i64).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+impl Pattern for UvCheckers {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    /// Off any object the point is mapped like a sphere's
+    fn pattern_at(&self, point: Point) -> Color {
+        let (u, v) = UvMap::Spherical.map(point);
+        self.uv_pattern_at(u, v)
+    }
+
+    fn pattern_at_shape<S: Shape + ?Sized>(&self, shape: &S, world_point: Point) -> Color {
+        let point = self.inverse_transform * (shape.inverse_transform() * world_point);
+        let (u, v) = shape.uv_at(point);
+        self.uv_pattern_at(u, v)
+    }
+}
+
+/// Soft bands of `a` and `b` across x, bent into veins by `turbulence`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marble {
+    pub a: Color,
+    pub b: Color,
+    /// How far the noise moves the pattern, 2.0 by default
+    pub turbulence: f64,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl Marble {
+    pub fn new(a: Color, b: Color) -> Self {
+        Marble {
+            a,
+            b,
+            turbulence: 2.0,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for Marble {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let x = point.x + self.turbulence * turbulence(point, OCTAVES);
+        self.a.lerp(self.b, 0.5 + 0.5 * (x * PI).sin())
+    }
+}
+
+/// Growth rings around the y axis, blending from `a` out to `b` across each, wobbled by
+/// `turbulence` times the noise
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wood {
+    pub a: Color,
+    pub b: Color,
+    /// How far the noise moves the pattern, 0.3 by default
+    pub turbulence: f64,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl Wood {
+    pub fn new(a: Color, b: Color) -> Self {
+        Wood {
+            a,
+            b,
+            turbulence: 0.3,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for Wood {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let distance =
+            (point.x * point.x + point.z * point.z).sqrt() + self.turbulence * noise(point);
+        self.a.lerp(self.b, distance - distance.floor())
+    }
+}
+
+/// Stripes of `a` and `b` a unit wide across x, their edges shifted by up to `turbulence`
+/// with the noise
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct TurbulentStripes {
+    pub a: Color,
+    pub b: Color,
+    /// How far the noise moves the pattern, 0.5 by default
+    pub turbulence: f64,
+    transform: M4x4,
+    inverse_transform: M4x4,
+}
+
+impl TurbulentStripes {
+    pub fn new(a: Color, b: Color) -> Self {
+        TurbulentStripes {
+            a,
+            b,
+            turbulence: 0.5,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+        }
+    }
+
+    /// Places the pattern on the object, failing on a transform that can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        self.inverse_transform = inverted(transform)?;
+        self.transform = transform;
+        Ok(())
+    }
+}
+
+impl Pattern for TurbulentStripes {
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn pattern_at(&self, point: Point) -> Color {
+        let x = point.x + self.turbulence * noise(point);
+        if (x.floor() as i64).rem_euclid(2) == 0 {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// Any of the patterns, for a material to hold
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub enum MaterialPattern {
+    Gradient(Gradient),
+    Ring(Ring),
+    Checkers(Checkers),
+    UvCheckers(UvCheckers),
+    Marble(Marble),
+    Wood(Wood),
+    TurbulentStripes(TurbulentStripes),
+}
+
+impl Pattern for MaterialPattern {
+    fn pattern_at(&self, point: Point) -> Color {
+        match self {
+            MaterialPattern::Gradient(gradient) => gradient.pattern_at(point),
+            MaterialPattern::Ring(ring) => ring.pattern_at(point),
+            MaterialPattern::Checkers(checkers) => checkers.pattern_at(point),
+            MaterialPattern::UvCheckers(checkers) => checkers.pattern_at(point),
+            MaterialPattern::Marble(marble) => marble.pattern_at(point),
+            MaterialPattern::Wood(wood) => wood.pattern_at(point),
+            MaterialPattern::TurbulentStripes(stripes) => stripes.pattern_at(point),
+        }
+    }
+
+    fn pattern_at_shape<S: Shape + ?Sized>(&self, shape: &S, world_point: Point) -> Color {
+        match self {
+            MaterialPattern::UvCheckers(checkers) => checkers.pattern_at_shape(shape, world_point),
+            _ => self
+                .pattern_at(self.inverse_transform() * (shape.inverse_transform() * world_point)),
+        }
+    }
+
+    fn transform(&self) -> M4x4 {
+        match self {
+            MaterialPattern::Gradient(gradient) => gradient.transform(),
+            MaterialPattern::Ring(ring) => ring.transform(),
+            MaterialPattern::Checkers(checkers) => checkers.transform(),
+            MaterialPattern::UvCheckers(checkers) => checkers.transform(),
+            MaterialPattern::Marble(marble) => marble.transform(),
+            MaterialPattern::Wood(wood) => wood.transform(),
+            MaterialPattern::TurbulentStripes(stripes) => stripes.transform(),
+        }
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        match self {
+            MaterialPattern::Gradient(gradient) => gradient.inverse_transform(),
+            MaterialPattern::Ring(ring) => ring.inverse_transform(),
+            MaterialPattern::Checkers(checkers) => checkers.inverse_transform(),
+            MaterialPattern::UvCheckers(checkers) => checkers.inverse_transform(),
+            MaterialPattern::Marble(marble) => marble.inverse_transform(),
+            MaterialPattern::Wood(wood) => wood.inverse_transform(),
+            MaterialPattern::TurbulentStripes(stripes) => stripes.inverse_transform(),
+        }
+    }
+}
+
+impl MaterialPattern {
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        match self {
+            MaterialPattern::Gradient(gradient) => gradient.set_transform(transform),
+            MaterialPattern::Ring(ring) => ring.set_transform(transform),
+            MaterialPattern::Checkers(checkers) => checkers.set_transform(transform),
+            MaterialPattern::UvCheckers(checkers) => checkers.set_transform(transform),
+            MaterialPattern::Marble(marble) => marble.set_transform(transform),
+            MaterialPattern::Wood(wood) => wood.set_transform(transform),
+            MaterialPattern::TurbulentStripes(stripes) => stripes.set_transform(transform),
+        }
+    }
+}
+
+impl From<Gradient> for MaterialPattern {
+    fn from(gradient: Gradient) -> Self {
+        MaterialPattern::Gradient(gradient)
+    }
+}
+
+impl From<Ring> for MaterialPattern {
+    fn from(ring: Ring) -> Self {
+        MaterialPattern::Ring(ring)
+    }
+}
+
+impl From<Checkers> for MaterialPattern {
+    fn from(checkers: Checkers) -> Self {
+        MaterialPattern::Checkers(checkers)
+    }
+}
+
+impl From<UvCheckers> for MaterialPattern {
+    fn from(checkers: UvCheckers) -> Self {
+        MaterialPattern::UvCheckers(checkers)
+    }
+}
+
+impl From<Marble> for MaterialPattern {
+    fn from(marble: Marble) -> Self {
+        MaterialPattern::Marble(marble)
+    }
+}
+
+impl From<Wood> for MaterialPattern {
+    fn from(wood: Wood) -> Self {
+        MaterialPattern::Wood(wood)
+    }
+}
+
+impl From<TurbulentStripes> for MaterialPattern {
+    fn from(stripes: TurbulentStripes) -> Self {
+        MaterialPattern::TurbulentStripes(stripes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::matrix::IDENTITY_MATRIX_4X4;
+    use crate::matrix_transformations::{scaling, translation};
+    use crate::pattern::{
+        Checkers, Gradient, Marble, MaterialPattern, Pattern, Ring, TurbulentStripes, UvCheckers,
+        Wood,
+    };
+    use crate::rays::Sphere;
+    use crate::tuple::Point;
+    use crate::uv::UvMap;
+
+    const WHITE: Color = Color::WHITE;
+    const BLACK: Color = Color::BLACK;
+
+    #[test]
+    fn gradient_blends_between_colors() {
+        let pattern = Gradient::new(WHITE, BLACK);
+        assert_eq!(pattern.pattern_at(Point::new_point(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(
+            pattern.pattern_at(Point::new_point(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            pattern.pattern_at(Point::new_point(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(Point::new_point(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+        // and starts over past 1
+        assert_eq!(pattern.pattern_at(Point::new_point(1.0, 0.0, 0.0)), WHITE);
+    }
+
+    #[test]
+    fn ring_extends_in_x_and_z() {
+        let pattern = Ring::new(WHITE, BLACK);
+        assert_eq!(pattern.pattern_at(Point::new_point(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(pattern.pattern_at(Point::new_point(1.0, 0.0, 0.0)), BLACK);
+        assert_eq!(pattern.pattern_at(Point::new_point(0.0, 0.0, 1.0)), BLACK);
+        // 0.708 is just past sqrt(2)/2
+        assert_eq!(
+            pattern.pattern_at(Point::new_point(0.708, 0.0, 0.708)),
+            BLACK
+        );
+    }
+
+    #[test]
+    fn checkers_repeat_in_every_direction() {
+        let pattern = Checkers::new(WHITE, BLACK);
+        for (x, y, z) in [(0.0, 0.0, 0.0), (0.99, 0.0, 0.0), (0.0, 0.99, 0.0)] {
+            assert_eq!(pattern.pattern_at(Point::new_point(x, y, z)), WHITE);
+        }
+        for (x, y, z) in [(1.01, 0.0, 0.0), (0.0, 1.01, 0.0), (0.0, 0.0, 1.01)] {
+            assert_eq!(pattern.pattern_at(Point::new_point(x, y, z)), BLACK);
+        }
+        // negative coordinates keep alternating instead of mirroring around 0
+        assert_eq!(pattern.pattern_at(Point::new_point(-0.5, 0.0, 0.0)), BLACK);
+        assert_eq!(pattern.pattern_at(Point::new_point(-1.5, 0.0, 0.0)), WHITE);
+
+        let any = MaterialPattern::from(pattern);
+        assert_eq!(any.pattern_at(Point::new_point(1.01, 0.0, 0.0)), BLACK);
+    }
+
+    #[test]
+    fn patterns_follow_the_object_and_their_own_transform() {
+        let mut object = Sphere::new();
+        object.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let mut pattern = Gradient::new(WHITE, BLACK);
+        let point = Point::new_point(1.5, 0.0, 0.0);
+        // 0.75 of the way across in the sphere's space
+        assert_eq!(
+            pattern.pattern_at_shape(&object, point),
+            Color::new(0.25, 0.25, 0.25)
+        );
+
+        pattern.set_transform(translation(0.5, 0.0, 0.0)).unwrap();
+        assert_eq!(
+            pattern.pattern_at_shape(&object, point),
+            Color::new(0.75, 0.75, 0.75)
+        );
+
+        object.set_transform(IDENTITY_MATRIX_4X4).unwrap();
+        pattern.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        assert_eq!(
+            pattern.pattern_at_shape(&object, point),
+            Color::new(0.25, 0.25, 0.25)
+        );
+
+        assert!(pattern.set_transform(scaling(0.0, 1.0, 1.0)).is_err());
+        assert_eq!(pattern.transform(), scaling(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn noisy_patterns() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        let mut marble = Marble::new(WHITE, BLACK);
+        let mut wood = Wood::new(WHITE, BLACK);
+        let mut stripes = TurbulentStripes::new(WHITE, BLACK);
+        // the noise is 0 on whole numbered points
+        assert_eq!(stripes.pattern_at(Point::new_point(0.0, 0.0, 0.0)), WHITE);
+        assert_eq!(stripes.pattern_at(Point::new_point(1.0, 3.0, 0.0)), BLACK);
+        assert_eq!(wood.pattern_at(Point::new_point(0.0, 5.0, 0.0)), WHITE);
+
+        // and moves the pattern around elsewhere
+        let points: Vec<Point> = (0..100)
+            .map(|i| Point::new_point(i as f64 * 0.13, 0.4, i as f64 * 0.07))
+            .collect();
+        let turbulent: Vec<Color> = points.iter().map(|&p| marble.pattern_at(p)).collect();
+        marble.turbulence = 0.0;
+        wood.turbulence = 0.0;
+        stripes.turbulence = 0.0;
+        let calm: Vec<Color> = points.iter().map(|&p| marble.pattern_at(p)).collect();
+        assert_ne!(turbulent, calm);
+
+        // without it they're plain bands, rings and stripes
+        assert_eq!(marble.pattern_at(Point::new_point(0.0, 0.3, 0.3)), grey);
+        assert_eq!(marble.pattern_at(Point::new_point(0.5, 0.3, 0.3)), BLACK);
+        assert_eq!(wood.pattern_at(Point::new_point(0.3, 0.3, 0.4)), grey);
+        assert_eq!(stripes.pattern_at(Point::new_point(1.5, 0.3, 0.3)), BLACK);
+        assert_eq!(
+            MaterialPattern::from(stripes).pattern_at(Point::new_point(2.5, 0.3, 0.3)),
+            WHITE
+        );
+    }
+
+    #[test]
+    fn uv_checkers() {
+        let pattern = UvCheckers::new(2.0, 2.0, BLACK, WHITE);
+        assert_eq!(pattern.uv_pattern_at(0.0, 0.0), BLACK);
+        assert_eq!(pattern.uv_pattern_at(0.5, 0.0), WHITE);
+        assert_eq!(pattern.uv_pattern_at(0.0, 0.5), WHITE);
+        assert_eq!(pattern.uv_pattern_at(0.5, 0.5), BLACK);
+        assert_eq!(pattern.uv_pattern_at(1.0, 1.0), BLACK);
+    }
+
+    #[test]
+    fn uv_patterns_use_the_shapes_mapping() {
+        let pattern = MaterialPattern::from(UvCheckers::new(2.0, 2.0, BLACK, WHITE));
+        let mut object = Sphere::new();
+        let point = Point::new_point(0.25, 0.0, 0.25);
+        // (0.375, 0.5) around the sphere
+        assert_eq!(pattern.pattern_at_shape(&object, point), WHITE);
+
+        object.material.uv_map = Some(UvMap::Planar);
+        // (0.25, 0.25) flat
+        assert_eq!(pattern.pattern_at_shape(&object, point), BLACK);
+    }
+}
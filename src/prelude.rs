@@ -0,0 +1,49 @@
+//! The types and functions needed to build and render a scene, in one import:
+//!
+//! ```
+//! use ray_tracer::prelude::*;
+//! ```
+
+pub use crate::adaptive::{Adaptive, SampleMap};
+pub use crate::bake::{Bake, Occlusion};
+pub use crate::camera::{Camera, RenderMode};
+pub use crate::canvas::Canvas;
+pub use crate::capsule::Capsule;
+pub use crate::color::Color;
+pub use crate::composite::Compositor;
+pub use crate::coordinates::{Convention, Handedness, UpAxis};
+pub use crate::cube::Cube;
+pub use crate::error::Error;
+pub use crate::exposure::{Exposure, ToneMap};
+pub use crate::filter::Filter;
+pub use crate::fog::Fog;
+pub use crate::light::{
+    lighting, AmbientLight, AreaLight, Attenuation, LightGroups, Material, PointLight, SphereLight,
+    SpotLight,
+};
+pub use crate::limits::RayLimits;
+pub use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+pub use crate::matrix_transformations::{
+    rotation_x, rotation_y, rotation_z, scaling, shearing, translation, translation_from,
+    uniform_scaling, view_transform,
+};
+pub use crate::normal_map::NormalMap;
+pub use crate::orbit::OrbitCamera;
+pub use crate::pattern::{
+    Checkers, Gradient, Marble, MaterialPattern, Pattern, Ring, TurbulentStripes, UvCheckers, Wood,
+};
+pub use crate::preview::Preview;
+pub use crate::quad::Quad;
+pub use crate::rays::{
+    hit, intersect, intersect_into, prepare_computations, prepare_computations_with, reflect,
+    schlick, Computations, Intersection, Intersections, Ray, RayKind, Sphere, Visibility,
+};
+pub use crate::scenes::Preset;
+pub use crate::shape::{Object, Shape};
+pub use crate::sky::Sky;
+pub use crate::skybox::Skybox;
+pub use crate::stats::WorldStats;
+pub use crate::triangle::{SmoothTriangle, Triangle};
+pub use crate::tuple::{Point, Tuple, Vector};
+pub use crate::uv::UvMap;
+pub use crate::world::{default_world, RayHit, World};
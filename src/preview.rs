@@ -0,0 +1,326 @@
+//! Interactive preview: moves the camera with the mouse and WASD and renders progressively,
+//! a blocky pass first and sharper ones after it, starting over whenever the view changes.
+//!
+//! The terminal itself (raw mode, drawing) is up to the `preview` command in the binary. This
+//! module reads what a terminal sends with mouse reporting on (`parse_input`) and decides what
+//! to render next (`Preview`), so any frontend that can produce `Input`s can drive it.
+//!
+//! - drag: orbit around the target
+//! - shift or middle drag: pan
+//! - scroll: zoom
+//! - WASD: walk
+
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::error::Result;
+use crate::orbit::OrbitCamera;
+use crate::tuple::{Point, Vector};
+use crate::world::World;
+
+/// Turned per character cell dragged across, in radians
+const ORBIT_PER_CELL: f64 = 0.05;
+
+/// Panned per character cell dragged across, as a fraction of the distance to the target
+const PAN_PER_CELL: f64 = 0.02;
+
+/// Walked per key press, as a fraction of the distance to the target
+const WALK_STEP: f64 = 0.1;
+
+/// Distance to the target scaled per scroll wheel click
+const ZOOM_STEP: f64 = 1.1;
+
+/// Passes per view, the first at 1/8 of the resolution and each one after twice as sharp
+const PASSES: u32 = 4;
+
+/// Something the user did, in terminal character cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(char),
+    /// A button went down at a cell, `pan` for the middle button or with shift held
+    Press {
+        column: usize,
+        row: usize,
+        pan: bool,
+    },
+    /// The mouse moved to a cell with a button held
+    Drag {
+        column: usize,
+        row: usize,
+        pan: bool,
+    },
+    Release,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// The inputs in bytes read from a terminal with SGR mouse reporting on (`ESC [ ? 1002 h` and
+/// `ESC [ ? 1006 h`). Other escape sequences, e.g. arrow keys, are skipped, and so is a
+/// sequence cut off at the end of `bytes`.
+pub fn parse_input(bytes: &[u8]) -> Vec<Input> {
+    let mut inputs = Vec::new();
+    let mut rest = bytes;
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte != 0x1b || after.first() != Some(&b'[') {
+            inputs.push(Input::Key(byte as char));
+            rest = after;
+            continue;
+        }
+        let sequence = &after[1..];
+        // escape sequences end at their first letter (or ~)
+        let Some(end) = sequence
+            .iter()
+            .position(|b| b.is_ascii_alphabetic() || *b == b'~')
+        else {
+            break;
+        };
+        if let Some(mouse) = sequence.strip_prefix(b"<") {
+            inputs.extend(parse_mouse(&mouse[..end - 1], sequence[end]));
+        }
+        rest = &sequence[end + 1..];
+    }
+    inputs
+}
+
+/// `button;column;row` of an SGR mouse report, ended by `M` for a press or drag, `m` for a release
+fn parse_mouse(fields: &[u8], end: u8) -> Option<Input> {
+    let fields = std::str::from_utf8(fields).ok()?;
+    let mut numbers = fields.split(';').map(|n| n.parse::<usize>().ok());
+    let (button, column, row) = (numbers.next()??, numbers.next()??, numbers.next()??);
+    if end == b'm' {
+        return Some(Input::Release);
+    }
+    const SHIFT: usize = 4;
+    const MOTION: usize = 32;
+    const WHEEL: usize = 64;
+    let pan = button & 3 == 1 || button & SHIFT != 0;
+    match button {
+        b if b & WHEEL != 0 && b & 1 == 0 => Some(Input::ScrollUp),
+        b if b & WHEEL != 0 => Some(Input::ScrollDown),
+        b if b & MOTION != 0 => Some(Input::Drag { column, row, pan }),
+        _ => Some(Input::Press { column, row, pan }),
+    }
+}
+
+/// The camera being moved around and how far along the current view's render is
+#[derive(Debug, Clone)]
+pub struct Preview {
+    camera: Camera,
+    orbit: OrbitCamera,
+    /// Where the mouse was last seen with a button down
+    dragging_from: Option<(usize, usize)>,
+    pass: u32,
+}
+
+impl Preview {
+    /// Starts at `camera`'s view, orbiting the point it looks at nearest to the world's origin
+    pub fn new(camera: Camera) -> Result<Self> {
+        let to_world = camera.transform().inverse()?;
+        let eye = to_world * Point::new_point(0.0, 0.0, 0.0);
+        let mut forward = to_world * Vector::new(0.0, 0.0, -1.0);
+        forward.w = 0.0;
+        let forward = forward.normalize();
+        let distance = (Point::new_point(0.0, 0.0, 0.0) - eye)
+            .dot(&forward)
+            .max(1.0);
+        Ok(Preview {
+            orbit: OrbitCamera::new(eye, eye + forward * distance),
+            camera,
+            dragging_from: None,
+            pass: 0,
+        })
+    }
+
+    /// The camera at full resolution, where the view is now
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn orbit(&self) -> &OrbitCamera {
+        &self.orbit
+    }
+
+    /// Moves the camera for the input, `true` when the view changed and rendering starts over
+    pub fn handle(&mut self, input: Input) -> Result<bool> {
+        let step = self.orbit.distance * WALK_STEP;
+        match input {
+            Input::Key('w') => self.orbit.walk(step, 0.0),
+            Input::Key('s') => self.orbit.walk(-step, 0.0),
+            Input::Key('a') => self.orbit.walk(0.0, -step),
+            Input::Key('d') => self.orbit.walk(0.0, step),
+            Input::Key(_) => return Ok(false),
+            Input::Press { column, row, .. } => {
+                self.dragging_from = Some((column, row));
+                return Ok(false);
+            }
+            Input::Drag { column, row, pan } => {
+                let Some((from_column, from_row)) = self.dragging_from.replace((column, row))
+                else {
+                    return Ok(false);
+                };
+                let dx = column as f64 - from_column as f64;
+                let dy = row as f64 - from_row as f64;
+                if pan {
+                    // the scene follows the mouse
+                    self.orbit.pan(-dx * PAN_PER_CELL, dy * PAN_PER_CELL);
+                } else {
+                    self.orbit.orbit(-dx * ORBIT_PER_CELL, dy * ORBIT_PER_CELL);
+                }
+            }
+            Input::Release => {
+                self.dragging_from = None;
+                return Ok(false);
+            }
+            Input::ScrollUp => self.orbit.zoom(1.0 / ZOOM_STEP),
+            Input::ScrollDown => self.orbit.zoom(ZOOM_STEP),
+        }
+        self.camera.set_transform(self.orbit.view_transform())?;
+        self.restart();
+        Ok(true)
+    }
+
+    /// Renders the current view again from the blockiest pass, e.g. after the world changed
+    pub fn restart(&mut self) {
+        self.pass = 0;
+    }
+
+    /// Whether every pass of the current view has been rendered
+    pub fn is_done(&self) -> bool {
+        self.pass == PASSES
+    }
+
+    /// The next pass, scaled up to the camera's full resolution, or `None` when the view is
+    /// already as sharp as it gets
+    pub fn next_pass(&mut self, world: &World) -> Option<Canvas> {
+        if self.is_done() {
+            return None;
+        }
+        let scale = 1 << (PASSES - 1 - self.pass);
+        self.pass += 1;
+        let (width, height) = (self.camera.hsize(), self.camera.vsize());
+        let coarse = self
+            .camera
+            .with_resolution((width / scale).max(1), (height / scale).max(1))
+            .render(world);
+        Some(coarse.resize(width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::Camera;
+    use crate::matrix_transformations::view_transform;
+    use crate::preview::{parse_input, Input, Preview};
+    use crate::tuple::{Point, Vector};
+    use crate::utils::equal_f64;
+    use crate::world::default_world;
+
+    #[test]
+    fn parsing_keys_and_mouse_reports() {
+        let bytes = b"wq\x1b[<0;10;5M\x1b[<32;12;5M\x1b[<36;13;6M\x1b[<0;13;6m\x1b[<64;1;1M\x1b[<65;1;1M\x1b[A\x1b[<1;2;3M";
+        assert_eq!(
+            parse_input(bytes),
+            [
+                Input::Key('w'),
+                Input::Key('q'),
+                Input::Press {
+                    column: 10,
+                    row: 5,
+                    pan: false
+                },
+                Input::Drag {
+                    column: 12,
+                    row: 5,
+                    pan: false
+                },
+                Input::Drag {
+                    column: 13,
+                    row: 6,
+                    pan: true
+                },
+                Input::Release,
+                Input::ScrollUp,
+                Input::ScrollDown,
+                // the up arrow is skipped
+                Input::Press {
+                    column: 2,
+                    row: 3,
+                    pan: true
+                },
+            ]
+        );
+        // a report split across two reads is dropped rather than misread
+        assert_eq!(parse_input(b"a\x1b[<0;1"), [Input::Key('a')]);
+    }
+
+    fn preview() -> Preview {
+        let mut camera = Camera::new(16, 8, 1.0);
+        camera
+            .set_transform(view_transform(
+                Point::new_point(0.0, 0.0, -5.0),
+                Point::new_point(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .unwrap();
+        Preview::new(camera).unwrap()
+    }
+
+    #[test]
+    fn previews_orbit_what_the_camera_looks_at() {
+        let p = preview();
+        assert_eq!(p.orbit().eye(), Point::new_point(0.0, 0.0, -5.0));
+        assert_eq!(p.orbit().target, Point::new_point(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn input_moves_the_camera() {
+        let mut p = preview();
+        assert!(!p.handle(Input::Key('x')).unwrap());
+        assert!(p.handle(Input::Key('w')).unwrap());
+        assert_eq!(p.orbit().target, Point::new_point(0.0, 0.0, 0.5));
+        assert_eq!(p.camera().transform(), p.orbit().view_transform());
+
+        // dragging orbits by how far the mouse went since the last report
+        let press = Input::Press {
+            column: 10,
+            row: 5,
+            pan: false,
+        };
+        assert!(!p.handle(press).unwrap());
+        let drag = |column| Input::Drag {
+            column,
+            row: 5,
+            pan: false,
+        };
+        assert!(p.handle(drag(12)).unwrap());
+        assert!(p.handle(drag(14)).unwrap());
+        assert!(equal_f64(p.orbit().yaw, -0.2));
+        assert!(!p.handle(Input::Release).unwrap());
+        // the mouse moving without a press first isn't a drag
+        assert!(!p.handle(drag(20)).unwrap());
+
+        assert!(p.handle(Input::ScrollUp).unwrap());
+        assert!(equal_f64(p.orbit().distance, 5.0 / 1.1));
+    }
+
+    #[test]
+    fn rendering_gets_sharper_until_the_view_changes() {
+        let world = default_world();
+        let mut p = preview();
+        let mut passes = Vec::new();
+        while let Some(canvas) = p.next_pass(&world) {
+            assert_eq!((canvas.width(), canvas.height()), (16, 8));
+            passes.push(canvas);
+        }
+        assert_eq!(passes.len(), 4);
+        assert!(p.is_done());
+        // the last pass is the full render
+        let full = p.camera().render(&world);
+        for (x, y) in [(0, 0), (8, 4), (15, 7)] {
+            assert_eq!(passes[3].get_pixel(x, y), full.get_pixel(x, y));
+        }
+
+        p.handle(Input::ScrollDown).unwrap();
+        assert!(!p.is_done());
+        assert!(p.next_pass(&world).is_some());
+    }
+}
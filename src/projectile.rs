@@ -2,9 +2,15 @@ use std::fmt::{Display, Formatter, Result};
 
 use crate::tuple::{Point, Vector};
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Projectile {
     pub position: Point,
     pub velocity: Vector,
+    /// Time since launch, one per tick unless the environment's timestep says otherwise
+    pub time: f64,
+    /// Heavier projectiles are pushed around less by wind and drag, gravity pulls on all of
+    /// them the same
+    pub mass: f64,
 }
 
 impl Display for Projectile {
@@ -14,5 +20,10 @@ impl Display for Projectile {
 }
 
 pub fn new_projectile(position: Point, velocity: Vector) -> Projectile {
-    Projectile { position, velocity }
+    Projectile {
+        position,
+        velocity,
+        time: 0.0,
+        mass: 1.0,
+    }
 }
@@ -0,0 +1,326 @@
+//! Flat parallelograms, a corner and the two edges leaving it, hit directly instead of built
+//! from a plane cut down to size.
+//!
+//! Quads are world objects like any other primitive, the corner and edges in the quad's own
+//! space and its transform placing them, and also the shape of `AreaLight`. Textures are
+//! laid along the edges unless the material picks a uv map.
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, Material};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::random::Rng;
+use crate::rays::{Ray, Visibility};
+use crate::shape::{LocalHits, Shape};
+use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quad {
+    pub id: Uuid,
+    corner: Point,
+    u: Vector,
+    v: Vector,
+    normal: Vector,
+    // n / (n . n) for n = u x v, turns a point on the plane into its uv coordinates
+    w: Vector,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    pub material: Material,
+    /// Lights that shine on the quad, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the quad, the others pass straight through it
+    pub visibility: Visibility,
+}
+
+/// Where a ray crosses a quad, `u` and `v` run from 0 to 1 along its two edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadHit {
+    pub t: f64,
+    pub u: f64,
+    pub v: f64,
+}
+
+impl Quad {
+    /// The quad spanning `corner + a * u + b * v` for a and b from 0 to 1, facing `u x v`
+    pub fn new(corner: Point, u: Vector, v: Vector) -> Self {
+        let n = u.cross(&v);
+        Quad {
+            id: Uuid::new_v4(),
+            corner,
+            u,
+            v,
+            normal: n.normalize(),
+            w: n / n.dot(&n),
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
+        }
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The quad as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Quad {
+        Quad {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn corner(&self) -> Point {
+        self.corner
+    }
+
+    pub fn u(&self) -> Vector {
+        self.u
+    }
+
+    pub fn v(&self) -> Vector {
+        self.v
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    pub fn area(&self) -> f64 {
+        self.u.cross(&self.v).magnitude()
+    }
+
+    pub fn center(&self) -> Point {
+        self.point_at(0.5, 0.5)
+    }
+
+    /// The point at the given uv coordinates
+    pub fn point_at(&self, u: f64, v: f64) -> Point {
+        self.corner + self.u * u + self.v * v
+    }
+
+    /// A random point on the quad, uniformly distributed
+    pub fn sample_point(&self, rng: &mut Rng) -> Point {
+        self.point_at(rng.next_f64(), rng.next_f64())
+    }
+
+    /// The uv coordinates of a point on the quad's plane, the inverse of `point_at`
+    pub fn uv_of(&self, point: Point) -> (f64, f64) {
+        let offset = point - self.corner;
+        (
+            self.w.dot(&offset.cross(&self.v)),
+            self.w.dot(&self.u.cross(&offset)),
+        )
+    }
+
+    /// Where the ray crosses the quad, from either side, `None` when it misses or runs along it.
+    /// Both are in the quad's own space, its transform isn't applied.
+    pub fn intersect(&self, ray: &Ray) -> Option<QuadHit> {
+        let n = self.u.cross(&self.v);
+        let denominator = n.dot(&ray.direction());
+        if denominator.abs() < EPSILON {
+            return None;
+        }
+        let t = n.dot(&(self.corner - ray.origin())) / denominator;
+        let (u, v) = self.uv_of(ray.position(t));
+        let inside = (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v);
+        inside.then_some(QuadHit { t, u, v })
+    }
+}
+
+impl Shape for Quad {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        let mut hits = LocalHits::new();
+        if let Some(hit) = self.intersect(ray) {
+            hits.push_with_uv(hit.t, hit.u, hit.v);
+        }
+        hits
+    }
+
+    /// The same everywhere, `u x v`
+    fn local_normal_at(&self, _point: Point, _uv: Option<(f64, f64)>) -> Vector {
+        self.normal
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::empty();
+        for (u, v) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            bounds.add_point(self.point_at(u, v));
+        }
+        bounds
+    }
+
+    /// Along the edges, 0 to 1 from the corner, unless the material picks a uv map
+    fn uv_at(&self, point: Point) -> (f64, f64) {
+        match self.material.uv_map {
+            Some(uv_map) => uv_map.map(point),
+            None => self.uv_of(point),
+        }
+    }
+
+    fn uv_tangents(&self, point: Point) -> (Vector, Vector) {
+        match self.material.uv_map {
+            Some(uv_map) => uv_map.tangents(point),
+            None => (self.u, self.v),
+        }
+    }
+}
+
+impl PartialEq for Quad {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::light::PointLight;
+    use crate::matrix_transformations::translation;
+    use crate::pattern::{MaterialPattern, UvCheckers};
+    use crate::quad::Quad;
+    use crate::random::{Rng, Seedable};
+    use crate::rays::Ray;
+    use crate::shape::{Object, Shape};
+    use crate::tuple::{Point, Vector};
+    use crate::world::World;
+
+    /// 2 x 1 rectangle in the xy plane, facing -z toward the usual camera
+    fn rectangle() -> Quad {
+        Quad::new(
+            Point::new_point(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(2.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn quad_geometry() {
+        let q = rectangle();
+        assert_eq!(q.normal(), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(q.area(), 2.0);
+        assert_eq!(q.center(), Point::new_point(0.0, 0.5, 0.0));
+        let mut rng = Rng::from_seed(1);
+        for _ in 0..20 {
+            let p = q.sample_point(&mut rng);
+            assert!(p.z == 0.0 && p.x.abs() <= 1.0 && (0.0..=1.0).contains(&p.y));
+        }
+    }
+
+    #[test]
+    fn intersecting_a_quad() {
+        let q = rectangle();
+        let r = Ray::new(
+            Point::new_point(0.5, 0.25, -5.0),
+            Vector::new(0.0, 0.0, 2.0),
+        );
+        let hit = q.intersect(&r).unwrap();
+        assert_eq!(hit.t, 2.5);
+        assert!((hit.u - 0.25).abs() < 1e-12 && (hit.v - 0.75).abs() < 1e-12);
+        // from behind as well
+        let back = Ray::new(
+            Point::new_point(0.5, 0.25, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+        assert_eq!(q.intersect(&back).map(|h| h.t), Some(5.0));
+
+        let beside = Ray::new(
+            Point::new_point(1.5, 0.25, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(q.intersect(&beside), None);
+        let parallel = Ray::new(Point::new_point(0.0, 0.5, -5.0), Vector::new(1.0, 0.0, 0.0));
+        assert_eq!(q.intersect(&parallel), None);
+    }
+
+    #[test]
+    fn quads_in_the_world() {
+        let mut q = rectangle();
+        q.set_transform(translation(0.0, 0.0, 1.0)).unwrap();
+        q.material.ambient = 1.0;
+        q.material.diffuse = 0.0;
+        q.material.specular = 0.0;
+        q.material.pattern = Some(MaterialPattern::from(UvCheckers::new(
+            1.0,
+            2.0,
+            Color::BLACK,
+            Color::WHITE,
+        )));
+        let mut w = World::new();
+        w.lights.push(PointLight::new(
+            Point::new_point(0.0, 0.0, -10.0),
+            Color::WHITE,
+        ));
+        w.objects.push(Object::from(q));
+        assert_eq!(q.bounds().min(), Point::new_point(-1.0, 0.0, 1.0));
+        assert_eq!(q.bounds().max(), Point::new_point(1.0, 1.0, 1.0));
+
+        let r = Ray::new(
+            Point::new_point(0.5, 0.25, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect(&r);
+        assert_eq!(xs.size(), 1);
+        let hit = xs[0];
+        assert_eq!(hit.t, 6.0);
+        assert_eq!(hit.uv, Some((0.25, 0.75)));
+        assert_eq!(
+            hit.object.normal_at(r.position(hit.t), &hit),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+        // textures run along the edges, checkers across the long one
+        assert_eq!(w.color_at(&r), Color::WHITE);
+        let left = Ray::new(
+            Point::new_point(-0.5, 0.25, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(w.color_at(&left), Color::BLACK);
+        let beside = Ray::new(
+            Point::new_point(1.5, 0.25, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(w.intersect(&beside).size(), 0);
+    }
+}
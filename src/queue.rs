@@ -0,0 +1,246 @@
+//! Batch rendering: a queue of scene files rendered one after the other or in parallel,
+//! e.g. overnight renders of many scenes.
+//!
+//! Queues can be built in code or loaded from a YAML job file:
+//!
+//! ```yaml
+//! parallel: true
+//! jobs:
+//!   - scene: spheres.json
+//!     output: spheres.ppm
+//!   - scene: spheres.json
+//!     output: spheres_large.ppm
+//!     width: 1920
+//!     height: 1080
+//!     samples: 16
+//!     seed: 1
+//!     max_bounces: 2
+//! ```
+//!
+//! `width`, `height`, `samples` and `seed` override the scene's camera when given, and
+//! `max_reflections`, `max_refractions` and `max_bounces` its ray limits. A job that
+//! fails (missing scene, bad transform, unwritable output) is reported and the rest still run.
+
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::scene::{parse_scene, Scene};
+
+/// One scene to render and where to write the image
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenderJob {
+    /// Path of the JSON scene file
+    pub scene: String,
+    /// Path of the PPM image to write
+    pub output: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub samples: Option<usize>,
+    pub seed: Option<u64>,
+    pub max_reflections: Option<u32>,
+    pub max_refractions: Option<u32>,
+    pub max_bounces: Option<u32>,
+}
+
+impl RenderJob {
+    pub fn new(scene: &str, output: &str) -> Self {
+        RenderJob {
+            scene: scene.to_string(),
+            output: output.to_string(),
+            width: None,
+            height: None,
+            samples: None,
+            seed: None,
+            max_reflections: None,
+            max_refractions: None,
+            max_bounces: None,
+        }
+    }
+
+    /// Loads the scene with this job's overrides applied
+    pub fn load(&self) -> Result<Scene> {
+        let mut scene = parse_scene(&fs::read(&self.scene)?)?.build()?;
+        let camera = &mut scene.camera;
+        if self.width.is_some() || self.height.is_some() {
+            *camera = camera.with_resolution(
+                self.width.unwrap_or(camera.hsize()),
+                self.height.unwrap_or(camera.vsize()),
+            );
+        }
+        if let Some(samples) = self.samples {
+            camera.set_samples(samples);
+        }
+        if let Some(seed) = self.seed {
+            camera.set_seed(seed);
+        }
+        let limits = &mut scene.world.ray_limits;
+        limits.reflection_depth = self.max_reflections.unwrap_or(limits.reflection_depth);
+        limits.refraction_depth = self.max_refractions.unwrap_or(limits.refraction_depth);
+        limits.bounces = self.max_bounces.unwrap_or(limits.bounces);
+        Ok(scene)
+    }
+
+    /// Loads, renders and writes the image, returning how long it took
+    pub fn run(&self) -> Result<Duration> {
+        let start = Instant::now();
+        let scene = self.load()?;
+        scene.camera.render(&scene.world).to_ppm(&self.output)?;
+        Ok(start.elapsed())
+    }
+}
+
+/// How a job went, handed to the progress callback as soon as it finishes
+#[derive(Debug)]
+pub struct JobReport<'a> {
+    pub job: &'a RenderJob,
+    /// Render time, or why the job failed
+    pub result: Result<Duration>,
+    /// Jobs finished so far, this one included
+    pub finished: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenderQueue {
+    #[serde(default)]
+    pub parallel: bool,
+    #[serde(default)]
+    pub jobs: Vec<RenderJob>,
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        RenderQueue::default()
+    }
+
+    pub fn add(&mut self, job: RenderJob) {
+        self.jobs.push(job);
+    }
+
+    /// Render several jobs at once instead of one after the other. Each render already uses
+    /// every core, this mostly helps with many small scenes.
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<RenderQueue> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+
+    /// Loads a job file, scene and output paths in it are relative to the file
+    pub fn load(filename: &str) -> Result<RenderQueue> {
+        let mut queue = RenderQueue::from_yaml(&fs::read_to_string(filename)?)?;
+        let dir = Path::new(filename).parent().unwrap_or(Path::new(""));
+        for job in &mut queue.jobs {
+            job.scene = dir.join(&job.scene).to_string_lossy().into_owned();
+            job.output = dir.join(&job.output).to_string_lossy().into_owned();
+        }
+        Ok(queue)
+    }
+
+    /// Runs every job, calling `progress` as each one finishes (in completion order when parallel).
+    /// Returns how many jobs failed.
+    pub fn run(&self, progress: impl Fn(JobReport) + Sync) -> usize {
+        let finished = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        let run_job = |job: &RenderJob| {
+            let result = job.run();
+            if result.is_err() {
+                failed.fetch_add(1, Ordering::Relaxed);
+            }
+            progress(JobReport {
+                job,
+                result,
+                finished: finished.fetch_add(1, Ordering::Relaxed) + 1,
+                total: self.jobs.len(),
+            });
+        };
+        if self.parallel {
+            self.jobs.par_iter().for_each(run_job);
+        } else {
+            self.jobs.iter().for_each(run_job);
+        }
+        failed.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Mutex;
+
+    use crate::error::Error;
+    use crate::queue::{RenderJob, RenderQueue};
+
+    const SCENE: &str = r#"{
+        "camera": { "width": 8, "height": 4, "field_of_view": 1.0472,
+                    "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0] },
+        "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1] } ],
+        "spheres": [ {} ]
+    }"#;
+
+    #[test]
+    fn job_file() {
+        let queue = RenderQueue::from_yaml(
+            "parallel: true\njobs:\n  - scene: a.json\n    output: a.ppm\n    width: 20\n    seed: 3\n    max_bounces: 2\n",
+        )
+        .unwrap();
+        assert!(queue.parallel);
+        let mut expected = RenderJob::new("a.json", "a.ppm");
+        expected.width = Some(20);
+        expected.seed = Some(3);
+        expected.max_bounces = Some(2);
+        assert_eq!(queue.jobs, vec![expected]);
+
+        assert!(matches!(
+            RenderQueue::from_yaml("jobs:\n  - scene: a.json\n"),
+            Err(Error::Jobs(_))
+        ));
+    }
+
+    #[test]
+    fn running_a_queue() {
+        let dir = std::env::temp_dir().join(format!("ray_tracer_queue_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("scene.json"), SCENE).unwrap();
+        fs::write(
+            dir.join("jobs.yaml"),
+            "jobs:\n  - scene: scene.json\n    output: small.ppm\n  - scene: scene.json\n    output: large.ppm\n    width: 16\n  - scene: missing.json\n    output: missing.ppm\n",
+        )
+        .unwrap();
+
+        let mut queue = RenderQueue::load(dir.join("jobs.yaml").to_str().unwrap()).unwrap();
+        assert_eq!(
+            queue.jobs[1].load().unwrap().camera.hsize(),
+            16,
+            "overrides apply to the loaded scene"
+        );
+        for parallel in [false, true] {
+            queue.set_parallel(parallel);
+            let reports = Mutex::new(Vec::new());
+            let failed = queue.run(|report| {
+                reports.lock().unwrap().push((
+                    report.finished,
+                    report.total,
+                    report.result.is_ok(),
+                ));
+            });
+            assert_eq!(failed, 1);
+            let mut reports = reports.into_inner().unwrap();
+            reports.sort();
+            assert_eq!(reports.iter().map(|r| r.0).collect::<Vec<_>>(), [1, 2, 3]);
+            assert!(reports.iter().all(|r| r.1 == 3));
+        }
+        let large = fs::read_to_string(dir.join("large.ppm")).unwrap();
+        assert!(large.starts_with("P3\n16 4\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
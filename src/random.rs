@@ -0,0 +1,87 @@
+//! Reproducible randomness for everything that samples, so the same seed always renders the
+//! same image no matter how many threads the tiles were spread over.
+//!
+//! Every pixel gets its own generator from the render seed and its coordinates
+//! (`Seedable::for_pixel`), instead of all pixels drawing from one shared stream in whatever
+//! order the tiles happen to finish.
+
+/// Random number generators that can be recreated from a seed
+pub trait Seedable: Sized {
+    fn from_seed(seed: u64) -> Self;
+
+    /// Generator for one pixel of a render seeded with `seed`
+    fn for_pixel(seed: u64, x: usize, y: usize) -> Self {
+        Self::from_seed(mix(seed ^ mix(((x as u64) << 32) | y as u64)))
+    }
+}
+
+/// SplitMix64, small and fast with good enough statistics for sampling, not for cryptography
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Seedable for Rng {
+    fn from_seed(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+}
+
+impl Rng {
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill an f64's mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [low, high)
+    pub fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+}
+
+/// SplitMix64's finalizer, spreads every input bit over the whole output
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::{Rng, Seedable};
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut a = Rng::from_seed(42);
+        let mut b = Rng::from_seed(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::from_seed(1).next_u64(), Rng::from_seed(2).next_u64());
+    }
+
+    #[test]
+    fn pixels_get_their_own_streams() {
+        let first = Rng::for_pixel(7, 3, 4).next_u64();
+        assert_eq!(Rng::for_pixel(7, 3, 4).next_u64(), first);
+        assert_ne!(Rng::for_pixel(7, 4, 3).next_u64(), first);
+        assert_ne!(Rng::for_pixel(8, 3, 4).next_u64(), first);
+    }
+
+    #[test]
+    fn floats_stay_in_range() {
+        let mut rng = Rng::from_seed(0);
+        for _ in 0..1000 {
+            let f = rng.next_f64();
+            assert!((0.0..1.0).contains(&f));
+            let r = rng.range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&r));
+        }
+    }
+}
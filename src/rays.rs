@@ -1,9 +1,14 @@
-use std::borrow::Borrow;
+use std::fmt;
 use std::ops::Index;
 
-use crate::light::Material;
-use crate::matrix::{invert_4x4, transpose, M4x4, IDENTITY_MATRIX_4X4};
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, Material};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::matrix_transformations::{scaling, translation};
+use crate::shape::{LocalHits, Object, Shape};
 use crate::tuple::{Point, Tuple, Vector};
+use crate::utils::{epsilon, equal_f64_relative};
 use uuid::Uuid;
 
 pub const SPHERE_ORIGIN: Tuple = Point {
@@ -13,15 +18,60 @@ pub const SPHERE_ORIGIN: Tuple = Point {
     w: 1.0,
 }; // is a point
 
-#[derive(Debug)]
+/// How close (relative to their size) the two terms of a ray-sphere discriminant
+/// have to be for the ray to count as touching the sphere at a single point
+pub(crate) const TANGENT_MAX_RELATIVE: f64 = 1e-12;
+
+/// What a ray is cast for, objects can be hidden from some kinds of rays, see `Visibility`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RayKind {
+    /// Straight from the camera
+    #[default]
+    Camera,
+    /// Bounced off or bent through a surface
+    Reflection,
+    /// Toward a light, to check whether a point is in shadow
+    Shadow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Ray {
-    pub origin: Point,
-    pub direction: Vector,
+    origin: Point,
+    direction: Vector,
+    kind: RayKind,
 }
 
 impl Ray {
+    /// A camera ray, see `with_kind` for the others
     pub fn new(origin: Point, direction: Vector) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            kind: RayKind::Camera,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: RayKind) -> Ray {
+        self.kind = kind;
+        self
+    }
+
+    pub fn kind(&self) -> RayKind {
+        self.kind
+    }
+
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Not necessarily normalized, rays transformed into object space keep the scaling
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+
+    /// The same ray with `m` applied to both its origin and direction
+    pub fn transformed(&self, m: &M4x4) -> Ray {
+        Ray::new(*m * self.origin, *m * self.direction).with_kind(self.kind)
     }
 
     pub fn position(&self, time: f64) -> Tuple {
@@ -53,11 +103,64 @@ impl Ray {
     }
 }
 
+/// `(ox, oy, oz) -> (dx, dy, dz)`, compact enough to log a ray per line
+impl fmt::Display for Ray {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (o, d) = (self.origin, self.direction);
+        write!(
+            f,
+            "({}, {}, {}) -> ({}, {}, {})",
+            o.x, o.y, o.z, d.x, d.y, d.z
+        )
+    }
+}
+
+/// Which kinds of rays see an object, e.g. a backdrop that shows up behind everything but not
+/// in reflections. Visible to all of them by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Visibility {
+    pub camera: bool,
+    /// Reflected and refracted rays
+    pub reflections: bool,
+    /// Whether the object casts shadows
+    pub shadows: bool,
+}
+
+impl Visibility {
+    pub const ALL: Visibility = Visibility {
+        camera: true,
+        reflections: true,
+        shadows: true,
+    };
+
+    pub fn sees(self, kind: RayKind) -> bool {
+        match kind {
+            RayKind::Camera => self.camera,
+            RayKind::Reflection => self.reflections,
+            RayKind::Shadow => self.shadows,
+        }
+    }
+}
+
+impl Default for Visibility {
+    fn default() -> Self {
+        Visibility::ALL
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sphere {
     pub id: Uuid,
-    pub transform: M4x4,
+    transform: M4x4,
+    // every ray and normal needs the inverse, so it's computed once when the transform is set
+    inverse_transform: M4x4,
     pub material: Material,
+    /// Lights that shine on the sphere, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the sphere, the others pass straight through it
+    pub visibility: Visibility,
 }
 
 impl Sphere {
@@ -65,74 +168,159 @@ impl Sphere {
         Sphere {
             id: Uuid::new_v4(),
             transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
             material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
         }
     }
 
-    pub fn set_transform(&mut self, transform: M4x4) {
+    /// A sphere stretched to the given radius along each axis, centered on `center`. Fails like
+    /// `set_transform` when a radius is 0.
+    pub fn ellipsoid(center: Point, radii: Vector) -> Result<Sphere> {
+        let mut ellipsoid = Sphere::new();
+        ellipsoid.set_transform(
+            translation(center.x, center.y, center.z) * scaling(radii.x, radii.y, radii.z),
+        )?;
+        Ok(ellipsoid)
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    ///
+    /// Checking here means a broken scene is reported while it's being built
+    /// rather than as a panic in the middle of rendering. Setting the transform it already has
+    /// is cheap, the cached inverse is only recomputed when the transform actually changes.
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
         self.transform = transform;
+        Ok(())
+    }
+
+    /// The sphere as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Sphere {
+        Sphere {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
     }
 
     pub fn set_material(&mut self, material: Material) {
         self.material = material;
     }
+}
 
-    /// Calculates the normal vector at a given point on the surface of the sphere, transforming
-    /// from world space to object space and back to world space correctly.
-    ///
-    /// # Arguments
-    /// * `world_point` - A `Point` in world space for which the normal vector is to be calculated.
-    ///                   This point is assumed to lie on the surface of the sphere.
+impl Shape for Sphere {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    /// Computes the intersection points between a ray and a unit sphere at the origin
+    /// using the quadratic formula.
     ///
-    /// # Returns
-    /// * `Vector` - The normal vector at the given point in world space.
+    /// The quadratic equation used is derived from the formula for a sphere and a parametric
+    /// equation for a ray:
     ///
-    /// # Methodology
-    /// 1. **World to Object Space Transformation**:
-    ///    - The method begins by transforming the given `world_point` into the sphere's local
-    ///      coordinate system (object space). This is achieved by applying the inverse of the
-    ///      sphere's transformation matrix. In object space, the sphere is assumed to be centered
-    ///      at the origin with a radius of 1. This simplifies the normal calculation.
-    ///      \[
-    ///      \text{object\_point} = T^{-1} \times \text{world\_point}
-    ///      \]
+    /// - Sphere equation: `(x - cx)^2 + (y - cy)^2 + (z - cz)^2 = r^2`
+    /// - Ray equation: `P(t) = O + tD`, where `O` is the origin, `D` is the direction, and `t` is the parameter
     ///
-    /// 2. **Normal Calculation in Object Space**:
-    ///    - In object space, the normal at any point on the sphere's surface is simply the vector
-    ///      from the origin (the sphere's center) to the point itself. This vector is calculated
-    ///      by subtracting the origin from the `object_point`.
+    /// By substituting the ray equation into the sphere equation and rearranging terms,
+    /// we get a quadratic equation of the form `at^2 + bt + c = 0`, where:
     ///
-    /// 3. **Transforming the Normal to World Space**:
-    ///    - The normal vector is then transformed back to world space. However, because normals
-    ///      interact with transformations differently from points (especially under non-uniform
-    ///      scaling), the transpose of the inverse of the transformation matrix is used:
-    ///      \[
-    ///      \text{world\_normal} = (T^{-1})^{T} \times \text{object\_normal}
-    ///      \]
+    /// - `a` is the dot product of the direction vector with itself.
+    /// - `b` is 2 times the dot product of the direction vector and the vector from the sphere's center to the ray's origin.
+    /// - `c` is the dot product of the vector from the sphere's center to the ray's origin with itself, minus the radius squared (1.0 in this case).
     ///
-    /// 4. **Normalization and Correction**:
-    ///    - The resulting world-space normal vector is normalized to ensure it has unit length.
-    ///      Additionally, the `w` component of the normal vector is explicitly set to `0.0` to
-    ///      indicate that it represents a direction rather than a point in space.
+    /// The discriminant `d = b^2 - 4ac` determines the nature of the intersection:
     ///
-    /// # Considerations
-    /// - The function assumes that the `world_point` provided is exactly on the sphere's surface.
-    /// - The matrix inversion and transposition steps are computationally intensive and must be
-    ///   carefully implemented to avoid numerical instability.
-    /// - This method is crucial for accurate lighting and shading calculations, as the normal
-    ///   vector plays a key role in determining how light interacts with the surface.
-    pub fn normal_at(&self, world_point: Point) -> Vector {
-        let object_point = invert_4x4(&self.transform).unwrap() * world_point;
-        let object_normal = object_point - Point::new_point(0.0, 0.0, 0.0);
-        // transposing the inverse matrix is necessary because it ensures that the normal vector
-        // is correctly transformed to remain perpendicular to the surface after
-        // non-uniform scaling, rotation, and other transformations
-        let world_normal = transpose(invert_4x4(&self.transform).unwrap()) * object_normal;
-        let mut normal = Vector::new(world_normal.x, world_normal.y, world_normal.z).normalize();
-        // translation can mess up the w coordinate
-        // avoid more complex code with hack / set w to 0
-        normal.w = 0.0;
-        normal
+    /// - If `d < 0`, the ray does not intersect the sphere.
+    /// - If `d = 0`, the ray touches the sphere at exactly one point (tangent).
+    /// - If `d > 0`, the ray intersects the sphere at two points (entering and exiting).
+    fn local_intersect(&self, r: &Ray) -> LocalHits {
+        let mut hits = LocalHits::new();
+
+        // Vector from the sphere's origin (assumed to be the origin in this case) to the ray's origin
+        let sphere_to_ray = r.origin() - SPHERE_ORIGIN;
+
+        // Calculate the coefficients of the quadratic equation
+        let a = r.direction().dot(&r.direction()); // Coefficient 'a' (direction vector dot product with itself)
+        let b = 2.0 * r.direction().dot(&sphere_to_ray); // Coefficient 'b' (2 times direction dot product with sphere_to_ray vector)
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+
+        // Calculate the discriminant, which determines the number of intersection points
+        let d = b.powf(2.0) - (4.0 * a * c);
+
+        // The rounding error in the discriminant grows with the size of its two terms, so a ray
+        // grazing the sphere can come out slightly negative (or positive) far from the origin.
+        // Comparing the terms relative to their size catches those tangents at any distance.
+        let tangent = equal_f64_relative(b.powf(2.0), 4.0 * a * c, TANGENT_MAX_RELATIVE);
+
+        // If the discriminant is negative, there are no real intersections (ray misses the sphere)
+        if d < 0.0 && !tangent {
+            return hits; // No intersections to add
+        }
+
+        // The discriminant is zero, meaning the ray is tangent to the sphere.
+        // This results in exactly one intersection point (the ray just touches the sphere).
+        if tangent {
+            let t = -b / (2.0 * a); // Calculate the single intersection point
+            hits.push(t); // Add the single intersection twice, as both the entry and the exit
+            hits.push(t);
+            return hits;
+        }
+
+        // Calculate the two possible values of t (parameter along the ray) where intersections occur
+        let t1 = (-b - d.sqrt()) / (2.0 * a); // First intersection point (entering the sphere)
+        let t2 = (-b + d.sqrt()) / (2.0 * a); // Second intersection point (exiting the sphere)
+
+        // Add both intersections, t1 is always the smaller one
+        hits.push(t1);
+        hits.push(t2);
+        hits
+    }
+
+    /// In object space, the normal at any point on the sphere's surface is simply the vector
+    /// from the origin (the sphere's center) to the point itself
+    fn local_normal_at(&self, point: Point, _uv: Option<(f64, f64)>) -> Vector {
+        point - SPHERE_ORIGIN
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
+    }
+}
+
+impl Default for Sphere {
+    fn default() -> Self {
+        Sphere::new()
     }
 }
 
@@ -142,162 +330,306 @@ impl PartialEq for Sphere {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Intersection<T> {
     pub t: f64,
     // value of intersection
     pub object: T, // object that was intersected
+    /// Where on the surface the hit is, for shapes that know (triangles give their barycentric
+    /// coordinates), used to interpolate normals and look up textures
+    pub uv: Option<(f64, f64)>,
 }
 
 impl<T> Intersection<T> {
     // Factory method to create a new Intersection
     pub fn new(t: f64, object: T) -> Self {
-        Intersection { t, object }
+        Intersection {
+            t,
+            object,
+            uv: None,
+        }
     }
-}
 
-impl PartialEq for Intersection<Sphere> {
-    fn eq(&self, other: &Self) -> bool {
-        self.object == other.object
+    pub fn with_uv(t: f64, object: T, u: f64, v: f64) -> Self {
+        Intersection {
+            t,
+            object,
+            uv: Some((u, v)),
+        }
     }
 }
 
-impl Copy for Intersection<Sphere> {}
-
-impl Clone for Intersection<Sphere> {
-    fn clone(&self) -> Self {
-        Intersection {
-            t: self.t,
-            object: self.object,
-        }
+impl<S: Shape> PartialEq for Intersection<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.object.id() == other.object.id()
     }
 }
 
+/// Intersections along a ray, always sorted by t so the hit can be found without a full scan
+#[derive(Debug, Clone)]
 pub struct Intersections<T> {
     items: Vec<Intersection<T>>,
 }
 
 impl<T> Intersections<T> {
+    pub fn new() -> Self {
+        Intersections { items: Vec::new() }
+    }
+
+    /// Room for `capacity` intersections before it has to grow
+    pub fn with_capacity(capacity: usize) -> Self {
+        Intersections {
+            items: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Empties the collection, keeping its memory for the next ray
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
     pub fn size(&self) -> usize {
         self.items.len()
     }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Intersection<T>> {
+        self.items.iter()
+    }
+
+    /// Adds an intersection in t order, after any others with the same t
+    pub fn insert(&mut self, intersection: Intersection<T>) {
+        let index = self.items.partition_point(|i| i.t <= intersection.t);
+        self.items.insert(index, intersection);
+    }
+
+    /// Moves every intersection of `other` into this collection, keeping it sorted
+    pub fn merge(&mut self, other: Intersections<T>) {
+        self.items.extend(other.items);
+        // the stable sort finds the two sorted runs and merges them in linear time
+        self.items.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    /// Keeps only the intersections `keep` returns true for, still in order
+    pub fn retain(&mut self, keep: impl FnMut(&Intersection<T>) -> bool) {
+        self.items.retain(keep);
+    }
+
+    /// The visible intersection: the one with the lowest non-negative t
+    pub fn hit(&self) -> Option<&Intersection<T>> {
+        self.items.get(self.items.partition_point(|i| i.t < 0.0))
+    }
 }
 
-impl Index<usize> for Intersections<Sphere> {
-    type Output = Intersection<Sphere>;
+impl<T> Default for Intersections<T> {
+    fn default() -> Self {
+        Intersections::new()
+    }
+}
+
+impl<T> Index<usize> for Intersections<T> {
+    type Output = Intersection<T>;
     fn index(&self, index: usize) -> &Self::Output {
-        self.items[index].borrow()
+        &self.items[index]
     }
 }
 
 impl<T> From<Vec<Intersection<T>>> for Intersections<T> {
-    fn from(items: Vec<Intersection<T>>) -> Self {
+    fn from(mut items: Vec<Intersection<T>>) -> Self {
+        items.sort_by(|a, b| a.t.total_cmp(&b.t));
         Intersections { items }
     }
 }
 
-/// Computes the intersection points between a ray and a sphere.
-///
-/// This function calculates the intersection points, if any, between a ray and a sphere
-/// using the quadratic formula. The ray is defined by its origin and direction, and the sphere
-/// is assumed to be centered at `SPHERE_ORIGIN` with a radius of 1.0.
-///
-/// The quadratic equation used is derived from the formula for a sphere and a parametric
-/// equation for a ray:
-///
-/// - Sphere equation: `(x - cx)^2 + (y - cy)^2 + (z - cz)^2 = r^2`
-/// - Ray equation: `P(t) = O + tD`, where `O` is the origin, `D` is the direction, and `t` is the parameter
-///
-/// By substituting the ray equation into the sphere equation and rearranging terms,
-/// we get a quadratic equation of the form `at^2 + bt + c = 0`, where:
-///
-/// - `a` is the dot product of the direction vector with itself.
-/// - `b` is 2 times the dot product of the direction vector and the vector from the sphere's center to the ray's origin.
-/// - `c` is the dot product of the vector from the sphere's center to the ray's origin with itself, minus the radius squared (1.0 in this case).
-///
-/// The discriminant `d = b^2 - 4ac` determines the nature of the intersection:
-///
-/// - If `d < 0`, the ray does not intersect the sphere.
-/// - If `d = 0`, the ray touches the sphere at exactly one point (tangent).
-/// - If `d > 0`, the ray intersects the sphere at two points (entering and exiting).
-///
-/// The function returns an `Intersections<Sphere>` object containing the `t` values where the intersections occur.
+impl<T> IntoIterator for Intersections<T> {
+    type Item = Intersection<T>;
+    type IntoIter = std::vec::IntoIter<Intersection<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Intersections<T> {
+    type Item = &'a Intersection<T>;
+    type IntoIter = std::slice::Iter<'a, Intersection<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// Intersects a ray with a shape: the ray is moved into the shape's own space, where the
+/// shape finds where it crosses its surface (`Shape::local_intersect`)
 ///
 /// # Arguments
 ///
-/// * `r` - A reference to the `Ray` that might intersect the sphere.
-/// * `s` - The `Sphere` that the ray might intersect.
+/// * `r` - A reference to the `Ray` that might intersect the shape.
+/// * `s` - The shape the ray might intersect, `Copy` as every intersection holds it.
 ///
 /// # Returns
 ///
-/// An `Intersections<Sphere>` object containing the intersection points, if any.
-pub fn intersect(r: &Ray, s: Sphere) -> Intersections<Sphere> {
-    // first transform ray by inverse of sphere's transformation
-    let inverted_tx = invert_4x4(&s.transform).unwrap();
-    let r = transform(r, inverted_tx);
-
-    // Calculate the discriminant, which determines the number of intersection points
-    let d = r.discriminant();
+/// An `Intersections` object containing the intersection points, if any.
+pub fn intersect<S: Shape + Copy>(r: &Ray, s: S) -> Intersections<S> {
+    let mut xs = Intersections::new();
+    intersect_into(r, s, &mut xs);
+    xs
+}
 
-    // If the discriminant is negative, there are no real intersections (ray misses the sphere)
-    if d < 0.0 {
-        return Intersections::from(vec![]); // Return an empty list of intersections
+/// `intersect`, adding the intersections to `xs` (in t order) instead of allocating a new
+/// collection, so a buffer can be reused ray after ray
+pub fn intersect_into<S: Shape + Copy>(r: &Ray, s: S, xs: &mut Intersections<S>) {
+    // first transform ray by inverse of the shape's transformation
+    let local = r.transformed(&s.inverse_transform());
+    for (t, uv) in s.local_intersect(&local).iter() {
+        xs.insert(Intersection { t, object: s, uv });
     }
+}
 
-    // Vector from the sphere's origin (assumed to be the origin in this case) to the ray's origin
-    let sphere_to_ray = r.origin - SPHERE_ORIGIN;
-
-    // Calculate the coefficients of the quadratic equation
-    let a = r.direction.dot(&r.direction); // Coefficient 'a' (direction vector dot product with itself)
-    let b = 2.0 * r.direction.dot(&sphere_to_ray); // Coefficient 'b' (2 times direction dot product with sphere_to_ray vector)
-
-    // The discriminant is zero, meaning the ray is tangent to the sphere.
-    // This results in exactly one intersection point (the ray just touches the sphere).
-    if d == 0.0 {
-        let t = -b / (2.0 * a); // Calculate the single intersection point
-        let i = Intersection::new(t, s.clone()); // Create the Intersection object for this point
-        return Intersections::from(vec![i, i]); // Return the single intersection as a list with two elements, but they are the same
-    }
+/// Owned copy of `Intersections::hit`
+pub fn hit<S: Copy>(xs: &Intersections<S>) -> Option<Intersection<S>> {
+    xs.hit().copied()
+}
 
-    // Calculate the two possible values of t (parameter along the ray) where intersections occur
-    let t1 = (-b - d.sqrt()) / (2.0 * a); // First intersection point (entering the sphere)
-    let t2 = (-b + d.sqrt()) / (2.0 * a); // Second intersection point (exiting the sphere)
+/// Everything about a hit that shading needs, computed once up front
+#[derive(Debug, Clone, Copy)]
+pub struct Computations<S = Object> {
+    pub t: f64,
+    pub object: S,
+    pub point: Point,
+    pub eyev: Vector,
+    pub normalv: Vector,
+    /// The eye ray bounced off the surface, where reflections come from
+    pub reflectv: Vector,
+    /// `point` nudged off the surface along the normal, where rays leaving the surface start
+    /// so rounding errors don't make them hit the surface they started on (shadow acne)
+    pub over_point: Point,
+    /// `point` pushed just below the surface, where refracted rays start
+    pub under_point: Point,
+    /// Refractive index of the medium the ray is leaving
+    pub n1: f64,
+    /// Refractive index of the medium the ray is entering
+    pub n2: f64,
+    // true when the hit is on the inside of the object, the normal is flipped so it faces the eye
+    pub inside: bool,
+}
 
-    // Create Intersection objects for each intersection point with the sphere
-    let i1 = Intersection::new(t1, s.clone()); // Intersection at t1
-    let i2 = Intersection::new(t2, s.clone()); // Intersection at t2
+/// How far `Computations::over_point` and `under_point` are pushed off the surface unless the
+/// world says otherwise: the crate wide tolerance, see `utils::set_epsilon`
+pub fn default_bias() -> f64 {
+    epsilon()
+}
 
-    // Return a list of the intersections
-    Intersections::from(vec![i1, i2])
+/// Precomputes the point in world space where the intersection occurred,
+/// the eye vector (pointing back toward the camera) and the normal vector at that point
+///
+/// If the normal points away from the eye the hit is on the inside of the shape,
+/// in which case the normal is inverted so the surface is lit properly.
+///
+/// The hit is taken on its own, as if no other object overlapped it: entering it from air or
+/// leaving into air. `prepare_computations_with` looks at the rest of the intersections.
+pub fn prepare_computations<S: Shape + Copy>(
+    intersection: &Intersection<S>,
+    ray: &Ray,
+) -> Computations<S> {
+    let xs = Intersections::from(vec![*intersection]);
+    prepare_computations_with(intersection, ray, &xs, default_bias())
 }
 
-pub fn hit(xs: &Intersections<Sphere>) -> Option<Intersection<Sphere>> {
-    xs.items
-        .iter() // Iterate over the intersections
-        .filter(|i| i.t >= 0.0) // Only consider intersections with t >= 0.0
-        .min_by(|a, b| a.t.partial_cmp(&b.t).unwrap()) // Find the intersection with the smallest t
-        .copied() // Convert the reference to an owned value
+/// `prepare_computations` for a hit among all of the ray's intersections `xs`, which tell what
+/// the ray is inside of (a bubble in a glass of water) for the refractive indices either side
+/// of the surface, with `over_point` and `under_point` pushed `bias` units off the surface
+pub fn prepare_computations_with<S: Shape + Copy>(
+    intersection: &Intersection<S>,
+    ray: &Ray,
+    xs: &Intersections<S>,
+    bias: f64,
+) -> Computations<S> {
+    let point = ray.position(intersection.t);
+    let eyev = -ray.direction();
+    let mut normalv = intersection.object.normal_at(point, intersection);
+    let inside = normalv.dot(&eyev) < 0.0;
+    if inside {
+        normalv = -normalv;
+    }
+    let (n1, n2) = refractive_indices(intersection, xs);
+    Computations {
+        t: intersection.t,
+        object: intersection.object,
+        point,
+        eyev,
+        normalv,
+        reflectv: reflect(ray.direction(), normalv),
+        over_point: point + normalv * bias,
+        under_point: point - normalv * bias,
+        n1,
+        n2,
+        inside,
+    }
 }
 
-pub fn transform(ray: &Ray, translation_matrix: M4x4) -> Ray {
-    let new_origin = translation_matrix * ray.origin;
-    let new_direction = translation_matrix * ray.direction;
-    Ray::new(new_origin, new_direction)
+/// Walks the intersections up to the hit keeping track of which objects the ray is inside of,
+/// the most recently entered one being the medium it's travelling through
+fn refractive_indices<S: Shape>(hit: &Intersection<S>, xs: &Intersections<S>) -> (f64, f64) {
+    let mut containers: Vec<&S> = Vec::new();
+    let index_of = |containers: &[&S]| {
+        containers
+            .last()
+            .map_or(1.0, |object| object.material().refractive_index)
+    };
+    for i in xs {
+        let is_hit = i.t == hit.t && i.object.id() == hit.object.id();
+        let n1 = index_of(&containers);
+        match containers
+            .iter()
+            .position(|object| object.id() == i.object.id())
+        {
+            Some(position) => {
+                containers.remove(position);
+            }
+            None => containers.push(&i.object),
+        }
+        if is_hit {
+            return (n1, index_of(&containers));
+        }
+    }
+    (1.0, 1.0)
 }
 
 pub fn reflect(incoming: Vector, normal: Vector) -> Vector {
     incoming - normal * 2.0_f64 * incoming.dot(&normal)
 }
 
+/// Fraction of the light a transparent surface reflects at the hit, the rest being refracted
+/// through it: little when looking straight at glass, nearly all of it at grazing angles and
+/// all of it under total internal reflection. Schlick's approximation of the Fresnel equations.
+pub fn schlick<S>(comps: &Computations<S>) -> f64 {
+    let mut cos = comps.eyev.dot(&comps.normalv);
+    // leaving a denser medium the angle that counts is the one the ray leaves at
+    if comps.n1 > comps.n2 {
+        let n = comps.n1 / comps.n2;
+        let sin2_t = n * n * (1.0 - cos * cos);
+        if sin2_t > 1.0 {
+            return 1.0;
+        }
+        cos = (1.0 - sin2_t).sqrt();
+    }
+    let r0 = ((comps.n1 - comps.n2) / (comps.n1 + comps.n2)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::error::Error;
     use crate::light::Material;
-    use crate::matrix::IDENTITY_MATRIX_4X4;
+    use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
     use crate::matrix_transformations::{rotation_z, scaling, translation};
     use crate::rays::{
-        hit, intersect, reflect, transform, Intersection, Intersections, Ray, Sphere,
+        default_bias, hit, intersect, prepare_computations, prepare_computations_with, reflect,
+        schlick, Intersection, Intersections, Ray, Sphere,
     };
+    use crate::shape::Shape;
     use crate::tuple::{Point, Vector};
+    use crate::utils::{equal_f64, EPSILON};
     use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
     #[test]
@@ -305,8 +637,14 @@ mod tests {
         let origin = Point::new_point(1.0, 2.0, 3.0);
         let direction = Vector::new(4.0, 5.0, 6.0);
         let r = Ray::new(origin, direction);
-        assert_eq!(r.origin, origin);
-        assert_eq!(r.direction, direction);
+        assert_eq!(r.origin(), origin);
+        assert_eq!(r.direction(), direction);
+    }
+
+    #[test]
+    fn displaying_a_ray() {
+        let r = Ray::new(Point::new_point(1.0, 2.5, 3.0), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(r.to_string(), "(1, 2.5, 3) -> (0, 0, -1)");
     }
 
     #[test]
@@ -338,6 +676,17 @@ mod tests {
         assert_eq!(xs[1].t, 5.0);
     }
 
+    #[test]
+    fn ray_tangent_despite_rounding() {
+        // 0.1 * 0.1 isn't exactly 0.01, which makes this discriminant come out a hair below zero
+        let r = Ray::new(Point::new_point(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 0.1));
+        let s = Sphere::new();
+        let xs = intersect(&r, s);
+        assert_eq!(xs.size(), 2);
+        assert_eq!(r.position(xs[0].t), Point::new_point(0.0, 1.0, 0.0));
+        assert_eq!(xs[0].t, xs[1].t);
+    }
+
     #[test]
     fn ray_misses_sphere() {
         let r = Ray::new(Point::new_point(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
@@ -374,6 +723,14 @@ mod tests {
         let i = Intersection::new(3.5, s);
         assert_eq!(i.t, 3.5);
         assert_eq!(i.object, s);
+        assert_eq!(i.uv, None);
+    }
+
+    #[test]
+    fn intersection_with_uv() {
+        let i = Intersection::with_uv(3.5, Sphere::new(), 0.2, 0.4);
+        assert_eq!(i.t, 3.5);
+        assert_eq!(i.uv, Some((0.2, 0.4)));
     }
 
     #[test]
@@ -388,6 +745,23 @@ mod tests {
         assert_eq!(xs.size(), 2);
     }
 
+    #[test]
+    fn intersections_stay_sorted() {
+        let s = Sphere::new();
+        let mut xs =
+            Intersections::from(vec![Intersection::new(3.0, s), Intersection::new(-1.0, s)]);
+        xs.insert(Intersection::new(1.0, s));
+        xs.merge(Intersections::from(vec![
+            Intersection::new(2.0, s),
+            Intersection::new(-4.0, s),
+        ]));
+        let ts: Vec<f64> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, [-4.0, -1.0, 1.0, 2.0, 3.0]);
+        assert_eq!(xs.hit().unwrap().t, 1.0);
+        assert_eq!(xs.into_iter().count(), 5);
+        assert!(Intersections::<Sphere>::new().hit().is_none());
+    }
+
     #[test]
     fn intersect_sets_object_on_intersection() {
         let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
@@ -432,43 +806,221 @@ mod tests {
         assert_eq!(i, None);
     }
 
+    #[test]
+    fn precomputing_state_of_intersection() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(4.0, shape);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(comps.t, i.t);
+        assert_eq!(comps.object, i.object);
+        assert_eq!(comps.point, Point::new_point(0.0, 0.0, -1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(comps.normalv, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let r = Ray::new(Point::new_point(0.0, 0.6, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.2, Sphere::new());
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(comps.normalv, Vector::new(0.0, 0.6, -0.8));
+        assert_eq!(comps.reflectv, Vector::new(0.0, 0.96, -0.28));
+    }
+
+    #[test]
+    fn hit_when_intersection_occurs_on_outside() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, Sphere::new());
+        let comps = prepare_computations(&i, &r);
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn hit_when_intersection_occurs_on_inside() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, Sphere::new());
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(comps.point, Point::new_point(0.0, 0.0, 1.0));
+        assert_eq!(comps.eyev, Vector::new(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+        // normal would have been (0, 0, 1), but is inverted
+        assert_eq!(comps.normalv, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn hit_should_offset_the_point() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(translation(0.0, 0.0, 1.0)).unwrap();
+        let i = Intersection::new(5.0, s);
+        let comps = prepare_computations(&i, &r);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+    }
+
+    #[test]
+    fn bias_sets_how_far_the_point_is_offset() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, Sphere::new());
+        let comps = prepare_computations_with(&i, &r, &Intersections::from(vec![i]), 0.01);
+        assert_eq!(comps.over_point, Point::new_point(0.0, 0.0, -1.01));
+        assert_eq!(comps.under_point, Point::new_point(0.0, 0.0, -0.99));
+    }
+
+    fn glass_sphere(transform: M4x4, refractive_index: f64) -> Sphere {
+        let mut s = Sphere::new();
+        s.set_transform(transform).unwrap();
+        s.material.refractive_index = refractive_index;
+        s
+    }
+
+    #[test]
+    fn schlick_under_total_internal_reflection() {
+        let shape = glass_sphere(IDENTITY_MATRIX_4X4, 1.5);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = Intersections::from(vec![
+            Intersection::new(-FRAC_1_SQRT_2, shape),
+            Intersection::new(FRAC_1_SQRT_2, shape),
+        ]);
+        let comps = prepare_computations_with(&xs[1], &r, &xs, default_bias());
+        assert_eq!(schlick(&comps), 1.0);
+    }
+
+    #[test]
+    fn schlick_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere(IDENTITY_MATRIX_4X4, 1.5);
+        let r = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = Intersections::from(vec![
+            Intersection::new(-1.0, shape),
+            Intersection::new(1.0, shape),
+        ]);
+        let comps = prepare_computations_with(&xs[1], &r, &xs, default_bias());
+        assert!(equal_f64(schlick(&comps), 0.04));
+    }
+
+    #[test]
+    fn schlick_with_a_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere(IDENTITY_MATRIX_4X4, 1.5);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.99, -2.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let xs = Intersections::from(vec![Intersection::new(1.8589, shape)]);
+        let comps = prepare_computations_with(&xs[0], &r, &xs, default_bias());
+        assert!(equal_f64(schlick(&comps), 0.48873));
+    }
+
+    #[test]
+    fn refractive_indices_at_nested_surfaces() {
+        let a = glass_sphere(scaling(2.0, 2.0, 2.0), 1.5);
+        let b = glass_sphere(translation(0.0, 0.0, -0.25), 2.0);
+        let c = glass_sphere(translation(0.0, 0.0, 0.25), 2.5);
+        let r = Ray::new(Point::new_point(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = Intersections::from(vec![
+            Intersection::new(2.0, a),
+            Intersection::new(2.75, b),
+            Intersection::new(3.25, c),
+            Intersection::new(4.75, b),
+            Intersection::new(5.25, c),
+            Intersection::new(6.0, a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in expected.into_iter().enumerate() {
+            let comps = prepare_computations_with(&xs[i], &r, &xs, default_bias());
+            assert_eq!((comps.n1, comps.n2), (n1, n2), "intersection {}", i);
+        }
+
+        let alone = prepare_computations(&xs[0], &r);
+        assert_eq!((alone.n1, alone.n2), (1.0, 1.5));
+    }
+
+    #[test]
+    fn setting_same_transform_keeps_cached_inverse() {
+        let mut s = Sphere::new();
+        s.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        let inverse = s.inverse_transform();
+        s.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
+        assert_eq!(s.inverse_transform().matrix, inverse.matrix);
+        s.set_transform(IDENTITY_MATRIX_4X4).unwrap();
+        assert_eq!(s.inverse_transform(), IDENTITY_MATRIX_4X4);
+    }
+
     #[test]
     fn translating_a_ray() {
         let r = Ray::new(Point::new_point(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
         let m = translation(3.0, 4.0, 5.0);
-        let r2 = transform(&r, m);
-        assert_eq!(r2.origin, Point::new_point(4.0, 6.0, 8.0));
-        assert_eq!(r2.direction, Vector::new(0.0, 1.0, 0.0));
+        let r2 = r.transformed(&m);
+        assert_eq!(r2.origin(), Point::new_point(4.0, 6.0, 8.0));
+        assert_eq!(r2.direction(), Vector::new(0.0, 1.0, 0.0));
     }
 
     #[test]
     fn scaling_a_ray() {
         let r = Ray::new(Point::new_point(1.0, 2.0, 3.0), Vector::new(0.0, 1.0, 0.0));
         let m = scaling(2.0, 3.0, 4.0);
-        let r2 = transform(&r, m);
-        assert_eq!(r2.origin, Point::new_point(2.0, 6.0, 12.0));
-        assert_eq!(r2.direction, Vector::new(0.0, 3.0, 0.0));
+        let r2 = r.transformed(&m);
+        assert_eq!(r2.origin(), Point::new_point(2.0, 6.0, 12.0));
+        assert_eq!(r2.direction(), Vector::new(0.0, 3.0, 0.0));
     }
 
     #[test]
     fn sphere_default_transform() {
         let s = Sphere::new();
-        assert_eq!(s.transform, IDENTITY_MATRIX_4X4);
+        assert_eq!(s.transform(), IDENTITY_MATRIX_4X4);
     }
 
     #[test]
     fn changing_sphere_transform() {
         let mut s = Sphere::new();
         let t = translation(2.0, 3.0, 4.0);
-        s.set_transform(t);
-        assert_eq!(s.transform, t)
+        s.set_transform(t).unwrap();
+        assert_eq!(s.transform(), t)
+    }
+
+    #[test]
+    fn degenerate_sphere_transform_is_rejected() {
+        let mut s = Sphere::new();
+        let t = translation(2.0, 3.0, 4.0);
+        s.set_transform(t).unwrap();
+
+        let flattened = scaling(1.0, 0.0, 1.0);
+        assert!(matches!(
+            s.set_transform(flattened),
+            Err(Error::DegenerateTransform(m)) if *m == flattened
+        ));
+        // the previous transform is kept
+        assert_eq!(s.transform(), t);
+    }
+
+    #[test]
+    fn ellipsoids() {
+        let e =
+            Sphere::ellipsoid(Point::new_point(0.0, 1.0, 0.0), Vector::new(3.0, 1.0, 0.5)).unwrap();
+        assert_eq!(e.bounds().min(), Point::new_point(-3.0, 0.0, -0.5));
+        assert_eq!(e.bounds().max(), Point::new_point(3.0, 2.0, 0.5));
+        let r = Ray::new(Point::new_point(-5.0, 1.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let xs = intersect(&r, e);
+        assert!(equal_f64(xs[0].t, 2.0) && equal_f64(xs[1].t, 8.0));
+        let flat = Sphere::ellipsoid(Point::new_point(0.0, 0.0, 0.0), Vector::new(1.0, 0.0, 1.0));
+        assert!(matches!(flat, Err(Error::DegenerateTransform(_))));
     }
 
     #[test]
     fn intersecting_scaled_sphere_with_ray() {
         let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let mut s = Sphere::new();
-        s.set_transform(scaling(2.0, 2.0, 2.0));
+        s.set_transform(scaling(2.0, 2.0, 2.0)).unwrap();
         let xs = intersect(&r, s);
         assert_eq!(xs.size(), 2);
         assert_eq!(xs[0].t, 3.0);
@@ -479,7 +1031,7 @@ mod tests {
     fn intersecting_translated_sphere_with_ray() {
         let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let mut s = Sphere::new();
-        s.set_transform(translation(5.0, 0.0, 0.0));
+        s.set_transform(translation(5.0, 0.0, 0.0)).unwrap();
         let xs = intersect(&r, s);
         assert_eq!(xs.size(), 0);
     }
@@ -487,7 +1039,7 @@ mod tests {
     #[test]
     fn normal_on_sphere_at_point_x_axis() {
         let s = Sphere::new();
-        let norm = s.normal_at(Point::new_point(1.0, 0.0, 0.0));
+        let norm = s.normal_at(Point::new_point(1.0, 0.0, 0.0), &Intersection::new(1.0, s));
         assert_eq!(norm, Vector::new(1.0, 0.0, 0.0));
         assert_eq!(norm, norm.normalize());
     }
@@ -495,14 +1047,14 @@ mod tests {
     #[test]
     fn normal_on_sphere_at_point_y_axis() {
         let s = Sphere::new();
-        let norm = s.normal_at(Point::new_point(0.0, 1.0, 0.0));
+        let norm = s.normal_at(Point::new_point(0.0, 1.0, 0.0), &Intersection::new(1.0, s));
         assert_eq!(norm, Vector::new(0.0, 1.0, 0.0));
         assert_eq!(norm, norm.normalize());
     }
     #[test]
     fn normal_on_sphere_at_point_z_axis() {
         let s = Sphere::new();
-        let norm = s.normal_at(Point::new_point(0.0, 0.0, 1.0));
+        let norm = s.normal_at(Point::new_point(0.0, 0.0, 1.0), &Intersection::new(1.0, s));
         assert_eq!(norm, Vector::new(0.0, 0.0, 1.0));
         assert_eq!(norm, norm.normalize());
     }
@@ -511,7 +1063,7 @@ mod tests {
     fn normal_on_sphere_at_non_axial_point() {
         let s = Sphere::new();
         let val = (3.0_f64).sqrt() / 3.0;
-        let norm = s.normal_at(Point::new_point(val, val, val));
+        let norm = s.normal_at(Point::new_point(val, val, val), &Intersection::new(1.0, s));
         assert_eq!(norm, Vector::new(val, val, val));
         assert_eq!(norm, norm.normalize());
     }
@@ -519,8 +1071,11 @@ mod tests {
     #[test]
     fn normal_on_translated_sphere() {
         let mut s = Sphere::new();
-        s.set_transform(translation(0.0, 1.0, 0.0));
-        let n = s.normal_at(Point::new_point(0.0, 1.70711, -FRAC_1_SQRT_2));
+        s.set_transform(translation(0.0, 1.0, 0.0)).unwrap();
+        let n = s.normal_at(
+            Point::new_point(0.0, 1.70711, -FRAC_1_SQRT_2),
+            &Intersection::new(1.0, s),
+        );
         assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
     }
 
@@ -528,12 +1083,11 @@ mod tests {
     fn normal_on_transformed_sphere() {
         let mut s = Sphere::new();
         let m = scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0);
-        s.set_transform(m);
-        let n = s.normal_at(Point::new_point(
-            0.0,
-            (2.0_f64.sqrt()) / 2.0,
-            -(2.0_f64.sqrt()) / 2.0,
-        ));
+        s.set_transform(m).unwrap();
+        let n = s.normal_at(
+            Point::new_point(0.0, (2.0_f64.sqrt()) / 2.0, -(2.0_f64.sqrt()) / 2.0),
+            &Intersection::new(1.0, s),
+        );
         assert_eq!(n, Vector::new(0.0, 0.97014, -0.24254));
     }
 
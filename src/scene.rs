@@ -0,0 +1,1399 @@
+//! Scenes described as JSON, so they can be handed over from outside of Rust
+//! (a web frontend, a config file) without mirroring the crate's types.
+//!
+//! ```json
+//! {
+//!   "camera": { "width": 100, "height": 50, "field_of_view": 1.0472,
+//!               "from": [0, 1.5, -5], "to": [0, 1, 0], "up": [0, 1, 0],
+//!               "samples": 4, "seed": 7, "filter": { "gaussian": { "radius": 1.5, "alpha": 2 } },
+//!               "adaptive": { "min_samples": 4, "threshold": 0.01 },
+//!               "exposure": { "auto": { "key": 0.18 } }, "tone_map": "reinhard" },
+//!   "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1] } ],
+//!   "sphere_lights": [ { "position": [5, 10, -5], "radius": 1, "intensity": [0.5, 0.5, 0.5] } ],
+//!   "area_lights": [ { "corner": [-1, 5, -1], "u": [2, 0, 0], "v": [0, 0, 2],
+//!                      "intensity": [1, 1, 1] } ],
+//!   "spot_lights": [ { "position": [0, 5, 0], "direction": [0, -1, 0], "angle": 0.5,
+//!                      "intensity": [1, 1, 1], "attenuation": { "linear": 0.1, "quadratic": 0.05 } } ],
+//!   "sky": { "elevation": 0.8, "azimuth": 0.5, "turbidity": 3 },
+//!   "fog": { "color": [0.7, 0.75, 0.8], "density": 0.02 },
+//!   "bias": 0.0001,
+//!   "ray_limits": { "reflection_depth": 3, "refraction_depth": 6, "bounces": 8 },
+//!   "clip_planes": [ { "point": [0, 0, 0], "normal": [0, 0, -1], "cap": { "color": [1, 0, 0] } } ],
+//!   "ambient": { "hemisphere": { "sky": [0.1, 0.1, 0.15], "ground": [0.05, 0.04, 0.03] } },
+//!   "spheres": [
+//!     { "transform": [ { "scale": [0.5, 0.5, 0.5] }, { "translate": [1.5, 0.5, -0.5] } ],
+//!       "material": { "color": [0.5, 1, 0.1], "diffuse": 0.7, "specular": 0.3 } }
+//!   ],
+//!   "cubes": [
+//!     { "transform": [ { "scale": [10, 0.1, 10] }, { "translate": [0, -0.1, 0] } ] }
+//!   ],
+//!   "triangles": [
+//!     { "points": [[-1, 0, 2], [0, 2, 2], [1, 0, 2]], "material": { "color": [0, 0, 1] } }
+//!   ],
+//!   "meshes": [
+//!     { "vertices": [[0, 0, 0], [1, 0, 0], [1, 0, 1], [0, 0, 1]],
+//!       "faces": [[0, 2, 1], [0, 3, 2]], "transform": [ { "translate": [2, 0, 0] } ],
+//!       "material": { "normal_map": 0 } }
+//!   ],
+//!   "skybox": ["left.png", "front.png", "right.png", "back.png", "up.png", "down.png"],
+//!   "normal_maps": [ { "image": "bumps.png", "strength": 0.5 } ]
+//! }
+//! ```
+//!
+//! Spheres and cubes are the unit shapes, placed by their `transform`. Triangles take their
+//! three corners (`points`) as well, in the space their transform starts from, and are
+//! smooth shaded when given a `normals` for each corner. A mesh lists its `vertices` and
+//! `faces` of three vertex indices each, counter-clockwise seen from the front, and becomes a
+//! group of triangles, smooth when it has as many `normals` as vertices (see `Mesh::group`).
+//! Transforms are applied in the order they are listed, angles are in radians and every
+//! material field falls back to the `Material::new` default. The camera's `samples`, `seed`
+//! and `filter` are optional, one ray per pixel, seed 0 and plain averaging by default.
+//! Filters are `box`, `tent` or `gaussian`, see `Filter`. `adaptive` turns on adaptive sampling
+//! with `samples` as the most rays a pixel gets, both its fields are optional. `exposure` is `{ "fixed": 2 }` or
+//! `{ "auto": {} }` (the key defaults to middle grey) and `tone_map` is `linear` (the default)
+//! or `reinhard`.
+//!
+//! Point and spot lights can fade with distance, their intensity divided by
+//! `constant + linear * d + quadratic * d²` (1, 0 and 0 when left out), see `Attenuation`.
+//!
+//! Lights can be linked to objects by naming light groups in a light's `groups` and an
+//! object's `light_groups`, a light then only shines on objects sharing one of its groups. Leaving
+//! either out means every group.
+//!
+//! `coordinates` sets the axes everything else in the file is written in, e.g.
+//! `{ "handedness": "right", "up": "z" }` for a scene exported from Blender. It's the tracer's
+//! own left-handed y up by default, see `Convention`.
+//!
+//! A material's `pattern` colors it with `gradient`, `ring` or `checkers` between two colors
+//! `a` and `b`, e.g. `{ "checkers": { "a": [1, 1, 1], "b": [0, 0, 0] } }`. `uv_checkers`
+//! takes a `width` and `height` too and is wrapped around the object by its `uv_map`:
+//! `spherical`, `planar`, `cylindrical` or `cube`, the shape's own choice when left out.
+//! `marble`, `wood` and `turbulent_stripes` are noisy, with an optional `turbulence` for how
+//! much.
+//!
+//! An object's `visibility` hides it from some kinds of rays, e.g. `{ "shadows": false }` for
+//! one that casts no shadow.
+//!
+//! The `skybox` faces and `normal_maps` are .png or PPM images, read when the scene is built
+//! from paths relative to the working directory (`SceneDescription::assets` lists them). A
+//! material's `normal_map` is an index into `normal_maps`, the strength is 1 when left out.
+//!
+//! The crate's own types (`World`, the shapes and lights, `Material`, `Color`, `M4x4`) also
+//! derive serde's `Serialize` and `Deserialize` with this feature. That JSON mirrors the types
+//! field for field, transforms as rows of numbers, and is meant for saving a built world and
+//! restoring it exactly, e.g. in regression tests, rather than for writing by hand.
+//!
+//! Scene files may come from anywhere, so [`parse_scene`] rejects oversized input and
+//! resolutions before anything gets allocated for them, and cameras with no pixels. It's the
+//! entry point fuzzed in `fuzz/`.
+
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::adaptive::Adaptive;
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+use crate::clip::ClipPlane;
+use crate::color::Color;
+use crate::coordinates::{Convention, Handedness, UpAxis};
+use crate::cube::Cube;
+use crate::error::{Error, Result};
+use crate::exposure::{Exposure, ToneMap, MIDDLE_GREY};
+use crate::filter::Filter;
+use crate::fog::Fog;
+use crate::light::{
+    AmbientLight, AreaLight, Attenuation, LightGroups, Material, PointLight, SphereLight, SpotLight,
+};
+use crate::limits::RayLimits;
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::matrix_transformations::{
+    rotation_x, rotation_y, rotation_z, scaling, shearing, translation, view_transform,
+};
+use crate::mesh::Mesh;
+use crate::normal_map::NormalMap;
+use crate::pattern::{
+    Checkers, Gradient, Marble, MaterialPattern, Ring, TurbulentStripes, UvCheckers, Wood,
+};
+use crate::quad::Quad;
+use crate::rays::{Sphere, Visibility};
+use crate::shape::Object;
+use crate::sky::Sky;
+use crate::skybox::Skybox;
+use crate::triangle::{SmoothTriangle, Triangle};
+use crate::tuple::{Point, Vector};
+use crate::uv::UvMap;
+use crate::world::World;
+
+/// Largest scene file accepted by `parse_scene`
+pub const MAX_SCENE_BYTES: usize = 16 * 1024 * 1024;
+
+pub use crate::camera::MAX_RESOLUTION;
+
+/// Largest number of pixels accepted by `parse_scene`, a canvas this size takes about 1.5 GB
+pub const MAX_PIXELS: usize = 64 * 1024 * 1024;
+
+/// Parses a scene file without trusting it: malformed or oversized input is an error,
+/// never a panic or an allocation the size of the requested image
+pub fn parse_scene(bytes: &[u8]) -> Result<SceneDescription> {
+    if bytes.len() > MAX_SCENE_BYTES {
+        return Err(Error::SceneTooLarge(format!(
+            "{} bytes, the limit is {}",
+            bytes.len(),
+            MAX_SCENE_BYTES
+        )));
+    }
+    let description: SceneDescription = serde_json::from_slice(bytes)?;
+    let CameraDescription { width, height, .. } = description.camera;
+    if width == 0 || height == 0 {
+        return Err(invalid(format!(
+            "{}x{} camera, it needs at least one pixel per side",
+            width, height
+        )));
+    }
+    if width > MAX_RESOLUTION || height > MAX_RESOLUTION || width * height > MAX_PIXELS {
+        return Err(Error::SceneTooLarge(format!(
+            "{}x{} camera, the limit is {} pixels and {} per side",
+            width, height, MAX_PIXELS, MAX_RESOLUTION
+        )));
+    }
+    Ok(description)
+}
+
+/// A scene ready to render: the world and the camera looking at it
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub camera: Camera,
+    pub world: World,
+}
+
+impl Scene {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, name = "scene_load")
+    )]
+    pub fn from_json(json: &str) -> Result<Scene> {
+        parse_scene(json.as_bytes())?.build()
+    }
+}
+
+/// The JSON layout of a scene, see the module docs for an example
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SceneDescription {
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+    #[serde(default)]
+    pub sphere_lights: Vec<SphereLightDescription>,
+    #[serde(default)]
+    pub area_lights: Vec<AreaLightDescription>,
+    #[serde(default)]
+    pub spot_lights: Vec<SpotLightDescription>,
+    pub ambient: Option<AmbientDescription>,
+    pub sky: Option<SkyDescription>,
+    pub fog: Option<FogDescription>,
+    /// Shadow acne offset, see `World::bias`
+    pub bias: Option<f64>,
+    #[serde(default)]
+    pub ray_limits: RayLimitsDescription,
+    #[serde(default)]
+    pub clip_planes: Vec<ClipPlaneDescription>,
+    /// Axes the rest of the file is written in, the tracer's own by default
+    #[serde(default)]
+    pub coordinates: CoordinatesDescription,
+    #[serde(default)]
+    pub spheres: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub cubes: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub triangles: Vec<TriangleDescription>,
+    #[serde(default)]
+    pub meshes: Vec<MeshDescription>,
+    /// Images for the faces in `CubeFace::ALL` order, see `Skybox`
+    pub skybox: Option<[String; 6]>,
+    /// For materials to pick by index, see `World::normal_maps`
+    #[serde(default)]
+    pub normal_maps: Vec<NormalMapDescription>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NormalMapDescription {
+    pub image: String,
+    #[serde(default = "full_strength")]
+    pub strength: f64,
+}
+
+fn full_strength() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDescription {
+    pub width: usize,
+    pub height: usize,
+    pub field_of_view: f64,
+    pub from: [f64; 3],
+    pub to: [f64; 3],
+    pub up: [f64; 3],
+    /// Rays per pixel, see `Camera::set_samples`
+    #[serde(default = "one_sample")]
+    pub samples: usize,
+    #[serde(default)]
+    pub seed: u64,
+    /// How samples are weighted into pixels, see `Camera::set_filter`
+    pub filter: Option<FilterDescription>,
+    pub adaptive: Option<AdaptiveDescription>,
+    pub exposure: Option<ExposureDescription>,
+    #[serde(default)]
+    pub tone_map: ToneMapDescription,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ExposureDescription {
+    Fixed(f64),
+    Auto {
+        #[serde(default = "middle_grey")]
+        key: f64,
+    },
+}
+
+fn middle_grey() -> f64 {
+    MIDDLE_GREY
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapDescription {
+    #[default]
+    Linear,
+    Reinhard,
+}
+
+/// `{ "handedness": "right", "up": "z" }` for a scene from Blender, both fields default to
+/// the tracer's own left-handed y up
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoordinatesDescription {
+    pub handedness: HandednessDescription,
+    pub up: UpAxisDescription,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandednessDescription {
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpAxisDescription {
+    #[default]
+    Y,
+    Z,
+}
+
+/// `{ "box": { "radius": 0.5 } }`, `{ "tent": { "radius": 1 } }` or
+/// `{ "gaussian": { "radius": 1.5, "alpha": 2 } }`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum FilterDescription {
+    Box { radius: f64 },
+    Tent { radius: f64 },
+    Gaussian { radius: f64, alpha: f64 },
+}
+
+fn one_sample() -> usize {
+    1
+}
+
+/// See `Camera::set_adaptive`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveDescription {
+    #[serde(default = "min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "threshold")]
+    pub threshold: f64,
+}
+
+fn min_samples() -> usize {
+    Adaptive::default().min_samples
+}
+
+fn threshold() -> f64 {
+    Adaptive::default().threshold
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LightDescription {
+    pub position: [f64; 3],
+    pub intensity: [f64; 3],
+    /// Light groups the light shines on, all of them when left out
+    pub groups: Option<Vec<String>>,
+    /// How the light fades with distance, not at all when left out
+    pub attenuation: Option<AttenuationDescription>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SphereLightDescription {
+    pub position: [f64; 3],
+    pub radius: f64,
+    pub intensity: [f64; 3],
+    /// Shadow rays per shaded point, see `SphereLight::set_samples`
+    pub samples: Option<usize>,
+    pub groups: Option<Vec<String>>,
+}
+
+/// A light shaped like the quad `corner + a * u + b * v`, shining toward `u x v`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AreaLightDescription {
+    pub corner: [f64; 3],
+    pub u: [f64; 3],
+    pub v: [f64; 3],
+    pub intensity: [f64; 3],
+    /// Shadow rays per shaded point, see `AreaLight::set_samples`
+    pub samples: Option<usize>,
+    pub groups: Option<Vec<String>>,
+}
+
+/// Spot lights without gobos, those can only be set from code for now
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpotLightDescription {
+    pub position: [f64; 3],
+    pub direction: [f64; 3],
+    /// Half the cone's opening angle, in radians
+    pub angle: f64,
+    pub intensity: [f64; 3],
+    pub groups: Option<Vec<String>>,
+    pub attenuation: Option<AttenuationDescription>,
+}
+
+/// See `Attenuation`, fields left out keep the values of a light that doesn't fade
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttenuationDescription {
+    pub constant: f64,
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+impl Default for AttenuationDescription {
+    fn default() -> Self {
+        let none = Attenuation::NONE;
+        AttenuationDescription {
+            constant: none.constant,
+            linear: none.linear,
+            quadratic: none.quadratic,
+        }
+    }
+}
+
+impl AttenuationDescription {
+    fn build(self) -> Attenuation {
+        Attenuation::new(self.constant, self.linear, self.quadratic)
+    }
+}
+
+/// Sun position in radians, see `Sky::new`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SkyDescription {
+    pub elevation: f64,
+    #[serde(default)]
+    pub azimuth: f64,
+    #[serde(default = "clear_day")]
+    pub turbidity: f64,
+}
+
+fn clear_day() -> f64 {
+    3.0
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FogDescription {
+    pub color: [f64; 3],
+    pub density: f64,
+}
+
+/// See `RayLimits`, fields left out keep their defaults
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RayLimitsDescription {
+    pub reflection_depth: u32,
+    pub refraction_depth: u32,
+    pub bounces: u32,
+}
+
+impl Default for RayLimitsDescription {
+    fn default() -> Self {
+        let limits = RayLimits::default();
+        RayLimitsDescription {
+            reflection_depth: limits.reflection_depth,
+            refraction_depth: limits.refraction_depth,
+            bounces: limits.bounces,
+        }
+    }
+}
+
+/// Removes everything on the side `normal` points to, filling the cut with `cap` if given
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClipPlaneDescription {
+    pub point: [f64; 3],
+    pub normal: [f64; 3],
+    pub cap: Option<MaterialDescription>,
+}
+
+/// `{ "flat": [r, g, b] }` or `{ "hemisphere": { "sky": [r, g, b], "ground": [r, g, b] } }`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum AmbientDescription {
+    Flat([f64; 3]),
+    Hemisphere { sky: [f64; 3], ground: [f64; 3] },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ObjectDescription {
+    #[serde(default)]
+    pub transform: Vec<TransformDescription>,
+    #[serde(default)]
+    pub material: MaterialDescription,
+    /// Light groups lighting the object, all of them when left out
+    pub light_groups: Option<Vec<String>>,
+    #[serde(default)]
+    pub visibility: VisibilityDescription,
+}
+
+/// A triangle's corners and the same placement and looks as any other object
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TriangleDescription {
+    pub points: [[f64; 3]; 3],
+    /// One per corner for a smooth shaded triangle
+    pub normals: Option<[[f64; 3]; 3]>,
+    #[serde(default)]
+    pub transform: Vec<TransformDescription>,
+    #[serde(default)]
+    pub material: MaterialDescription,
+    pub light_groups: Option<Vec<String>>,
+    #[serde(default)]
+    pub visibility: VisibilityDescription,
+}
+
+/// A triangle mesh, placed and colored as a whole
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MeshDescription {
+    pub vertices: Vec<[f64; 3]>,
+    /// Indices into `vertices`
+    pub faces: Vec<[usize; 3]>,
+    /// One per vertex, or none for flat faces
+    #[serde(default)]
+    pub normals: Vec<[f64; 3]>,
+    #[serde(default)]
+    pub transform: Vec<TransformDescription>,
+    #[serde(default)]
+    pub material: MaterialDescription,
+    pub light_groups: Option<Vec<String>>,
+    #[serde(default)]
+    pub visibility: VisibilityDescription,
+}
+
+/// Which rays see an object, every field defaults to `true`
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VisibilityDescription {
+    pub camera: bool,
+    pub reflections: bool,
+    pub shadows: bool,
+}
+
+impl Default for VisibilityDescription {
+    fn default() -> Self {
+        VisibilityDescription {
+            camera: true,
+            reflections: true,
+            shadows: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformDescription {
+    Translate([f64; 3]),
+    Scale([f64; 3]),
+    RotateX(f64),
+    RotateY(f64),
+    RotateZ(f64),
+    /// xy, xz, yx, yz, zx, zy
+    Shear([f64; 6]),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaterialDescription {
+    pub color: [f64; 3],
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+    pub pattern: Option<PatternDescription>,
+    pub uv_map: Option<UvMapDescription>,
+    /// Index into the scene's `normal_maps`
+    pub normal_map: Option<usize>,
+}
+
+impl Default for MaterialDescription {
+    fn default() -> Self {
+        let material = Material::new();
+        MaterialDescription {
+            color: [
+                material.color.red(),
+                material.color.green(),
+                material.color.blue(),
+            ],
+            ambient: material.ambient,
+            diffuse: material.diffuse,
+            specular: material.specular,
+            shininess: material.shininess,
+            reflective: material.reflective,
+            transparency: material.transparency,
+            refractive_index: material.refractive_index,
+            pattern: None,
+            uv_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+/// `{ "checkers": { "a": [1, 1, 1], "b": [0, 0, 0] } }`, `gradient` or `ring` the same way
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum PatternDescription {
+    Gradient {
+        a: [f64; 3],
+        b: [f64; 3],
+    },
+    Ring {
+        a: [f64; 3],
+        b: [f64; 3],
+    },
+    Checkers {
+        a: [f64; 3],
+        b: [f64; 3],
+    },
+    UvCheckers {
+        width: f64,
+        height: f64,
+        a: [f64; 3],
+        b: [f64; 3],
+    },
+    Marble {
+        a: [f64; 3],
+        b: [f64; 3],
+        turbulence: Option<f64>,
+    },
+    Wood {
+        a: [f64; 3],
+        b: [f64; 3],
+        turbulence: Option<f64>,
+    },
+    TurbulentStripes {
+        a: [f64; 3],
+        b: [f64; 3],
+        turbulence: Option<f64>,
+    },
+}
+
+impl PatternDescription {
+    pub fn build(&self) -> MaterialPattern {
+        match *self {
+            PatternDescription::Gradient { a, b } => Gradient::new(color(a), color(b)).into(),
+            PatternDescription::Ring { a, b } => Ring::new(color(a), color(b)).into(),
+            PatternDescription::Checkers { a, b } => Checkers::new(color(a), color(b)).into(),
+            PatternDescription::UvCheckers {
+                width,
+                height,
+                a,
+                b,
+            } => UvCheckers::new(width, height, color(a), color(b)).into(),
+            PatternDescription::Marble { a, b, turbulence } => {
+                let mut marble = Marble::new(color(a), color(b));
+                marble.turbulence = turbulence.unwrap_or(marble.turbulence);
+                marble.into()
+            }
+            PatternDescription::Wood { a, b, turbulence } => {
+                let mut wood = Wood::new(color(a), color(b));
+                wood.turbulence = turbulence.unwrap_or(wood.turbulence);
+                wood.into()
+            }
+            PatternDescription::TurbulentStripes { a, b, turbulence } => {
+                let mut stripes = TurbulentStripes::new(color(a), color(b));
+                stripes.turbulence = turbulence.unwrap_or(stripes.turbulence);
+                stripes.into()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UvMapDescription {
+    Spherical,
+    Planar,
+    Cylindrical,
+    Cube,
+}
+
+impl UvMapDescription {
+    pub fn build(&self) -> UvMap {
+        match self {
+            UvMapDescription::Spherical => UvMap::Spherical,
+            UvMapDescription::Planar => UvMap::Planar,
+            UvMapDescription::Cylindrical => UvMap::Cylindrical,
+            UvMapDescription::Cube => UvMap::Cube,
+        }
+    }
+}
+
+impl SceneDescription {
+    /// The files the scene reads when it's built, the skybox's faces and the normal maps
+    pub fn assets(&self) -> impl Iterator<Item = &str> {
+        let faces = self.skybox.iter().flatten();
+        let maps = self.normal_maps.iter().map(|map| &map.image);
+        faces.chain(maps).map(String::as_str)
+    }
+
+    /// Turns the description into a renderable scene, reading the images it refers to. Fails on
+    /// transforms that can't be inverted and images that can't be read.
+    pub fn build(&self) -> Result<Scene> {
+        let convention = self.coordinates.build();
+        let at = |p| convention.convert(point(p));
+        let along = |v| convention.convert(vector(v));
+
+        let c = &self.camera;
+        let mut camera = Camera::new(c.width, c.height, c.field_of_view);
+        camera.set_samples(c.samples);
+        camera.set_seed(c.seed);
+        if let Some(filter) = c.filter {
+            camera.set_filter(filter.build());
+        }
+        camera.set_adaptive(c.adaptive.map(|a| Adaptive {
+            min_samples: a.min_samples,
+            threshold: a.threshold,
+        }));
+        if let Some(exposure) = c.exposure {
+            camera.set_exposure(match exposure {
+                ExposureDescription::Fixed(exposure) => Exposure::Fixed(exposure),
+                ExposureDescription::Auto { key } => Exposure::Auto { key },
+            });
+        }
+        camera.set_tone_map(match c.tone_map {
+            ToneMapDescription::Linear => ToneMap::Linear,
+            ToneMapDescription::Reinhard => ToneMap::Reinhard,
+        });
+        camera.set_transform(view_transform(at(c.from), at(c.to), along(c.up)))?;
+
+        let mut world = World::new();
+        let mut groups = GroupNames::default();
+        for l in &self.lights {
+            let mut light = PointLight::new(at(l.position), color(l.intensity));
+            light.set_groups(groups.resolve(&l.groups)?);
+            if let Some(attenuation) = l.attenuation {
+                light.set_attenuation(attenuation.build());
+            }
+            world.lights.push(light);
+        }
+        for l in &self.sphere_lights {
+            let mut light = SphereLight::new(at(l.position), l.radius, color(l.intensity));
+            if let Some(samples) = l.samples {
+                light.set_samples(samples);
+            }
+            light.set_groups(groups.resolve(&l.groups)?);
+            world.sphere_lights.push(light);
+        }
+        for l in &self.area_lights {
+            // mirroring turns u x v around, swapping the edges keeps the light facing the same way
+            let (u, v) = if convention.mirrors() {
+                (along(l.v), along(l.u))
+            } else {
+                (along(l.u), along(l.v))
+            };
+            let mut light = AreaLight::new(Quad::new(at(l.corner), u, v), color(l.intensity));
+            if let Some(samples) = l.samples {
+                light.set_samples(samples);
+            }
+            light.set_groups(groups.resolve(&l.groups)?);
+            world.area_lights.push(light);
+        }
+        for l in &self.spot_lights {
+            let mut light = SpotLight::new(
+                at(l.position),
+                along(l.direction),
+                l.angle,
+                color(l.intensity),
+            );
+            light.set_groups(groups.resolve(&l.groups)?);
+            if let Some(attenuation) = l.attenuation {
+                light.set_attenuation(attenuation.build());
+            }
+            world.spot_lights.push(light);
+        }
+        world.ambient = self.ambient.map(|a| a.build());
+        world.sky = self
+            .sky
+            .map(|s| Sky::new(s.elevation, s.azimuth, s.turbidity));
+        world.fog = self.fog.map(|f| Fog::new(color(f.color), f.density));
+        if let Some([left, front, right, back, up, down]) = &self.skybox {
+            world.skybox = Some(Skybox::new([
+                read_image(left)?,
+                read_image(front)?,
+                read_image(right)?,
+                read_image(back)?,
+                read_image(up)?,
+                read_image(down)?,
+            ]));
+        }
+        for description in &self.normal_maps {
+            let mut map = NormalMap::new(read_image(&description.image)?);
+            map.strength = description.strength;
+            world.normal_maps.push(map);
+        }
+        world.clip_planes = self
+            .clip_planes
+            .iter()
+            .map(|c| {
+                let plane = ClipPlane::new(at(c.point), along(c.normal));
+                match c.cap {
+                    Some(cap) => plane.with_cap(cap.build()),
+                    None => plane,
+                }
+            })
+            .collect();
+        if let Some(bias) = self.bias {
+            world.bias = bias;
+        }
+        let limits = self.ray_limits;
+        world.ray_limits = RayLimits::new(
+            limits.reflection_depth,
+            limits.refraction_depth,
+            limits.bounces,
+        );
+        for description in &self.spheres {
+            let sphere = description.place(Sphere::new(), convention, &mut groups)?;
+            world.objects.push(sphere);
+        }
+        for description in &self.cubes {
+            let cube = description.place(Cube::new(), convention, &mut groups)?;
+            world.objects.push(cube);
+        }
+        for description in &self.triangles {
+            let [p1, mut p2, mut p3] = description.points.map(at);
+            let mut normals = description.normals.map(|normals| normals.map(along));
+            // mirroring flips the winding, swapping two corners keeps the triangle facing the same way
+            if convention.mirrors() {
+                (p2, p3) = (p3, p2);
+                if let Some([_, n2, n3]) = &mut normals {
+                    std::mem::swap(n2, n3);
+                }
+            }
+            let triangle: Object = match normals {
+                Some([n1, n2, n3]) => SmoothTriangle::new(p1, p2, p3, n1, n2, n3).into(),
+                None => Triangle::new(p1, p2, p3).into(),
+            };
+            let triangle = description
+                .placement()
+                .place(triangle, convention, &mut groups)?;
+            world.objects.push(triangle);
+        }
+        for description in &self.meshes {
+            let mut mesh = description.mesh()?;
+            mesh.convert_from(convention);
+            let mut group = mesh.group(description.material.build());
+            group.set_transform(convention.convert_transform(transform(&description.transform)))?;
+            let light_groups = groups.resolve(&description.light_groups)?;
+            for object in group.objects_mut() {
+                object.set_light_groups(light_groups);
+                object.set_visibility(description.visibility.build());
+            }
+            world.groups.push(group);
+        }
+
+        Ok(Scene { camera, world })
+    }
+}
+
+/// Light group names in the order they first show up, each one gets the next bit of `LightGroups`
+#[derive(Default)]
+struct GroupNames(Vec<String>);
+
+impl GroupNames {
+    fn resolve(&mut self, names: &Option<Vec<String>>) -> Result<LightGroups> {
+        let Some(names) = names else {
+            return Ok(LightGroups::ALL);
+        };
+        let mut groups = LightGroups::NONE;
+        for name in names {
+            let index = match self.0.iter().position(|known| known == name) {
+                Some(index) => index,
+                None => {
+                    self.0.push(name.clone());
+                    self.0.len() - 1
+                }
+            };
+            if index >= 32 {
+                return Err(Error::SceneTooLarge(format!(
+                    "light group {:?}, the limit is 32 groups",
+                    name
+                )));
+            }
+            groups = groups.with(LightGroups::group(index as u32));
+        }
+        Ok(groups)
+    }
+}
+
+impl ObjectDescription {
+    /// All the listed transforms combined, the first one listed is applied first
+    pub fn transform(&self) -> M4x4 {
+        transform(&self.transform)
+    }
+
+    /// `object` with the described transform, material, light groups and visibility
+    fn place(
+        &self,
+        object: impl Into<Object>,
+        convention: Convention,
+        groups: &mut GroupNames,
+    ) -> Result<Object> {
+        let mut object = object.into();
+        object.set_transform(convention.convert_transform(self.transform()))?;
+        object.set_material(self.material.build());
+        object.set_light_groups(groups.resolve(&self.light_groups)?);
+        object.set_visibility(self.visibility.build());
+        Ok(object)
+    }
+}
+
+impl VisibilityDescription {
+    pub fn build(&self) -> Visibility {
+        Visibility {
+            camera: self.camera,
+            reflections: self.reflections,
+            shadows: self.shadows,
+        }
+    }
+}
+
+impl MeshDescription {
+    /// The mesh as written, failing on faces and normals that don't match the vertices
+    pub fn mesh(&self) -> Result<Mesh> {
+        let vertex_count = self.vertices.len();
+        if let Some(face) = self
+            .faces
+            .iter()
+            .find(|face| face.iter().any(|&i| i >= vertex_count))
+        {
+            return Err(invalid(format!(
+                "mesh face {:?} points past its {} vertices",
+                face, vertex_count
+            )));
+        }
+        if !self.normals.is_empty() && self.normals.len() != vertex_count {
+            return Err(invalid(format!(
+                "mesh has {} normals for {} vertices",
+                self.normals.len(),
+                vertex_count
+            )));
+        }
+        Ok(Mesh {
+            vertices: self.vertices.iter().copied().map(point).collect(),
+            normals: self.normals.iter().copied().map(vector).collect(),
+            uvs: Vec::new(),
+            faces: self.faces.clone(),
+        })
+    }
+}
+
+/// All the listed transforms combined, the first one listed is applied first
+fn transform(transforms: &[TransformDescription]) -> M4x4 {
+    transforms
+        .iter()
+        .fold(IDENTITY_MATRIX_4X4, |acc, t| t.build() * acc)
+}
+
+/// A scene that parses but doesn't make sense, reported like a parse error
+/// An image the scene refers to, relative to the working directory
+#[cfg(not(target_arch = "wasm32"))]
+fn read_image(path: &str) -> Result<Arc<Canvas>> {
+    Ok(Arc::new(Canvas::read(path)?))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_image(path: &str) -> Result<Arc<Canvas>> {
+    Err(invalid(format!(
+        "{}: there are no files to read images from on wasm",
+        path
+    )))
+}
+
+fn invalid(message: String) -> Error {
+    Error::Scene(serde::de::Error::custom(message))
+}
+
+impl TriangleDescription {
+    /// Everything but the corners
+    fn placement(&self) -> ObjectDescription {
+        ObjectDescription {
+            transform: self.transform.clone(),
+            material: self.material,
+            light_groups: self.light_groups.clone(),
+            visibility: self.visibility,
+        }
+    }
+}
+
+impl AmbientDescription {
+    pub fn build(&self) -> AmbientLight {
+        match *self {
+            AmbientDescription::Flat(c) => AmbientLight::Flat(color(c)),
+            AmbientDescription::Hemisphere { sky, ground } => AmbientLight::Hemisphere {
+                sky: color(sky),
+                ground: color(ground),
+            },
+        }
+    }
+}
+
+impl CoordinatesDescription {
+    pub fn build(&self) -> Convention {
+        Convention::new(
+            match self.handedness {
+                HandednessDescription::Left => Handedness::Left,
+                HandednessDescription::Right => Handedness::Right,
+            },
+            match self.up {
+                UpAxisDescription::Y => UpAxis::Y,
+                UpAxisDescription::Z => UpAxis::Z,
+            },
+        )
+    }
+}
+
+impl FilterDescription {
+    pub fn build(&self) -> Filter {
+        match *self {
+            FilterDescription::Box { radius } => Filter::Box { radius },
+            FilterDescription::Tent { radius } => Filter::Tent { radius },
+            FilterDescription::Gaussian { radius, alpha } => Filter::Gaussian { radius, alpha },
+        }
+    }
+}
+
+impl TransformDescription {
+    pub fn build(&self) -> M4x4 {
+        match *self {
+            TransformDescription::Translate([x, y, z]) => translation(x, y, z),
+            TransformDescription::Scale([x, y, z]) => scaling(x, y, z),
+            TransformDescription::RotateX(r) => rotation_x(r),
+            TransformDescription::RotateY(r) => rotation_y(r),
+            TransformDescription::RotateZ(r) => rotation_z(r),
+            TransformDescription::Shear([xy, xz, yx, yz, zx, zy]) => {
+                shearing(xy, xz, yx, yz, zx, zy)
+            }
+        }
+    }
+}
+
+impl MaterialDescription {
+    pub fn build(&self) -> Material {
+        Material {
+            color: color(self.color),
+            ambient: self.ambient,
+            diffuse: self.diffuse,
+            specular: self.specular,
+            shininess: self.shininess,
+            reflective: self.reflective,
+            transparency: self.transparency,
+            refractive_index: self.refractive_index,
+            pattern: self.pattern.map(|pattern| pattern.build()),
+            uv_map: self.uv_map.map(|uv_map| uv_map.build()),
+            normal_map: self.normal_map,
+        }
+    }
+}
+
+fn point([x, y, z]: [f64; 3]) -> Point {
+    Point::new_point(x, y, z)
+}
+
+fn vector([x, y, z]: [f64; 3]) -> Vector {
+    Vector::new(x, y, z)
+}
+
+fn color([r, g, b]: [f64; 3]) -> Color {
+    Color::new(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adaptive::Adaptive;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::error::Error;
+    use crate::exposure::{Exposure, ToneMap, MIDDLE_GREY};
+    use crate::filter::Filter;
+    use crate::light::{AmbientLight, Attenuation, LightGroups, Material};
+    use crate::limits::RayLimits;
+    use crate::matrix_transformations::{scaling, translation};
+    use crate::pattern::{Marble, Ring};
+    use crate::rays::{Ray, Visibility};
+    use crate::scene::{parse_scene, Scene, MAX_SCENE_BYTES};
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+    use crate::uv::{CubeFace, UvMap};
+
+    const SCENE: &str = r#"{
+        "camera": { "width": 20, "height": 10, "field_of_view": 1.0472,
+                    "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0],
+                    "filter": { "tent": { "radius": 1 } }, "adaptive": { "threshold": 0.02 },
+                    "exposure": { "auto": {} },
+                    "tone_map": "reinhard" },
+        "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1] },
+                    { "position": [0, 0, 10], "intensity": [1, 1, 1], "groups": ["rim"] } ],
+        "sphere_lights": [ { "position": [0, 10, 0], "radius": 0.5, "intensity": [1, 1, 1], "samples": 4 } ],
+        "area_lights": [ { "corner": [-1, 5, -1], "u": [2, 0, 0], "v": [0, 0, 2], "intensity": [1, 1, 1],
+                           "groups": ["rim"] } ],
+        "spot_lights": [ { "position": [0, 5, 0], "direction": [0, -2, 0], "angle": 0.5, "intensity": [1, 1, 1],
+                           "attenuation": { "quadratic": 0.5 } } ],
+        "ambient": { "flat": [0.1, 0.2, 0.3] },
+        "sky": { "elevation": 0.5 },
+        "fog": { "color": [0.5, 0.5, 0.5], "density": 0.1 },
+        "bias": 0.01,
+        "ray_limits": { "bounces": 3 },
+        "clip_planes": [ { "point": [0, 0, 0], "normal": [0, 0, -2], "cap": { "ambient": 1 } } ],
+        "spheres": [
+            { "transform": [ { "scale": [0.5, 0.5, 0.5] }, { "translate": [1, 0, 0] } ],
+              "material": { "color": [0.5, 1, 0.1], "diffuse": 0.7,
+                            "pattern": { "ring": { "a": [1, 1, 1], "b": [0, 0, 1] } } },
+              "light_groups": ["key", "rim"], "visibility": { "shadows": false } },
+            {},
+            { "material": { "pattern": { "marble": { "a": [1, 1, 1], "b": [0, 0, 0],
+                                                     "turbulence": 1 } },
+                            "uv_map": "planar" } }
+        ]
+    }"#;
+
+    #[test]
+    fn scene_from_json() {
+        let scene = Scene::from_json(SCENE).unwrap();
+        assert_eq!(scene.camera.hsize(), 20);
+        assert_eq!(scene.camera.vsize(), 10);
+        assert_eq!(scene.camera.samples(), 1);
+        assert_eq!(scene.camera.filter(), Filter::Tent { radius: 1.0 });
+        assert_eq!(
+            scene.camera.adaptive(),
+            Some(Adaptive {
+                min_samples: 4,
+                threshold: 0.02
+            })
+        );
+        assert_eq!(scene.camera.exposure(), Exposure::Auto { key: MIDDLE_GREY });
+        assert_eq!(scene.camera.tone_map(), ToneMap::Reinhard);
+        assert_eq!(scene.world.lights.len(), 2);
+        assert_eq!(scene.world.lights[0].groups(), LightGroups::ALL);
+        assert_eq!(scene.world.lights[1].groups(), LightGroups::group(0));
+        assert_eq!(
+            scene.world.objects[0].light_groups(),
+            LightGroups::group(0).with(LightGroups::group(1))
+        );
+        assert_eq!(scene.world.objects[1].light_groups(), LightGroups::ALL);
+        assert!(!scene.world.objects[0].visibility().shadows);
+        assert!(scene.world.objects[0].visibility().camera);
+        assert_eq!(scene.world.objects[1].visibility(), Visibility::ALL);
+        assert_eq!(scene.world.sphere_lights[0].radius(), 0.5);
+        assert_eq!(scene.world.sphere_lights[0].samples(), 4);
+        let area = scene.world.area_lights[0];
+        assert_eq!(area.quad().normal(), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(area.quad().area(), 4.0);
+        assert_eq!(area.groups(), LightGroups::group(0));
+        assert_eq!(scene.world.sky.unwrap().turbidity(), 3.0);
+        assert_eq!(scene.world.fog.unwrap().density, 0.1);
+        assert_eq!(scene.world.bias, 0.01);
+        assert_eq!(
+            scene.world.ray_limits,
+            RayLimits {
+                bounces: 3,
+                ..RayLimits::default()
+            }
+        );
+        assert_eq!(
+            scene.world.clip_planes[0].normal(),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+        assert_eq!(scene.world.clip_planes[0].cap.unwrap().ambient, 1.0);
+        assert_eq!(
+            scene.world.spot_lights[0].direction(),
+            Vector::new(0.0, -1.0, 0.0)
+        );
+        assert_eq!(
+            scene.world.spot_lights[0].attenuation(),
+            Attenuation::new(1.0, 0.0, 0.5)
+        );
+        assert_eq!(scene.world.lights[0].attenuation(), Attenuation::NONE);
+        assert_eq!(
+            scene.world.ambient,
+            Some(AmbientLight::Flat(Color::new(0.1, 0.2, 0.3)))
+        );
+        assert_eq!(scene.world.objects.len(), 3);
+
+        let s1 = scene.world.objects[0];
+        assert_eq!(
+            s1.transform(),
+            translation(1.0, 0.0, 0.0) * scaling(0.5, 0.5, 0.5)
+        );
+        assert_eq!(s1.material().color, Color::new(0.5, 1.0, 0.1));
+        assert_eq!(s1.material().diffuse, 0.7);
+        assert_eq!(s1.material().specular, Material::new().specular);
+        assert_eq!(
+            s1.material().pattern,
+            Some(Ring::new(Color::WHITE, Color::BLUE).into())
+        );
+        assert_eq!(*scene.world.objects[1].material(), Material::new());
+        let mut marble = Marble::new(Color::WHITE, Color::BLACK);
+        marble.turbulence = 1.0;
+        let s3 = scene.world.objects[2].material();
+        assert_eq!(s3.pattern, Some(marble.into()));
+        assert_eq!(s3.uv_map, Some(UvMap::Planar));
+    }
+
+    #[test]
+    fn cubes_from_json() {
+        let scene = Scene::from_json(
+            r#"{
+            "camera": { "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, 1, -5], "to": [0, 1, 0], "up": [0, 1, 0] },
+            "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1], "groups": ["key"] } ],
+            "spheres": [ {} ],
+            "cubes": [ { "transform": [ { "scale": [10, 0.1, 10] }, { "translate": [0, -1, 0] } ],
+                         "material": { "color": [1, 0, 0], "reflective": 0.5 },
+                         "light_groups": ["key"], "visibility": { "camera": false } } ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(scene.world.objects.len(), 2);
+        assert!(scene.world.objects[0].as_sphere().is_some());
+        let cube = scene.world.objects[1];
+        assert!(cube.as_cube().is_some());
+        assert_eq!(
+            cube.transform(),
+            translation(0.0, -1.0, 0.0) * scaling(10.0, 0.1, 10.0)
+        );
+        assert_eq!(cube.material().color, Color::new(1.0, 0.0, 0.0));
+        assert_eq!(cube.material().reflective, 0.5);
+        assert_eq!(cube.light_groups(), LightGroups::group(0));
+        assert!(!cube.visibility().camera);
+    }
+
+    #[test]
+    fn triangles_from_json() {
+        let json = r#"{
+            "camera": { "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, 1, -5], "to": [0, 1, 0], "up": [0, 1, 0] },
+            "triangles": [ { "points": [[0, 1, 0], [-1, 0, 0], [1, 0, 0]],
+                             "transform": [ { "translate": [0, 0, 2] } ],
+                             "material": { "color": [0, 0, 1] } } ]
+        }"#;
+        let scene = Scene::from_json(json).unwrap();
+        assert_eq!(scene.world.objects.len(), 1);
+        let triangle = scene.world.objects[0];
+        let t = triangle.as_triangle().unwrap();
+        assert_eq!(t.p1(), Point::new_point(0.0, 1.0, 0.0));
+        assert_eq!(t.p3(), Point::new_point(1.0, 0.0, 0.0));
+        assert_eq!(triangle.transform(), translation(0.0, 0.0, 2.0));
+        assert_eq!(triangle.material().color, Color::new(0.0, 0.0, 1.0));
+        let r = Ray::new(Point::new_point(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(scene.world.intersect(&r)[0].t, 7.0);
+
+        let missing_point = json.replace("[-1, 0, 0], ", "");
+        assert!(Scene::from_json(&missing_point).is_err());
+
+        let smooth = json.replace(
+            r#""points""#,
+            r#""normals": [[0, 1, -1], [-1, 0, -1], [1, 0, -1]], "points""#,
+        );
+        let scene = Scene::from_json(&smooth).unwrap();
+        let s = scene.world.objects[0];
+        assert_eq!(s.as_smooth().unwrap().n2(), Vector::new(-1.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn meshes_from_json() {
+        let json = r#"{
+            "camera": { "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, 1, -5], "to": [0, 1, 0], "up": [0, 1, 0] },
+            "meshes": [ { "vertices": [[-1, 0, -1], [1, 0, -1], [1, 0, 1], [-1, 0, 1]],
+                          "faces": [[0, 2, 1], [0, 3, 2]],
+                          "transform": [ { "translate": [0, -1, 0] } ],
+                          "material": { "color": [0, 1, 0] }, "visibility": { "shadows": false } } ]
+        }"#;
+        let scene = Scene::from_json(json).unwrap();
+        assert!(scene.world.objects.is_empty());
+        let group = &scene.world.groups[0];
+        assert_eq!(group.transform(), translation(0.0, -1.0, 0.0));
+        assert_eq!(group.objects().len(), 2);
+        for object in group.objects() {
+            assert!(object.as_triangle().is_some());
+            assert_eq!(object.material().color, Color::new(0.0, 1.0, 0.0));
+            assert!(!object.visibility().shadows);
+        }
+        let r = Ray::new(Point::new_point(0.5, 5.0, 0.2), Vector::new(0.0, -1.0, 0.0));
+        assert_eq!(scene.world.intersect(&r)[0].t, 6.0);
+
+        let smooth = json.replace(
+            r#""faces""#,
+            r#""normals": [[0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0]], "faces""#,
+        );
+        let scene = Scene::from_json(&smooth).unwrap();
+        assert!(scene.world.groups[0].objects()[0].as_smooth().is_some());
+
+        let past_the_end = json.replace("[0, 3, 2]", "[0, 4, 2]");
+        assert!(matches!(
+            Scene::from_json(&past_the_end),
+            Err(Error::Scene(_))
+        ));
+        let too_few_normals = json.replace(r#""faces""#, r#""normals": [[0, 1, 0]], "faces""#);
+        assert!(Scene::from_json(&too_few_normals).is_err());
+    }
+
+    #[test]
+    fn images_from_files() {
+        let path = std::env::temp_dir().join(format!("scene_image_{}.ppm", std::process::id()));
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, Color::new(0.5, 0.5, 1.0));
+        image.to_ppm(&path.to_string_lossy()).unwrap();
+        let path = path.to_string_lossy().replace('\\', "/");
+        let json = format!(
+            r#"{{
+            "camera": {{ "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0] }},
+            "spheres": [ {{ "material": {{ "normal_map": 0 }} }} ],
+            "skybox": ["{0}", "{0}", "{0}", "{0}", "{0}", "{0}"],
+            "normal_maps": [ {{ "image": "{0}", "strength": 0.5 }} ]
+        }}"#,
+            path
+        );
+        let description = parse_scene(json.as_bytes()).unwrap();
+        assert_eq!(description.assets().count(), 7);
+        assert!(description.assets().all(|asset| asset == path));
+        let scene = description.build().unwrap();
+        let skybox = scene.world.skybox.as_ref().unwrap();
+        assert_eq!(skybox.face(CubeFace::Up).width(), 2);
+        assert_eq!(scene.world.normal_maps[0].strength, 0.5);
+        assert_eq!(scene.world.objects[0].material().normal_map, Some(0));
+        std::fs::remove_file(&path).unwrap();
+
+        // gone, e.g. halfway through being written
+        assert!(Scene::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn scenes_from_blender() {
+        let native = Scene::from_json(
+            r#"{
+            "camera": { "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, 1, -5], "to": [0, 1, 0], "up": [0, 1, 0] },
+            "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1] } ],
+            "spheres": [ { "transform": [ { "translate": [1, 2, 0] } ] } ]
+        }"#,
+        )
+        .unwrap();
+        // the same scene with z up and y into the screen
+        let blender = Scene::from_json(
+            r#"{
+            "coordinates": { "handedness": "right", "up": "z" },
+            "camera": { "width": 8, "height": 8, "field_of_view": 1,
+                        "from": [0, -5, 1], "to": [0, 0, 1], "up": [0, 0, 1] },
+            "lights": [ { "position": [-10, -10, 10], "intensity": [1, 1, 1] } ],
+            "spheres": [ { "transform": [ { "translate": [1, 0, 2] } ] } ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(blender.camera.transform(), native.camera.transform());
+        assert_eq!(
+            blender.world.lights[0].position(),
+            native.world.lights[0].position()
+        );
+        assert_eq!(
+            blender.world.objects[0].transform(),
+            native.world.objects[0].transform()
+        );
+    }
+
+    #[test]
+    fn malformed_scene_is_an_error() {
+        assert!(matches!(
+            Scene::from_json(r#"{ "lights": [] }"#),
+            Err(Error::Scene(_))
+        ));
+        assert!(matches!(
+            Scene::from_json(&SCENE.replace("\"diffuse\"", "\"difuse\"")),
+            Err(Error::Scene(_))
+        ));
+    }
+
+    #[test]
+    fn degenerate_sphere_transform_is_an_error() {
+        let json = SCENE.replace("[0.5, 0.5, 0.5]", "[0, 1, 1]");
+        assert!(matches!(
+            Scene::from_json(&json),
+            Err(Error::DegenerateTransform(_))
+        ));
+    }
+
+    #[test]
+    fn oversized_scenes_are_rejected_before_building() {
+        let huge = SCENE.replace("\"width\": 20", "\"width\": 1000000");
+        assert!(matches!(
+            parse_scene(huge.as_bytes()),
+            Err(Error::SceneTooLarge(_))
+        ));
+        let padded = format!("{}{}", SCENE, " ".repeat(MAX_SCENE_BYTES));
+        assert!(matches!(
+            parse_scene(padded.as_bytes()),
+            Err(Error::SceneTooLarge(_))
+        ));
+        assert!(matches!(parse_scene(b"\xff\xfe"), Err(Error::Scene(_))));
+    }
+
+    #[test]
+    fn empty_cameras_are_rejected() {
+        for (from, to) in [
+            ("\"width\": 20", "\"width\": 0"),
+            ("\"height\": 10", "\"height\": 0"),
+        ] {
+            let empty = SCENE.replace(from, to);
+            assert_ne!(empty, SCENE);
+            assert!(matches!(
+                parse_scene(empty.as_bytes()),
+                Err(Error::Scene(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Ready-made scenes to try the tracer out on, or to benchmark it against, without writing a
+//! scene file. `ray_tracer preset <name>` renders them from the command line.
+//!
+//! Floors and walls are flat cubes.
+
+use std::f64::consts::PI;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::light::{AreaLight, Material, PointLight};
+use crate::matrix_transformations::{scaling, translation, view_transform};
+use crate::pattern::Checkers;
+use crate::quad::Quad;
+use crate::rays::Sphere;
+use crate::shape::Object;
+use crate::tuple::{Point, Vector};
+use crate::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Three spheres of different sizes on a floor, the book's first world
+    ThreeSpheres,
+    /// A glass ball hovering over a floor
+    GlassBall,
+    /// A closed room with a red and a green wall, lit by a panel in the ceiling
+    CornellBox,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::ThreeSpheres, Preset::GlassBall, Preset::CornellBox];
+
+    /// The name on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::ThreeSpheres => "three_spheres",
+            Preset::GlassBall => "glass_ball",
+            Preset::CornellBox => "cornell_box",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    pub fn world(&self) -> World {
+        match self {
+            Preset::ThreeSpheres => three_spheres(),
+            Preset::GlassBall => glass_ball(),
+            Preset::CornellBox => cornell_box(),
+        }
+    }
+
+    /// A camera framing the scene at the given resolution
+    pub fn camera(&self, hsize: usize, vsize: usize) -> Camera {
+        let (fov, from, to) = match self {
+            Preset::ThreeSpheres => (PI / 3.0, [0.0, 1.5, -5.0], [0.0, 1.0, 0.0]),
+            Preset::GlassBall => (PI / 3.0, [0.0, 2.5, -4.5], [0.0, 0.8, 0.0]),
+            Preset::CornellBox => (PI / 3.0, [0.0, 1.0, -3.2], [0.0, 1.0, 0.0]),
+        };
+        let point = |[x, y, z]: [f64; 3]| Point::new_point(x, y, z);
+        let mut camera = Camera::new(hsize, vsize, fov);
+        camera
+            .set_transform(view_transform(
+                point(from),
+                point(to),
+                Vector::new(0.0, 1.0, 0.0),
+            ))
+            .expect("preset views are invertible");
+        camera
+    }
+}
+
+/// A box reaching `radii` from its center along each axis, for floors and walls
+fn slab(center: Point, radii: Vector, material: Material) -> Object {
+    let mut slab = Cube::new();
+    slab.set_transform(
+        translation(center.x, center.y, center.z) * scaling(radii.x, radii.y, radii.z),
+    )
+    .expect("slabs have no zero radius");
+    slab.set_material(material);
+    slab.into()
+}
+
+fn ball(center: Point, radius: f64, material: Material) -> Object {
+    let mut ball = Sphere::ellipsoid(center, Vector::new(radius, radius, radius))
+        .expect("balls have a radius");
+    ball.set_material(material);
+    ball.into()
+}
+
+fn matte(color: Color) -> Material {
+    Material {
+        color,
+        specular: 0.0,
+        ..Material::new()
+    }
+}
+
+fn floor() -> Object {
+    let mut checkers = Checkers::new(Color::new(1.0, 0.9, 0.9), Color::new(0.3, 0.3, 0.3));
+    // undoes the slab's stretch so the checkers are a unit across, and keeps the top face
+    // halfway through one layer of them
+    checkers
+        .set_transform(scaling(0.1, 2.0, 0.1))
+        .expect("the checkers' scaling is invertible");
+    slab(
+        Point::new_point(0.0, 0.0, 0.0),
+        Vector::new(10.0, 0.01, 10.0),
+        Material {
+            pattern: Some(checkers.into()),
+            ..matte(Color::new(1.0, 0.9, 0.9))
+        },
+    )
+}
+
+fn three_spheres() -> World {
+    let shiny = |color| Material {
+        color,
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Material::new()
+    };
+    let mut world = World::new();
+    world.objects = vec![
+        floor(),
+        ball(
+            Point::new_point(-0.5, 1.0, 0.5),
+            1.0,
+            shiny(Color::new(0.1, 1.0, 0.5)),
+        ),
+        ball(
+            Point::new_point(1.5, 0.5, -0.5),
+            0.5,
+            shiny(Color::new(0.5, 1.0, 0.1)),
+        ),
+        ball(
+            Point::new_point(-1.5, 0.33, -0.75),
+            0.33,
+            shiny(Color::new(1.0, 0.8, 0.1)),
+        ),
+    ];
+    world.lights = vec![PointLight::new(
+        Point::new_point(-10.0, 10.0, -10.0),
+        Color::WHITE,
+    )];
+    world
+}
+
+fn glass_ball() -> World {
+    let glass = Material {
+        color: Color::new(0.05, 0.05, 0.05),
+        ambient: 0.0,
+        diffuse: 0.1,
+        specular: 1.0,
+        shininess: 300.0,
+        reflective: 0.9,
+        transparency: 0.9,
+        refractive_index: 1.5,
+        pattern: None,
+        uv_map: None,
+        normal_map: None,
+    };
+    let mut world = World::new();
+    world.objects = vec![floor(), ball(Point::new_point(0.0, 1.0, 0.0), 0.8, glass)];
+    world.lights = vec![PointLight::new(
+        Point::new_point(-5.0, 8.0, -6.0),
+        Color::WHITE,
+    )];
+    world
+}
+
+fn cornell_box() -> World {
+    let white = matte(Color::new(0.73, 0.73, 0.73));
+    let red = matte(Color::new(0.65, 0.05, 0.05));
+    let green = matte(Color::new(0.12, 0.45, 0.15));
+    let p = Point::new_point;
+    // the room runs from -1 to 1 across, 0 to 2 up and -1 to 1 deep, open toward the camera.
+    let wide = 10.0;
+    let thin = 0.01;
+    let mut world = World::new();
+    world.objects = vec![
+        slab(p(0.0, 0.0, 0.0), Vector::new(wide, thin, wide), white),
+        slab(p(0.0, 2.0, 0.0), Vector::new(wide, thin, wide), white),
+        slab(p(0.0, 1.0, 1.0), Vector::new(wide, wide, thin), white),
+        slab(p(-1.0, 1.0, 0.0), Vector::new(thin, wide, wide), red),
+        slab(p(1.0, 1.0, 0.0), Vector::new(thin, wide, wide), green),
+        ball(p(-0.4, 0.4, 0.3), 0.4, white),
+        ball(p(0.45, 0.3, -0.3), 0.3, white),
+    ];
+    // a panel just under the ceiling, facing down
+    let panel = Quad::new(
+        p(-0.25, 1.98, -0.25),
+        Vector::new(0.5, 0.0, 0.0),
+        Vector::new(0.0, 0.0, 0.5),
+    );
+    world.area_lights = vec![AreaLight::new(panel, Color::new(1.2, 1.2, 1.2))];
+    world
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::scenes::Preset;
+
+    #[test]
+    fn presets_by_name() {
+        for preset in Preset::ALL {
+            assert_eq!(Preset::from_name(preset.name()), Some(preset));
+        }
+        assert_eq!(Preset::from_name("teapot"), None);
+    }
+
+    #[test]
+    fn presets_render_something() {
+        for preset in Preset::ALL {
+            let image = preset.camera(16, 9).render(&preset.world());
+            let center = image.get_pixel(8, 4).unwrap();
+            assert_ne!(center, Color::BLACK, "{}", preset.name());
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Shapes given by a distance estimate instead of an intersection formula, rendered by marching
+//! rays toward them.
+//!
+//! A distance estimator returns (a lower bound of) how far a point is from the surface, so a ray
+//! can safely step that far forward and ask again, until it's close enough to call it a hit.
+//! That's all fractals like the Mandelbulb and the Menger sponge give us, there's no closed form
+//! to solve for where a ray meets them.
+//!
+//! These shapes live outside `World` for now, see `Marcher::march` and the `fractal` demo in
+//! `main.rs`.
+
+use crate::color::Color;
+use crate::rays::Ray;
+use crate::tuple::{Point, Vector};
+
+pub trait DistanceEstimator: Sync {
+    /// Distance from the point to the surface, or less, negative inside
+    fn distance(&self, point: Point) -> f64;
+
+    /// How close the point's orbit came to the trap, from 0 to 1, for coloring.
+    /// `None` for shapes without an orbit.
+    fn orbit_trap(&self, _point: Point) -> Option<f64> {
+        None
+    }
+}
+
+/// The 3D cousin of the Mandelbrot set, `z -> z^power + c` with powers taken in spherical
+/// coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mandelbulb {
+    /// 8 for the classic bulb
+    pub power: f64,
+    /// More iterations show finer detail and take longer
+    pub iterations: u32,
+    /// Orbits that get this far from the origin escape
+    pub bailout: f64,
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Mandelbulb {
+            power: 8.0,
+            iterations: 12,
+            bailout: 2.0,
+        }
+    }
+}
+
+impl Mandelbulb {
+    /// Iterates the point, returning its final radius, the running derivative and the closest
+    /// the orbit came to the origin
+    fn orbit(&self, c: Point) -> (f64, f64, f64) {
+        let mut z = c - Point::new_point(0.0, 0.0, 0.0);
+        let (mut r, mut dr) = (z.magnitude(), 1.0);
+        let mut trap = r;
+        for _ in 0..self.iterations {
+            if r > self.bailout {
+                break;
+            }
+            let theta = if r > 0.0 { (z.z / r).acos() } else { 0.0 };
+            let phi = z.y.atan2(z.x);
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            let (zr, theta, phi) = (r.powf(self.power), theta * self.power, phi * self.power);
+            z = Vector::new(
+                theta.sin() * phi.cos(),
+                phi.sin() * theta.sin(),
+                theta.cos(),
+            ) * zr
+                + (c - Point::new_point(0.0, 0.0, 0.0));
+            r = z.magnitude();
+            trap = trap.min(r);
+        }
+        (r, dr, trap)
+    }
+}
+
+impl DistanceEstimator for Mandelbulb {
+    fn distance(&self, point: Point) -> f64 {
+        let (r, dr, _) = self.orbit(point);
+        if r <= 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr
+    }
+
+    fn orbit_trap(&self, point: Point) -> Option<f64> {
+        let (_, _, trap) = self.orbit(point);
+        Some((trap / self.bailout).clamp(0.0, 1.0))
+    }
+}
+
+/// The cube from -1 to 1 with its middle thirds carved out, again and again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MengerSponge {
+    pub iterations: u32,
+}
+
+impl Default for MengerSponge {
+    fn default() -> Self {
+        MengerSponge { iterations: 4 }
+    }
+}
+
+impl DistanceEstimator for MengerSponge {
+    fn distance(&self, point: Point) -> f64 {
+        let p = [point.x, point.y, point.z];
+        let mut distance = unit_box(p);
+        let mut scale = 1.0;
+        for _ in 0..self.iterations {
+            // fold into the cell around the origin, then measure to the cross cut through it
+            let a = p.map(|x| (x * scale).rem_euclid(2.0) - 1.0);
+            scale *= 3.0;
+            let r = a.map(|x| (1.0 - 3.0 * x.abs()).abs());
+            let cross = r[0].max(r[1]).min(r[1].max(r[2])).min(r[2].max(r[0]));
+            distance = distance.max((cross - 1.0) / scale);
+        }
+        distance
+    }
+}
+
+fn unit_box(p: [f64; 3]) -> f64 {
+    let q = p.map(|x| x.abs() - 1.0);
+    let outside = q.map(|x| x.max(0.0));
+    let outside =
+        (outside[0] * outside[0] + outside[1] * outside[1] + outside[2] * outside[2]).sqrt();
+    outside + q[0].max(q[1]).max(q[2]).min(0.0)
+}
+
+/// How carefully rays are marched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marcher {
+    pub max_steps: usize,
+    /// Distance from the surface that counts as a hit
+    pub epsilon: f64,
+    /// Rays that get this far without hitting anything miss
+    pub max_distance: f64,
+}
+
+impl Default for Marcher {
+    fn default() -> Self {
+        Marcher {
+            max_steps: 200,
+            epsilon: 1e-4,
+            max_distance: 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarchHit {
+    pub t: f64,
+    pub point: Point,
+    /// Steps it took, a cheap ambient occlusion: rays that squeezed past a lot of surface
+    /// take many
+    pub steps: usize,
+}
+
+impl Marcher {
+    /// Steps along the ray until it's within `epsilon` of the surface, `None` when it runs out
+    /// of steps or distance first
+    pub fn march(&self, shape: &dyn DistanceEstimator, ray: &Ray) -> Option<MarchHit> {
+        let speed = ray.direction().magnitude();
+        let mut t = 0.0;
+        for steps in 0..self.max_steps {
+            let point = ray.position(t);
+            let distance = shape.distance(point);
+            if distance < self.epsilon {
+                return Some(MarchHit { t, point, steps });
+            }
+            t += distance / speed;
+            if t * speed > self.max_distance {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Surface normal from the gradient of the distance, by central differences
+    pub fn normal_at(&self, shape: &dyn DistanceEstimator, point: Point) -> Vector {
+        let e = self.epsilon;
+        let gradient =
+            |axis: Vector| shape.distance(point + axis * e) - shape.distance(point - axis * e);
+        Vector::new(
+            gradient(Vector::new(1.0, 0.0, 0.0)),
+            gradient(Vector::new(0.0, 1.0, 0.0)),
+            gradient(Vector::new(0.0, 0.0, 1.0)),
+        )
+        .normalize()
+    }
+}
+
+/// Colors a point by its orbit trap, `near` where the orbit came closest to the origin
+pub fn trap_color(shape: &dyn DistanceEstimator, point: Point, near: Color, far: Color) -> Color {
+    match shape.orbit_trap(point) {
+        Some(trap) => near.lerp(far, trap),
+        None => near,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::rays::Ray;
+    use crate::sdf::{trap_color, DistanceEstimator, Mandelbulb, Marcher, MengerSponge};
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn mandelbulb_distances() {
+        let bulb = Mandelbulb::default();
+        let far = bulb.distance(Point::new_point(0.0, 0.0, -5.0));
+        assert!(far > 3.0 && far < 5.0, "{}", far);
+        assert_eq!(bulb.distance(Point::new_point(0.0, 0.0, 0.0)), 0.0);
+        let trap = bulb.orbit_trap(Point::new_point(0.0, 0.0, -5.0)).unwrap();
+        assert_eq!(trap, 1.0);
+    }
+
+    #[test]
+    fn marching_into_the_bulb() {
+        let bulb = Mandelbulb::default();
+        let marcher = Marcher::default();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 2.0));
+        let hit = marcher.march(&bulb, &r).unwrap();
+        // the bulb reaches a little past the unit sphere along the axes
+        assert!(hit.point.z > -1.5 && hit.point.z < -0.5, "{}", hit.point.z);
+        assert!((hit.t * 2.0 - (hit.point.z + 5.0)).abs() < 1e-9);
+        let normal = marcher.normal_at(&bulb, hit.point);
+        assert!(normal.z < 0.0);
+        let miss = Ray::new(Point::new_point(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(marcher.march(&bulb, &miss), None);
+    }
+
+    #[test]
+    fn menger_sponge_has_holes() {
+        let sponge = MengerSponge { iterations: 1 };
+        let marcher = Marcher::default();
+        let solid = Ray::new(Point::new_point(0.8, 0.8, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = marcher.march(&sponge, &solid).unwrap();
+        assert!((hit.t - 4.0).abs() < 1e-3);
+        assert!(
+            (marcher.normal_at(&sponge, hit.point) - Vector::new(0.0, 0.0, -1.0)).magnitude()
+                < 1e-3
+        );
+        // straight through the middle hole
+        let hole = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(marcher.march(&sponge, &hole), None);
+        assert_eq!(
+            trap_color(&sponge, hit.point, Color::RED, Color::BLUE),
+            Color::RED
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Rendering image sequences, e.g. a turntable spin around a scene or a keyframed animation.
+//!
+//! Frames go to a [`FrameSink`] as soon as they are rendered: numbered image files with
+//! [`ImageSequence`], or straight into a video with [`FfmpegSink`].
+
+use std::f64::consts::TAU;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+use std::ops::Range;
+#[cfg(not(target_arch = "wasm32"))]
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use crate::animation::Animation;
+use crate::camera::Camera;
+use crate::canvas::Canvas;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::Error;
+use crate::error::Result;
+use crate::matrix_transformations::{rotation_y, translation_from, view_transform};
+use crate::shape::Shape;
+use crate::tuple::{Point, Vector};
+use crate::world::World;
+
+/// Placeholder in a filename pattern that is replaced by the zero padded frame number
+const FRAME_PLACEHOLDER: char = '#';
+
+/// What goes around in a turntable, one full turn about the vertical axis over the sequence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spin {
+    /// The camera circles the target point, keeping its height and distance
+    Camera { target: Point },
+    /// The object at this index in `World::objects` turns in place around its center
+    Object(usize),
+}
+
+/// Fills in the frame number for the run of `#` in the pattern, padded to the run's length,
+/// e.g. `frame_####.ppm` becomes `frame_0001.ppm`. Without a `#` the number goes before the extension.
+pub fn frame_filename(pattern: &str, number: usize) -> String {
+    match pattern.find(FRAME_PLACEHOLDER) {
+        Some(start) => {
+            let width = pattern[start..]
+                .chars()
+                .take_while(|c| *c == FRAME_PLACEHOLDER)
+                .count();
+            format!(
+                "{}{:0width$}{}",
+                &pattern[..start],
+                number,
+                &pattern[start + width..]
+            )
+        }
+        None => match pattern.rfind('.') {
+            Some(dot) => format!("{}_{:04}{}", &pattern[..dot], number, &pattern[dot..]),
+            None => format!("{}_{:04}", pattern, number),
+        },
+    }
+}
+
+/// The world and camera posed for one frame of a turntable `frames` long
+pub fn turntable_frame(
+    world: &World,
+    camera: &Camera,
+    spin: Spin,
+    frame: usize,
+    frames: usize,
+) -> Result<(World, Camera)> {
+    let angle = TAU * frame as f64 / frames.max(1) as f64;
+    let mut world = world.clone();
+    let mut camera = *camera;
+    match spin {
+        Spin::Camera { target } => {
+            let eye = camera.transform().inverse()? * Point::new_point(0.0, 0.0, 0.0);
+            let from = target + rotation_y(angle) * (eye - target);
+            camera.set_transform(view_transform(from, target, Vector::new(0.0, 1.0, 0.0)))?;
+        }
+        Spin::Object(index) => {
+            if let Some(object) = world.objects.get_mut(index) {
+                let transform = object.transform();
+                let center = transform * Point::new_point(0.0, 0.0, 0.0);
+                let to_origin = center - Point::new_point(0.0, 0.0, 0.0);
+                object.set_transform(
+                    translation_from(to_origin)
+                        * rotation_y(angle)
+                        * translation_from(-to_origin)
+                        * transform,
+                )?;
+            }
+        }
+    }
+    Ok((world, camera))
+}
+
+/// Somewhere to send rendered frames, in order
+pub trait FrameSink {
+    fn write_frame(&mut self, frame: &Canvas) -> Result<()>;
+
+    /// Called once after the last frame, e.g. to flush an encoder
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Keeps the frames in memory
+impl FrameSink for Vec<Canvas> {
+    fn write_frame(&mut self, frame: &Canvas) -> Result<()> {
+        self.push(frame.clone());
+        Ok(())
+    }
+}
+
+/// Writes every frame to its own file named after a pattern (see `frame_filename`),
+/// numbered from 1
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct ImageSequence {
+    pattern: String,
+    files: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ImageSequence {
+    pub fn new(pattern: &str) -> Self {
+        ImageSequence {
+            pattern: pattern.to_string(),
+            files: Vec::new(),
+        }
+    }
+
+    /// The files written so far
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FrameSink for ImageSequence {
+    fn write_frame(&mut self, frame: &Canvas) -> Result<()> {
+        let filename = frame_filename(&self.pattern, self.files.len() + 1);
+        frame.to_ppm(&filename)?;
+        self.files.push(filename);
+        Ok(())
+    }
+}
+
+/// Pipes raw RGBA frames into an `ffmpeg` process, which has to be on the `PATH`.
+/// The container and codec are picked by ffmpeg from the output's extension (mp4, webm, gif...).
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct FfmpegSink {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    width: usize,
+    height: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FfmpegSink {
+    /// Starts encoding `width` x `height` frames at `fps` frames per second into `output`,
+    /// overwriting it if it already exists
+    pub fn new(output: &str, width: usize, height: usize, fps: u32) -> Result<Self> {
+        let size = format!("{}x{}", width, height);
+        let fps = fps.to_string();
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(["-loglevel", "error", "-y"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &size, "-r", &fps, "-i", "-"]);
+        // most players can't handle the yuv444 h264 ffmpeg picks for rgba input
+        if output.ends_with(".mp4") || output.ends_with(".mov") {
+            command.args(["-pix_fmt", "yuv420p"]);
+        }
+        let mut child = command.arg(output).stdin(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take();
+        Ok(FfmpegSink {
+            child,
+            stdin,
+            width,
+            height,
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FrameSink for FfmpegSink {
+    fn write_frame(&mut self, frame: &Canvas) -> Result<()> {
+        if (frame.width(), frame.height()) != (self.width, self.height) {
+            return Err(Error::Encoder(format!(
+                "frame is {}x{} but the video is {}x{}",
+                frame.width(),
+                frame.height(),
+                self.width,
+                self.height
+            )));
+        }
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| Error::Encoder("video already finished".to_string()))?;
+        stdin.write_all(&frame.to_rgba())?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // closing stdin tells ffmpeg there are no more frames
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(Error::Encoder(format!("ffmpeg exited with {}", status)));
+        }
+        Ok(())
+    }
+}
+
+/// Renders a full turn in `frames` steps, handing each frame to the sink as soon as it's done
+pub fn render_turntable(
+    world: &World,
+    camera: &Camera,
+    spin: Spin,
+    frames: usize,
+    sink: &mut dyn FrameSink,
+) -> Result<()> {
+    for frame in 0..frames {
+        let (world, camera) = turntable_frame(world, camera, spin, frame, frames)?;
+        sink.write_frame(&camera.render(&world))?;
+    }
+    sink.finish()
+}
+
+/// Like `render_animation`, but handing each frame to the sink instead of keeping them all
+pub fn render_animation_to(
+    world: &World,
+    camera: &Camera,
+    animation: &Animation,
+    frames: Range<usize>,
+    sink: &mut dyn FrameSink,
+) -> Result<()> {
+    let mut world = world.clone();
+    let mut camera = *camera;
+    for frame in frames {
+        animation.apply(&mut world, &mut camera, frame as f64)?;
+        sink.write_frame(&camera.render(&world))?;
+    }
+    sink.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::fs;
+
+    use crate::animation::{Animation, Easing};
+    use crate::camera::Camera;
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::error::Error;
+    use crate::matrix_transformations::{rotation_y, translation, view_transform};
+    use crate::sequence::{
+        frame_filename, render_animation_to, render_turntable, turntable_frame, FfmpegSink,
+        FrameSink, ImageSequence, Spin,
+    };
+    use crate::shape::Shape;
+    use crate::tuple::{Point, Vector};
+    use crate::world::default_world;
+
+    #[test]
+    fn numbering_frames() {
+        assert_eq!(frame_filename("frame_####.ppm", 1), "frame_0001.ppm");
+        assert_eq!(frame_filename("out/##.ppm", 123), "out/123.ppm");
+        assert_eq!(frame_filename("spin.ppm", 7), "spin_0007.ppm");
+        assert_eq!(frame_filename("spin", 7), "spin_0007");
+    }
+
+    #[test]
+    fn camera_turntable_circles_the_target() {
+        let world = default_world();
+        let mut camera = Camera::new(10, 10, PI / 2.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        let target = Point::new_point(0.0, 0.0, 0.0);
+        camera
+            .set_transform(view_transform(Point::new_point(0.0, 1.0, -5.0), target, up))
+            .unwrap();
+
+        let spin = Spin::Camera { target };
+        let (_, quarter) = turntable_frame(&world, &camera, spin, 1, 4).unwrap();
+        assert_eq!(
+            quarter.transform(),
+            view_transform(Point::new_point(-5.0, 1.0, 0.0), target, up)
+        );
+        let (_, full) = turntable_frame(&world, &camera, spin, 4, 4).unwrap();
+        assert_eq!(full.transform(), camera.transform());
+    }
+
+    #[test]
+    fn object_turntable_turns_in_place() {
+        let mut world = default_world();
+        world.objects[0]
+            .set_transform(translation(2.0, 0.0, 0.0))
+            .unwrap();
+        let camera = Camera::new(10, 10, PI / 2.0);
+        let (posed, _) = turntable_frame(&world, &camera, Spin::Object(0), 1, 4).unwrap();
+        assert_eq!(
+            posed.objects[0].transform(),
+            translation(2.0, 0.0, 0.0) * rotation_y(PI / 2.0)
+        );
+        assert_eq!(posed.objects[1].transform(), world.objects[1].transform());
+    }
+
+    #[test]
+    fn writing_a_turntable_sequence() {
+        let dir = std::env::temp_dir().join(format!("turntable_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let pattern = dir.join("frame_####.ppm");
+        let camera = Camera::new(4, 4, PI / 2.0);
+        let mut sequence = ImageSequence::new(pattern.to_str().unwrap());
+        render_turntable(&default_world(), &camera, Spin::Object(0), 3, &mut sequence).unwrap();
+        let files = sequence.files();
+        assert_eq!(files.len(), 3);
+        assert!(files[2].ends_with("frame_0003.ppm"));
+        for file in files {
+            assert!(fs::read_to_string(file).unwrap().starts_with("P3\n4 4\n"));
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn animation_frames_to_a_sink() {
+        let mut animation = Animation::new();
+        animation
+            .light_intensity(0)
+            .add(0.0, Color::WHITE, Easing::Linear)
+            .add(1.0, Color::BLACK, Easing::Linear);
+        let camera = Camera::new(3, 3, PI / 2.0);
+        let mut frames: Vec<Canvas> = Vec::new();
+        render_animation_to(&default_world(), &camera, &animation, 0..2, &mut frames).unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn piping_frames_to_ffmpeg() {
+        let has_ffmpeg = std::process::Command::new("ffmpeg")
+            .arg("-version")
+            .output()
+            .is_ok_and(|output| output.status.success());
+        let sink = FfmpegSink::new("/dev/null.mp4", 4, 4, 24);
+        if !has_ffmpeg {
+            assert!(matches!(sink, Err(Error::Io(_))));
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("ffmpeg_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("spin.mp4");
+        let mut sink = FfmpegSink::new(output.to_str().unwrap(), 4, 4, 24).unwrap();
+        assert!(matches!(
+            sink.write_frame(&Canvas::new(2, 2)),
+            Err(Error::Encoder(_))
+        ));
+        let camera = Camera::new(4, 4, PI / 2.0);
+        render_turntable(&default_world(), &camera, Spin::Object(0), 3, &mut sink).unwrap();
+        assert!(fs::metadata(&output).unwrap().len() > 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,638 @@
+//! What every primitive has in common, so the intersection plumbing (`intersect`, `hit`,
+//! `prepare_computations`, groups and the world) is written once for all of them.
+//!
+//! A primitive only knows how to meet a ray and what its normal is in its own space, where
+//! it sits at the origin at unit size: `local_intersect` and `local_normal_at`. `Shape` moves
+//! rays and normals between world space and that space with the transform.
+//!
+//! The world holds `Object`s, one variant per primitive, so intersections stay small `Copy`
+//! values that can be sorted and passed around without boxing.
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::capsule::Capsule;
+use crate::cube::Cube;
+use crate::error::Result;
+use crate::light::{LightGroups, Material};
+use crate::matrix::M4x4;
+use crate::quad::Quad;
+use crate::rays::{Intersection, Ray, Sphere, Visibility};
+use crate::triangle::{SmoothTriangle, Triangle};
+use crate::tuple::{Point, Vector};
+use crate::uv::UvMap;
+
+pub trait Shape {
+    fn id(&self) -> Uuid;
+
+    fn transform(&self) -> M4x4;
+
+    /// Kept alongside the transform, every ray and normal needs it
+    fn inverse_transform(&self) -> M4x4;
+
+    fn material(&self) -> &Material;
+
+    /// Lights that shine on the shape, see `LightGroups`
+    fn light_groups(&self) -> LightGroups;
+
+    /// Kinds of rays that hit the shape, the others pass straight through it
+    fn visibility(&self) -> Visibility;
+
+    /// Where a ray already in the shape's own space crosses its surface
+    fn local_intersect(&self, ray: &Ray) -> LocalHits;
+
+    /// The normal at a point on the surface in the shape's own space, not necessarily
+    /// normalized. `uv` is what `local_intersect` gave the hit, if anything.
+    fn local_normal_at(&self, point: Point, uv: Option<(f64, f64)>) -> Vector;
+
+    /// Box around the shape in its own space
+    fn local_bounds(&self) -> BoundingBox;
+
+    /// How textures wrap around this kind of shape unless its material says otherwise
+    fn default_uv_map(&self) -> UvMap {
+        UvMap::Spherical
+    }
+
+    /// How textures wrap around the shape: the material's `uv_map`, or the shape's default
+    fn uv_map(&self) -> UvMap {
+        self.material()
+            .uv_map
+            .unwrap_or_else(|| self.default_uv_map())
+    }
+
+    /// uv of a point in the shape's own space, by its `uv_map` unless the shape has its own
+    /// way to lay out textures
+    fn uv_at(&self, point: Point) -> (f64, f64) {
+        self.uv_map().map(point)
+    }
+
+    /// The directions `u` and `v` grow in at a point in the shape's own space, matching
+    /// `uv_at`, see `UvMap::tangents`
+    fn uv_tangents(&self, point: Point) -> (Vector, Vector) {
+        self.uv_map().tangents(point)
+    }
+
+    /// Box around the shape in the space it sits in, world space unless it's in a group
+    fn bounds(&self) -> BoundingBox {
+        self.local_bounds().transformed(&self.transform())
+    }
+
+    /// Calculates the normal vector at a given point on the surface of the shape, transforming
+    /// from world space to object space and back to world space correctly.
+    ///
+    /// # Methodology
+    /// 1. **World to Object Space Transformation**:
+    ///    - The given `world_point` is transformed into the shape's local coordinate system
+    ///      (object space) with the inverse of the shape's transformation matrix.
+    ///      \[
+    ///      \text{object\_point} = T^{-1} \times \text{world\_point}
+    ///      \]
+    ///
+    /// 2. **Normal Calculation in Object Space**:
+    ///    - `local_normal_at` gives the normal there, e.g. the vector from the origin to the
+    ///      point for a unit sphere.
+    ///
+    /// 3. **Transforming the Normal to World Space**:
+    ///    - Normals interact with transformations differently from points (especially under
+    ///      non-uniform scaling), so the transpose of the inverse of the transformation matrix
+    ///      is used:
+    ///      \[
+    ///      \text{world\_normal} = (T^{-1})^{T} \times \text{object\_normal}
+    ///      \]
+    ///
+    /// 4. **Normalization and Correction**:
+    ///    - The resulting vector is normalized, and its `w` component set to `0.0` as a
+    ///      translation in the matrix can leak into it.
+    ///
+    /// `hit` is the intersection the point came from. A sphere's normal only depends on the
+    /// point, shapes that interpolate normals (smooth triangles) need its u/v.
+    fn normal_at<S>(&self, world_point: Point, hit: &Intersection<S>) -> Vector
+    where
+        Self: Sized,
+    {
+        let object_point = self.inverse_transform() * world_point;
+        let object_normal = self.local_normal_at(object_point, hit.uv);
+        // transposing the inverse matrix is necessary because it ensures that the normal vector
+        // is correctly transformed to remain perpendicular to the surface after
+        // non-uniform scaling, rotation, and other transformations
+        let mut world_normal = self.inverse_transform().transpose() * object_normal;
+        // translation can mess up the w coordinate
+        // avoid more complex code with hack / set w to 0
+        world_normal.w = 0.0;
+        world_normal.normalize()
+    }
+}
+
+/// Shapes can be intersected by reference, e.g. as `&dyn Shape`
+impl<S: Shape + ?Sized> Shape for &S {
+    fn id(&self) -> Uuid {
+        (**self).id()
+    }
+
+    fn transform(&self) -> M4x4 {
+        (**self).transform()
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        (**self).inverse_transform()
+    }
+
+    fn material(&self) -> &Material {
+        (**self).material()
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        (**self).light_groups()
+    }
+
+    fn visibility(&self) -> Visibility {
+        (**self).visibility()
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        (**self).local_intersect(ray)
+    }
+
+    fn local_normal_at(&self, point: Point, uv: Option<(f64, f64)>) -> Vector {
+        (**self).local_normal_at(point, uv)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        (**self).local_bounds()
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        (**self).default_uv_map()
+    }
+
+    fn uv_at(&self, point: Point) -> (f64, f64) {
+        (**self).uv_at(point)
+    }
+
+    fn uv_tangents(&self, point: Point) -> (Vector, Vector) {
+        (**self).uv_tangents(point)
+    }
+}
+
+/// The t of each crossing `Shape::local_intersect` found, with its u/v for shapes that give
+/// one. Holds two: every primitive is convex or flat, so a ray can't cross it more often.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LocalHits {
+    hits: [(f64, Option<(f64, f64)>); 2],
+    len: usize,
+}
+
+impl LocalHits {
+    pub fn new() -> Self {
+        LocalHits::default()
+    }
+
+    pub fn push(&mut self, t: f64) {
+        self.push_hit(t, None);
+    }
+
+    pub fn push_with_uv(&mut self, t: f64, u: f64, v: f64) {
+        self.push_hit(t, Some((u, v)));
+    }
+
+    fn push_hit(&mut self, t: f64, uv: Option<(f64, f64)>) {
+        assert!(
+            self.len < self.hits.len(),
+            "a ray crosses a primitive twice at most"
+        );
+        self.hits[self.len] = (t, uv);
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (f64, Option<(f64, f64)>)> + '_ {
+        self.hits[..self.len].iter().copied()
+    }
+}
+
+/// Anything the world can hold, one variant per primitive
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub enum Object {
+    Sphere(Sphere),
+    Cube(Cube),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Quad(Quad),
+    Capsule(Capsule),
+}
+
+impl Object {
+    pub fn as_sphere(&self) -> Option<&Sphere> {
+        match self {
+            Object::Sphere(sphere) => Some(sphere),
+            _ => None,
+        }
+    }
+
+    pub fn as_cube(&self) -> Option<&Cube> {
+        match self {
+            Object::Cube(cube) => Some(cube),
+            _ => None,
+        }
+    }
+
+    pub fn as_triangle(&self) -> Option<&Triangle> {
+        match self {
+            Object::Triangle(triangle) => Some(triangle),
+            _ => None,
+        }
+    }
+
+    pub fn as_smooth(&self) -> Option<&SmoothTriangle> {
+        match self {
+            Object::SmoothTriangle(smooth) => Some(smooth),
+            _ => None,
+        }
+    }
+
+    pub fn as_quad(&self) -> Option<&Quad> {
+        match self {
+            Object::Quad(quad) => Some(quad),
+            _ => None,
+        }
+    }
+
+    pub fn as_capsule(&self) -> Option<&Capsule> {
+        match self {
+            Object::Capsule(capsule) => Some(capsule),
+            _ => None,
+        }
+    }
+
+    /// Sets the transform, failing like `Sphere::set_transform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        match self {
+            Object::Sphere(sphere) => sphere.set_transform(transform),
+            Object::Cube(cube) => cube.set_transform(transform),
+            Object::Triangle(triangle) => triangle.set_transform(transform),
+            Object::SmoothTriangle(smooth) => smooth.set_transform(transform),
+            Object::Quad(quad) => quad.set_transform(transform),
+            Object::Capsule(capsule) => capsule.set_transform(transform),
+        }
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        match self {
+            Object::Sphere(sphere) => &mut sphere.material,
+            Object::Cube(cube) => &mut cube.material,
+            Object::Triangle(triangle) => &mut triangle.material,
+            Object::SmoothTriangle(smooth) => &mut smooth.material,
+            Object::Quad(quad) => &mut quad.material,
+            Object::Capsule(capsule) => &mut capsule.material,
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        *self.material_mut() = material;
+    }
+
+    pub fn set_light_groups(&mut self, light_groups: LightGroups) {
+        match self {
+            Object::Sphere(sphere) => sphere.light_groups = light_groups,
+            Object::Cube(cube) => cube.light_groups = light_groups,
+            Object::Triangle(triangle) => triangle.light_groups = light_groups,
+            Object::SmoothTriangle(smooth) => smooth.light_groups = light_groups,
+            Object::Quad(quad) => quad.light_groups = light_groups,
+            Object::Capsule(capsule) => capsule.light_groups = light_groups,
+        }
+    }
+
+    pub fn set_visibility(&mut self, visibility: Visibility) {
+        match self {
+            Object::Sphere(sphere) => sphere.visibility = visibility,
+            Object::Cube(cube) => cube.visibility = visibility,
+            Object::Triangle(triangle) => triangle.visibility = visibility,
+            Object::SmoothTriangle(smooth) => smooth.visibility = visibility,
+            Object::Quad(quad) => quad.visibility = visibility,
+            Object::Capsule(capsule) => capsule.visibility = visibility,
+        }
+    }
+
+    /// The object as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Object {
+        match self {
+            Object::Sphere(sphere) => Object::Sphere(sphere.placed_in(group, group_inverse)),
+            Object::Cube(cube) => Object::Cube(cube.placed_in(group, group_inverse)),
+            Object::Triangle(triangle) => {
+                Object::Triangle(triangle.placed_in(group, group_inverse))
+            }
+            Object::SmoothTriangle(smooth) => {
+                Object::SmoothTriangle(smooth.placed_in(group, group_inverse))
+            }
+            Object::Quad(quad) => Object::Quad(quad.placed_in(group, group_inverse)),
+            Object::Capsule(capsule) => Object::Capsule(capsule.placed_in(group, group_inverse)),
+        }
+    }
+}
+
+impl Shape for Object {
+    fn id(&self) -> Uuid {
+        match self {
+            Object::Sphere(sphere) => sphere.id,
+            Object::Cube(cube) => cube.id,
+            Object::Triangle(triangle) => triangle.id,
+            Object::SmoothTriangle(smooth) => smooth.id,
+            Object::Quad(quad) => quad.id,
+            Object::Capsule(capsule) => capsule.id,
+        }
+    }
+
+    fn transform(&self) -> M4x4 {
+        match self {
+            Object::Sphere(sphere) => sphere.transform(),
+            Object::Cube(cube) => cube.transform(),
+            Object::Triangle(triangle) => triangle.transform(),
+            Object::SmoothTriangle(smooth) => smooth.transform(),
+            Object::Quad(quad) => quad.transform(),
+            Object::Capsule(capsule) => capsule.transform(),
+        }
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        match self {
+            Object::Sphere(sphere) => sphere.inverse_transform(),
+            Object::Cube(cube) => cube.inverse_transform(),
+            Object::Triangle(triangle) => triangle.inverse_transform(),
+            Object::SmoothTriangle(smooth) => smooth.inverse_transform(),
+            Object::Quad(quad) => quad.inverse_transform(),
+            Object::Capsule(capsule) => capsule.inverse_transform(),
+        }
+    }
+
+    fn material(&self) -> &Material {
+        match self {
+            Object::Sphere(sphere) => &sphere.material,
+            Object::Cube(cube) => &cube.material,
+            Object::Triangle(triangle) => &triangle.material,
+            Object::SmoothTriangle(smooth) => &smooth.material,
+            Object::Quad(quad) => &quad.material,
+            Object::Capsule(capsule) => &capsule.material,
+        }
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        match self {
+            Object::Sphere(sphere) => sphere.light_groups,
+            Object::Cube(cube) => cube.light_groups,
+            Object::Triangle(triangle) => triangle.light_groups,
+            Object::SmoothTriangle(smooth) => smooth.light_groups,
+            Object::Quad(quad) => quad.light_groups,
+            Object::Capsule(capsule) => capsule.light_groups,
+        }
+    }
+
+    fn visibility(&self) -> Visibility {
+        match self {
+            Object::Sphere(sphere) => sphere.visibility,
+            Object::Cube(cube) => cube.visibility,
+            Object::Triangle(triangle) => triangle.visibility,
+            Object::SmoothTriangle(smooth) => smooth.visibility,
+            Object::Quad(quad) => quad.visibility,
+            Object::Capsule(capsule) => capsule.visibility,
+        }
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        match self {
+            Object::Sphere(sphere) => sphere.local_intersect(ray),
+            Object::Cube(cube) => cube.local_intersect(ray),
+            Object::Triangle(triangle) => triangle.local_intersect(ray),
+            Object::SmoothTriangle(smooth) => smooth.local_intersect(ray),
+            Object::Quad(quad) => quad.local_intersect(ray),
+            Object::Capsule(capsule) => capsule.local_intersect(ray),
+        }
+    }
+
+    fn local_normal_at(&self, point: Point, uv: Option<(f64, f64)>) -> Vector {
+        match self {
+            Object::Sphere(sphere) => sphere.local_normal_at(point, uv),
+            Object::Cube(cube) => cube.local_normal_at(point, uv),
+            Object::Triangle(triangle) => triangle.local_normal_at(point, uv),
+            Object::SmoothTriangle(smooth) => smooth.local_normal_at(point, uv),
+            Object::Quad(quad) => quad.local_normal_at(point, uv),
+            Object::Capsule(capsule) => capsule.local_normal_at(point, uv),
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        match self {
+            Object::Sphere(sphere) => sphere.local_bounds(),
+            Object::Cube(cube) => cube.local_bounds(),
+            Object::Triangle(triangle) => triangle.local_bounds(),
+            Object::SmoothTriangle(smooth) => smooth.local_bounds(),
+            Object::Quad(quad) => quad.local_bounds(),
+            Object::Capsule(capsule) => capsule.local_bounds(),
+        }
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        match self {
+            Object::Sphere(sphere) => sphere.default_uv_map(),
+            Object::Cube(cube) => cube.default_uv_map(),
+            Object::Triangle(triangle) => triangle.default_uv_map(),
+            Object::SmoothTriangle(smooth) => smooth.default_uv_map(),
+            Object::Quad(quad) => quad.default_uv_map(),
+            Object::Capsule(capsule) => capsule.default_uv_map(),
+        }
+    }
+
+    fn uv_at(&self, point: Point) -> (f64, f64) {
+        match self {
+            Object::Sphere(sphere) => sphere.uv_at(point),
+            Object::Cube(cube) => cube.uv_at(point),
+            Object::Triangle(triangle) => triangle.uv_at(point),
+            Object::SmoothTriangle(smooth) => smooth.uv_at(point),
+            Object::Quad(quad) => quad.uv_at(point),
+            Object::Capsule(capsule) => capsule.uv_at(point),
+        }
+    }
+
+    fn uv_tangents(&self, point: Point) -> (Vector, Vector) {
+        match self {
+            Object::Sphere(sphere) => sphere.uv_tangents(point),
+            Object::Cube(cube) => cube.uv_tangents(point),
+            Object::Triangle(triangle) => triangle.uv_tangents(point),
+            Object::SmoothTriangle(smooth) => smooth.uv_tangents(point),
+            Object::Quad(quad) => quad.uv_tangents(point),
+            Object::Capsule(capsule) => capsule.uv_tangents(point),
+        }
+    }
+}
+
+impl From<Sphere> for Object {
+    fn from(sphere: Sphere) -> Self {
+        Object::Sphere(sphere)
+    }
+}
+
+impl From<Cube> for Object {
+    fn from(cube: Cube) -> Self {
+        Object::Cube(cube)
+    }
+}
+
+impl From<Triangle> for Object {
+    fn from(triangle: Triangle) -> Self {
+        Object::Triangle(triangle)
+    }
+}
+
+impl From<SmoothTriangle> for Object {
+    fn from(smooth: SmoothTriangle) -> Self {
+        Object::SmoothTriangle(smooth)
+    }
+}
+
+impl From<Quad> for Object {
+    fn from(quad: Quad) -> Self {
+        Object::Quad(quad)
+    }
+}
+
+impl From<Capsule> for Object {
+    fn from(capsule: Capsule) -> Self {
+        Object::Capsule(capsule)
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use uuid::Uuid;
+
+    use crate::bounds::BoundingBox;
+    use crate::light::{LightGroups, Material};
+    use crate::matrix::M4x4;
+    use crate::matrix_transformations::{scaling, translation};
+    use crate::rays::{intersect, Intersection, Ray, Sphere, Visibility};
+    use crate::shape::{LocalHits, Object, Shape};
+    use crate::tuple::{Point, Vector};
+
+    /// The book's test shape: no surface, it only remembers the last ray it was given
+    #[derive(Debug)]
+    struct TestShape {
+        transform: M4x4,
+        inverse_transform: M4x4,
+        material: Material,
+        saved_ray: Cell<Option<Ray>>,
+    }
+
+    impl TestShape {
+        fn with_transform(transform: M4x4) -> Self {
+            TestShape {
+                transform,
+                inverse_transform: transform.inverse().unwrap(),
+                material: Material::new(),
+                saved_ray: Cell::new(None),
+            }
+        }
+    }
+
+    impl Shape for TestShape {
+        fn id(&self) -> Uuid {
+            Uuid::nil()
+        }
+
+        fn transform(&self) -> M4x4 {
+            self.transform
+        }
+
+        fn inverse_transform(&self) -> M4x4 {
+            self.inverse_transform
+        }
+
+        fn material(&self) -> &Material {
+            &self.material
+        }
+
+        fn light_groups(&self) -> LightGroups {
+            LightGroups::ALL
+        }
+
+        fn visibility(&self) -> Visibility {
+            Visibility::ALL
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> LocalHits {
+            self.saved_ray.set(Some(*ray));
+            LocalHits::new()
+        }
+
+        fn local_normal_at(&self, point: Point, _uv: Option<(f64, f64)>) -> Vector {
+            Vector::new(point.x, point.y, point.z)
+        }
+
+        fn local_bounds(&self) -> BoundingBox {
+            BoundingBox::new(
+                Point::new_point(-1.0, -1.0, -1.0),
+                Point::new_point(1.0, 1.0, 1.0),
+            )
+        }
+    }
+
+    #[test]
+    fn rays_are_transformed_into_shape_space() {
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = TestShape::with_transform(scaling(2.0, 2.0, 2.0));
+        let xs = intersect(&r, &shape);
+        assert_eq!(xs.size(), 0);
+        let saved = shape.saved_ray.get().unwrap();
+        assert_eq!(saved.origin(), Point::new_point(0.0, 0.0, -2.5));
+        assert_eq!(saved.direction(), Vector::new(0.0, 0.0, 0.5));
+
+        let shape = TestShape::with_transform(translation(5.0, 0.0, 0.0));
+        intersect(&r, &shape);
+        let saved = shape.saved_ray.get().unwrap();
+        assert_eq!(saved.origin(), Point::new_point(-5.0, 0.0, -5.0));
+        assert_eq!(saved.direction(), Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normals_are_transformed_out_of_shape_space() {
+        let shape = TestShape::with_transform(translation(0.0, 1.0, 0.0));
+        let n = shape.normal_at(
+            Point::new_point(0.0, 1.70711, -FRAC_1_SQRT_2),
+            &Intersection::new(1.0, &shape),
+        );
+        assert_eq!(n, Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2));
+        assert_eq!(shape.bounds().min(), Point::new_point(-1.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn objects_act_like_their_shape() {
+        let mut sphere = Sphere::new();
+        sphere.set_transform(translation(0.0, 0.0, 1.0)).unwrap();
+        let mut object = Object::from(sphere);
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let ts: Vec<f64> = intersect(&r, object).iter().map(|i| i.t).collect();
+        assert_eq!(ts, [5.0, 7.0]);
+        assert_eq!(object.id(), sphere.id);
+        assert_eq!(object, Object::Sphere(sphere));
+
+        object.material_mut().ambient = 1.0;
+        assert_eq!(object.material().ambient, 1.0);
+        assert!(object.set_transform(scaling(0.0, 1.0, 1.0)).is_err());
+        assert_eq!(object.as_sphere().unwrap().transform(), sphere.transform());
+    }
+}
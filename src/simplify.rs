@@ -0,0 +1,339 @@
+//! Mesh simplification by edge collapse, e.g. to render a decimated copy of a huge scan for
+//! previews and the full mesh for the final image.
+//!
+//! Each vertex keeps a quadric, the sum of the squared distances to the planes of the faces
+//! around it (Garland and Heckbert). Collapsing an edge merges its two vertices into one, at
+//! whichever of the two ends or the middle is closest to all of their planes, and the cheapest
+//! edges go first. Open borders get extra planes standing up along them so they keep their
+//! outline, and collapses that would flip a face over are skipped.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::mesh::Mesh;
+use crate::tuple::{Point, Vector};
+
+/// How much more moving a border vertex off its border costs than moving it off its face
+const BORDER_WEIGHT: f64 = 1000.0;
+
+/// Symmetric 4x4 matrix, upper triangle row by row
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Squared distance to the plane through `point` with unit `normal`, times `weight`
+    fn plane(point: Point, normal: Vector, weight: f64) -> Quadric {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -(a * point.x + b * point.y + c * point.z);
+        Quadric(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|x| x * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (x, y) in self.0.iter_mut().zip(other.0) {
+            *x += y;
+        }
+    }
+
+    fn error(&self, p: Point) -> f64 {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        aa * x * x
+            + 2.0 * ab * x * y
+            + 2.0 * ac * x * z
+            + 2.0 * ad * x
+            + bb * y * y
+            + 2.0 * bc * y * z
+            + 2.0 * bd * y
+            + cc * z * z
+            + 2.0 * cd * z
+            + dd
+    }
+}
+
+/// Merging `from` into `into` at `target`, valid while neither vertex has changed since
+struct Collapse {
+    cost: f64,
+    into: usize,
+    from: usize,
+    target: Point,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    /// Reversed, so the max-heap pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Simplifier {
+    positions: Vec<Point>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    alive: Vec<bool>,
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    /// Faces using each vertex, dead ones included until the vertex is next touched
+    vertex_faces: Vec<Vec<usize>>,
+    heap: BinaryHeap<Collapse>,
+}
+
+impl Simplifier {
+    fn new(mesh: &Mesh) -> Self {
+        let mut quadrics = vec![Quadric::default(); mesh.vertices.len()];
+        let mut vertex_faces = vec![Vec::new(); mesh.vertices.len()];
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let [a, b, c] = face.map(|i| mesh.vertices[i]);
+            let cross = (b - a).cross(&(c - a));
+            let area = cross.magnitude() / 2.0;
+            if area > 0.0 {
+                let plane = Quadric::plane(a, cross.normalize(), area);
+                for i in face {
+                    quadrics[*i].add(&plane);
+                }
+            }
+            for k in 0..3 {
+                vertex_faces[face[k]].push(f);
+                let (u, v) = (face[k], face[(k + 1) % 3]);
+                edge_faces.entry((u.min(v), u.max(v))).or_default().push(f);
+            }
+        }
+        for (&(u, v), faces) in &edge_faces {
+            if let [f] = faces[..] {
+                let [a, b, c] = mesh.faces[f].map(|i| mesh.vertices[i]);
+                let face_normal = (b - a).cross(&(c - a));
+                let edge = mesh.vertices[v] - mesh.vertices[u];
+                let side = edge.cross(&face_normal);
+                if side.magnitude() > 0.0 {
+                    let weight = BORDER_WEIGHT * edge.magnitude() * edge.magnitude();
+                    let plane = Quadric::plane(mesh.vertices[u], side.normalize(), weight);
+                    quadrics[u].add(&plane);
+                    quadrics[v].add(&plane);
+                }
+            }
+        }
+        let mut simplifier = Simplifier {
+            positions: mesh.vertices.clone(),
+            quadrics,
+            versions: vec![0; mesh.vertices.len()],
+            alive: vec![true; mesh.vertices.len()],
+            faces: mesh.faces.clone(),
+            face_alive: vec![true; mesh.faces.len()],
+            vertex_faces,
+            heap: BinaryHeap::new(),
+        };
+        for (u, v) in edge_faces.into_keys() {
+            simplifier.push(u, v);
+        }
+        simplifier
+    }
+
+    fn push(&mut self, into: usize, from: usize) {
+        let mut quadric = self.quadrics[into];
+        quadric.add(&self.quadrics[from]);
+        let (a, b) = (self.positions[into], self.positions[from]);
+        let middle = a + (b - a) * 0.5;
+        let (cost, target) = [a, b, middle]
+            .into_iter()
+            .map(|p| (quadric.error(p), p))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .expect("three candidates");
+        self.heap.push(Collapse {
+            cost,
+            into,
+            from,
+            target,
+            versions: (self.versions[into], self.versions[from]),
+        });
+    }
+
+    fn is_current(&self, collapse: &Collapse) -> bool {
+        let (into, from) = (collapse.into, collapse.from);
+        self.alive[into]
+            && self.alive[from]
+            && collapse.versions == (self.versions[into], self.versions[from])
+    }
+
+    /// Whether moving both vertices to `target` turns any face around them over
+    fn flips(&self, into: usize, from: usize, target: Point) -> bool {
+        [into, from].iter().any(|&v| {
+            self.vertex_faces[v].iter().any(|&f| {
+                let face = self.faces[f];
+                if !self.face_alive[f] || (face.contains(&into) && face.contains(&from)) {
+                    return false;
+                }
+                let corners = face.map(|i| self.positions[i]);
+                let moved = face.map(|i| if i == v { target } else { self.positions[i] });
+                let normal = |[a, b, c]: [Point; 3]| (b - a).cross(&(c - a));
+                normal(corners).dot(&normal(moved)) <= 0.0
+            })
+        })
+    }
+
+    /// Merges `from` into `into`, returning how many faces disappeared
+    fn collapse(&mut self, collapse: &Collapse) -> usize {
+        let (into, from) = (collapse.into, collapse.from);
+        self.positions[into] = collapse.target;
+        let quadric = self.quadrics[from];
+        self.quadrics[into].add(&quadric);
+        self.alive[from] = false;
+        self.versions[into] += 1;
+
+        let mut removed = 0;
+        for f in std::mem::take(&mut self.vertex_faces[from]) {
+            if !self.face_alive[f] {
+                continue;
+            }
+            if self.faces[f].contains(&into) {
+                self.face_alive[f] = false;
+                removed += 1;
+            } else {
+                for i in self.faces[f].iter_mut() {
+                    if *i == from {
+                        *i = into;
+                    }
+                }
+                self.vertex_faces[into].push(f);
+            }
+        }
+        let face_alive = &self.face_alive;
+        self.vertex_faces[into].retain(|f| face_alive[*f]);
+
+        let mut neighbors: Vec<usize> = self.vertex_faces[into]
+            .iter()
+            .flat_map(|f| self.faces[*f])
+            .filter(|v| *v != into)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        for neighbor in neighbors {
+            self.push(into, neighbor);
+        }
+        removed
+    }
+}
+
+/// A copy of the mesh collapsed down to at most `target_faces` triangles where it can be
+/// without flipping faces over. Vertices keep the normal and texture coordinates of the end
+/// of the edge they were merged into.
+pub fn simplify(mesh: &Mesh, target_faces: usize) -> Mesh {
+    let mut simplifier = Simplifier::new(mesh);
+    let mut faces = mesh.faces.len();
+    while faces > target_faces {
+        let Some(collapse) = simplifier.heap.pop() else {
+            break;
+        };
+        if !simplifier.is_current(&collapse)
+            || simplifier.flips(collapse.into, collapse.from, collapse.target)
+        {
+            continue;
+        }
+        faces -= simplifier.collapse(&collapse);
+    }
+
+    // drop what was collapsed away and number what's left
+    let mut simplified = Mesh::new();
+    let mut index = vec![usize::MAX; mesh.vertices.len()];
+    for (f, face) in simplifier.faces.iter().enumerate() {
+        if !simplifier.face_alive[f] {
+            continue;
+        }
+        let face = face.map(|v| {
+            if index[v] == usize::MAX {
+                index[v] = simplified.vertices.len();
+                simplified.vertices.push(simplifier.positions[v]);
+                if let Some(normal) = mesh.normals.get(v) {
+                    simplified.normals.push(*normal);
+                }
+                if let Some(uv) = mesh.uvs.get(v) {
+                    simplified.uvs.push(*uv);
+                }
+            }
+            index[v]
+        });
+        simplified.faces.push(face);
+    }
+    simplified
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::Mesh;
+    use crate::simplify::simplify;
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn flat_planes_keep_their_outline() {
+        let plane = Mesh::plane(8);
+        let simplified = simplify(&plane, 20);
+        assert!(simplified.faces.len() <= 20, "{}", simplified.faces.len());
+        assert!(simplified.vertices.iter().all(|v| v.y == 0.0));
+        assert_eq!(simplified.bounds(), plane.bounds());
+        for face in 0..simplified.faces.len() {
+            assert_eq!(simplified.face_normal(face), Vector::new(0.0, 1.0, 0.0));
+        }
+        assert_eq!(simplified.normals.len(), simplified.vertices.len());
+    }
+
+    #[test]
+    fn a_tessellated_triangle_goes_back_to_one() {
+        let corners = [
+            Point::new_point(0.0, 0.0, 0.0),
+            Point::new_point(4.0, 0.0, 0.0),
+            Point::new_point(0.0, 4.0, 0.0),
+        ];
+        let [a, b, c] = corners;
+        let simplified = simplify(&Mesh::triangle(a, b, c, 4), 1);
+        assert_eq!(simplified.faces.len(), 1);
+        for corner in corners {
+            assert!(simplified.vertices.contains(&corner));
+        }
+        assert_eq!(simplified.face_normal(0), Vector::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn bumps_are_flattened_last() {
+        let mut mesh = Mesh::plane(10);
+        mesh.displace(|p, _| {
+            if p.x.abs() < 0.1 && p.z.abs() < 0.1 {
+                0.5
+            } else {
+                0.0
+            }
+        });
+        let simplified = simplify(&mesh, 60);
+        assert!(simplified.faces.len() <= 60);
+        // the peak in the middle is the most expensive thing to lose
+        let top = simplified.bounds().max().y;
+        assert!((top - 0.5).abs() < 1e-9, "{}", top);
+    }
+}
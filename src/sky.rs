@@ -0,0 +1,207 @@
+//! Daylight from the Preetham sky model ("A Practical Analytic Model for Daylight", 1999).
+//!
+//! Given where the sun is and how hazy the air is, `Sky` provides both the color of the sky in
+//! any direction (the background of outdoor scenes) and the color of the sunlight itself, so
+//! the two always match.
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::color::Color;
+use crate::tuple::Vector;
+
+/// Coefficients A to E of the Perez distribution, each linear in turbidity: (slope, offset)
+type PerezCoefficients = [(f64, f64); 5];
+
+const LUMINANCE: PerezCoefficients = [
+    (0.1787, -1.4630),
+    (-0.3554, 0.4275),
+    (-0.0227, 5.3251),
+    (0.1206, -2.5771),
+    (-0.0670, 0.3703),
+];
+const CHROMATICITY_X: PerezCoefficients = [
+    (-0.0193, -0.2592),
+    (-0.0665, 0.0008),
+    (-0.0004, 0.2125),
+    (-0.0641, -0.8989),
+    (-0.0033, 0.0452),
+];
+const CHROMATICITY_Y: PerezCoefficients = [
+    (-0.0167, -0.2608),
+    (-0.0950, 0.0092),
+    (-0.0079, 0.2102),
+    (-0.0441, -1.6537),
+    (-0.0109, 0.0529),
+];
+
+/// Sky luminance is in kcd/m², about 20 overhead at noon, this brings it into displayable range
+const DEFAULT_EXPOSURE: f64 = 0.03;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sky {
+    elevation: f64,
+    azimuth: f64,
+    turbidity: f64,
+    exposure: f64,
+}
+
+impl Sky {
+    /// The sun `elevation` radians above the horizon, `azimuth` radians around the y axis from
+    /// +z toward +x. `turbidity` is how hazy the air is, 2 for a clear day up to about 10.
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        Sky {
+            elevation,
+            azimuth,
+            turbidity,
+            exposure: DEFAULT_EXPOSURE,
+        }
+    }
+
+    pub fn elevation(&self) -> f64 {
+        self.elevation
+    }
+
+    pub fn azimuth(&self) -> f64 {
+        self.azimuth
+    }
+
+    pub fn turbidity(&self) -> f64 {
+        self.turbidity
+    }
+
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /// Scales the sky's brightness, the sun is left alone
+    pub fn set_exposure(&mut self, exposure: f64) {
+        self.exposure = exposure;
+    }
+
+    /// Unit vector pointing toward the sun
+    pub fn sun_direction(&self) -> Vector {
+        let (sin_el, cos_el) = self.elevation.sin_cos();
+        let (sin_az, cos_az) = self.azimuth.sin_cos();
+        Vector::new(cos_el * sin_az, sin_el, cos_el * cos_az)
+    }
+
+    /// Color of the sky looking along `direction`. Below the horizon it stays at the horizon's
+    /// color, there's no ground in the model.
+    pub fn color(&self, direction: Vector) -> Color {
+        let direction = direction.normalize();
+        let sun_theta = FRAC_PI_2 - self.elevation;
+        // angle from the zenith, kept just above the horizon where the model breaks down
+        let theta = direction.y.clamp(0.01, 1.0).acos();
+        let gamma = direction.dot(&self.sun_direction()).clamp(-1.0, 1.0).acos();
+
+        let (luminance, x, y) = self.zenith();
+        let relative = |coefficients: &PerezCoefficients| {
+            perez(coefficients, self.turbidity, theta, gamma)
+                / perez(coefficients, self.turbidity, 0.0, sun_theta)
+        };
+        xyy_to_rgb(
+            x * relative(&CHROMATICITY_X),
+            y * relative(&CHROMATICITY_Y),
+            luminance * relative(&LUMINANCE) * self.exposure,
+        )
+    }
+
+    /// Color of direct sunlight after passing through the atmosphere, white-ish at noon and
+    /// fading to deep orange as the sun sets
+    pub fn sun_color(&self) -> Color {
+        let zenith_degrees = 90.0 - self.elevation.to_degrees();
+        if zenith_degrees >= 90.0 {
+            return Color::BLACK;
+        }
+        // relative optical air mass (Kasten and Young)
+        let air_mass = 1.0
+            / (zenith_degrees.to_radians().cos()
+                + 0.50572 * (96.07995 - zenith_degrees).powf(-1.6364));
+        let aerosols = 0.04608 * self.turbidity - 0.04586;
+        let transmittance = |wavelength_um: f64| {
+            let rayleigh = 0.008735 * wavelength_um.powf(-4.08);
+            let mie = aerosols * wavelength_um.powf(-1.3);
+            (-air_mass * (rayleigh + mie)).exp()
+        };
+        Color::new(
+            transmittance(0.68),
+            transmittance(0.55),
+            transmittance(0.45),
+        )
+    }
+
+    /// Luminance and chromaticity straight up
+    fn zenith(&self) -> (f64, f64, f64) {
+        let t = self.turbidity;
+        let theta = FRAC_PI_2 - self.elevation;
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let (t2, th, th2, th3) = (t * t, theta, theta * theta, theta * theta * theta);
+        let x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+        (luminance, x, y)
+    }
+}
+
+/// The Perez sky distribution for a view `theta` from the zenith and `gamma` from the sun
+fn perez(coefficients: &PerezCoefficients, turbidity: f64, theta: f64, gamma: f64) -> f64 {
+    let [a, b, c, d, e] = coefficients.map(|(slope, offset)| slope * turbidity + offset);
+    (1.0 + a * (b / theta.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// CIE xyY to linear sRGB
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color::new(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::sky::Sky;
+    use crate::tuple::Vector;
+
+    #[test]
+    fn sun_direction_from_elevation_and_azimuth() {
+        assert_eq!(
+            Sky::new(FRAC_PI_2, 0.0, 3.0).sun_direction(),
+            Vector::new(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            Sky::new(0.0, FRAC_PI_2, 3.0).sun_direction(),
+            Vector::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn clear_midday_sky_is_blue_and_brightest_near_the_sun() {
+        let sky = Sky::new(1.2, 0.0, 2.5);
+        let up = sky.color(Vector::new(0.0, 1.0, 0.0));
+        assert!(up.blue() > up.red());
+        let near_sun = sky.color(sky.sun_direction() + Vector::new(0.0, -0.05, 0.0));
+        let away = sky.color(Vector::new(0.0, 0.3, -1.0));
+        assert!(near_sun.luminance() > away.luminance());
+    }
+
+    #[test]
+    fn sunlight_reddens_toward_sunset() {
+        let noon = Sky::new(1.4, 0.0, 3.0).sun_color();
+        let evening = Sky::new(0.05, 0.0, 3.0).sun_color();
+        assert!(noon.green() > 0.6 && noon.blue() > 0.5);
+        assert!(evening.red() > evening.green() && evening.green() > evening.blue());
+        assert!(evening.luminance() < noon.luminance());
+        assert_eq!(Sky::new(-0.1, 0.0, 3.0).sun_color().luminance(), 0.0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Skyboxes: the distant surroundings of a scene as six images, one for each face of a cube
+//! around it, seen behind everything instead of a black background.
+//!
+//! The skybox is infinitely far away, so only the direction of a ray that misses everything
+//! picks the color. Faces are mapped with `uv::cube_map`, each image the way it looks from
+//! inside the cube, which is how skybox images usually come.
+
+use std::sync::Arc;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::tuple::{Point, Vector};
+use crate::uv::{cube_map, CubeFace};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skybox {
+    faces: [Arc<Canvas>; 6],
+}
+
+impl Skybox {
+    /// The images for the faces in `CubeFace::ALL` order: left, front, right, back, up and down.
+    /// They're shared so copies of the world don't copy the images.
+    pub fn new(faces: [Arc<Canvas>; 6]) -> Self {
+        Skybox { faces }
+    }
+
+    pub fn face(&self, face: CubeFace) -> &Canvas {
+        &self.faces[face as usize]
+    }
+
+    /// The color seen looking along `direction`
+    pub fn color(&self, direction: Vector) -> Color {
+        let largest = direction
+            .x
+            .abs()
+            .max(direction.y.abs())
+            .max(direction.z.abs());
+        if largest == 0.0 {
+            return Color::BLACK;
+        }
+        // where the direction leaves the cube from -1 to 1
+        let on_cube = Point::new_point(
+            direction.x / largest,
+            direction.y / largest,
+            direction.z / largest,
+        );
+        let (face, u, v) = cube_map(on_cube);
+        self.face(face).sample_uv(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::skybox::Skybox;
+    use crate::tuple::Vector;
+    use crate::uv::CubeFace;
+
+    /// Each face a flat color
+    fn colored_faces() -> Skybox {
+        let colors = [
+            Color::RED,
+            Color::GREEN,
+            Color::BLUE,
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 1.0),
+        ];
+        Skybox::new(colors.map(|color| {
+            let mut face = Canvas::new(2, 2);
+            for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                face.write_pixel(x, y, color);
+            }
+            Arc::new(face)
+        }))
+    }
+
+    #[test]
+    fn looking_up_the_skybox_by_direction() {
+        let mut skybox = colored_faces();
+        let cases = [
+            ((-1.0, 0.2, 0.3), Color::RED),
+            ((0.1, -0.2, 5.0), Color::GREEN),
+            ((1.0, 0.0, 0.0), Color::BLUE),
+            ((0.0, 0.5, -0.7), Color::new(1.0, 1.0, 0.0)),
+            ((0.3, 2.0, 0.0), Color::new(0.0, 1.0, 1.0)),
+            ((0.0, -1.0, 0.0), Color::new(1.0, 0.0, 1.0)),
+        ];
+        for ((x, y, z), color) in cases {
+            assert_eq!(skybox.color(Vector::new(x, y, z)), color);
+        }
+
+        // the top face's top left is toward -x and -z, its top edge meeting the back face
+        let mut up = Canvas::new(2, 2);
+        up.write_pixel(0, 0, Color::WHITE);
+        skybox.faces[CubeFace::Up as usize] = Arc::new(up);
+        assert_eq!(skybox.color(Vector::new(-0.5, 1.0, -0.5)), Color::WHITE);
+        assert_eq!(skybox.color(Vector::new(0.5, 1.0, 0.5)), Color::BLACK);
+        assert_eq!(skybox.color(Vector::new(0.0, 0.0, 0.0)), Color::BLACK);
+    }
+}
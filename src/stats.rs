@@ -0,0 +1,138 @@
+//! What's in a world and what it's going to cost, before rendering it: `World::stats`.
+//!
+//! Objects and the groups they're nested in are what's counted, groups being the only
+//! hierarchy to search through. The number that usually explains a slow scene is the shadow
+//! rays per shaded point, every sample of every soft light is a ray traced through the world.
+
+use std::fmt;
+use std::mem::{size_of, size_of_val};
+
+use crate::group::Group;
+use crate::shape::Object;
+use crate::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WorldStats {
+    /// Every object, in groups or not
+    pub objects: usize,
+    /// Those of `objects` inside a group
+    pub grouped_objects: usize,
+    pub groups: usize,
+    /// How deep groups are nested, 1 for groups holding only objects, 0 without groups
+    pub group_depth: usize,
+    pub point_lights: usize,
+    pub sphere_lights: usize,
+    pub area_lights: usize,
+    pub spot_lights: usize,
+    pub clip_planes: usize,
+    /// Rays every shaded point casts toward the lights, at most: one per point or spot light
+    /// and the sun, and `samples` per sphere or area light
+    pub shadow_rays: usize,
+    /// Memory held by the objects and groups, roughly
+    pub geometry_bytes: usize,
+}
+
+impl WorldStats {
+    pub(crate) fn of(world: &World) -> WorldStats {
+        let mut stats = WorldStats {
+            objects: world.objects.len(),
+            point_lights: world.lights.len(),
+            sphere_lights: world.sphere_lights.len(),
+            area_lights: world.area_lights.len(),
+            spot_lights: world.spot_lights.len(),
+            clip_planes: world.clip_planes.len(),
+            geometry_bytes: world.objects.capacity() * size_of::<Object>(),
+            ..WorldStats::default()
+        };
+        for group in &world.groups {
+            stats.count_group(group, 1);
+        }
+        stats.shadow_rays = stats.point_lights
+            + stats.spot_lights
+            + usize::from(world.sky.is_some())
+            + world
+                .sphere_lights
+                .iter()
+                .map(|l| l.samples())
+                .sum::<usize>()
+            + world.area_lights.iter().map(|l| l.samples()).sum::<usize>();
+        stats
+    }
+
+    fn count_group(&mut self, group: &Group, depth: usize) {
+        self.groups += 1;
+        self.group_depth = self.group_depth.max(depth);
+        self.objects += group.objects().len();
+        self.grouped_objects += group.objects().len();
+        self.geometry_bytes +=
+            size_of::<Group>() + size_of_val(group.objects()) + size_of_val(group.groups());
+        for subgroup in group.groups() {
+            self.count_group(subgroup, depth + 1);
+        }
+    }
+}
+
+impl fmt::Display for WorldStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "objects: {}", self.objects)?;
+        if self.groups > 0 {
+            write!(
+                f,
+                ", {} of them in {} groups nested {} deep",
+                self.grouped_objects, self.groups, self.group_depth
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "lights: {} point, {} sphere, {} area, {} spot",
+            self.point_lights, self.sphere_lights, self.area_lights, self.spot_lights
+        )?;
+        writeln!(f, "shadow rays per shaded point: {}", self.shadow_rays)?;
+        writeln!(f, "clip planes: {}", self.clip_planes)?;
+        writeln!(
+            f,
+            "geometry: {:.1} KiB",
+            self.geometry_bytes as f64 / 1024.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Color;
+    use crate::group::Group;
+    use crate::light::SphereLight;
+    use crate::rays::Sphere;
+    use crate::tuple::Point;
+    use crate::world::default_world;
+
+    #[test]
+    fn counting_a_world() {
+        let mut world = default_world();
+        let mut inner = Group::new();
+        inner.add_object(Sphere::new());
+        let mut outer = Group::new();
+        outer.add_object(Sphere::new());
+        outer.add_object(Sphere::new());
+        outer.add_group(inner);
+        world.groups.push(outer);
+        let mut soft = SphereLight::new(Point::new_point(0.0, 5.0, 0.0), 1.0, Color::WHITE);
+        soft.set_samples(8);
+        world.sphere_lights.push(soft);
+
+        let stats = world.stats();
+        assert_eq!(stats.objects, 5);
+        assert_eq!(stats.grouped_objects, 3);
+        assert_eq!(stats.groups, 2);
+        assert_eq!(stats.group_depth, 2);
+        assert_eq!(stats.point_lights, 1);
+        assert_eq!(stats.sphere_lights, 1);
+        assert_eq!(stats.shadow_rays, 9);
+        assert!(stats.geometry_bytes > 0);
+
+        let report = stats.to_string();
+        assert!(report.starts_with("objects: 5, 3 of them in 2 groups nested 2 deep\n"));
+        assert!(report.contains("shadow rays per shaded point: 9\n"));
+    }
+}
@@ -0,0 +1,124 @@
+//! [proptest](https://docs.rs/proptest) strategies for the core types, for property tests here
+//! and in downstream crates.
+//!
+//! Values stay in ranges where the crate's `EPSILON` comparisons still hold after a few matrix
+//! multiplications, and transforms are built from translations, rotations and scalings away from
+//! zero so they are always invertible.
+
+use std::f64::consts::TAU;
+
+use proptest::prelude::*;
+
+use crate::color::Color;
+use crate::matrix::M4x4;
+use crate::matrix_transformations::{rotation_x, rotation_y, rotation_z, scaling, translation};
+use crate::rays::Sphere;
+use crate::tuple::{Point, Tuple, Vector};
+
+/// A single coordinate, kept small enough that products stay comparable with `EPSILON`
+pub fn coordinate() -> impl Strategy<Value = f64> {
+    -10.0..10.0
+}
+
+pub fn tuple() -> impl Strategy<Value = Tuple> {
+    (coordinate(), coordinate(), coordinate(), coordinate()).prop_map(|(x, y, z, w)| Tuple {
+        x,
+        y,
+        z,
+        w,
+    })
+}
+
+pub fn point() -> impl Strategy<Value = Point> {
+    (coordinate(), coordinate(), coordinate()).prop_map(|(x, y, z)| Point::new_point(x, y, z))
+}
+
+pub fn vector() -> impl Strategy<Value = Vector> {
+    (coordinate(), coordinate(), coordinate()).prop_map(|(x, y, z)| Vector::new(x, y, z))
+}
+
+/// Vectors long enough to normalize
+pub fn nonzero_vector() -> impl Strategy<Value = Vector> {
+    vector().prop_filter("vector too short to normalize", |v| v.magnitude() > 0.01)
+}
+
+/// Colors with every channel in 0..1
+pub fn color() -> impl Strategy<Value = Color> {
+    (0.0..1.0, 0.0..1.0, 0.0..1.0).prop_map(|(r, g, b)| Color::new(r, g, b))
+}
+
+fn scale_factor() -> impl Strategy<Value = f64> {
+    prop_oneof![0.25..4.0, -4.0..-0.25]
+}
+
+/// Translation * rotations * scaling, in the order the book chains them
+pub fn invertible_transform() -> impl Strategy<Value = M4x4> {
+    (
+        (coordinate(), coordinate(), coordinate()),
+        (0.0..TAU, 0.0..TAU, 0.0..TAU),
+        (scale_factor(), scale_factor(), scale_factor()),
+    )
+        .prop_map(|((tx, ty, tz), (rx, ry, rz), (sx, sy, sz))| {
+            translation(tx, ty, tz)
+                * rotation_x(rx)
+                * rotation_y(ry)
+                * rotation_z(rz)
+                * scaling(sx, sy, sz)
+        })
+}
+
+/// Spheres with a random invertible transform
+pub fn sphere() -> impl Strategy<Value = Sphere> {
+    invertible_transform().prop_map(|transform| {
+        let mut sphere = Sphere::new();
+        sphere
+            .set_transform(transform)
+            .expect("generated transforms are invertible");
+        sphere
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::matrix::IDENTITY_MATRIX_4X4;
+    use crate::rays::Intersection;
+    use crate::shape::Shape;
+    use crate::strategies::{invertible_transform, nonzero_vector, point, sphere, tuple, vector};
+    use crate::tuple::Point;
+    use crate::utils::equal_f64;
+
+    proptest! {
+        #[test]
+        fn transform_times_inverse_is_identity(m in invertible_transform()) {
+            prop_assert_eq!(m * m.inverse().unwrap(), IDENTITY_MATRIX_4X4);
+        }
+
+        #[test]
+        fn normals_are_unit_vectors(s in sphere(), direction in nonzero_vector()) {
+            let surface_point = s.transform() * (Point::new_point(0.0, 0.0, 0.0) + direction.normalize());
+            let normal = s.normal_at(surface_point, &Intersection::new(1.0, s));
+            prop_assert!(equal_f64(normal.magnitude(), 1.0));
+            prop_assert!(normal.is_vector());
+        }
+
+        #[test]
+        fn dot_product_of_a_tuple_with_itself_is_its_squared_magnitude(t in tuple()) {
+            let squared = t.x * t.x + t.y * t.y + t.z * t.z + t.w * t.w;
+            prop_assert!(equal_f64(t.dot(&t), squared));
+        }
+
+        #[test]
+        fn cross_product_is_perpendicular(a in vector(), b in vector()) {
+            let c = a.cross(&b);
+            prop_assert!(c.dot(&a).abs() < 1e-9 * (1.0 + a.magnitude() * b.magnitude()).powi(2));
+            prop_assert!(c.dot(&b).abs() < 1e-9 * (1.0 + a.magnitude() * b.magnitude()).powi(2));
+        }
+
+        #[test]
+        fn point_minus_point_is_a_vector(a in point(), b in point()) {
+            prop_assert!((a - b).is_vector());
+        }
+    }
+}
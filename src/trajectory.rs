@@ -0,0 +1,422 @@
+//! Plots of projectile paths.
+//!
+//! A `Trajectory` records every step of a projectile, and `draw_trajectories` draws them as
+//! connected anti-aliased lines, scaled to fill the canvas and colored by how old the
+//! projectile is or how fast it's going at each point. The recorded time series can also be
+//! written out as CSV or JSON, to plot or check it with other tools, or turned into an
+//! animation track to render the projectile flying through a 3D scene.
+
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::animation::{Easing, Track, TransformKey};
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::environment::{tick, Environment};
+use crate::error::Result;
+use crate::projectile::Projectile;
+use crate::tuple::{Point, Vector};
+
+/// Every step of a projectile's flight, launch first
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trajectory {
+    points: Vec<Projectile>,
+}
+
+impl Trajectory {
+    pub fn new() -> Self {
+        Trajectory::default()
+    }
+
+    /// Ticks the projectile until `keep_going` is false for it or `max_steps` have passed,
+    /// recording the launch and every step after it
+    pub fn simulate(
+        env: &Environment,
+        mut projectile: Projectile,
+        max_steps: usize,
+        keep_going: impl Fn(&Projectile) -> bool,
+    ) -> Self {
+        let mut trajectory = Trajectory::new();
+        trajectory.push(projectile);
+        for _ in 0..max_steps {
+            if !keep_going(&projectile) {
+                break;
+            }
+            projectile = tick(env, projectile);
+            trajectory.push(projectile);
+        }
+        trajectory
+    }
+
+    pub fn push(&mut self, projectile: Projectile) {
+        self.points.push(projectile);
+    }
+
+    pub fn points(&self) -> &[Projectile] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// One line per step: `t,x,y,z,vx,vy,vz`, after a header naming the columns
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("t,x,y,z,vx,vy,vz\n");
+        for p in &self.points {
+            let (position, velocity) = (p.position, p.velocity);
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                p.time, position.x, position.y, position.z, velocity.x, velocity.y, velocity.z
+            );
+        }
+        csv
+    }
+
+    /// An array with an object per step:
+    /// `{"t": 0, "position": [x, y, z], "velocity": [vx, vy, vz]}`
+    pub fn to_json(&self) -> String {
+        let steps: Vec<String> = self
+            .points
+            .iter()
+            .map(|p| {
+                let (position, velocity) = (p.position, p.velocity);
+                format!(
+                    "{{\"t\":{},\"position\":[{},{},{}],\"velocity\":[{},{},{}]}}",
+                    json_number(p.time),
+                    json_number(position.x),
+                    json_number(position.y),
+                    json_number(position.z),
+                    json_number(velocity.x),
+                    json_number(velocity.y),
+                    json_number(velocity.z),
+                )
+            })
+            .collect();
+        format!("[{}]\n", steps.join(","))
+    }
+
+    /// Keyframes moving an object along the path, one frame per recorded step, to animate a
+    /// sphere of radius `scale` with `Animation::object`
+    pub fn to_track(&self, scale: f64) -> Track<TransformKey> {
+        let mut track = Track::new();
+        for (frame, p) in self.points.iter().enumerate() {
+            let key = TransformKey {
+                translation: p.position - Point::new_point(0.0, 0.0, 0.0),
+                scale: Vector::new(scale, scale, scale),
+                ..TransformKey::default()
+            };
+            track.add(frame as f64, key, Easing::Linear);
+        }
+        track
+    }
+
+    pub fn to_csv_file(&self, filename: &str) -> Result<()> {
+        Ok(fs::write(filename, self.to_csv())?)
+    }
+
+    pub fn to_json_file(&self, filename: &str) -> Result<()> {
+        Ok(fs::write(filename, self.to_json())?)
+    }
+}
+
+/// JSON has no NaN or infinity, a projectile that blew up gets `null`s
+fn json_number(x: f64) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+/// What the color gradient along a path shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorBy {
+    /// `start` at launch, `end` at the last recorded step
+    #[default]
+    Age,
+    /// `start` at the slowest point of all the paths, `end` at the fastest
+    Speed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectoryStyle {
+    pub start: Color,
+    pub end: Color,
+    pub color_by: ColorBy,
+    /// Pixels kept clear around the paths
+    pub margin: f64,
+}
+
+impl Default for TrajectoryStyle {
+    fn default() -> Self {
+        TrajectoryStyle {
+            start: Color::YELLOW,
+            end: Color::RED,
+            color_by: ColorBy::Age,
+            margin: 10.0,
+        }
+    }
+}
+
+/// Draws the paths in the x-y plane (y up), scaled and centered to fit the canvas together
+pub fn draw_trajectories(
+    canvas: &mut Canvas,
+    trajectories: &[Trajectory],
+    style: &TrajectoryStyle,
+) {
+    let points = || trajectories.iter().flat_map(|t| t.points());
+    let Some(fit) = Fit::new(
+        canvas,
+        points().map(|p| (p.position.x, p.position.y)),
+        style.margin,
+    ) else {
+        return;
+    };
+    let speeds = points().map(|p| p.velocity.magnitude());
+    let (slowest, fastest) = speeds.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), s| {
+        (lo.min(s), hi.max(s))
+    });
+
+    for trajectory in trajectories {
+        let last = trajectory.len().saturating_sub(1).max(1) as f64;
+        let color_at = |i: usize, p: &Projectile| {
+            let t = match style.color_by {
+                ColorBy::Age => i as f64 / last,
+                ColorBy::Speed if fastest > slowest => {
+                    (p.velocity.magnitude() - slowest) / (fastest - slowest)
+                }
+                ColorBy::Speed => 0.0,
+            };
+            style.start.lerp(style.end, t)
+        };
+        let points = trajectory.points();
+        if let [only] = points {
+            draw_line_aa(
+                canvas,
+                fit.apply(only),
+                fit.apply(only),
+                style.start,
+                style.start,
+            );
+        }
+        for (i, pair) in points.windows(2).enumerate() {
+            draw_line_aa(
+                canvas,
+                fit.apply(&pair[0]),
+                fit.apply(&pair[1]),
+                color_at(i, &pair[0]),
+                color_at(i + 1, &pair[1]),
+            );
+        }
+    }
+}
+
+/// Maps simulation x-y coordinates to pixels
+struct Fit {
+    min: (f64, f64),
+    scale: f64,
+    offset: (f64, f64),
+    height: f64,
+}
+
+impl Fit {
+    /// `None` when there's nothing to fit
+    fn new(canvas: &Canvas, points: impl Iterator<Item = (f64, f64)>, margin: f64) -> Option<Fit> {
+        let (mut min, mut max) = (
+            (f64::INFINITY, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::NEG_INFINITY),
+        );
+        for (x, y) in points {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        if min.0 > max.0 {
+            return None;
+        }
+        let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+        // from the center of the first pixel inside the margin to the center of the last one
+        let room = (
+            (width - 2.0 * margin - 1.0).max(1.0),
+            (height - 2.0 * margin - 1.0).max(1.0),
+        );
+        let size = (max.0 - min.0, max.1 - min.1);
+        // the same scale on both axes so arcs keep their shape, a flat path only needs to fit
+        // one way and a single point none
+        let scale = match (size.0 > 0.0, size.1 > 0.0) {
+            (true, true) => (room.0 / size.0).min(room.1 / size.1),
+            (true, false) => room.0 / size.0,
+            (false, true) => room.1 / size.1,
+            (false, false) => 1.0,
+        };
+        Some(Fit {
+            min,
+            scale,
+            offset: (
+                (width - size.0 * scale) / 2.0,
+                (height - size.1 * scale) / 2.0,
+            ),
+            height,
+        })
+    }
+
+    fn apply(&self, projectile: &Projectile) -> (f64, f64) {
+        let (x, y) = (projectile.position.x, projectile.position.y);
+        (
+            self.offset.0 + (x - self.min.0) * self.scale,
+            self.height - (self.offset.1 + (y - self.min.1) * self.scale),
+        )
+    }
+}
+
+/// Draws an anti-aliased line between two points in pixels (Wu's algorithm), blending from
+/// `from_color` to `to_color` along it. Whatever falls off the canvas is skipped.
+pub fn draw_line_aa(
+    canvas: &mut Canvas,
+    from: (f64, f64),
+    to: (f64, f64),
+    from_color: Color,
+    to_color: Color,
+) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steep = dy.abs() > dx.abs();
+    let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+        let color = from_color.lerp(to_color, t);
+        // split the coverage between the two pixels across the line whose centers straddle it
+        let (along, across) = if steep { (y, x) } else { (x, y) };
+        let below = (across - 0.5).floor();
+        let coverage = across - 0.5 - below;
+        for (offset, weight) in [(0.0, 1.0 - coverage), (1.0, coverage)] {
+            let (px, py) = if steep {
+                (below + offset, along.floor())
+            } else {
+                (along.floor(), below + offset)
+            };
+            blend(canvas, px, py, color, weight);
+        }
+    }
+}
+
+fn blend(canvas: &mut Canvas, x: f64, y: f64, color: Color, weight: f64) {
+    if x < 0.0 || y < 0.0 || x >= canvas.width() as f64 || y >= canvas.height() as f64 {
+        return;
+    }
+    let (x, y) = (x as usize, y as usize);
+    if let Some(existing) = canvas.get_pixel(x, y) {
+        canvas.write_pixel(x, y, existing.lerp(color, weight));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::color::Color;
+    use crate::environment::new_environment;
+    use crate::projectile::new_projectile;
+    use crate::trajectory::{
+        draw_line_aa, draw_trajectories, ColorBy, Trajectory, TrajectoryStyle,
+    };
+    use crate::tuple::{Point, Vector};
+
+    #[test]
+    fn recording_a_flight() {
+        let env = new_environment(Vector::new(0.0, -0.5, 0.0), Vector::new(0.0, 0.0, 0.0));
+        let start = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 0.0));
+        let flight = Trajectory::simulate(&env, start, 100, |p| p.position.y >= 0.0);
+        let heights: Vec<f64> = flight.points().iter().map(|p| p.position.y).collect();
+        assert_eq!(heights, vec![0.0, 1.0, 1.5, 1.5, 1.0, 0.0, -1.5]);
+        assert_eq!(Trajectory::simulate(&env, start, 2, |_| true).len(), 3);
+    }
+
+    #[test]
+    fn exporting_the_time_series() {
+        let mut flight = Trajectory::new();
+        flight.push(new_projectile(
+            Point::new_point(0.0, 1.5, 0.0),
+            Vector::new(1.0, f64::NAN, 0.0),
+        ));
+        assert_eq!(flight.to_csv(), "t,x,y,z,vx,vy,vz\n0,0,1.5,0,1,NaN,0\n");
+        assert_eq!(
+            flight.to_json(),
+            "[{\"t\":0,\"position\":[0,1.5,0],\"velocity\":[1,null,0]}]\n"
+        );
+        assert_eq!(Trajectory::new().to_json(), "[]\n");
+    }
+
+    #[test]
+    fn following_the_path_frame_by_frame() {
+        let env = new_environment(Vector::new(0.0, -0.5, 0.0), Vector::new(0.0, 0.0, 0.0));
+        let start = new_projectile(Point::new_point(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 0.0));
+        let flight = Trajectory::simulate(&env, start, 3, |_| true);
+        let track = flight.to_track(0.25);
+        assert_eq!(track.keys().len(), 4);
+        let frame_2 = track.sample(2.0).unwrap().to_matrix();
+        assert_eq!(
+            frame_2 * Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(2.0, 1.75, 0.0)
+        );
+        let between = track.sample(2.5).unwrap();
+        assert_eq!(between.translation, Vector::new(2.5, 1.5, 0.0));
+    }
+
+    #[test]
+    fn anti_aliased_lines_split_between_pixels() {
+        let mut canvas = Canvas::new(5, 5);
+        draw_line_aa(
+            &mut canvas,
+            (0.5, 2.0),
+            (4.5, 2.0),
+            Color::WHITE,
+            Color::WHITE,
+        );
+        // exactly between rows 1 and 2
+        assert_eq!(canvas.get_pixel(2, 1), Some(Color::new(0.5, 0.5, 0.5)));
+        assert_eq!(canvas.get_pixel(2, 2), Some(Color::new(0.5, 0.5, 0.5)));
+        assert_eq!(canvas.get_pixel(2, 3), Some(Color::BLACK));
+
+        draw_line_aa(
+            &mut canvas,
+            (2.5, -10.0),
+            (2.5, 10.0),
+            Color::RED,
+            Color::RED,
+        );
+        assert_eq!(canvas.get_pixel(2, 0), Some(Color::RED));
+    }
+
+    #[test]
+    fn paths_are_fit_to_the_canvas() {
+        let mut flight = Trajectory::new();
+        for (x, y) in [(100.0, 0.0), (150.0, 50.0), (200.0, 0.0)] {
+            flight.push(new_projectile(
+                Point::new_point(x, y, 0.0),
+                Vector::new(x / 100.0, 0.0, 0.0),
+            ));
+        }
+        let mut canvas = Canvas::new(20, 10);
+        let style = TrajectoryStyle {
+            start: Color::BLUE,
+            end: Color::RED,
+            color_by: ColorBy::Speed,
+            margin: 0.0,
+        };
+        draw_trajectories(&mut canvas, &[flight], &style);
+        let lit = |x, y| canvas.get_pixel(x, y).unwrap().max_component() > 0.0;
+        // corner to corner and up to the top edge in the middle
+        assert!(lit(1, 9) && lit(19, 9) && lit(10, 0));
+        // too wide to fill the height as well, so centered side to side
+        assert!(!lit(0, 9));
+        // slowest on the left, fastest on the right
+        let left = canvas.get_pixel(1, 9).unwrap();
+        let right = canvas.get_pixel(19, 9).unwrap();
+        assert!(left.blue() > left.red() && right.red() > right.blue());
+    }
+}
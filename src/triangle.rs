@@ -0,0 +1,457 @@
+//! Triangles, what meshes are made of. A triangle is given by its three corners in its own
+//! space, so most of them keep the identity transform and sit wherever their corners are.
+//!
+//! Rays are intersected with the Möller–Trumbore method, which finds the hit's barycentric
+//! coordinates (u toward `p2`, v toward `p3`) along the way. They're kept on the intersection
+//! for anything interpolating across the face, like a `SmoothTriangle` blending the normals
+//! given at its corners so a mesh of flat faces shades like the curved surface it stands for.
+
+use uuid::Uuid;
+
+use crate::bounds::BoundingBox;
+use crate::error::{Error, Result};
+use crate::light::{LightGroups, Material};
+use crate::matrix::{M4x4, IDENTITY_MATRIX_4X4};
+use crate::rays::{Ray, Visibility};
+use crate::shape::{LocalHits, Shape};
+use crate::tuple::{Point, Vector};
+use crate::utils::EPSILON;
+use crate::uv::UvMap;
+
+/// What both kinds of triangle need to be hit
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+struct Corners {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    // the edges leaving `p1` only depend on the corners, every ray needs them
+    e1: Vector,
+    e2: Vector,
+}
+
+impl Corners {
+    fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        Corners {
+            p1,
+            p2,
+            p3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    fn normal(&self) -> Vector {
+        self.e2.cross(&self.e1).normalize()
+    }
+
+    /// Where the ray crosses the triangle and the hit's barycentric coordinates, if it does
+    fn hit(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        let dir_cross_e2 = ray.direction().cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        // the ray runs along the triangle's plane
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin() - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * ray.direction().dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        Some((f * self.e2.dot(&origin_cross_e1), u, v))
+    }
+
+    fn intersect(&self, ray: &Ray) -> LocalHits {
+        let mut hits = LocalHits::new();
+        if let Some((t, u, v)) = self.hit(ray) {
+            hits.push_with_uv(t, u, v);
+        }
+        hits
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut bounds = BoundingBox::new(self.p1, self.p1);
+        bounds.add_point(self.p2);
+        bounds.add_point(self.p3);
+        bounds
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangle {
+    pub id: Uuid,
+    corners: Corners,
+    normal: Vector,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    pub material: Material,
+    /// Lights that shine on the triangle, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the triangle, the others pass straight through it
+    pub visibility: Visibility,
+}
+
+impl Triangle {
+    /// The front is the side the corners go around counter-clockwise from, though both sides
+    /// are hit and lit alike
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let corners = Corners::new(p1, p2, p3);
+        Triangle {
+            id: Uuid::new_v4(),
+            corners,
+            normal: corners.normal(),
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.corners.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.corners.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.corners.p3
+    }
+
+    pub fn e1(&self) -> Vector {
+        self.corners.e1
+    }
+
+    pub fn e2(&self) -> Vector {
+        self.corners.e2
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The triangle as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> Triangle {
+        Triangle {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+}
+
+impl Shape for Triangle {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        self.corners.intersect(ray)
+    }
+
+    /// The same all over the face
+    fn local_normal_at(&self, _point: Point, _uv: Option<(f64, f64)>) -> Vector {
+        self.normal
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.corners.bounds()
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        UvMap::Planar
+    }
+}
+
+impl PartialEq for Triangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+/// A triangle with a normal at each corner, blended across the face by where it's hit
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmoothTriangle {
+    pub id: Uuid,
+    corners: Corners,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+    transform: M4x4,
+    inverse_transform: M4x4,
+    pub material: Material,
+    /// Lights that shine on the triangle, see `LightGroups`
+    pub light_groups: LightGroups,
+    /// Kinds of rays that hit the triangle, the others pass straight through it
+    pub visibility: Visibility,
+}
+
+impl SmoothTriangle {
+    /// The corners and the normals at each of them, in the same order
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        SmoothTriangle {
+            id: Uuid::new_v4(),
+            corners: Corners::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+            transform: IDENTITY_MATRIX_4X4,
+            inverse_transform: IDENTITY_MATRIX_4X4,
+            material: Material::new(),
+            light_groups: LightGroups::ALL,
+            visibility: Visibility::ALL,
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.corners.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.corners.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.corners.p3
+    }
+
+    pub fn n1(&self) -> Vector {
+        self.n1
+    }
+
+    pub fn n2(&self) -> Vector {
+        self.n2
+    }
+
+    pub fn n3(&self) -> Vector {
+        self.n3
+    }
+
+    /// Sets the transform, failing with `Error::DegenerateTransform` when it can't be inverted
+    pub fn set_transform(&mut self, transform: M4x4) -> Result<()> {
+        if transform.matrix == self.transform.matrix {
+            return Ok(());
+        }
+        self.inverse_transform = transform
+            .inverse()
+            .map_err(|_| Error::DegenerateTransform(Box::new(transform)))?;
+        self.transform = transform;
+        Ok(())
+    }
+
+    /// The triangle as seen from outside a group with the given transform, keeping its id
+    pub(crate) fn placed_in(&self, group: M4x4, group_inverse: M4x4) -> SmoothTriangle {
+        SmoothTriangle {
+            transform: group * self.transform,
+            inverse_transform: self.inverse_transform * group_inverse,
+            ..*self
+        }
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn transform(&self) -> M4x4 {
+        self.transform
+    }
+
+    fn inverse_transform(&self) -> M4x4 {
+        self.inverse_transform
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn light_groups(&self) -> LightGroups {
+        self.light_groups
+    }
+
+    fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> LocalHits {
+        self.corners.intersect(ray)
+    }
+
+    /// The corner normals weighted by the hit's barycentric coordinates, or the face's own
+    /// normal without them
+    fn local_normal_at(&self, _point: Point, uv: Option<(f64, f64)>) -> Vector {
+        match uv {
+            Some((u, v)) => self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v),
+            None => self.corners.normal(),
+        }
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.corners.bounds()
+    }
+
+    fn default_uv_map(&self) -> UvMap {
+        UvMap::Planar
+    }
+}
+
+impl PartialEq for SmoothTriangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rays::{hit, intersect, prepare_computations, Intersection, Ray};
+    use crate::shape::Shape;
+    use crate::triangle::{SmoothTriangle, Triangle};
+    use crate::tuple::{Point, Vector};
+
+    fn book_triangle() -> Triangle {
+        Triangle::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(-1.0, 0.0, 0.0),
+            Point::new_point(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let t = book_triangle();
+        assert_eq!(t.e1(), Vector::new(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2(), Vector::new(1.0, -1.0, 0.0));
+        assert_eq!(t.normal(), Vector::new(0.0, 0.0, -1.0));
+        for point in [(0.0, 0.5, 0.0), (-0.5, 0.75, 0.0), (0.5, 0.25, 0.0)] {
+            let (x, y, z) = point;
+            assert_eq!(
+                t.local_normal_at(Point::new_point(x, y, z), None),
+                t.normal()
+            );
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_triangle() {
+        let t = book_triangle();
+        let cases = [
+            // parallel to the triangle
+            ((0.0, -1.0, -2.0), (0.0, 1.0, 0.0)),
+            // past the p1-p3 edge, the p1-p2 edge and the p2-p3 edge
+            ((1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((-1.0, 1.0, -2.0), (0.0, 0.0, 1.0)),
+            ((0.0, -1.0, -2.0), (0.0, 0.0, 1.0)),
+        ];
+        for ((ox, oy, oz), (dx, dy, dz)) in cases {
+            let r = Ray::new(Point::new_point(ox, oy, oz), Vector::new(dx, dy, dz));
+            assert!(t.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let t = book_triangle();
+        let r = Ray::new(Point::new_point(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs: Vec<_> = t.local_intersect(&r).iter().collect();
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0], (2.0, Some((0.25, 0.25))));
+
+        let bounds = t.local_bounds();
+        assert_eq!(bounds.min(), Point::new_point(-1.0, 0.0, 0.0));
+        assert_eq!(bounds.max(), Point::new_point(1.0, 1.0, 0.0));
+    }
+
+    fn book_smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(-1.0, 0.0, 0.0),
+            Point::new_point(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn smooth_triangle_hits_keep_their_uv() {
+        let tri = book_smooth_triangle();
+        let r = Ray::new(
+            Point::new_point(-0.2, 0.3, -2.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let i = hit(&intersect(&r, tri)).unwrap();
+        let (u, v) = i.uv.unwrap();
+        assert!((u - 0.45).abs() < 1e-9 && (v - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn smooth_triangle_interpolates_its_normal() {
+        let tri = book_smooth_triangle();
+        let i = Intersection::with_uv(1.0, tri, 0.45, 0.25);
+        let expected = Vector::new(-0.5547, 0.83205, 0.0);
+        let n = tri.normal_at(Point::new_point(0.0, 0.0, 0.0), &i);
+        assert!((n - expected).magnitude() < 1e-4);
+
+        let r = Ray::new(
+            Point::new_point(-0.2, 0.3, -2.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        let comps = prepare_computations(&i, &r);
+        assert!((comps.normalv - expected).magnitude() < 1e-4);
+        assert_eq!(
+            tri.local_normal_at(Point::new_point(0.0, 0.5, 0.0), None),
+            Vector::new(0.0, 0.0, -1.0)
+        );
+    }
+}
@@ -6,7 +6,10 @@ use std::ops::{Add, Div, Mul, Neg, Sub};
 
 use crate::utils::equal_f64;
 
+/// `repr(C)` keeps the layout at x, y, z, w so tuples can be handed to GPUs or over FFI as is
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
 pub struct Tuple {
     pub x: f64,
     pub y: f64,
@@ -17,9 +20,15 @@ pub struct Tuple {
 pub type Point = Tuple;
 pub type Vector = Tuple;
 
+// SAFETY: four f64 fields in a repr(C) struct leave no padding, and any bit pattern is a valid f64
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Tuple {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Tuple {}
+
 impl Display for Tuple {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "x: {} y: {} z: {}", self.x, self.y, self.z)
+        write!(f, "x: {} y: {} z: {} w: {}", self.x, self.y, self.z, self.w)
     }
 }
 
@@ -34,6 +43,38 @@ impl PartialEq for Tuple {
     }
 }
 
+/// Component-wise fuzzy comparison, so downstream tests can use `assert_relative_eq!`
+/// with an explicit tolerance instead of the crate's fixed EPSILON in `PartialEq`
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for Tuple {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.x.abs_diff_eq(&other.x, epsilon)
+            && self.y.abs_diff_eq(&other.y, epsilon)
+            && self.z.abs_diff_eq(&other.z, epsilon)
+            && self.w.abs_diff_eq(&other.w, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for Tuple {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.x.relative_eq(&other.x, epsilon, max_relative)
+            && self.y.relative_eq(&other.y, epsilon, max_relative)
+            && self.z.relative_eq(&other.z, epsilon, max_relative)
+            && self.w.relative_eq(&other.w, epsilon, max_relative)
+    }
+}
+
 impl Add for Tuple {
     type Output = Self;
 
@@ -153,13 +194,13 @@ impl Vector {
     ///
     /// If the vectors are unit vectors, the dot product is actually the cosine of the angles between them
     pub fn dot(&self, v: &Vector) -> f64 {
-        self.x * v.x + self.y * v.y + self.z * v.z + self.w + v.w
+        self.x * v.x + self.y * v.y + self.z * v.z + self.w * v.w
     }
 
     /// Returns a new vector that is perpendicular to both of the original vectors
     ///
     /// Order matters for the cross product. X cross Y gives you Z, Y cross X gives -Z
-    fn cross(&self, vec_b: &Vector) -> Vector {
+    pub fn cross(&self, vec_b: &Vector) -> Vector {
         Vector::new(
             self.y * vec_b.z - self.z * vec_b.y,
             self.z * vec_b.x - self.x * vec_b.z,
@@ -169,7 +210,7 @@ impl Vector {
 }
 
 impl Point {
-    pub fn new_point(x: f64, y: f64, z: f64) -> Point {
+    pub const fn new_point(x: f64, y: f64, z: f64) -> Point {
         Point { x, y, z, w: 1.0 }
     }
 }
@@ -178,6 +219,8 @@ impl Point {
 #[cfg(test)]
 mod tests {
     use crate::tuple::{Point, Tuple, Vector};
+    #[cfg(feature = "approx")]
+    use approx::{assert_abs_diff_eq, assert_relative_eq, assert_relative_ne};
 
     #[test]
     fn new_vector_is_vector() {
@@ -191,6 +234,14 @@ mod tests {
         assert_eq!(1.0, x.w);
     }
 
+    #[test]
+    fn display_includes_w() {
+        let p = Point::new_point(1.0, -2.5, 3.0);
+        assert_eq!(format!("{}", p), "x: 1 y: -2.5 z: 3 w: 1");
+        let v = Vector::new(1.0, -2.5, 3.0);
+        assert_eq!(format!("{}", v), "x: 1 y: -2.5 z: 3 w: 0");
+    }
+
     #[test]
     fn tuples_equal() {
         let x = Point::new_point(4.3, -4.2, 3.1);
@@ -385,6 +436,17 @@ mod tests {
         assert_eq!(vec_a.dot(&vec_b), expected);
     }
 
+    #[test]
+    fn dot_product_multiplies_w() {
+        let a = Tuple {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            w: 4.0,
+        };
+        assert_eq!(a.dot(&a), 30.0);
+    }
+
     #[test]
     fn cross_product_success() {
         let vec_a = Vector::new(1.0, 2.0, 3.0);
@@ -395,4 +457,26 @@ mod tests {
         assert_eq!(vec_a.cross(&vec_b), expected_a_b);
         assert_eq!(vec_b.cross(&vec_a), expected_b_a);
     }
+
+    #[cfg(feature = "approx")]
+    #[test]
+    fn approx_comparisons() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        let b = Vector::new(1.0 + 1e-12, 2.0, 3.0 - 1e-12);
+        assert_relative_eq!(a, b, max_relative = 1e-9);
+        assert_abs_diff_eq!(a, Vector::new(1.001, 2.0, 3.0), epsilon = 0.01);
+        assert_relative_ne!(a, Point::new_point(1.0, 2.0, 3.0));
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn cast_tuple_to_floats() {
+        let p = Point::new_point(1.0, 2.0, 3.0);
+        let floats: [f64; 4] = bytemuck::cast(p);
+        assert_eq!(floats, [1.0, 2.0, 3.0, 1.0]);
+
+        let tuples = [p, Vector::new(4.0, 5.0, 6.0)];
+        let floats: &[f64] = bytemuck::cast_slice(&tuples);
+        assert_eq!(floats, &[1.0, 2.0, 3.0, 1.0, 4.0, 5.0, 6.0, 0.0]);
+    }
 }
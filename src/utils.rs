@@ -1,9 +1,159 @@
-const EPSILON: f64 = 0.00001;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+/// Default tolerance for treating two floats as equal.
+///
+/// Floating point math rarely lands on exactly the same value twice, so tuples, colors
+/// and matrices all compare their components with this tolerance in `PartialEq`.
+pub const EPSILON: f64 = 0.00001;
+
+/// A tolerance shared between threads, stored as the bits of an f64 so it can live in an atomic
+struct Tolerance(AtomicU64);
+
+impl Tolerance {
+    const fn new(eps: f64) -> Self {
+        Tolerance(AtomicU64::new(eps.to_bits()))
+    }
+
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, eps: f64) {
+        self.0.store(eps.abs().to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// The crate wide tolerance
+static COMPARISON_EPSILON: Tolerance = Tolerance::new(EPSILON);
+
+/// Returns the crate wide comparison tolerance, `EPSILON` unless changed with `set_epsilon`
+///
+/// Besides `PartialEq` of tuples, colors and matrices, this is the value to reach for when
+/// nudging points off a surface to avoid shadow/acne artifacts.
+pub fn epsilon() -> f64 {
+    COMPARISON_EPSILON.get()
+}
+
+/// Changes the crate wide comparison tolerance.
+///
+/// Very large or very high precision scenes may need a looser or tighter value than the
+/// default. This affects every comparison in the process, so set it once up front
+/// rather than toggling it while rendering.
+pub fn set_epsilon(eps: f64) {
+    COMPARISON_EPSILON.set(eps);
+}
+
+/// Compares two floats using the crate wide tolerance, see `epsilon`
 pub fn equal_f64(a: f64, b: f64) -> bool {
+    equal_f64_eps(a, b, epsilon())
+}
+
+/// Compares two floats using the given tolerance
+pub fn equal_f64_eps(a: f64, b: f64, eps: f64) -> bool {
     let diff = a - b;
-    if num::abs(diff) < EPSILON {
+    if num::abs(diff) < eps {
         return true;
     }
     false
 }
+
+/// Compares two floats relative to their magnitude: equal when they differ by at most
+/// `max_relative` times the larger of the two.
+///
+/// A fixed absolute tolerance is too strict for large values (1e6 apart by rounding alone
+/// is already way more than `EPSILON`) and too loose for tiny ones, while this scales with them.
+pub fn equal_f64_relative(a: f64, b: f64, max_relative: f64) -> bool {
+    if a == b {
+        return true;
+    }
+    let largest = a.abs().max(b.abs());
+    (a - b).abs() <= largest * max_relative
+}
+
+/// Compares two floats by how many representable f64 values lie between them
+/// (units in the last place), which is a relative comparison at any magnitude.
+///
+/// Values of opposite sign are only equal when both are zero, and NaN is never equal.
+pub fn equal_f64_ulps(a: f64, b: f64, max_ulps: u64) -> bool {
+    if a.is_nan() || b.is_nan() {
+        return false;
+    }
+    ulps_between(a, b) <= max_ulps as u128
+}
+
+/// Number of representable values between two floats.
+///
+/// The bit patterns of floats sort like sign-magnitude integers, flipping the negative ones
+/// makes them sort like regular two's complement integers so they can be subtracted
+fn ulps_between(a: f64, b: f64) -> u128 {
+    let ordered = |x: f64| {
+        let bits = x.to_bits() as i64;
+        if bits < 0 {
+            i64::MIN - bits
+        } else {
+            bits
+        }
+    };
+    (ordered(a) as i128 - ordered(b) as i128).unsigned_abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        epsilon, equal_f64, equal_f64_eps, equal_f64_relative, equal_f64_ulps, Tolerance, EPSILON,
+    };
+
+    #[test]
+    fn equal_within_default_epsilon() {
+        assert!(equal_f64(1.0, 1.0 + EPSILON / 2.0));
+        assert!(!equal_f64(1.0, 1.0 + EPSILON * 3.0));
+    }
+
+    #[test]
+    fn equal_with_explicit_epsilon() {
+        assert!(equal_f64_eps(1.0, 1.05, 0.1));
+        assert!(!equal_f64_eps(1.0, 1.05, 0.01));
+        assert!(!equal_f64_eps(1.0, 1.0 + 1e-9, 1e-12));
+    }
+
+    #[test]
+    fn set_crate_epsilon() {
+        // other tests compare floats against the crate wide value while this one runs, so the
+        // setter is tried on a tolerance of its own
+        assert_eq!(epsilon(), EPSILON);
+        let tolerance = Tolerance::new(EPSILON);
+        tolerance.set(-EPSILON * 2.0);
+        assert_eq!(tolerance.get(), EPSILON * 2.0);
+        assert!(equal_f64_eps(1.0, 1.0 + EPSILON * 1.5, tolerance.get()));
+        assert!(!equal_f64(1.0, 1.0 + EPSILON * 1.5));
+    }
+
+    #[test]
+    fn equal_relative_scales_with_magnitude() {
+        assert!(equal_f64_relative(1e9, 1e9 + 1.0, 1e-8));
+        assert!(!equal_f64(1e9, 1e9 + 1.0));
+        assert!(!equal_f64_relative(1e-9, 2e-9, 1e-8));
+        assert!(equal_f64(1e-9, 2e-9));
+        assert!(equal_f64_relative(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn equal_ulps() {
+        let a = 0.1 + 0.2;
+        assert_ne!(a, 0.3);
+        assert!(equal_f64_ulps(a, 0.3, 1));
+        assert!(equal_f64_ulps(
+            1e300,
+            f64::from_bits(1e300_f64.to_bits() + 4),
+            4
+        ));
+        assert!(!equal_f64_ulps(
+            1e300,
+            f64::from_bits(1e300_f64.to_bits() + 5),
+            4
+        ));
+        assert!(equal_f64_ulps(0.0, -0.0, 0));
+        assert!(!equal_f64_ulps(1e-300, -1e-300, 4));
+        assert!(!equal_f64_ulps(f64::NAN, f64::NAN, u64::MAX));
+    }
+}
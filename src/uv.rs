@@ -0,0 +1,261 @@
+//! Texture coordinates: where on a flat image a point on a surface lands, and back.
+//!
+//! `u` runs 0 to 1 left to right across the image and `v` 0 to 1 bottom to top, so row 0 of a
+//! canvas is `v` near 1, see `Canvas::sample_uv`.
+
+use std::f64::consts::{PI, TAU};
+
+use crate::tuple::{Point, Vector};
+
+/// uv of a point on a sphere around the origin (in the sphere's own space), like a globe:
+/// `u` goes once around the equator, `v` from the south pole to the north pole
+pub fn spherical_map(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    let radius = (point.x * point.x + point.y * point.y + point.z * point.z).sqrt();
+    let phi = (point.y / radius).acos();
+    (1.0 - (theta / TAU + 0.5), 1.0 - phi / PI)
+}
+
+/// uv of a point on the xz plane, one texture per unit square repeating in every direction
+pub fn planar_map(point: Point) -> (f64, f64) {
+    (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0))
+}
+
+/// uv of a point on a cylinder of radius 1 around the y axis: `u` goes once around it like
+/// `spherical_map`, `v` repeats every unit up it
+pub fn cylindrical_map(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    (1.0 - (theta / TAU + 0.5), point.y.rem_euclid(1.0))
+}
+
+/// How a texture wraps around a shape, see `Shape::uv_map`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvMap {
+    Spherical,
+    Planar,
+    Cylindrical,
+    /// The whole texture on each face of the cube, see `cube_map`
+    Cube,
+}
+
+impl UvMap {
+    /// uv of a point in the shape's own space
+    pub fn map(&self, point: Point) -> (f64, f64) {
+        match self {
+            UvMap::Spherical => spherical_map(point),
+            UvMap::Planar => planar_map(point),
+            UvMap::Cylindrical => cylindrical_map(point),
+            UvMap::Cube => {
+                let (_, u, v) = cube_map(point);
+                (u, v)
+            }
+        }
+    }
+
+    /// The directions `u` and `v` grow in at a point in the shape's own space, along the
+    /// surface for the shape the map is made for. Not normalized, and zero where `u` has no
+    /// direction (the poles of a sphere).
+    pub fn tangents(&self, point: Point) -> (Vector, Vector) {
+        let around = Vector::new(-point.z, 0.0, point.x);
+        match self {
+            // toward the north pole
+            UvMap::Spherical => (
+                around,
+                around.cross(&Vector::new(point.x, point.y, point.z)),
+            ),
+            UvMap::Planar => (Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0)),
+            UvMap::Cylindrical => (around, Vector::new(0.0, 1.0, 0.0)),
+            UvMap::Cube => {
+                let (x, y, z) = (
+                    Vector::new(1.0, 0.0, 0.0),
+                    Vector::new(0.0, 1.0, 0.0),
+                    Vector::new(0.0, 0.0, 1.0),
+                );
+                match cube_map(point).0 {
+                    CubeFace::Right => (-z, y),
+                    CubeFace::Left => (z, y),
+                    CubeFace::Up => (x, -z),
+                    CubeFace::Down => (x, z),
+                    CubeFace::Front => (x, y),
+                    CubeFace::Back => (-x, y),
+                }
+            }
+        }
+    }
+}
+
+/// The point on the unit sphere that `spherical_map` puts at `u`, `v`
+pub fn spherical_point(u: f64, v: f64) -> Point {
+    let theta = (0.5 - u) * TAU;
+    let phi = (1.0 - v) * PI;
+    Point::new_point(phi.sin() * theta.sin(), phi.cos(), phi.sin() * theta.cos())
+}
+
+/// The faces of a cube, each mapped to an image of its own by `cube_map`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::Left,
+        CubeFace::Front,
+        CubeFace::Right,
+        CubeFace::Back,
+        CubeFace::Up,
+        CubeFace::Down,
+    ];
+}
+
+/// The face of the cube from -1 to 1 a point is on, and its uv on that face. Each face reads
+/// left to right and bottom to top as seen from inside the cube, with +y up and the front at +z.
+pub fn cube_map(point: Point) -> (CubeFace, f64, f64) {
+    let unit = |value: f64| value.rem_euclid(2.0) / 2.0;
+    let (x, y, z) = (point.x, point.y, point.z);
+    let largest = x.abs().max(y.abs()).max(z.abs());
+    if largest == x {
+        (CubeFace::Right, unit(1.0 - z), unit(y + 1.0))
+    } else if largest == -x {
+        (CubeFace::Left, unit(z + 1.0), unit(y + 1.0))
+    } else if largest == y {
+        (CubeFace::Up, unit(x + 1.0), unit(1.0 - z))
+    } else if largest == -y {
+        (CubeFace::Down, unit(x + 1.0), unit(z + 1.0))
+    } else if largest == z {
+        (CubeFace::Front, unit(x + 1.0), unit(y + 1.0))
+    } else {
+        (CubeFace::Back, unit(1.0 - x), unit(y + 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::tuple::Point;
+    use crate::uv::{
+        cube_map, cylindrical_map, planar_map, spherical_map, spherical_point, CubeFace, UvMap,
+    };
+
+    #[test]
+    fn spherical_mapping() {
+        let cases = [
+            (Point::new_point(0.0, 0.0, -1.0), (0.0, 0.5)),
+            (Point::new_point(1.0, 0.0, 0.0), (0.25, 0.5)),
+            (Point::new_point(0.0, 0.0, 1.0), (0.5, 0.5)),
+            (Point::new_point(-1.0, 0.0, 0.0), (0.75, 0.5)),
+            (Point::new_point(0.0, 1.0, 0.0), (0.5, 1.0)),
+            (Point::new_point(0.0, -1.0, 0.0), (0.5, 0.0)),
+            (
+                Point::new_point(FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0),
+                (0.25, 0.75),
+            ),
+        ];
+        for (point, (u, v)) in cases {
+            let (mapped_u, mapped_v) = spherical_map(point);
+            assert!((mapped_u - u).abs() < 1e-9 && (mapped_v - v).abs() < 1e-9);
+        }
+        // and back again
+        for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let (back_u, back_v) = spherical_map(spherical_point(u, v));
+            assert!((back_u - u).abs() < 1e-9 && (back_v - v).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn planar_mapping() {
+        let cases = [
+            ((0.25, 0.0, 0.5), (0.25, 0.5)),
+            ((0.25, 0.0, -0.25), (0.25, 0.75)),
+            ((0.25, 0.5, -0.25), (0.25, 0.75)),
+            ((1.25, 0.0, 0.5), (0.25, 0.5)),
+            ((0.25, 0.0, -1.75), (0.25, 0.25)),
+            ((1.0, 0.0, -1.0), (0.0, 0.0)),
+            ((0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        for ((x, y, z), uv) in cases {
+            assert_eq!(planar_map(Point::new_point(x, y, z)), uv);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        let h = FRAC_1_SQRT_2;
+        let cases = [
+            ((0.0, 0.0, -1.0), (0.0, 0.0)),
+            ((0.0, 0.5, -1.0), (0.0, 0.5)),
+            ((0.0, 1.0, -1.0), (0.0, 0.0)),
+            ((h, 0.5, -h), (0.125, 0.5)),
+            ((1.0, 0.5, 0.0), (0.25, 0.5)),
+            ((h, 0.5, h), (0.375, 0.5)),
+            ((0.0, -0.25, 1.0), (0.5, 0.75)),
+            ((-h, 0.5, h), (0.625, 0.5)),
+            ((-1.0, 1.25, 0.0), (0.75, 0.25)),
+            ((-h, 0.5, -h), (0.875, 0.5)),
+        ];
+        for ((x, y, z), (u, v)) in cases {
+            let (mapped_u, mapped_v) = cylindrical_map(Point::new_point(x, y, z));
+            assert!((mapped_u - u).abs() < 1e-9 && (mapped_v - v).abs() < 1e-9);
+        }
+        // through the enum, the same as calling the function
+        let point = Point::new_point(h, 0.5, h);
+        assert_eq!(UvMap::Cylindrical.map(point), cylindrical_map(point));
+        assert_eq!(
+            UvMap::Cube.map(Point::new_point(-0.5, 0.5, 1.0)),
+            (0.25, 0.75)
+        );
+    }
+
+    #[test]
+    fn cube_mapping() {
+        let cases = [
+            ((-0.5, 0.5, 1.0), CubeFace::Front, (0.25, 0.75)),
+            ((0.5, -0.5, 1.0), CubeFace::Front, (0.75, 0.25)),
+            ((0.5, 0.5, -1.0), CubeFace::Back, (0.25, 0.75)),
+            ((-0.5, -0.5, -1.0), CubeFace::Back, (0.75, 0.25)),
+            ((-1.0, 0.5, -0.5), CubeFace::Left, (0.25, 0.75)),
+            ((-1.0, -0.5, 0.5), CubeFace::Left, (0.75, 0.25)),
+            ((1.0, 0.5, 0.5), CubeFace::Right, (0.25, 0.75)),
+            ((1.0, -0.5, -0.5), CubeFace::Right, (0.75, 0.25)),
+            ((-0.5, 1.0, -0.5), CubeFace::Up, (0.25, 0.75)),
+            ((0.5, 1.0, 0.5), CubeFace::Up, (0.75, 0.25)),
+            ((-0.5, -1.0, 0.5), CubeFace::Down, (0.25, 0.75)),
+            ((0.5, -1.0, -0.5), CubeFace::Down, (0.75, 0.25)),
+        ];
+        for ((x, y, z), face, (u, v)) in cases {
+            let (mapped_face, mapped_u, mapped_v) = cube_map(Point::new_point(x, y, z));
+            assert_eq!(mapped_face, face);
+            assert!((mapped_u - u).abs() < 1e-9 && (mapped_v - v).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn tangents_point_where_u_and_v_grow() {
+        let h = FRAC_1_SQRT_2;
+        let cases = [
+            (UvMap::Spherical, (0.0, h, -h)),
+            (UvMap::Spherical, (0.6, 0.0, 0.8)),
+            (UvMap::Planar, (0.3, 0.0, 0.4)),
+            (UvMap::Cylindrical, (-h, 0.5, h)),
+            (UvMap::Cube, (0.5, 1.0, 0.5)),
+            (UvMap::Cube, (-0.5, -0.5, -1.0)),
+            (UvMap::Cube, (-1.0, 0.2, 0.3)),
+        ];
+        for (map, (x, y, z)) in cases {
+            let point = Point::new_point(x, y, z);
+            let (u, v) = map.map(point);
+            let (tangent, bitangent) = map.tangents(point);
+            let (u1, v1) = map.map(point + tangent.normalize() * 1e-4);
+            assert!(u1 > u && (v1 - v).abs() < 1e-6, "{:?} at {:?}", map, point);
+            let (u2, v2) = map.map(point + bitangent.normalize() * 1e-4);
+            assert!(v2 > v && (u2 - u).abs() < 1e-6, "{:?} at {:?}", map, point);
+        }
+    }
+}
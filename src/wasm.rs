@@ -0,0 +1,148 @@
+//! JavaScript bindings, so a web frontend can render a scene without knowing about the Rust types.
+//!
+//! ```js
+//! const renderer = Renderer.fromJson(sceneJson);
+//! renderer.setResolution(320, 240);
+//! while (!renderer.renderRows(16)) {
+//!   progressBar.value = renderer.progress();
+//!   await nextFrame();
+//! }
+//! const image = new ImageData(new Uint8ClampedArray(renderer.pixels()), renderer.width());
+//! ```
+//!
+//! Rendering happens a few rows at a time so the page can stay responsive and report progress,
+//! `render` does it all in one go for small images.
+
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::Canvas;
+use crate::scene::Scene;
+
+#[wasm_bindgen]
+pub struct Renderer {
+    scene: Scene,
+    canvas: Canvas,
+    // next row to render
+    row: usize,
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    /// Loads a scene in the JSON layout described in the `scene` module
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<Renderer, JsError> {
+        let scene = Scene::from_json(json)?;
+        Ok(Renderer::new(scene))
+    }
+
+    /// Changes the size of the image, keeping the camera's position and field of view.
+    /// Anything rendered so far is thrown away.
+    #[wasm_bindgen(js_name = setResolution)]
+    pub fn set_resolution(&mut self, width: usize, height: usize) {
+        self.scene.camera = self.scene.camera.with_resolution(width, height);
+        self.reset();
+    }
+
+    pub fn width(&self) -> usize {
+        self.scene.camera.hsize()
+    }
+
+    pub fn height(&self) -> usize {
+        self.scene.camera.vsize()
+    }
+
+    /// Renders up to `count` more rows, returns true once the whole image is done
+    #[wasm_bindgen(js_name = renderRows)]
+    pub fn render_rows(&mut self, count: usize) -> bool {
+        let camera = &self.scene.camera;
+        let end = (self.row + count).min(camera.vsize());
+        for y in self.row..end {
+            for x in 0..camera.hsize() {
+                let color = camera.color_at_pixel(&self.scene.world, x, y);
+                self.canvas.write_pixel(x, y, color);
+            }
+        }
+        self.row = end;
+        self.is_done()
+    }
+
+    /// Renders the whole image (starting over if some of it was already rendered) and returns its pixels
+    pub fn render(&mut self) -> Vec<u8> {
+        self.reset();
+        self.render_rows(self.height());
+        self.pixels()
+    }
+
+    /// Fraction of rows rendered so far, between 0 and 1
+    pub fn progress(&self) -> f64 {
+        if self.height() == 0 {
+            return 1.0;
+        }
+        self.row as f64 / self.height() as f64
+    }
+
+    #[wasm_bindgen(js_name = isDone)]
+    pub fn is_done(&self) -> bool {
+        self.row >= self.height()
+    }
+
+    /// The image as 8 bit RGBA, rows that aren't rendered yet are black
+    pub fn pixels(&self) -> Vec<u8> {
+        self.canvas.to_rgba()
+    }
+}
+
+impl Renderer {
+    pub fn new(scene: Scene) -> Self {
+        let canvas = Canvas::new(scene.camera.hsize(), scene.camera.vsize());
+        Renderer {
+            scene,
+            canvas,
+            row: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.canvas = Canvas::new(self.width(), self.height());
+        self.row = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wasm::Renderer;
+
+    const SCENE: &str = r#"{
+        "camera": { "width": 11, "height": 11, "field_of_view": 1.5707963267948966,
+                    "from": [0, 0, -5], "to": [0, 0, 0], "up": [0, 1, 0] },
+        "lights": [ { "position": [-10, 10, -10], "intensity": [1, 1, 1] } ],
+        "spheres": [ { "material": { "color": [0.8, 1.0, 0.6], "diffuse": 0.7, "specular": 0.2 } },
+                     { "transform": [ { "scale": [0.5, 0.5, 0.5] } ] } ]
+    }"#;
+
+    #[test]
+    fn render_in_steps_reports_progress() {
+        let mut renderer = Renderer::from_json(SCENE).unwrap();
+        assert_eq!(renderer.progress(), 0.0);
+        assert!(!renderer.render_rows(5));
+        assert!(!renderer.is_done());
+        assert!(renderer.render_rows(5) || renderer.render_rows(5));
+        assert_eq!(renderer.progress(), 1.0);
+
+        let pixels = renderer.pixels();
+        assert_eq!(pixels.len(), 11 * 11 * 4);
+        let center = (5 * 11 + 5) * 4;
+        assert_eq!(&pixels[center..center + 4], &[98, 122, 73, 255]);
+        assert_eq!(pixels, renderer.render());
+    }
+
+    #[test]
+    fn changing_resolution() {
+        let mut renderer = Renderer::from_json(SCENE).unwrap();
+        renderer.render_rows(3);
+        renderer.set_resolution(4, 2);
+        assert_eq!((renderer.width(), renderer.height()), (4, 2));
+        assert_eq!(renderer.progress(), 0.0);
+        assert_eq!(renderer.render().len(), 4 * 2 * 4);
+    }
+}
@@ -0,0 +1,1294 @@
+use crate::clip::ClipPlane;
+use crate::color::Color;
+use crate::fog::Fog;
+use crate::group::Group;
+use crate::light::{
+    ambient_lighting, lighting, lighting_with_visibility, AmbientLight, AreaLight, Material,
+    PointLight, SphereLight, SpotLight,
+};
+use crate::limits::{RayDepth, RayLimits};
+use crate::matrix_transformations::uniform_scaling;
+use crate::metrics;
+use crate::normal_map::NormalMap;
+use crate::packet::RayPacket;
+use crate::pattern::Pattern;
+use crate::random::{Rng, Seedable};
+use crate::rays::{
+    default_bias, hit, intersect_into, prepare_computations_with, reflect, schlick, Computations,
+    Intersection, Intersections, Ray, RayKind, Sphere,
+};
+use crate::shape::{Object, Shape};
+use crate::sky::Sky;
+use crate::skybox::Skybox;
+use crate::stats::WorldStats;
+use crate::tuple::{Point, Vector};
+use std::cell::RefCell;
+use uuid::Uuid;
+
+/// How far away the sun is checked for shadows, anything further can't block it
+pub(crate) const SUN_DISTANCE: f64 = 1e6;
+
+thread_local! {
+    /// Intersection buffers for this thread's rays, handed out by `with_buffer`
+    static BUFFERS: RefCell<Vec<Intersections<Object>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `f` with an empty buffer from this thread's pool, so tracing a ray doesn't allocate
+/// once the pool has warmed up. Rays traced inside `f` get a buffer of their own.
+fn with_buffer<R>(f: impl FnOnce(&mut Intersections<Object>) -> R) -> R {
+    let mut xs = BUFFERS
+        .with(|buffers| buffers.borrow_mut().pop())
+        .unwrap_or_default();
+    let result = f(&mut xs);
+    xs.clear();
+    BUFFERS.with(|buffers| buffers.borrow_mut().push(xs));
+    result
+}
+
+/// Everything in a scene: the objects to render and the lights shining on them
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "scene", derive(serde::Serialize, serde::Deserialize))]
+pub struct World {
+    pub objects: Vec<Object>,
+    /// Objects sharing a transform, hits on them come back like hits on `objects`
+    pub groups: Vec<Group>,
+    pub lights: Vec<PointLight>,
+    /// Lights with a size, casting soft shadows
+    pub sphere_lights: Vec<SphereLight>,
+    /// Lights shaped like rectangles, also casting soft shadows
+    pub area_lights: Vec<AreaLight>,
+    pub spot_lights: Vec<SpotLight>,
+    /// Fill light added to every surface, none by default
+    pub ambient: Option<AmbientLight>,
+    /// Background for rays that miss everything and a sun shining on every object,
+    /// rays that miss are black without one
+    pub sky: Option<Sky>,
+    /// Images of the surroundings seen by rays that miss everything, in place of the sky's
+    /// background (its sun still shines)
+    pub skybox: Option<Skybox>,
+    /// Normal maps for materials to pick by index, see `Material::normal_map`
+    pub normal_maps: Vec<NormalMap>,
+    /// Haze between the camera and everything it sees, clear air by default
+    pub fog: Option<Fog>,
+    /// Cuts away everything on the far side of each plane, for looking inside objects
+    pub clip_planes: Vec<ClipPlane>,
+    /// How far shading points are pushed off surfaces before casting shadow rays, to avoid acne.
+    /// Scenes measured in kilometers need more, tiny ones less to keep shadows attached.
+    pub bias: f64,
+    /// How many times reflected and refracted rays are followed
+    pub ray_limits: RayLimits,
+}
+
+/// Where a ray query hit the world, see `World::first_hit`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub point: Point,
+    /// Outward surface normal, even when the ray starts inside the object
+    pub normal: Vector,
+    /// Distance from the ray's origin in world units, whatever the length of its direction
+    pub distance: f64,
+    /// Look the object up with `World::object`
+    pub object: Uuid,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        World {
+            objects: Vec::new(),
+            groups: Vec::new(),
+            lights: Vec::new(),
+            sphere_lights: Vec::new(),
+            area_lights: Vec::new(),
+            spot_lights: Vec::new(),
+            ambient: None,
+            sky: None,
+            skybox: None,
+            normal_maps: Vec::new(),
+            fog: None,
+            clip_planes: Vec::new(),
+            bias: default_bias(),
+            ray_limits: RayLimits::default(),
+        }
+    }
+
+    /// What's in the world and what it will cost to render, see `WorldStats`
+    pub fn stats(&self) -> WorldStats {
+        WorldStats::of(self)
+    }
+
+    pub fn object(&self, id: Uuid) -> Option<&Object> {
+        self.objects.iter().find(|object| object.id() == id)
+    }
+
+    /// The object with the given id, for editing it in place between frames (moving it,
+    /// changing its material) without rebuilding the rest of the world
+    pub fn object_mut(&mut self, id: Uuid) -> Option<&mut Object> {
+        self.objects.iter_mut().find(|object| object.id() == id)
+    }
+
+    /// Intersects the ray with every object in the world, sorted by t, leaving out intersections
+    /// cut away by the clipping planes and objects hidden from the ray's kind
+    pub fn intersect(&self, ray: &Ray) -> Intersections<Object> {
+        let mut xs = Intersections::new();
+        self.intersect_into(ray, &mut xs);
+        xs
+    }
+
+    /// `intersect`, filling `xs` instead of allocating a new collection. Anything already in
+    /// `xs` is cleared first, so one buffer can be reused for ray after ray.
+    pub fn intersect_into(&self, ray: &Ray, xs: &mut Intersections<Object>) {
+        metrics::count_ray();
+        self.intersect_uncounted(ray, xs);
+    }
+
+    fn intersect_uncounted(&self, ray: &Ray, xs: &mut Intersections<Object>) {
+        self.intersect_unclipped(ray, xs);
+        if !self.clip_planes.is_empty() {
+            xs.retain(|i| !self.is_clipped(ray.position(i.t)));
+        }
+    }
+
+    fn is_clipped(&self, point: Point) -> bool {
+        self.clip_planes.iter().any(|plane| plane.clips(point))
+    }
+
+    fn intersect_unclipped(&self, ray: &Ray, xs: &mut Intersections<Object>) {
+        xs.clear();
+        for object in &self.objects {
+            if object.visibility().sees(ray.kind()) {
+                intersect_into(ray, *object, xs);
+            }
+        }
+        for group in &self.groups {
+            group.intersect_into(ray, xs);
+        }
+        // objects in groups can only be told apart once they're hit
+        xs.retain(|i| i.object.visibility().sees(ray.kind()));
+    }
+
+    /// The closest hit of every ray in the packet, the same as `hit(&world.intersect(ray))` for
+    /// each of them. Packets are taken to be camera rays.
+    ///
+    /// With clip planes the rays are intersected one at a time: a clipped surface can leave the
+    /// hit on something further along, which the packet's nearest hits don't keep track of.
+    /// Like `intersect`, the caps are left to shading.
+    pub fn intersect_packet(&self, packet: &RayPacket) -> Vec<Option<Intersection<Object>>> {
+        metrics::count_rays(packet.len() as u64);
+        if !self.clip_planes.is_empty() {
+            return (0..packet.len())
+                .map(|lane| {
+                    with_buffer(|xs| {
+                        self.intersect_uncounted(&packet.ray(lane), xs);
+                        hit(xs)
+                    })
+                })
+                .collect();
+        }
+        if self.groups.is_empty() && self.objects.iter().all(|object| object.visibility().camera) {
+            return packet.hits(&self.objects);
+        }
+        let mut visible: Vec<Object> = self.objects.clone();
+        for group in &self.groups {
+            group.placed_objects_into(packet, &mut visible);
+        }
+        visible.retain(|object| object.visibility().camera);
+        packet.hits(&visible)
+    }
+
+    /// The closest surface in front of the ray, without shading anything, e.g. to find the
+    /// object under the mouse
+    pub fn first_hit(&self, ray: &Ray) -> Option<RayHit> {
+        with_buffer(|xs| {
+            self.intersect_into(ray, xs);
+            xs.hit()
+                .map(|intersection| Self::ray_hit(ray, intersection))
+        })
+    }
+
+    /// Every surface the ray passes through in front of its origin, nearest first, exits included
+    pub fn all_hits(&self, ray: &Ray) -> Vec<RayHit> {
+        self.intersect(ray)
+            .iter()
+            .filter(|intersection| intersection.t >= 0.0)
+            .map(|intersection| Self::ray_hit(ray, intersection))
+            .collect()
+    }
+
+    fn ray_hit(ray: &Ray, intersection: &Intersection<Object>) -> RayHit {
+        let point = ray.position(intersection.t);
+        RayHit {
+            point,
+            normal: intersection.object.normal_at(point, intersection),
+            distance: intersection.t * ray.direction().magnitude(),
+            object: intersection.object.id(),
+        }
+    }
+
+    /// Color at a precomputed hit, summing the contribution of every light linked to the object
+    ///
+    /// Samples for soft shadows come from a fixed seed, renders draw them from each
+    /// pixel's own generator with `shade_hit_with` instead.
+    pub fn shade_hit(&self, comps: &Computations) -> Color {
+        self.shade_hit_with(comps, &mut Rng::from_seed(0))
+    }
+
+    /// `shade_hit`, drawing the random samples soft shadows need from `rng`
+    pub fn shade_hit_with(&self, comps: &Computations, rng: &mut Rng) -> Color {
+        self.shade_hit_at(comps, RayDepth::PRIMARY, rng)
+    }
+
+    /// `shade_hit_with` for a hit `depth` bounces away from the camera, which decides how much
+    /// further its reflections and refractions are followed
+    fn shade_hit_at(&self, comps: &Computations, depth: RayDepth, rng: &mut Rng) -> Color {
+        let surface = self.surface_color(comps, rng);
+        let reflected = self.reflected_color_with(comps, depth, rng);
+        let refracted = self.refracted_color_with(comps, depth, rng);
+        let material = comps.object.material();
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            // glass reflects more and lets less through the more glancing the view
+            let reflectance = schlick(comps);
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// Light falling straight on the surface from the lights, the sky and the ambient light
+    fn surface_color(&self, comps: &Computations, rng: &mut Rng) -> Color {
+        let mut material = *comps.object.material();
+        if let Some(pattern) = material.pattern {
+            material.color = pattern.pattern_at_shape(&comps.object, comps.point);
+        }
+        let linked = comps.object.light_groups();
+        let point_lights: Color = self
+            .lights
+            .iter()
+            .filter(|light| light.groups().overlaps(linked))
+            .map(|light| {
+                lighting(
+                    material,
+                    *light,
+                    comps.point,
+                    comps.eyev,
+                    comps.normalv,
+                    !self.is_visible(comps.over_point, light.position()),
+                )
+            })
+            .sum();
+        let sphere_lights: Color = self
+            .sphere_lights
+            .iter()
+            .filter(|light| light.groups().overlaps(linked))
+            .map(|light| {
+                lighting_with_visibility(
+                    material,
+                    light.as_point_light(),
+                    comps.point,
+                    comps.eyev,
+                    comps.normalv,
+                    self.light_visibility(comps.over_point, light.samples(), rng, |rng| {
+                        light.sample_point(rng)
+                    }),
+                )
+            })
+            .sum();
+        let area_lights: Color = self
+            .area_lights
+            .iter()
+            .filter(|light| light.groups().overlaps(linked) && light.faces(comps.point))
+            .map(|light| {
+                lighting_with_visibility(
+                    material,
+                    light.as_point_light(),
+                    comps.point,
+                    comps.eyev,
+                    comps.normalv,
+                    self.light_visibility(comps.over_point, light.samples(), rng, |rng| {
+                        light.quad().sample_point(rng)
+                    }),
+                )
+            })
+            .sum();
+        let spot_lights: Color = self
+            .spot_lights
+            .iter()
+            .filter(|light| light.groups().overlaps(linked))
+            .map(|light| {
+                lighting(
+                    material,
+                    light.as_point_light_at(comps.point),
+                    comps.point,
+                    comps.eyev,
+                    comps.normalv,
+                    !self.is_visible(comps.over_point, light.position()),
+                )
+            })
+            .sum();
+        let sun = match self.sky {
+            // the sun is far enough away that its light arrives from the same direction everywhere
+            Some(sky) => lighting(
+                material,
+                PointLight::new(comps.point + sky.sun_direction(), sky.sun_color()),
+                comps.point,
+                comps.eyev,
+                comps.normalv,
+                !self.is_visible(
+                    comps.over_point,
+                    comps.over_point + sky.sun_direction() * SUN_DISTANCE,
+                ),
+            ),
+            None => Color::BLACK,
+        };
+        let direct = point_lights + sphere_lights + area_lights + spot_lights + sun;
+        match self.ambient {
+            Some(ambient) => direct + ambient_lighting(material, ambient, comps.normalv),
+            None => direct,
+        }
+    }
+
+    /// Fraction of `samples` random points on a light, picked by `sample_point`, that can be
+    /// seen from `point`
+    pub(crate) fn light_visibility(
+        &self,
+        point: Point,
+        samples: usize,
+        rng: &mut Rng,
+        sample_point: impl Fn(&mut Rng) -> Point,
+    ) -> f64 {
+        let visible = (0..samples)
+            .filter(|_| self.is_visible(point, sample_point(rng)))
+            .count();
+        visible as f64 / samples as f64
+    }
+
+    /// Whether no light reaches `point`, false in a world without lights. Each light is checked
+    /// the way shading sees it: sphere and area lights by their samples (any one visible is
+    /// enough), area lights only on the side they shine out of, spot lights only inside their
+    /// cone, and the sky's sun. `point` should be nudged off its surface first, like
+    /// `Computations::over_point`.
+    pub fn is_shadowed(&self, point: Point) -> bool {
+        let has_lights = !self.lights.is_empty()
+            || !self.sphere_lights.is_empty()
+            || !self.area_lights.is_empty()
+            || !self.spot_lights.is_empty()
+            || self.sky.is_some();
+        if !has_lights {
+            return false;
+        }
+        let rng = &mut Rng::from_seed(0);
+        let lit = self
+            .lights
+            .iter()
+            .any(|light| self.is_visible(point, light.position()))
+            || self.sphere_lights.iter().any(|light| {
+                self.light_visibility(point, light.samples(), rng, |rng| light.sample_point(rng))
+                    > 0.0
+            })
+            || self.area_lights.iter().any(|light| {
+                light.faces(point)
+                    && self.light_visibility(point, light.samples(), rng, |rng| {
+                        light.quad().sample_point(rng)
+                    }) > 0.0
+            })
+            || self.spot_lights.iter().any(|light| {
+                light.intensity_at(point) != Color::BLACK
+                    && self.is_visible(point, light.position())
+            })
+            || self.sky.is_some_and(|sky| {
+                self.is_visible(point, point + sky.sun_direction() * SUN_DISTANCE)
+            });
+        !lit
+    }
+
+    /// Whether nothing sits on the straight line between the two points, for shadows as well as
+    /// line of sight checks. Points on a surface should be nudged off it first, see `World::bias`.
+    /// The check uses a shadow ray, so objects that cast no shadows don't block it.
+    pub fn is_visible(&self, from: Point, to: Point) -> bool {
+        let v = to - from;
+        let distance = v.magnitude();
+        let ray = Ray::new(from, v.normalize()).with_kind(RayKind::Shadow);
+        with_buffer(|xs| {
+            self.intersect_into(&ray, xs);
+            !matches!(xs.hit(), Some(h) if h.t < distance)
+        })
+    }
+
+    /// Color seen along the ray, the skybox's or the sky's color (or black) when it doesn't hit
+    /// anything
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_at_with(ray, &mut Rng::from_seed(0))
+    }
+
+    /// `color_at`, drawing the random samples soft shadows need from `rng`
+    pub fn color_at_with(&self, ray: &Ray, rng: &mut Rng) -> Color {
+        self.color_at_depth(ray, RayDepth::PRIMARY, rng)
+    }
+
+    fn color_at_depth(&self, ray: &Ray, depth: RayDepth, rng: &mut Rng) -> Color {
+        let comps = with_buffer(|xs| {
+            self.intersect_into(ray, xs);
+            match (hit(xs), self.cap_hit(ray)) {
+                (Some(surface), Some((cap, _))) if surface.t <= cap.t => {
+                    Some(self.prepare_surface(&surface, ray, xs))
+                }
+                (_, Some((cap, normal))) => Some(self.prepare_cap(&cap, normal, ray)),
+                (Some(surface), None) => Some(self.prepare_surface(&surface, ray, xs)),
+                (None, None) => None,
+            }
+        });
+        let (color, distance) = match comps {
+            Some(comps) => (
+                self.shade_hit_at(&comps, depth, rng),
+                comps.t * ray.direction().magnitude(),
+            ),
+            None => match (&self.skybox, self.sky) {
+                (Some(skybox), _) => (skybox.color(ray.direction()), f64::INFINITY),
+                (None, Some(sky)) => (sky.color(ray.direction()), f64::INFINITY),
+                (None, None) => (Color::BLACK, f64::INFINITY),
+            },
+        };
+        match self.fog {
+            Some(fog) => fog.apply(color, distance),
+            None => color,
+        }
+    }
+
+    /// Color the surface mirrors, seen along `Computations::reflectv` and scaled by how
+    /// reflective its material is. Black for surfaces that don't reflect and once the ray at
+    /// `depth` can't bounce again within `ray_limits`.
+    pub fn reflected_color(&self, comps: &Computations, depth: RayDepth) -> Color {
+        self.reflected_color_with(comps, depth, &mut Rng::from_seed(0))
+    }
+
+    fn reflected_color_with(&self, comps: &Computations, depth: RayDepth, rng: &mut Rng) -> Color {
+        let reflective = comps.object.material().reflective;
+        if reflective == 0.0 {
+            return Color::BLACK;
+        }
+        match self.ray_limits.reflect(depth) {
+            Some(depth) => {
+                let ray = Ray::new(comps.over_point, comps.reflectv).with_kind(RayKind::Reflection);
+                self.color_at_depth(&ray, depth, rng) * reflective
+            }
+            None => Color::BLACK,
+        }
+    }
+
+    /// Color seen through the surface, along the ray bent by the change in refractive index
+    /// (Snell's law) and scaled by how transparent its material is. Black for opaque surfaces,
+    /// under total internal reflection and once the ray at `depth` can't bounce again within
+    /// `ray_limits`.
+    pub fn refracted_color(&self, comps: &Computations, depth: RayDepth) -> Color {
+        self.refracted_color_with(comps, depth, &mut Rng::from_seed(0))
+    }
+
+    fn refracted_color_with(&self, comps: &Computations, depth: RayDepth, rng: &mut Rng) -> Color {
+        let transparency = comps.object.material().transparency;
+        if transparency == 0.0 {
+            return Color::BLACK;
+        }
+        let Some(depth) = self.ray_limits.refract(depth) else {
+            return Color::BLACK;
+        };
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            // total internal reflection, all of the light is reflected
+            return Color::BLACK;
+        }
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let ray = Ray::new(comps.under_point, direction).with_kind(RayKind::Reflection);
+        self.color_at_depth(&ray, depth, rng) * transparency
+    }
+
+    /// The nearest point where the ray crosses a capped clipping plane inside an object, as an
+    /// intersection with that object wearing the cap's material, and the plane's normal
+    fn cap_hit(&self, ray: &Ray) -> Option<(Intersection<Object>, Vector)> {
+        if self.clip_planes.iter().all(|plane| plane.cap.is_none()) {
+            return None;
+        }
+        with_buffer(|xs| {
+            self.intersect_unclipped(ray, xs);
+            self.cap_hit_among(ray, xs)
+        })
+    }
+
+    fn cap_hit_among(
+        &self,
+        ray: &Ray,
+        xs: &Intersections<Object>,
+    ) -> Option<(Intersection<Object>, Vector)> {
+        self.clip_planes
+            .iter()
+            .filter_map(|plane| {
+                let material = plane.cap?;
+                let t = plane.crossing(ray).filter(|t| *t >= 0.0)?;
+                let point = ray.position(t);
+                let cut_by_another = self
+                    .clip_planes
+                    .iter()
+                    .any(|other| !std::ptr::eq(other, plane) && other.clips(point));
+                if cut_by_another {
+                    return None;
+                }
+                // the ray is inside an object after crossing its surface an odd number of times
+                let crossings_before = |object: &Object| {
+                    xs.iter()
+                        .filter(|i| i.object.id() == object.id() && i.t < t)
+                        .count()
+                };
+                let mut object = xs
+                    .iter()
+                    .map(|i| i.object)
+                    .find(|object| crossings_before(object) % 2 == 1)?;
+                object.set_material(material);
+                Some((Intersection::new(t, object), plane.normal()))
+            })
+            .min_by(|a, b| a.0.t.total_cmp(&b.0.t))
+    }
+
+    /// Computations for a hit on an object, its normal bent by the material's normal map if it
+    /// has one. Only the shading changes, rays leaving the surface still start off the real one.
+    fn prepare_surface(
+        &self,
+        surface: &Intersection<Object>,
+        ray: &Ray,
+        xs: &Intersections<Object>,
+    ) -> Computations {
+        let mut comps = prepare_computations_with(surface, ray, xs, self.bias);
+        let map = comps
+            .object
+            .material()
+            .normal_map
+            .and_then(|index| self.normal_maps.get(index));
+        if let Some(map) = map {
+            comps.normalv = map.normal_at(&comps.object, comps.point, comps.normalv);
+            comps.reflectv = reflect(ray.direction(), comps.normalv);
+        }
+        comps
+    }
+
+    /// Computations for a cap, flat with the plane's normal turned toward the eye
+    fn prepare_cap(&self, cap: &Intersection<Object>, normal: Vector, ray: &Ray) -> Computations {
+        let xs = Intersections::from(vec![*cap]);
+        let mut comps = prepare_computations_with(cap, ray, &xs, self.bias);
+        comps.normalv = if normal.dot(&comps.eyev) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        comps.reflectv = reflect(ray.direction(), comps.normalv);
+        comps.over_point = comps.point + comps.normalv * self.bias;
+        comps.under_point = comps.point - comps.normalv * self.bias;
+        comps.inside = false;
+        comps
+    }
+}
+
+/// The world most of the book's tests are written against: a white light up and to the left,
+/// and two concentric spheres at the origin, the inner one half the size of the outer one
+pub fn default_world() -> World {
+    let light = PointLight::new(Point::new_point(-10.0, 10.0, -10.0), Color::WHITE);
+
+    let mut s1 = Sphere::new();
+    s1.set_material(Material {
+        color: Color::new(0.8, 1.0, 0.6),
+        diffuse: 0.7,
+        specular: 0.2,
+        ..Material::new()
+    });
+
+    let mut s2 = Sphere::new();
+    s2.set_transform(uniform_scaling(0.5))
+        .expect("scaling by 0.5 is invertible");
+
+    World {
+        objects: vec![s1.into(), s2.into()],
+        groups: Vec::new(),
+        lights: vec![light],
+        sphere_lights: Vec::new(),
+        area_lights: Vec::new(),
+        spot_lights: Vec::new(),
+        ambient: None,
+        sky: None,
+        skybox: None,
+        normal_maps: Vec::new(),
+        fog: None,
+        clip_planes: Vec::new(),
+        bias: default_bias(),
+        ray_limits: RayLimits::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::canvas::Canvas;
+    use crate::clip::ClipPlane;
+    use crate::color::Color;
+    use crate::fog::Fog;
+    use crate::group::Group;
+    use crate::light::{
+        AmbientLight, AreaLight, LightGroups, Material, PointLight, SphereLight, SpotLight,
+    };
+    use crate::limits::RayDepth;
+    use crate::matrix_transformations::{scaling, translation};
+    use crate::normal_map::NormalMap;
+    use crate::packet::RayPacket;
+    use crate::pattern::Checkers;
+    use crate::quad::Quad;
+    use crate::rays::{
+        default_bias, prepare_computations, prepare_computations_with, schlick, Intersection,
+        Intersections, Ray, RayKind, Sphere, Visibility,
+    };
+    use crate::shape::Shape;
+    use crate::sky::Sky;
+    use crate::skybox::Skybox;
+    use crate::tuple::{Point, Vector};
+    use crate::world::{default_world, World};
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[test]
+    fn creating_a_world() {
+        let w = World::new();
+        assert!(w.objects.is_empty());
+        assert!(w.lights.is_empty());
+        assert_eq!(w.bias, default_bias());
+        assert_eq!(World::default().bias, default_bias());
+    }
+
+    #[test]
+    fn ray_queries() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 2.0));
+        let first = w.first_hit(&r).unwrap();
+        assert_eq!(first.point, Point::new_point(0.0, 0.0, -1.0));
+        assert_eq!(first.normal, Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(first.distance, 4.0);
+        assert_eq!(first.object, w.objects[0].id());
+
+        let distances: Vec<f64> = w.all_hits(&r).iter().map(|h| h.distance).collect();
+        assert_eq!(distances, [4.0, 4.5, 5.5, 6.0]);
+
+        let inside = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let exit = w.first_hit(&inside).unwrap();
+        assert_eq!(exit.normal, Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(exit.object, w.objects[1].id());
+        assert_eq!(w.all_hits(&inside).len(), 2);
+
+        let away = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+        assert!(w.first_hit(&away).is_none());
+    }
+
+    #[test]
+    fn grouped_objects_are_hit() {
+        let mut w = World::new();
+        let mut g = Group::new();
+        g.set_transform(translation(0.0, 0.0, 5.0)).unwrap();
+        let s = Sphere::new();
+        g.add_object(s);
+        w.groups.push(g);
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let hit = w.first_hit(&r).unwrap();
+        assert_eq!(hit.object, s.id);
+        assert_eq!(hit.point, Point::new_point(0.0, 0.0, 4.0));
+    }
+
+    #[test]
+    fn clipping_planes_cut_objects_open() {
+        let mut w = default_world();
+        let cut = ClipPlane::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0));
+        w.clip_planes.push(cut);
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(xs.size(), 2);
+        assert_eq!(xs[0].t, 5.5);
+
+        let cap = Material {
+            color: Color::RED,
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::new()
+        };
+        w.clip_planes[0] = cut.with_cap(cap);
+        assert_eq!(w.color_at(&r), Color::RED);
+        let beside = Ray::new(Point::new_point(5.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&beside), Color::BLACK);
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let w = default_world();
+        let light = Point::new_point(-10.0, 10.0, -10.0);
+        assert!(!w.is_visible(light, Point::new_point(10.0, -10.0, 10.0)));
+        assert!(w.is_visible(light, Point::new_point(-2.0, 2.0, -2.0)));
+        assert!(
+            w.is_visible(light, Point::new_point(-0.7, 0.7, -0.7)),
+            "a point in front of the sphere"
+        );
+        assert!(w.is_visible(light, Point::new_point(10.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn objects_hidden_from_some_rays() {
+        let mut w = default_world();
+        w.objects[0].set_visibility(Visibility {
+            reflections: false,
+            shadows: false,
+            ..Visibility::ALL
+        });
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.intersect(&r).size(), 4);
+        assert_eq!(w.intersect(&r.with_kind(RayKind::Reflection)).size(), 2);
+        assert_eq!(w.intersect(&r.with_kind(RayKind::Shadow)).size(), 2);
+        // the outer sphere no longer blocks the light, the inner one still does
+        let light = Point::new_point(-10.0, 10.0, -10.0);
+        assert!(w.is_visible(light, Point::new_point(-0.7, 0.7, -0.7)));
+        assert!(!w.is_visible(light, Point::new_point(10.0, -10.0, 10.0)));
+
+        w.objects[1].set_visibility(Visibility {
+            camera: false,
+            ..Visibility::ALL
+        });
+        assert_eq!(w.intersect(&r).size(), 2);
+        let packet = RayPacket::from_iter([r]);
+        assert_eq!(w.intersect_packet(&packet)[0].unwrap().t, 4.0);
+    }
+
+    #[test]
+    fn editing_an_object_by_id() {
+        let mut w = default_world();
+        let id = w.objects[1].id();
+        w.object_mut(id)
+            .unwrap()
+            .set_transform(translation(0.0, 1.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            w.object(id).unwrap().transform(),
+            translation(0.0, 1.0, 0.0)
+        );
+        assert!(w.object(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn intersect_world_with_ray() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        assert_eq!(xs.size(), 4);
+        let ts: Vec<f64> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn reusing_an_intersection_buffer() {
+        let w = default_world();
+        let mut xs = Intersections::with_capacity(4);
+        let hit = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.intersect_into(&hit, &mut xs);
+        let ts: Vec<f64> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+        // the buffer is cleared before each ray, not added to
+        let miss = Ray::new(Point::new_point(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.intersect_into(&miss, &mut xs);
+        assert_eq!(xs.size(), 0);
+        w.intersect_into(&hit, &mut xs);
+        assert_eq!(xs.size(), 4);
+    }
+
+    #[test]
+    fn shading_an_intersection() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0]);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(w.shade_hit(&comps), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn ambient_light_adds_to_shading() {
+        let mut w = default_world();
+        w.ambient = Some(AmbientLight::Flat(Color::new(0.1, 0.1, 0.1)));
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0]);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(
+            w.shade_hit(&comps),
+            Color::new(0.38066 + 0.08, 0.47583 + 0.1, 0.2855 + 0.06)
+        );
+    }
+
+    #[test]
+    fn lights_only_shine_on_linked_objects() {
+        let mut w = default_world();
+        let hero = LightGroups::group(0);
+        w.objects[0].set_light_groups(hero);
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[0]);
+        let comps = prepare_computations(&i, &r);
+        let lit = Color::new(0.38066, 0.47583, 0.2855);
+        assert_eq!(w.shade_hit(&comps), lit);
+
+        w.lights[0].set_groups(LightGroups::group(1));
+        assert_eq!(w.shade_hit(&comps), Color::BLACK);
+
+        w.lights[0].set_groups(LightGroups::group(1).with(hero));
+        assert_eq!(w.shade_hit(&comps), lit);
+    }
+
+    #[test]
+    fn sky_lights_the_background_and_objects() {
+        let mut w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::BLACK);
+        // the sun high up behind the camera
+        let sky = Sky::new(1.0, PI, 3.0);
+        w.sky = Some(sky);
+        assert_eq!(w.color_at(&r), sky.color(r.direction()));
+
+        let hit = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        w.lights.clear();
+        let sunlit = w.color_at(&hit);
+        assert!(sunlit.luminance() > 0.1);
+
+        // a skybox replaces the sky's background but not its sun
+        let mut face = Canvas::new(1, 1);
+        face.write_pixel(0, 0, Color::GREEN);
+        let face = Arc::new(face);
+        w.skybox = Some(Skybox::new([(); 6].map(|_| face.clone())));
+        assert_eq!(w.color_at(&r), Color::GREEN);
+        assert_eq!(w.color_at(&hit), sunlit);
+    }
+
+    #[test]
+    fn normal_maps_change_the_shading() {
+        let mut w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let smooth = w.color_at(&r);
+        let map = |color| {
+            let mut image = Canvas::new(1, 1);
+            image.write_pixel(0, 0, color);
+            NormalMap::new(Arc::new(image))
+        };
+        w.normal_maps.push(map(Color::new(0.5, 0.5, 1.0)));
+        w.normal_maps.push(map(Color::new(0.2, 0.8, 0.8)));
+        let mut material = *w.objects[0].material();
+        // missing maps are ignored
+        material.normal_map = Some(2);
+        w.objects[0].set_material(material);
+        assert_eq!(w.color_at(&r), smooth);
+
+        material.normal_map = Some(0);
+        w.objects[0].set_material(material);
+        let flat = w.color_at(&r);
+        assert!((flat.red() - smooth.red()).abs() < 1e-9);
+        assert!((flat.green() - smooth.green()).abs() < 1e-9);
+
+        material.normal_map = Some(1);
+        w.objects[0].set_material(material);
+        assert_ne!(w.color_at(&r), smooth);
+    }
+
+    #[test]
+    fn fog_fades_distant_hits() {
+        let mut w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let clear = w.color_at(&r);
+        let fog = Fog::new(Color::new(0.5, 0.5, 0.5), 0.2);
+        w.fog = Some(fog);
+        // the outer sphere is hit 4 units along the ray
+        assert_eq!(w.color_at(&r), fog.apply(clear, 4.0));
+        let miss = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&miss), fog.color);
+    }
+
+    #[test]
+    fn shading_an_intersection_from_inside() {
+        let mut w = default_world();
+        w.lights = vec![PointLight::new(
+            Point::new_point(0.0, 0.25, 0.0),
+            Color::WHITE,
+        )];
+        let r = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, w.objects[1]);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(w.shade_hit(&comps), Color::new(0.90498, 0.90498, 0.90498));
+    }
+
+    #[test]
+    fn points_in_shadow() {
+        let w = default_world();
+        let cases = [
+            // nothing collinear with the point and the light
+            (Point::new_point(0.0, 10.0, 0.0), false),
+            // the spheres sit between the point and the light
+            (Point::new_point(10.0, -10.0, 10.0), true),
+            // the light sits between the point and the spheres
+            (Point::new_point(-20.0, 20.0, -20.0), false),
+            // the point is between the light and the spheres
+            (Point::new_point(-2.0, 2.0, -2.0), false),
+        ];
+        for (point, shadowed) in cases {
+            assert_eq!(w.is_shadowed(point), shadowed, "{:?}", point);
+        }
+        assert!(!World::new().is_shadowed(Point::new_point(0.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn points_in_the_shadow_of_other_lights() {
+        let mut w = default_world();
+        w.lights.clear();
+        let behind = Point::new_point(0.0, 0.0, 5.0);
+        let beside = Point::new_point(5.0, 0.0, 0.0);
+
+        // half of the sphere light peeks out from behind the spheres
+        let mut soft = SphereLight::new(Point::new_point(0.0, 0.0, -10.0), 1.5, Color::WHITE);
+        soft.set_samples(16);
+        w.sphere_lights.push(soft);
+        assert!(w.is_shadowed(Point::new_point(0.0, 0.0, 1.5)));
+        assert!(!w.is_shadowed(Point::new_point(0.0, 0.0, 40.0)));
+        assert!(!w.is_shadowed(beside));
+        w.sphere_lights.clear();
+
+        // an area light only shines out of one side, down here
+        let quad = Quad::new(
+            Point::new_point(-1.0, 5.0, -1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 2.0),
+        );
+        w.area_lights.push(AreaLight::new(quad, Color::WHITE));
+        assert!(!w.is_shadowed(beside));
+        assert!(w.is_shadowed(Point::new_point(5.0, 10.0, 0.0)));
+        w.area_lights.clear();
+
+        // a spot light only reaches inside its cone
+        w.spot_lights.push(SpotLight::new(
+            Point::new_point(0.0, 0.0, -10.0),
+            Vector::new(0.0, 0.0, 1.0),
+            0.2,
+            Color::WHITE,
+        ));
+        assert!(w.is_shadowed(behind));
+        assert!(w.is_shadowed(beside));
+        assert!(!w.is_shadowed(Point::new_point(0.0, 0.0, -5.0)));
+    }
+
+    #[test]
+    fn shading_an_intersection_in_shadow() {
+        let mut w = World::new();
+        w.lights.push(PointLight::new(
+            Point::new_point(0.0, 0.0, -10.0),
+            Color::WHITE,
+        ));
+        let s1 = Sphere::new();
+        let mut s2 = Sphere::new();
+        s2.set_transform(translation(0.0, 0.0, 10.0)).unwrap();
+        w.objects = vec![s1.into(), s2.into()];
+        let r = Ray::new(Point::new_point(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects[1]);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(w.shade_hit(&comps), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn shading_a_patterned_surface() {
+        let mut w = World::new();
+        w.lights.push(PointLight::new(
+            Point::new_point(0.0, 0.0, -10.0),
+            Color::WHITE,
+        ));
+        let mut s = Sphere::new();
+        s.set_material(Material {
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            pattern: Some(Checkers::new(Color::WHITE, Color::BLACK).into()),
+            ..Material::new()
+        });
+        w.objects.push(s.into());
+        // hits at (0, 0, -1) and (-0.5, 0, -0.87), in neighboring checkers
+        let dark = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let light = Ray::new(
+            Point::new_point(-0.5, 0.0, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        assert_eq!(w.color_at(&dark), Color::BLACK);
+        assert_eq!(w.color_at(&light), Color::WHITE);
+    }
+
+    #[test]
+    fn reflected_color_of_a_nonreflective_material() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, w.objects[1]);
+        let comps = prepare_computations(&i, &r);
+        assert_eq!(w.reflected_color(&comps, RayDepth::PRIMARY), Color::BLACK);
+    }
+
+    #[test]
+    fn reflected_color_of_a_mirror() {
+        let mut w = default_world();
+        let mut mirror = Sphere::new();
+        mirror.set_transform(translation(0.0, 0.0, -3.0)).unwrap();
+        mirror.material.reflective = 0.5;
+        w.objects.push(mirror.into());
+        // between the default spheres and the mirror, looking at the mirror
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -1.5),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+        let i = Intersection::new(0.5, w.objects[2]);
+        let comps = prepare_computations(&i, &r);
+        let reflection = w.color_at(&Ray::new(comps.over_point, comps.reflectv));
+        assert_eq!(reflection, Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(
+            w.reflected_color(&comps, RayDepth::PRIMARY),
+            reflection * 0.5
+        );
+        let mut matte = w.clone();
+        matte.objects[2].material_mut().reflective = 0.0;
+        let matte_comps = prepare_computations(&Intersection::new(0.5, matte.objects[2]), &r);
+        assert_eq!(
+            w.shade_hit(&comps),
+            matte.shade_hit(&matte_comps) + reflection * 0.5
+        );
+
+        // out of bounces
+        let deepest = RayDepth {
+            reflections: w.ray_limits.reflection_depth,
+            ..RayDepth::PRIMARY
+        };
+        assert_eq!(w.reflected_color(&comps, deepest), Color::BLACK);
+    }
+
+    #[test]
+    fn refracted_color_of_an_opaque_surface() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = prepare_computations_with(&xs[0], &r, &xs, default_bias());
+        assert_eq!(w.refracted_color(&comps, RayDepth::PRIMARY), Color::BLACK);
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let mut w = default_world();
+        let glass = w.objects[0].material_mut();
+        glass.transparency = 1.0;
+        glass.refractive_index = 1.5;
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = Intersections::from(vec![
+            Intersection::new(-FRAC_1_SQRT_2, w.objects[0]),
+            Intersection::new(FRAC_1_SQRT_2, w.objects[0]),
+        ]);
+        // inside the sphere, so the second intersection is the hit
+        let comps = prepare_computations_with(&xs[1], &r, &xs, default_bias());
+        assert_eq!(w.refracted_color(&comps, RayDepth::PRIMARY), Color::BLACK);
+    }
+
+    #[test]
+    fn refracted_color_through_glass() {
+        let mut w = default_world();
+        let mut glass = Sphere::new();
+        glass.set_transform(translation(0.0, 0.0, -3.0)).unwrap();
+        glass.material.transparency = 0.5;
+        glass.material.refractive_index = 1.5;
+        w.objects.push(glass.into());
+        // straight through the middle of the glass, the ray isn't bent
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = prepare_computations_with(&xs[0], &r, &xs, default_bias());
+        assert_eq!((comps.n1, comps.n2), (1.0, 1.5));
+        let through = w.color_at(&Ray::new(comps.under_point, r.direction()));
+        assert!(through.red() > 0.0);
+        assert_eq!(w.refracted_color(&comps, RayDepth::PRIMARY), through * 0.5);
+
+        let deepest = RayDepth {
+            refractions: w.ray_limits.refraction_depth,
+            ..RayDepth::PRIMARY
+        };
+        assert_eq!(w.refracted_color(&comps, deepest), Color::BLACK);
+    }
+
+    #[test]
+    fn glass_reflects_and_refracts_by_the_fresnel_factor() {
+        let mut w = default_world();
+        let mut glass = Sphere::new();
+        glass.set_transform(translation(0.0, 0.0, -3.0)).unwrap();
+        glass.material.reflective = 0.5;
+        glass.material.transparency = 0.5;
+        glass.material.refractive_index = 1.5;
+        w.objects.push(glass.into());
+        let r = Ray::new(Point::new_point(0.0, 0.5, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let comps = prepare_computations_with(&xs[0], &r, &xs, default_bias());
+        let reflectance = schlick(&comps);
+        assert!(reflectance > 0.0 && reflectance < 1.0);
+
+        let mut plain = w.clone();
+        let material = plain.objects[2].material_mut();
+        material.reflective = 0.0;
+        material.transparency = 0.0;
+        let plain_xs = plain.intersect(&r);
+        let surface = plain.shade_hit(&prepare_computations_with(
+            &plain_xs[0],
+            &r,
+            &plain_xs,
+            default_bias(),
+        ));
+        let expected = surface
+            + w.reflected_color(&comps, RayDepth::PRIMARY) * reflectance
+            + w.refracted_color(&comps, RayDepth::PRIMARY) * (1.0 - reflectance);
+        assert_eq!(w.shade_hit(&comps), expected);
+    }
+
+    #[test]
+    fn mirrors_facing_each_other_stop_reflecting() {
+        let mut w = World::new();
+        w.lights.push(PointLight::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Color::WHITE,
+        ));
+        let mut mirror = Sphere::new();
+        mirror.set_transform(scaling(5.0, 5.0, 5.0)).unwrap();
+        mirror.material.reflective = 1.0;
+        w.objects.push(mirror.into());
+        // bounces around inside the mirror ball until it runs out of reflections
+        let r = Ray::new(Point::new_point(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        assert!(w.color_at(&r).red() > 0.0);
+    }
+
+    #[test]
+    fn color_when_ray_misses() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(w.color_at(&r), Color::BLACK);
+    }
+
+    #[test]
+    fn color_when_ray_hits() {
+        let w = default_world();
+        let r = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855));
+    }
+
+    #[test]
+    fn color_with_intersection_behind_ray() {
+        let mut w = default_world();
+        w.objects[0].material_mut().ambient = 1.0;
+        w.objects[1].material_mut().ambient = 1.0;
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, 0.75),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+        assert_eq!(w.color_at(&r), w.objects[1].material().color);
+    }
+
+    #[test]
+    fn sphere_lights_cast_soft_shadows() {
+        let mut w = World::new();
+        let mut light = SphereLight::new(Point::new_point(0.0, 10.0, 0.0), 1.0, Color::WHITE);
+        light.set_samples(64);
+        w.sphere_lights.push(light);
+        let mut ground = Sphere::new();
+        ground
+            .set_transform(translation(0.0, -100.0, 0.0) * scaling(100.0, 100.0, 100.0))
+            .unwrap();
+        let mut blocker = Sphere::new();
+        blocker.set_transform(translation(0.0, 5.0, 0.0)).unwrap();
+        w.objects = vec![ground.into(), blocker.into()];
+
+        let brightness = |x: f64| {
+            let ray = Ray::new(Point::new_point(x, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+            w.color_at(&ray).red()
+        };
+        // right under the blocker only the ambient term is left, well outside its shadow
+        // the ground is fully lit, and in between the shadow fades out
+        let umbra = brightness(0.0);
+        let penumbra = brightness(2.0);
+        let lit = brightness(5.0);
+        assert!((umbra - 0.1).abs() < 1e-6);
+        assert!(umbra < penumbra && penumbra < lit);
+        assert!(lit > 0.8);
+    }
+
+    #[test]
+    fn area_lights_shine_one_way() {
+        let mut w = World::new();
+        // a 2 x 2 panel at y = 10 facing down
+        let panel = Quad::new(
+            Point::new_point(-1.0, 10.0, -1.0),
+            Vector::new(2.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, 2.0),
+        );
+        assert_eq!(panel.normal(), Vector::new(0.0, -1.0, 0.0));
+        let mut light = AreaLight::new(panel, Color::WHITE);
+        light.set_samples(64);
+        w.area_lights.push(light);
+        let mut ground = Sphere::new();
+        ground
+            .set_transform(translation(0.0, -100.0, 0.0) * scaling(100.0, 100.0, 100.0))
+            .unwrap();
+        let mut blocker = Sphere::new();
+        blocker.set_transform(translation(0.0, 5.0, 0.0)).unwrap();
+        w.objects = vec![ground.into(), blocker.into()];
+
+        let brightness = |x: f64| {
+            let ray = Ray::new(Point::new_point(x, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+            w.color_at(&ray).red()
+        };
+        let (umbra, penumbra, lit) = (brightness(0.0), brightness(2.0), brightness(5.0));
+        assert!((umbra - 0.1).abs() < 1e-6);
+        assert!(umbra < penumbra && penumbra < lit);
+
+        // nothing above the panel gets any of its light, not even the ambient part
+        w.objects[0]
+            .set_transform(translation(0.0, 12.0, 0.0) * scaling(10.0, 0.5, 10.0))
+            .unwrap();
+        let above = Ray::new(
+            Point::new_point(5.0, 20.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&above), Color::BLACK);
+    }
+
+    #[cfg(feature = "scene")]
+    #[test]
+    fn worlds_round_trip_through_json() {
+        let mut w = default_world();
+        w.objects[0].material_mut().pattern =
+            Some(Checkers::new(Color::WHITE, Color::BLACK).into());
+        let mut group = Group::new();
+        group.set_transform(translation(0.0, 0.0, 3.0)).unwrap();
+        group.add_object(Sphere::new());
+        w.groups.push(group);
+
+        let json = serde_json::to_string(&w).unwrap();
+        let restored: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        assert_eq!(restored.objects, w.objects);
+        let ray = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(restored.color_at(&ray), w.color_at(&ray));
+        let behind = Ray::new(
+            Point::new_point(0.0, 0.0, 10.0),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+        assert_eq!(restored.color_at(&behind), w.color_at(&behind));
+    }
+}
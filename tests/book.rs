@@ -0,0 +1,263 @@
+//! Runs the Gherkin scenarios in `tests/features`, written the way The Ray Tracer Challenge
+//! specifies each chapter, against the crate's API.
+//!
+//! Variables on the left of `←` are stored by name in `BookWorld` and can be used by later steps.
+
+use std::collections::HashMap;
+
+use cucumber::{cli, given, then, when, World};
+use ray_tracer::prelude::*;
+use ray_tracer::utils::equal_f64;
+
+#[derive(Debug, Default, World)]
+struct BookWorld {
+    tuples: HashMap<String, Tuple>,
+    rays: HashMap<String, Ray>,
+    spheres: HashMap<String, Sphere>,
+    intersections: HashMap<String, Intersections<Sphere>>,
+}
+
+impl BookWorld {
+    fn tuple(&self, name: &str) -> Tuple {
+        *self
+            .tuples
+            .get(name)
+            .unwrap_or_else(|| panic!("no tuple named {}", name))
+    }
+
+    fn sphere(&self, name: &str) -> Sphere {
+        *self
+            .spheres
+            .get(name)
+            .unwrap_or_else(|| panic!("no sphere named {}", name))
+    }
+
+    /// A tuple literal (`point(1, 2, 3)`) or the name of a tuple
+    fn tuple_expr(&self, expr: &str) -> Tuple {
+        match parse_call(expr) {
+            Some((function, args)) => tuple_call(function, &args),
+            None => self.tuple(expr),
+        }
+    }
+}
+
+/// Numbers as the book writes them: `-4.2`, `√14`, `√3/3`, `-√2/2`
+fn number(text: &str) -> f64 {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix('-') {
+        return -number(rest);
+    }
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        return number(numerator) / number(denominator);
+    }
+    if let Some(root) = text.strip_prefix('√') {
+        return number(root).sqrt();
+    }
+    text.parse()
+        .unwrap_or_else(|_| panic!("not a number: {}", text))
+}
+
+/// Splits `name(a, b, c)` into the function name and its arguments,
+/// keeping nested calls like `ray(point(0, 0, 0), vector(0, 0, 1))` together
+fn parse_call(expr: &str) -> Option<(&str, Vec<&str>)> {
+    let expr = expr.trim();
+    let open = expr.find('(')?;
+    let inner = expr.strip_suffix(')')?.get(open + 1..)?;
+    let mut args = Vec::new();
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner.trim().is_empty() {
+        args.push(inner[start..].trim());
+    }
+    Some((&expr[..open], args))
+}
+
+fn tuple_call(function: &str, args: &[&str]) -> Tuple {
+    let n: Vec<f64> = args.iter().map(|a| number(a)).collect();
+    match function {
+        "tuple" => Tuple {
+            x: n[0],
+            y: n[1],
+            z: n[2],
+            w: n[3],
+        },
+        "point" => Point::new_point(n[0], n[1], n[2]),
+        "vector" => Vector::new(n[0], n[1], n[2]),
+        _ => panic!("unknown tuple function {}", function),
+    }
+}
+
+fn transform_call(expr: &str) -> M4x4 {
+    let (function, args) = parse_call(expr).unwrap_or_else(|| panic!("not a transform: {}", expr));
+    let n: Vec<f64> = args.iter().map(|a| number(a)).collect();
+    match function {
+        "translation" => translation(n[0], n[1], n[2]),
+        "scaling" => scaling(n[0], n[1], n[2]),
+        "rotation_x" => rotation_x(n[0]),
+        "rotation_y" => rotation_y(n[0]),
+        "rotation_z" => rotation_z(n[0]),
+        "shearing" => shearing(n[0], n[1], n[2], n[3], n[4], n[5]),
+        _ => panic!("unknown transform {}", function),
+    }
+}
+
+// Tuples
+
+#[given(regex = r"^(\w+) ← ((?:tuple|point|vector)\(.*\))$")]
+fn given_tuple(world: &mut BookWorld, name: String, expr: String) {
+    let tuple = world.tuple_expr(&expr);
+    world.tuples.insert(name, tuple);
+}
+
+#[when(regex = r"^(\w+) ← normalize\((\w+)\)$")]
+fn when_normalized(world: &mut BookWorld, name: String, of: String) {
+    let normalized = world.tuple(&of).normalize();
+    world.tuples.insert(name, normalized);
+}
+
+#[then(regex = r"^(\w+)\.([xyzw]) = (.+)$")]
+fn then_component(world: &mut BookWorld, name: String, component: String, expected: String) {
+    let t = world.tuple(&name);
+    let actual = match component.as_str() {
+        "x" => t.x,
+        "y" => t.y,
+        "z" => t.z,
+        _ => t.w,
+    };
+    assert!(equal_f64(actual, number(&expected)));
+}
+
+#[then(regex = r"^(\w+) is (not )?a (point|vector)$")]
+fn then_kind(world: &mut BookWorld, name: String, not: String, kind: String) {
+    let t = world.tuple(&name);
+    let is = if kind == "point" {
+        t.is_point()
+    } else {
+        t.is_vector()
+    };
+    assert_eq!(is, not.is_empty());
+}
+
+#[then(regex = r"^(\w+) = ((?:tuple|point|vector|normalize)\(.*\))$")]
+fn then_equals(world: &mut BookWorld, name: String, expr: String) {
+    let expected = match expr.strip_prefix("normalize(") {
+        Some(inner) => world.tuple(inner.trim_end_matches(')')).normalize(),
+        None => world.tuple_expr(&expr),
+    };
+    assert_eq!(world.tuple(&name), expected);
+}
+
+#[then(regex = r"^(\w+) ([+-]) (\w+) = (.+)$")]
+fn then_sum(world: &mut BookWorld, a: String, op: String, b: String, expected: String) {
+    let (a, b) = (world.tuple(&a), world.tuple(&b));
+    let actual = if op == "+" { a + b } else { a - b };
+    assert_eq!(actual, world.tuple_expr(&expected));
+}
+
+#[then(regex = r"^-(\w+) = (.+)$")]
+fn then_negated(world: &mut BookWorld, name: String, expected: String) {
+    assert_eq!(-world.tuple(&name), world.tuple_expr(&expected));
+}
+
+#[then(regex = r"^(\w+) ([*/]) ([\d.]+) = (.+)$")]
+fn then_scaled(world: &mut BookWorld, name: String, op: String, by: String, expected: String) {
+    let t = world.tuple(&name);
+    let actual = if op == "*" {
+        t * number(&by)
+    } else {
+        t / number(&by)
+    };
+    assert_eq!(actual, world.tuple_expr(&expected));
+}
+
+#[then(regex = r"^magnitude\((\w+)\) = (.+)$")]
+fn then_magnitude(world: &mut BookWorld, name: String, expected: String) {
+    assert!(equal_f64(world.tuple(&name).magnitude(), number(&expected)));
+}
+
+#[then(regex = r"^normalize\((\w+)\) = (?:approximately )?(.+)$")]
+fn then_normalize(world: &mut BookWorld, name: String, expected: String) {
+    assert_eq!(world.tuple(&name).normalize(), world.tuple_expr(&expected));
+}
+
+#[then(regex = r"^dot\((\w+), (\w+)\) = (.+)$")]
+fn then_dot(world: &mut BookWorld, a: String, b: String, expected: String) {
+    let actual = world.tuple(&a).dot(&world.tuple(&b));
+    assert!(equal_f64(actual, number(&expected)));
+}
+
+#[then(regex = r"^cross\((\w+), (\w+)\) = (.+)$")]
+fn then_cross(world: &mut BookWorld, a: String, b: String, expected: String) {
+    let actual = world.tuple(&a).cross(&world.tuple(&b));
+    assert_eq!(actual, world.tuple_expr(&expected));
+}
+
+// Rays and spheres
+
+#[given(regex = r"^(\w+) ← ray\((.*)\)$")]
+fn given_ray(world: &mut BookWorld, name: String, args: String) {
+    let call = format!("ray({})", args);
+    let (_, args) = parse_call(&call).unwrap();
+    let ray = Ray::new(world.tuple_expr(args[0]), world.tuple_expr(args[1]));
+    world.rays.insert(name, ray);
+}
+
+#[given(regex = r"^(\w+) ← sphere\(\)$")]
+fn given_sphere(world: &mut BookWorld, name: String) {
+    world.spheres.insert(name, Sphere::new());
+}
+
+#[given(regex = r"^set_transform\((\w+), (.+)\)$")]
+#[when(regex = r"^set_transform\((\w+), (.+)\)$")]
+fn set_sphere_transform(world: &mut BookWorld, name: String, transform: String) {
+    let mut sphere = world.sphere(&name);
+    sphere
+        .set_transform(transform_call(&transform))
+        .expect("transform should be invertible");
+    world.spheres.insert(name, sphere);
+}
+
+#[when(regex = r"^(\w+) ← intersect\((\w+), (\w+)\)$")]
+fn when_intersect(world: &mut BookWorld, name: String, sphere: String, ray: String) {
+    let xs = intersect(&world.rays[&ray], world.sphere(&sphere));
+    world.intersections.insert(name, xs);
+}
+
+#[when(regex = r"^(\w+) ← normal_at\((\w+), (.+)\)$")]
+fn when_normal_at(world: &mut BookWorld, name: String, sphere: String, point: String) {
+    let sphere = world.sphere(&sphere);
+    // the book's spheres don't look at the intersection, any will do
+    let normal = sphere.normal_at(world.tuple_expr(&point), &Intersection::new(0.0, sphere));
+    world.tuples.insert(name, normal);
+}
+
+#[then(regex = r"^(\w+)\.count = (\d+)$")]
+fn then_count(world: &mut BookWorld, name: String, count: usize) {
+    assert_eq!(world.intersections[&name].size(), count);
+}
+
+#[then(regex = r"^(\w+)\[(\d+)\] = (.+)$")]
+fn then_intersection_t(world: &mut BookWorld, name: String, index: usize, t: String) {
+    assert!(equal_f64(world.intersections[&name][index].t, number(&t)));
+}
+
+fn main() {
+    // `cargo test <filter>` passes libtest's arguments to every test binary, which cucumber's
+    // own command line would reject, so the runner always uses its defaults
+    futures::executor::block_on(
+        BookWorld::cucumber()
+            .with_cli(cli::Opts::<_, _, _, cli::Empty>::default())
+            .fail_on_skipped()
+            .run_and_exit("tests/features"),
+    );
+}
@@ -0,0 +1,24 @@
+//! `set_epsilon` changes a process wide value, so it's tried in a test binary of its own where
+//! no other test is comparing floats at the same time.
+
+use ray_tracer::prelude::*;
+use ray_tracer::rays::default_bias;
+use ray_tracer::utils::{epsilon, set_epsilon, EPSILON};
+
+#[test]
+fn the_crate_epsilon_moves_the_acne_offset() {
+    let ray = Ray::new(Point::new_point(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+    let hit = Intersection::new(4.0, Sphere::new());
+    assert_eq!(default_bias(), EPSILON);
+    let comps = prepare_computations(&hit, &ray);
+    assert!((comps.over_point.z - (-1.0 - EPSILON)).abs() < 1e-12);
+
+    set_epsilon(0.01);
+    assert_eq!(epsilon(), 0.01);
+    assert_eq!(default_bias(), 0.01);
+    let comps = prepare_computations(&hit, &ray);
+    assert!((comps.over_point.z - -1.01).abs() < 1e-12);
+    assert!((comps.under_point.z - -0.99).abs() < 1e-12);
+    assert_eq!(World::new().bias, 0.01);
+    set_epsilon(EPSILON);
+}
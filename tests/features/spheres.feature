@@ -0,0 +1,76 @@
+Feature: Spheres
+
+Scenario: A ray intersects a sphere at two points
+  Given r ← ray(point(0, 0, -5), vector(0, 0, 1))
+    And s ← sphere()
+  When xs ← intersect(s, r)
+  Then xs.count = 2
+    And xs[0] = 4.0
+    And xs[1] = 6.0
+
+Scenario: A ray intersects a sphere at a tangent
+  Given r ← ray(point(0, 1, -5), vector(0, 0, 1))
+    And s ← sphere()
+  When xs ← intersect(s, r)
+  Then xs.count = 2
+    And xs[0] = 5.0
+    And xs[1] = 5.0
+
+Scenario: A ray misses a sphere
+  Given r ← ray(point(0, 2, -5), vector(0, 0, 1))
+    And s ← sphere()
+  When xs ← intersect(s, r)
+  Then xs.count = 0
+
+Scenario: A ray originates inside a sphere
+  Given r ← ray(point(0, 0, 0), vector(0, 0, 1))
+    And s ← sphere()
+  When xs ← intersect(s, r)
+  Then xs.count = 2
+    And xs[0] = -1.0
+    And xs[1] = 1.0
+
+Scenario: A sphere is behind a ray
+  Given r ← ray(point(0, 0, 5), vector(0, 0, 1))
+    And s ← sphere()
+  When xs ← intersect(s, r)
+  Then xs.count = 2
+    And xs[0] = -6.0
+    And xs[1] = -4.0
+
+Scenario: Intersecting a scaled sphere with a ray
+  Given r ← ray(point(0, 0, -5), vector(0, 0, 1))
+    And s ← sphere()
+  When set_transform(s, scaling(2, 2, 2))
+    And xs ← intersect(s, r)
+  Then xs.count = 2
+    And xs[0] = 3
+    And xs[1] = 7
+
+Scenario: Intersecting a translated sphere with a ray
+  Given r ← ray(point(0, 0, -5), vector(0, 0, 1))
+    And s ← sphere()
+  When set_transform(s, translation(5, 0, 0))
+    And xs ← intersect(s, r)
+  Then xs.count = 0
+
+Scenario: The normal on a sphere at a point on the x axis
+  Given s ← sphere()
+  When n ← normal_at(s, point(1, 0, 0))
+  Then n = vector(1, 0, 0)
+
+Scenario: The normal on a sphere at a nonaxial point
+  Given s ← sphere()
+  When n ← normal_at(s, point(√3/3, √3/3, √3/3))
+  Then n = vector(√3/3, √3/3, √3/3)
+
+Scenario: The normal is a normalized vector
+  Given s ← sphere()
+  When n ← normal_at(s, point(√3/3, √3/3, √3/3))
+  Then n = normalize(n)
+
+Scenario: Computing the normal on a translated sphere
+  Given s ← sphere()
+    And set_transform(s, translation(0, 1, 0))
+  When n ← normal_at(s, point(0, 1.70711, -0.70711))
+  Then n = vector(0, 0.70711, -0.70711)